@@ -27,6 +27,8 @@ pub fn pattern(input: TokenStream) -> TokenStream {
             PatternFormatter {
                 factory_path: formatter.0,
                 kind: PatternFormatterKind::Custom,
+                takes_arg: false,
+                arg_validator: None,
             },
         ) {
             panic!("{}", err);
@@ -86,6 +86,13 @@ impl PatternTemplate {
         nom::combinator::complete(nom::multi::many0(token_parser).and(nom::combinator::eof))
             .map(|(tokens, _)| Self { tokens })
     }
+
+    #[must_use]
+    fn parser_without_groups<'a>() -> impl Parser<&'a str, Self, nom::error::Error<&'a str>> {
+        let token_parser = PatternTemplateToken::parser_without_groups();
+        nom::combinator::complete(nom::multi::many0(token_parser).and(nom::combinator::eof))
+            .map(|(tokens, _)| Self { tokens })
+    }
 }
 
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
@@ -93,6 +100,7 @@ pub(crate) enum PatternTemplateToken {
     Literal(PatternTemplateLiteral),
     Formatter(PatternTemplateFormatter),
     StyleRange(PatternTemplateStyleRange),
+    Optional(PatternTemplateOptional),
 }
 
 impl PatternTemplateToken {
@@ -106,6 +114,14 @@ impl PatternTemplateToken {
 
     #[must_use]
     fn parser_without_style_range<'a>() -> impl Parser<&'a str, Self, nom::error::Error<&'a str>> {
+        let optional_parser = PatternTemplateOptional::parser();
+        let other_parser = Self::parser_without_groups();
+
+        nom::combinator::map(optional_parser, Self::Optional).or(other_parser)
+    }
+
+    #[must_use]
+    fn parser_without_groups<'a>() -> impl Parser<&'a str, Self, nom::error::Error<&'a str>> {
         let literal_parser = PatternTemplateLiteral::parser();
         let formatter_parser = PatternTemplateFormatter::parser();
 
@@ -134,6 +150,7 @@ impl PatternTemplateLiteral {
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub(crate) struct PatternTemplateFormatter {
     pub(crate) name: String,
+    pub(crate) arg: Option<String>,
     pub(crate) kind: PatternFormatterKind,
 }
 
@@ -143,8 +160,7 @@ impl PatternTemplateFormatter {
         let open_paren_parser = nom::character::complete::char('{');
         let close_paren_parser = nom::character::complete::char('}');
         let formatter_prefix_parser = nom::character::complete::char('$');
-        let formatter_name_parser = nom::combinator::recognize(nom::sequence::tuple((
-            nom::combinator::opt(formatter_prefix_parser),
+        let identifier_parser = nom::combinator::recognize(nom::sequence::tuple((
             nom::branch::alt((
                 nom::character::complete::alpha1,
                 nom::bytes::complete::tag("_"),
@@ -154,35 +170,91 @@ impl PatternTemplateFormatter {
                 nom::bytes::complete::tag("_"),
             ))),
         )));
-
-        nom::sequence::delimited(open_paren_parser, formatter_name_parser, close_paren_parser).map(
-            |name: &str| match name.strip_prefix('$') {
+        let formatter_name_parser = nom::combinator::recognize(nom::sequence::tuple((
+            nom::combinator::opt(formatter_prefix_parser),
+            identifier_parser,
+        )));
+        // A formatter name may be followed by a `:arg` suffix, e.g.
+        // `{level:spdlog}` or `{strftime:%H:%M}`. The argument extends to the
+        // closing paren and cannot be empty.
+        let formatter_arg_parser = nom::sequence::preceded(
+            nom::character::complete::char(':'),
+            nom::bytes::complete::is_not("{}"),
+        );
+
+        nom::sequence::delimited(
+            open_paren_parser,
+            formatter_name_parser.and(nom::combinator::opt(formatter_arg_parser)),
+            close_paren_parser,
+        )
+        .map(|(name, arg): (&str, Option<&str>)| {
+            let arg = arg.map(|arg| arg.to_owned());
+            match name.strip_prefix('$') {
                 Some(custom_name) => Self {
                     name: custom_name.to_owned(),
+                    arg,
                     kind: PatternFormatterKind::Custom,
                 },
                 None => Self {
                     name: name.to_owned(),
+                    arg,
                     kind: PatternFormatterKind::BuiltIn,
                 },
-            },
-        )
+            }
+        })
     }
 }
 
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub(crate) struct PatternTemplateStyleRange {
+    /// The name of the least severe level for which the style range is
+    /// reported, if the style range is conditional.
+    pub(crate) min_level: Option<String>,
     pub(crate) body: PatternTemplate,
 }
 
 impl PatternTemplateStyleRange {
     #[must_use]
     fn parser<'a>() -> impl Parser<&'a str, Self, nom::error::Error<&'a str>> {
+        // The body may start with a `level+:` condition, e.g. `{^error+:{payload}}`.
+        let min_level_parser = nom::sequence::terminated(
+            nom::character::complete::alpha1,
+            nom::bytes::complete::tag("+:"),
+        );
+
         nom::bytes::complete::tag("{^")
             .and(helper::take_until_unbalanced('{', '}'))
             .and(nom::bytes::complete::tag("}"))
             .map(|((_, body), _)| body)
-            .and_then(PatternTemplate::parser_without_style_range())
+            .and_then(
+                nom::combinator::opt(min_level_parser)
+                    .and(PatternTemplate::parser_without_style_range()),
+            )
+            .map(|(min_level, body): (Option<&str>, PatternTemplate)| Self {
+                min_level: min_level.map(|level| level.to_owned()),
+                body,
+            })
+    }
+}
+
+/// An optional group, written as `{?...}`, e.g. `{?[{logger}] }`.
+///
+/// The body contains literals and placeholders only, and is omitted entirely if
+/// any placeholder in it writes nothing. Style ranges and other optional groups
+/// cannot be nested in it.
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub(crate) struct PatternTemplateOptional {
+    pub(crate) body: PatternTemplate,
+}
+
+impl PatternTemplateOptional {
+    #[must_use]
+    fn parser<'a>() -> impl Parser<&'a str, Self, nom::error::Error<&'a str>> {
+        nom::bytes::complete::tag("{?")
+            .and(helper::take_until_unbalanced('{', '}'))
+            .and(nom::bytes::complete::tag("}"))
+            .map(|((_, body), _)| body)
+            .and_then(PatternTemplate::parser_without_groups())
             .map(|body| Self { body })
     }
 }
@@ -320,6 +392,7 @@ mod tests {
                             }),
                             PatternTemplateToken::Formatter(PatternTemplateFormatter {
                                 name: String::from("name"),
+                                arg: None,
                                 kind: PatternFormatterKind::BuiltIn
                             }),
                             PatternTemplateToken::Literal(PatternTemplateLiteral {
@@ -327,6 +400,7 @@ mod tests {
                             }),
                             PatternTemplateToken::Formatter(PatternTemplateFormatter {
                                 name: String::from("custom"),
+                                arg: None,
                                 kind: PatternFormatterKind::Custom
                             }),
                         ],
@@ -355,6 +429,60 @@ mod tests {
             assert!(parse_template_str(r#"hello {name{}!"#).is_err());
         }
 
+        #[test]
+        fn test_parse_formatter_variant() {
+            assert_eq!(
+                parse_template_str(r#"[{level:spdlog}]"#),
+                Ok((
+                    "",
+                    PatternTemplate {
+                        tokens: vec![
+                            PatternTemplateToken::Literal(PatternTemplateLiteral {
+                                literal: String::from("["),
+                            }),
+                            PatternTemplateToken::Formatter(PatternTemplateFormatter {
+                                name: String::from("level"),
+                                arg: Some(String::from("spdlog")),
+                                kind: PatternFormatterKind::BuiltIn
+                            }),
+                            PatternTemplateToken::Literal(PatternTemplateLiteral {
+                                literal: String::from("]"),
+                            }),
+                        ],
+                    }
+                ))
+            );
+            assert!(parse_template_str(r#"{level:}"#).is_err());
+            assert!(parse_template_str(r#"{level:{x}}"#).is_err());
+        }
+
+        #[test]
+        fn test_parse_formatter_arg() {
+            assert_eq!(
+                parse_template_str(r#"{strftime:%H:%M} {$custom:a b}"#),
+                Ok((
+                    "",
+                    PatternTemplate {
+                        tokens: vec![
+                            PatternTemplateToken::Formatter(PatternTemplateFormatter {
+                                name: String::from("strftime"),
+                                arg: Some(String::from("%H:%M")),
+                                kind: PatternFormatterKind::BuiltIn
+                            }),
+                            PatternTemplateToken::Literal(PatternTemplateLiteral {
+                                literal: String::from(" "),
+                            }),
+                            PatternTemplateToken::Formatter(PatternTemplateFormatter {
+                                name: String::from("custom"),
+                                arg: Some(String::from("a b")),
+                                kind: PatternFormatterKind::Custom
+                            }),
+                        ],
+                    }
+                ))
+            );
+        }
+
         #[test]
         fn test_parse_formatter_missing_close_paren() {
             assert!(parse_template_str(r#"hello {name"#).is_err());
@@ -373,6 +501,7 @@ mod tests {
                             }),
                             PatternTemplateToken::Formatter(PatternTemplateFormatter {
                                 name: String::from("name"),
+                                arg: None,
                                 kind: PatternFormatterKind::BuiltIn
                             }),
                             PatternTemplateToken::Literal(PatternTemplateLiteral {
@@ -396,6 +525,7 @@ mod tests {
                                 literal: String::from("hello "),
                             }),
                             PatternTemplateToken::StyleRange(PatternTemplateStyleRange {
+                                min_level: None,
                                 body: PatternTemplate {
                                     tokens: vec![PatternTemplateToken::Literal(
                                         PatternTemplateLiteral {
@@ -419,6 +549,7 @@ mod tests {
                                 literal: String::from("hello "),
                             }),
                             PatternTemplateToken::StyleRange(PatternTemplateStyleRange {
+                                min_level: None,
                                 body: PatternTemplate {
                                     tokens: vec![
                                         PatternTemplateToken::Literal(PatternTemplateLiteral {
@@ -426,6 +557,7 @@ mod tests {
                                         }),
                                         PatternTemplateToken::Formatter(PatternTemplateFormatter {
                                             name: String::from("b_pat"),
+                                            arg: None,
                                             kind: PatternFormatterKind::BuiltIn
                                         }),
                                         PatternTemplateToken::Literal(PatternTemplateLiteral {
@@ -433,6 +565,7 @@ mod tests {
                                         }),
                                         PatternTemplateToken::Formatter(PatternTemplateFormatter {
                                             name: String::from("c_pat"),
+                                            arg: None,
                                             kind: PatternFormatterKind::Custom
                                         }),
                                         PatternTemplateToken::Literal(PatternTemplateLiteral {
@@ -447,9 +580,117 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_parse_style_range_min_level() {
+            assert_eq!(
+                parse_template_str(r#"{^error+:{payload}}"#),
+                Ok((
+                    "",
+                    PatternTemplate {
+                        tokens: vec![PatternTemplateToken::StyleRange(
+                            PatternTemplateStyleRange {
+                                min_level: Some(String::from("error")),
+                                body: PatternTemplate {
+                                    tokens: vec![PatternTemplateToken::Formatter(
+                                        PatternTemplateFormatter {
+                                            name: String::from("payload"),
+                                            arg: None,
+                                            kind: PatternFormatterKind::BuiltIn
+                                        }
+                                    )],
+                                },
+                            }
+                        )],
+                    }
+                ))
+            );
+        }
+
         #[test]
         fn test_parse_style_range_nested() {
             assert!(parse_template_str(r#"hello {^ hello {^ world } }"#).is_err());
         }
+
+        #[test]
+        fn test_parse_optional_basic() {
+            assert_eq!(
+                parse_template_str(r#"{?[{logger}] }{payload}"#),
+                Ok((
+                    "",
+                    PatternTemplate {
+                        tokens: vec![
+                            PatternTemplateToken::Optional(PatternTemplateOptional {
+                                body: PatternTemplate {
+                                    tokens: vec![
+                                        PatternTemplateToken::Literal(PatternTemplateLiteral {
+                                            literal: String::from("["),
+                                        }),
+                                        PatternTemplateToken::Formatter(PatternTemplateFormatter {
+                                            name: String::from("logger"),
+                                            arg: None,
+                                            kind: PatternFormatterKind::BuiltIn
+                                        }),
+                                        PatternTemplateToken::Literal(PatternTemplateLiteral {
+                                            literal: String::from("] "),
+                                        }),
+                                    ],
+                                },
+                            }),
+                            PatternTemplateToken::Formatter(PatternTemplateFormatter {
+                                name: String::from("payload"),
+                                arg: None,
+                                kind: PatternFormatterKind::BuiltIn
+                            }),
+                        ],
+                    }
+                ))
+            );
+        }
+
+        #[test]
+        fn test_parse_optional_in_style_range() {
+            assert_eq!(
+                parse_template_str(r#"{^{?{$custom} }}"#),
+                Ok((
+                    "",
+                    PatternTemplate {
+                        tokens: vec![PatternTemplateToken::StyleRange(
+                            PatternTemplateStyleRange {
+                                min_level: None,
+                                body: PatternTemplate {
+                                    tokens: vec![PatternTemplateToken::Optional(
+                                        PatternTemplateOptional {
+                                            body: PatternTemplate {
+                                                tokens: vec![
+                                                    PatternTemplateToken::Formatter(
+                                                        PatternTemplateFormatter {
+                                                            name: String::from("custom"),
+                                                            arg: None,
+                                                            kind: PatternFormatterKind::Custom
+                                                        }
+                                                    ),
+                                                    PatternTemplateToken::Literal(
+                                                        PatternTemplateLiteral {
+                                                            literal: String::from(" "),
+                                                        }
+                                                    ),
+                                                ],
+                                            },
+                                        }
+                                    )],
+                                },
+                            }
+                        )],
+                    }
+                ))
+            );
+        }
+
+        #[test]
+        fn test_parse_optional_nested() {
+            assert!(parse_template_str(r#"{?[{?{logger}}]}"#).is_err());
+            assert!(parse_template_str(r#"{?[{^{logger}}]}"#).is_err());
+            assert!(parse_template_str(r#"{?[{logger}]"#).is_err());
+        }
     }
 }
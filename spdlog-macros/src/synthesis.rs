@@ -9,8 +9,8 @@ use quote::ToTokens;
 use syn::{punctuated::Punctuated, token::Paren, Expr, ExprLit, ExprTuple, Lit, LitStr, Path};
 
 use crate::parse::{
-    PatternTemplate, PatternTemplateFormatter, PatternTemplateLiteral, PatternTemplateStyleRange,
-    PatternTemplateToken,
+    PatternTemplate, PatternTemplateFormatter, PatternTemplateLiteral, PatternTemplateOptional,
+    PatternTemplateStyleRange, PatternTemplateToken,
 };
 
 pub(crate) struct Synthesiser {
@@ -46,6 +46,38 @@ impl Synthesiser {
                                     stringify!(::spdlog::formatter::__pattern::$formatter::default)
                                 ).unwrap(),
                                 kind: PatternFormatterKind::BuiltIn,
+                                takes_arg: false,
+                                arg_validator: None,
+                            }
+                        ).unwrap();
+                    )+
+                )+
+            };
+        }
+
+        // Built-in patterns taking an argument, e.g. `{strftime:%H:%M}`, are created
+        // by calling the given function with the argument as a string literal. The
+        // argument is checked by the given validator at compile-time.
+        macro_rules! map_builtin_formatters_with_arg {
+            (
+                $synthesiser:expr,
+                $(
+                    [ $($name:literal),+ $(,)? ] =>
+                        $formatter:ident::$factory:ident ($validator:expr)
+                ),+
+                $(,)?
+            ) => {
+                $(
+                    $(
+                        $synthesiser.add_formatter_mapping(
+                            String::from($name),
+                            PatternFormatter {
+                                factory_path: syn::parse_str(
+                                    stringify!(::spdlog::formatter::__pattern::$formatter::$factory)
+                                ).unwrap(),
+                                kind: PatternFormatterKind::BuiltIn,
+                                takes_arg: true,
+                                arg_validator: Some($validator),
                             }
                         ).unwrap();
                     )+
@@ -78,6 +110,7 @@ impl Synthesiser {
             ["time"] => Time,
             ["tz_offset"] => TzOffset,
             ["unix_timestamp"] => UnixTimestamp,
+            ["subsec_seq"] => SubsecSeq,
             ["full"] => Full,
             ["level"] => Level,
             ["level_short"] => ShortLevel,
@@ -89,11 +122,32 @@ impl Synthesiser {
             ["module_path"] => SourceModulePath,
             ["logger"] => LoggerName,
             ["payload"] => Payload,
+            ["duration"] => RecordDuration,
             ["pid"] => ProcessId,
             ["tid"] => ThreadId,
+            ["thread_prio"] => ThreadPriority,
+            ["disk_free"] => DiskFree,
+            ["sink_count"] => SinkCount,
+            ["version"] => AppVersion,
             ["eol"] => Eol,
         }
 
+        map_builtin_formatters_with_arg! {synthesiser,
+            ["strftime"] => Strftime::new(validate_strftime_format),
+            ["strftime_utc"] => Strftime::new_utc(validate_strftime_format),
+            ["const"] => Constant::new(validate_any),
+        }
+
+        // Other forms of a built-in pattern, e.g. `{level:spdlog}` and
+        // `{datetime:%Y}`, are registered under the name with a trailing `:`, and
+        // take the part after `:` as their argument.
+        map_builtin_formatters_with_arg! {synthesiser,
+            ["datetime:"] => Strftime::new(validate_strftime_format),
+            ["level:"] => SpdlogLevel::with_variant(validate_level_variant),
+            ["logger:"] => ShortLoggerName::with_variant(validate_logger_variant),
+            ["tid:"] => ThreadIdHex::with_variant(validate_tid_variant),
+        }
+
         synthesiser
     }
 
@@ -149,6 +203,9 @@ impl Synthesiser {
                     style_range_seen = true;
                     self.build_style_range_template_pattern_expr(style_range_token)?
                 }
+                PatternTemplateToken::Optional(optional_token) => {
+                    self.build_optional_template_pattern_expr(optional_token)?
+                }
             };
             template_expr.elems.push(token_template_expr);
         }
@@ -172,7 +229,7 @@ impl Synthesiser {
         &self,
         formatter_token: &PatternTemplateFormatter,
     ) -> Result<Expr, SynthesisError> {
-        let formatter_creation_expr = self.build_formatter_creation_expr(formatter_token)?;
+        let formatter_creation_expr = self.build_formatter_creation_expr(formatter_token, false)?;
         Ok(formatter_creation_expr)
     }
 
@@ -181,48 +238,255 @@ impl Synthesiser {
         style_range_token: &PatternTemplateStyleRange,
     ) -> Result<Expr, SynthesisError> {
         let body_pattern_expr = self.build_template_pattern_expr(&style_range_token.body, true)?;
-        let expr = self.build_style_range_pattern_creation_expr(body_pattern_expr)?;
+        let expr = self.build_style_range_pattern_creation_expr(
+            body_pattern_expr,
+            style_range_token.min_level.as_deref(),
+        )?;
         Ok(expr)
     }
 
+    fn build_optional_template_pattern_expr(
+        &self,
+        optional_token: &PatternTemplateOptional,
+    ) -> Result<Expr, SynthesisError> {
+        let mut body_expr = ExprTuple {
+            attrs: Vec::new(),
+            paren_token: Paren {
+                span: Span::mixed_site(),
+            },
+            elems: Punctuated::new(),
+        };
+
+        // Placeholders are wrapped so that they can report to the group when they
+        // write nothing, literals are kept as is.
+        for token in &optional_token.body.tokens {
+            let token_expr = match token {
+                PatternTemplateToken::Literal(literal_token) => {
+                    self.build_literal_template_pattern_expr(literal_token)?
+                }
+                PatternTemplateToken::Formatter(formatter_token) => {
+                    self.build_formatter_creation_expr(formatter_token, true)?
+                }
+                PatternTemplateToken::StyleRange(_) | PatternTemplateToken::Optional(_) => {
+                    unreachable!("groups nested in optional groups are rejected by the parser")
+                }
+            };
+            body_expr.elems.push(token_expr);
+        }
+
+        let optional_new_path: Path =
+            syn::parse_str("::spdlog::formatter::__pattern::Optional::new").unwrap();
+        let expr = syn::parse2(quote::quote!( #optional_new_path (#body_expr) )).unwrap();
+        Ok(Expr::Call(expr))
+    }
+
     fn build_formatter_creation_expr(
         &self,
         formatter_token: &PatternTemplateFormatter,
+        optional_field: bool,
     ) -> Result<Expr, SynthesisError> {
-        let formatter = match self.formatters.get(&formatter_token.name) {
+        let formatter = self.get_formatter(&formatter_token.name, formatter_token.kind)?;
+
+        // A pattern not taking an argument may be given a padding spec instead,
+        // e.g. `{level:<8}`, or be an other form of a built-in pattern.
+        let (formatter, arg, padding) = match formatter_token.arg.as_ref() {
+            Some(arg) if !formatter.takes_arg => match PaddingSpec::parse(arg) {
+                Some(padding) => (formatter, None, Some(padding)),
+                None => match self
+                    .formatters
+                    .get(&format!("{}:", formatter_token.name))
+                    .filter(|formatter| formatter.kind == PatternFormatterKind::BuiltIn)
+                {
+                    Some(formatter_with_arg) => (formatter_with_arg, Some(arg), None),
+                    None => (formatter, Some(arg), None),
+                },
+            },
+            arg => (formatter, arg, None),
+        };
+        let formatter_factory_path = &formatter.factory_path;
+
+        let mut stream = match (formatter.takes_arg, arg) {
+            (false, None) => quote::quote!( #formatter_factory_path () ),
+            (true, Some(arg)) => {
+                if let Some(validator) = formatter.arg_validator {
+                    validator(arg).map_err(|reason| {
+                        SynthesisError::InvalidArgument(formatter_token.name.clone(), reason)
+                    })?;
+                }
+                let arg = LitStr::new(arg, Span::mixed_site());
+                quote::quote!( #formatter_factory_path (#arg) )
+            }
+            (false, Some(_)) => {
+                return Err(SynthesisError::UnexpectedArgument(
+                    formatter_token.name.clone(),
+                ))
+            }
+            (true, None) => {
+                return Err(SynthesisError::MissingArgument(
+                    formatter_token.name.clone(),
+                ))
+            }
+        };
+        // The field is checked for emptiness before it's padded.
+        if optional_field {
+            let optional_field_new_path: Path =
+                syn::parse_str("::spdlog::formatter::__pattern::OptionalField::new").unwrap();
+            stream = quote::quote!( #optional_field_new_path (#stream) );
+        }
+        if let Some(padding) = padding {
+            let padding_new_path: Path =
+                syn::parse_str("::spdlog::formatter::__pattern::Padding::new").unwrap();
+            let align_path: Path = syn::parse_str(&format!(
+                "::spdlog::formatter::__pattern::PaddingAlign::{}",
+                padding.align
+            ))
+            .unwrap();
+            let width = padding.width;
+            let truncate = padding.truncate;
+            stream = quote::quote!( #padding_new_path (#stream, #width, #align_path, #truncate) );
+        }
+
+        let factory_call_expr = syn::parse2(stream).unwrap();
+        Ok(Expr::Call(factory_call_expr))
+    }
+
+    fn get_formatter(
+        &self,
+        name: &str,
+        kind: PatternFormatterKind,
+    ) -> Result<&PatternFormatter, SynthesisError> {
+        match self.formatters.get(name) {
             Some(formatter) => {
-                if formatter_token.kind == formatter.kind {
+                if kind == formatter.kind {
                     Ok(formatter)
                 } else {
                     Err(SynthesisError::BuiltinPatternUsedAsCustomPattern(
-                        formatter_token.name.clone(),
+                        name.to_owned(),
                     ))
                 }
             }
-            None => Err(SynthesisError::UnknownFormatterName(
-                formatter_token.name.clone(),
-                formatter_token.kind,
-            )),
-        }?;
-        let formatter_factory_path = &formatter.factory_path;
-
-        let stream = quote::quote!( #formatter_factory_path () );
-        let factory_call_expr = syn::parse2(stream).unwrap();
-        Ok(Expr::Call(factory_call_expr))
+            None => Err(SynthesisError::UnknownFormatterName(name.to_owned(), kind)),
+        }
     }
 
-    fn build_style_range_pattern_creation_expr(&self, body: Expr) -> Result<Expr, SynthesisError> {
-        let style_range_pattern_new_path: Path =
-            syn::parse_str("::spdlog::formatter::__pattern::StyleRange::new").unwrap();
-        let stream = quote::quote!( #style_range_pattern_new_path (#body) );
+    fn build_style_range_pattern_creation_expr(
+        &self,
+        body: Expr,
+        min_level: Option<&str>,
+    ) -> Result<Expr, SynthesisError> {
+        let stream = match min_level {
+            None => {
+                let style_range_pattern_new_path: Path =
+                    syn::parse_str("::spdlog::formatter::__pattern::StyleRange::new").unwrap();
+                quote::quote!( #style_range_pattern_new_path (#body) )
+            }
+            Some(min_level) => {
+                let level_variant = match min_level {
+                    "critical" => "Critical",
+                    "error" => "Error",
+                    "warn" => "Warn",
+                    "info" => "Info",
+                    "debug" => "Debug",
+                    "trace" => "Trace",
+                    _ => return Err(SynthesisError::UnknownLevelName(min_level.to_owned())),
+                };
+                let style_range_pattern_new_path: Path =
+                    syn::parse_str("::spdlog::formatter::__pattern::StyleRange::with_min_level")
+                        .unwrap();
+                let level_path: Path =
+                    syn::parse_str(&format!("::spdlog::Level::{}", level_variant)).unwrap();
+                quote::quote!( #style_range_pattern_new_path (#body, #level_path) )
+            }
+        };
         let expr = syn::parse2(stream).unwrap();
         Ok(Expr::Call(expr))
     }
 }
 
+/// A padding spec given to a placeholder, in the form `[<|>|^]<width>[!]`.
+///
+/// The alignment defaults to left, and `!` truncates values longer than the
+/// width.
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub(crate) struct PaddingSpec {
+    pub(crate) width: usize,
+    pub(crate) align: &'static str,
+    pub(crate) truncate: bool,
+}
+
+impl PaddingSpec {
+    #[must_use]
+    pub(crate) fn parse(spec: &str) -> Option<Self> {
+        let (align, rest) = match spec.chars().next()? {
+            '<' => ("Left", &spec[1..]),
+            '>' => ("Right", &spec[1..]),
+            '^' => ("Center", &spec[1..]),
+            _ => ("Left", spec),
+        };
+        let (width, truncate) = match rest.strip_suffix('!') {
+            Some(width) => (width, true),
+            None => (rest, false),
+        };
+        if width.is_empty() || !width.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        Some(Self {
+            width: width.parse().ok()?,
+            align,
+            truncate,
+        })
+    }
+}
+
+type ArgValidator = fn(&str) -> Result<(), String>;
+
 pub(crate) struct PatternFormatter {
     pub(crate) factory_path: Path,
     pub(crate) kind: PatternFormatterKind,
+    pub(crate) takes_arg: bool,
+    pub(crate) arg_validator: Option<ArgValidator>,
+}
+
+fn validate_any(_arg: &str) -> Result<(), String> {
+    Ok(())
+}
+
+fn validate_strftime_format(format: &str) -> Result<(), String> {
+    use chrono::format::{Item, StrftimeItems};
+
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        Err(format!(
+            "'{}' is not a valid strftime format string",
+            format
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_level_variant(variant: &str) -> Result<(), String> {
+    validate_variant(variant, &["spdlog"])
+}
+
+fn validate_logger_variant(variant: &str) -> Result<(), String> {
+    validate_variant(variant, &["short"])
+}
+
+fn validate_tid_variant(variant: &str) -> Result<(), String> {
+    validate_variant(variant, &["hex"])
+}
+
+fn validate_variant(variant: &str, expected: &[&str]) -> Result<(), String> {
+    if expected.contains(&variant) {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' is not a valid variant, expected one of: {}",
+            variant,
+            expected.join(", ")
+        ))
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -273,7 +537,11 @@ impl Error for ConflictFormatterError {}
 pub(crate) enum SynthesisError {
     BuiltinPatternUsedAsCustomPattern(String),
     UnknownFormatterName(String, PatternFormatterKind),
+    UnexpectedArgument(String),
+    MissingArgument(String),
+    InvalidArgument(String, String),
     MultipleStyleRange,
+    UnknownLevelName(String),
 }
 
 impl Display for SynthesisError {
@@ -296,11 +564,60 @@ impl Display for SynthesisError {
                     name
                 ),
             },
+            Self::UnexpectedArgument(name) => {
+                write!(f, "pattern '{}' does not take an argument", name)
+            }
+            Self::MissingArgument(name) => {
+                write!(
+                    f,
+                    "pattern '{}' requires an argument, e.g. `{{{}:arg}}`",
+                    name, name
+                )
+            }
+            Self::InvalidArgument(name, reason) => {
+                write!(f, "invalid argument for pattern '{}': {}", name, reason)
+            }
             Self::MultipleStyleRange => {
                 write!(f, "multiple style ranges are not currently supported")
             }
+            Self::UnknownLevelName(name) => {
+                write!(
+                    f,
+                    "unknown level '{}' in style range condition, expected one of `critical`, `error`, `warn`, `info`, `debug`, `trace`",
+                    name
+                )
+            }
         }
     }
 }
 
 impl Error for SynthesisError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_padding_spec() {
+        let spec = |width, align, truncate| {
+            Some(PaddingSpec {
+                width,
+                align,
+                truncate,
+            })
+        };
+
+        assert_eq!(PaddingSpec::parse("8"), spec(8, "Left", false));
+        assert_eq!(PaddingSpec::parse("<8"), spec(8, "Left", false));
+        assert_eq!(PaddingSpec::parse(">12"), spec(12, "Right", false));
+        assert_eq!(PaddingSpec::parse("^8!"), spec(8, "Center", true));
+        assert_eq!(PaddingSpec::parse("8!"), spec(8, "Left", true));
+
+        assert_eq!(PaddingSpec::parse(""), None);
+        assert_eq!(PaddingSpec::parse("<"), None);
+        assert_eq!(PaddingSpec::parse("<!"), None);
+        assert_eq!(PaddingSpec::parse("+8"), None);
+        assert_eq!(PaddingSpec::parse("8x"), None);
+        assert_eq!(PaddingSpec::parse("spdlog"), None);
+    }
+}
@@ -187,4 +187,12 @@ fn main() {
         args.threads,
         args.iters,
     );
+    bench(
+        OverflowPolicy::DropOldest,
+        "basic_async-drop-oldest.log",
+        args.message_count,
+        queue_size,
+        args.threads,
+        args.iters,
+    );
 }
@@ -0,0 +1,79 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::{
+    sync::{Arc, Barrier, Mutex},
+    thread,
+};
+
+use test::{black_box, Bencher};
+
+// Compares a pure spin mutex with the blocking (spin-then-park) mutex from the
+// standard library, under heavy contention and a critical section of a similar
+// length to writing a record into a buffered file.
+
+const THREADS: usize = 16;
+const LOCKS_PER_THREAD: usize = 100;
+const MESSAGE: &[u8] = b"this is a test log message\n";
+
+trait BenchMutex: Send + Sync + 'static {
+    fn new() -> Self;
+    fn with_lock(&self, f: impl FnOnce(&mut Vec<u8>));
+}
+
+impl BenchMutex for spin::Mutex<Vec<u8>> {
+    fn new() -> Self {
+        spin::Mutex::new(Vec::new())
+    }
+
+    fn with_lock(&self, f: impl FnOnce(&mut Vec<u8>)) {
+        f(&mut self.lock())
+    }
+}
+
+impl BenchMutex for Mutex<Vec<u8>> {
+    fn new() -> Self {
+        Mutex::new(Vec::new())
+    }
+
+    fn with_lock(&self, f: impl FnOnce(&mut Vec<u8>)) {
+        f(&mut self.lock().unwrap())
+    }
+}
+
+fn bench_contention<M: BenchMutex>(bencher: &mut Bencher) {
+    bencher.iter(|| {
+        let mutex = Arc::new(M::new());
+        let barrier = Arc::new(Barrier::new(THREADS));
+
+        let handles = (0..THREADS)
+            .map(|_| {
+                let mutex = mutex.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..LOCKS_PER_THREAD {
+                        mutex.with_lock(|buf| {
+                            buf.clear();
+                            buf.extend_from_slice(black_box(MESSAGE));
+                        });
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    });
+}
+
+#[bench]
+fn bench_1_pure_spin(bencher: &mut Bencher) {
+    bench_contention::<spin::Mutex<Vec<u8>>>(bencher)
+}
+
+#[bench]
+fn bench_2_spin_then_park(bencher: &mut Bencher) {
+    bench_contention::<Mutex<Vec<u8>>>(bencher)
+}
@@ -2,13 +2,19 @@
 
 extern crate test;
 
-use std::{cell::RefCell, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    sync::Arc,
+    time::Duration,
+};
 
 use spdlog::{
-    formatter::{pattern, Formatter, FullFormatter, Pattern, PatternFormatter},
+    formatter::{
+        pattern, set_tz_offset_cache_duration, Formatter, FullFormatter, Pattern, PatternFormatter,
+    },
     prelude::*,
     sink::{Sink, WriteSink},
-    Record, StringBuf,
+    Record, RecordOwned, StringBuf,
 };
 use test::Bencher;
 
@@ -59,6 +65,66 @@ impl<F: Formatter> Sink for BenchSink<F> {
     }
 }
 
+// Formats each record as if it was logged one second after the previous one, so
+// that the cached time is outdated for every record.
+struct EverySecondSink<F> {
+    inner: BenchSink<F>,
+    records: Vec<RecordOwned>,
+    next_index: Cell<usize>,
+}
+
+impl<F> EverySecondSink<F> {
+    fn new(inner: BenchSink<F>) -> Self {
+        // Records of an hour, parsed beforehand to keep parsing out of the benchmark
+        let records = (0..60 * 60)
+            .map(|secs| {
+                let line = format!(
+                    r#"{{"timestamp":"2022-11-02T09:{:02}:{:02}.000+08:00","level":"info","payload":"payload"}}"#,
+                    secs / 60,
+                    secs % 60
+                );
+                Record::from_json(&line).unwrap()
+            })
+            .collect();
+        Self {
+            inner,
+            records,
+            next_index: Cell::new(0),
+        }
+    }
+}
+
+// Same as `BenchSink`, benchmarks are not executed in parallel.
+unsafe impl<F> Sync for EverySecondSink<F> {}
+
+impl<F: Formatter> Sink for EverySecondSink<F> {
+    fn log(&self, _record: &Record) -> spdlog::Result<()> {
+        let index = self.next_index.get();
+        self.next_index.set((index + 1) % self.records.len());
+        self.inner.log(&self.records[index].as_ref())
+    }
+
+    fn flush(&self) -> spdlog::Result<()> {
+        unimplemented!()
+    }
+
+    fn level_filter(&self) -> spdlog::LevelFilter {
+        unimplemented!()
+    }
+
+    fn set_level_filter(&self, _level_filter: spdlog::LevelFilter) {
+        unimplemented!()
+    }
+
+    fn set_formatter(&self, _formatter: Box<dyn Formatter>) {
+        unimplemented!()
+    }
+
+    fn set_error_handler(&self, _handler: Option<spdlog::ErrorHandler>) {
+        unimplemented!()
+    }
+}
+
 fn bench_formatter(bencher: &mut Bencher, formatter: impl Formatter + 'static) {
     let bench_sink = Arc::new(BenchSink::new(formatter));
     let logger = Logger::builder().sink(bench_sink).build().unwrap();
@@ -226,6 +292,27 @@ fn bench_tz_offset(bencher: &mut Bencher) {
     bench_pattern(bencher, pattern!("{tz_offset}"))
 }
 
+fn bench_every_second(bencher: &mut Bencher, tz_offset_cache_duration: Duration) {
+    let sink = Arc::new(EverySecondSink::new(BenchSink::new(PatternFormatter::new(
+        pattern!("{datetime} {tz_offset}"),
+    ))));
+    let logger = Logger::builder().sink(sink).build().unwrap();
+
+    set_tz_offset_cache_duration(tz_offset_cache_duration);
+    bencher.iter(|| info!(logger: logger, "payload"));
+    set_tz_offset_cache_duration(Duration::ZERO);
+}
+
+#[bench]
+fn bench_tz_offset_every_second(bencher: &mut Bencher) {
+    bench_every_second(bencher, Duration::ZERO)
+}
+
+#[bench]
+fn bench_tz_offset_every_second_cached(bencher: &mut Bencher) {
+    bench_every_second(bencher, Duration::from_secs(60))
+}
+
 #[bench]
 fn bench_unix_timestamp(bencher: &mut Bencher) {
     bench_pattern(bencher, pattern!("{unix_timestamp}"))
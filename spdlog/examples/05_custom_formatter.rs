@@ -93,15 +93,19 @@ fn use_pattern_formatter_by_async_sink() {
             .unwrap(),
     );
 
-    let async_sink = std::sync::Arc::new(
+    let async_sink: std::sync::Arc<spdlog::sink::AsyncPoolSink> = std::sync::Arc::new(
         spdlog::sink::AsyncPoolSink::builder()
             .sink(sss_sink)
             .build()
             .unwrap(),
     );
 
-    let logger: std::sync::Arc<spdlog::Logger> =
-        std::sync::Arc::new(spdlog::Logger::builder().sink(async_sink).build().unwrap());
+    let logger: std::sync::Arc<spdlog::Logger> = std::sync::Arc::new(
+        spdlog::Logger::builder()
+            .sink(async_sink.clone())
+            .build()
+            .unwrap(),
+    );
 
     spdlog::set_default_logger(logger);
 
@@ -119,10 +123,8 @@ fn use_pattern_formatter_by_async_sink() {
     }
 
     info!("format by `PatternFormatter`");
-    spdlog::default_logger().flush();
 
-    for _ in 1.. {
-        std::thread::sleep(std::time::Duration::from_millis(100));
-        spdlog::default_logger().flush();
-    }
+    // Records are written asynchronously, wait for them to be written before
+    // returning.
+    async_sink.flush_blocking().unwrap();
 }
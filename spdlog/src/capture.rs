@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+
+use crate::{
+    formatter::Formatter, sink::Sink, sync::*, ErrorHandler, LevelFilter, Logger, Record, Result,
+};
+
+thread_local! {
+    static CAPTURE_LOGGER: RefCell<Option<Arc<Logger>>> = const { RefCell::new(None) };
+}
+
+// Returns the logger capturing the records of the default logger on the
+// current thread, if the current thread is inside `capture`.
+#[must_use]
+pub(crate) fn capture_logger() -> Option<Arc<Logger>> {
+    CAPTURE_LOGGER.with(|logger| logger.borrow().clone())
+}
+
+/// Runs the given closure while capturing the records logged to the default
+/// logger on the current thread, and returns them as lines.
+///
+/// For the duration of the closure, [`default_logger`] returns a logger that
+/// captures every record (its level filter is [`LevelFilter::All`]) on the
+/// current thread, other threads are not affected and keep logging to the
+/// actual default logger. This is mainly useful for unit-testing code that
+/// logs, since tests usually run in parallel on multiple threads.
+///
+/// Each record is captured as a line in the form `[level] payload`, the time
+/// and the source location are left out so that the lines can be compared
+/// exactly. Records logged to other loggers explicitly (e.g. `info!(logger: app, ...)`)
+/// are not captured.
+///
+/// The previous state is restored afterward even if the closure panics, and
+/// calls can be nested, the records are then captured by the innermost call.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::prelude::*;
+///
+/// info!("not captured");
+/// let lines = spdlog::capture(|| {
+///     info!("hello");
+///     debug!("world");
+/// });
+/// assert_eq!(lines, ["[info] hello", "[debug] world"]);
+/// ```
+///
+/// [`default_logger`]: crate::default_logger
+pub fn capture<F>(f: F) -> Vec<String>
+where
+    F: FnOnce(),
+{
+    let sink = Arc::new(CaptureSink::new());
+    let logger = Logger::builder()
+        .level_filter(LevelFilter::All)
+        .sink(sink.clone())
+        .build()
+        .expect("building an unnamed logger should not fail");
+
+    {
+        let _guard = CaptureGuard::install(Arc::new(logger));
+        f();
+    }
+
+    let lines = std::mem::take(&mut *sink.lines.lock_expect());
+    lines
+}
+
+struct CaptureGuard {
+    previous: Option<Arc<Logger>>,
+}
+
+impl CaptureGuard {
+    #[must_use]
+    fn install(logger: Arc<Logger>) -> Self {
+        let previous = CAPTURE_LOGGER.with(|current| current.borrow_mut().replace(logger));
+        Self { previous }
+    }
+}
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CAPTURE_LOGGER.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+struct CaptureSink {
+    lines: Mutex<Vec<String>>,
+}
+
+impl CaptureSink {
+    #[must_use]
+    fn new() -> Self {
+        Self {
+            lines: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl Sink for CaptureSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        let line = format!("[{}] {}", record.level(), record.payload());
+        self.lines.lock_expect().push(line);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn level_filter(&self) -> LevelFilter {
+        LevelFilter::All
+    }
+
+    fn set_level_filter(&self, _level_filter: LevelFilter) {}
+
+    fn set_formatter(&self, _formatter: Box<dyn Formatter>) {}
+
+    fn set_error_handler(&self, _handler: Option<ErrorHandler>) {}
+}
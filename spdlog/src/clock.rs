@@ -0,0 +1,84 @@
+//! Provides clocks used by time-based sinks.
+
+use std::time::{Duration, SystemTime};
+
+use crate::sync::*;
+
+/// A trait for clocks, providing the current time to time-based sinks.
+///
+/// Sinks such as [`RotatingFileSink`] use a clock instead of calling
+/// [`SystemTime::now`] directly, so that their time-based behavior can be
+/// driven deterministically, e.g. in tests by a [`MockClock`].
+///
+/// [`RotatingFileSink`]: crate::sink::RotatingFileSink
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    #[must_use]
+    fn now(&self) -> SystemTime;
+}
+
+/// A clock returning the real system time.
+///
+/// It is the default clock for sinks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock returning a manually controlled time.
+///
+/// The time does not advance by itself, it only changes when
+/// [`MockClock::set`] or [`MockClock::advance`] is called.
+///
+/// # Examples
+///
+/// ```
+/// use std::{
+///     sync::Arc,
+///     time::{Duration, SystemTime},
+/// };
+///
+/// use spdlog::{Clock, MockClock};
+///
+/// let clock = Arc::new(MockClock::new(SystemTime::UNIX_EPOCH));
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+/// ```
+#[derive(Debug)]
+pub struct MockClock {
+    time: SpinMutex<SystemTime>,
+}
+
+impl MockClock {
+    /// Constructs a `MockClock` starting at the given time.
+    #[must_use]
+    pub fn new(time: SystemTime) -> Self {
+        Self {
+            time: SpinMutex::new(time),
+        }
+    }
+
+    /// Sets the current time.
+    pub fn set(&self, time: SystemTime) {
+        *self.time.lock() = time;
+    }
+
+    /// Advances the current time by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        *self.time.lock() += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.time.lock()
+    }
+}
+
+pub(crate) fn default_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
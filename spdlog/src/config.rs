@@ -0,0 +1,109 @@
+use crate::{formatter::Formatter, sink::Sink, sync::*, Level, LevelFilter};
+
+/// A structured description of the default logger, consumed by [`init_with`].
+///
+/// It's a plain Rust struct describing the sinks, their formatters and the
+/// levels, so that the whole setup can be written in one place instead of
+/// wiring up builders one by one.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use spdlog::{
+///     formatter::JsonFormatter,
+///     prelude::*,
+///     sink::{StdStream, StdStreamSink},
+///     Config, SinkConfig,
+/// };
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let stderr = Arc::new(StdStreamSink::builder().std_stream(StdStream::Stderr).build()?);
+///
+/// spdlog::init_with(Config {
+///     level_filter: LevelFilter::All,
+///     sinks: vec![SinkConfig {
+///         formatter: Some(Box::new(JsonFormatter::new())),
+///         ..SinkConfig::new(stderr)
+///     }],
+///     ..Config::default()
+/// })?;
+/// # Ok(()) }
+/// ```
+///
+/// [`init_with`]: crate::init_with
+pub struct Config {
+    /// The name of the logger.
+    ///
+    /// Defaults to `None`.
+    pub name: Option<String>,
+
+    /// The level filter of the logger.
+    ///
+    /// Defaults to `LevelFilter::MoreSevereEqual(Level::Info)`. Like the
+    /// default logger, it's overridden by the environment variable level
+    /// filter if one was initialized, see [`init_env_level`].
+    ///
+    /// [`init_env_level`]: crate::init_env_level
+    pub level_filter: LevelFilter,
+
+    /// The flush level filter of the logger.
+    ///
+    /// Defaults to [`LevelFilter::Off`].
+    pub flush_level_filter: LevelFilter,
+
+    /// The sinks of the logger.
+    ///
+    /// Defaults to empty.
+    pub sinks: Vec<SinkConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            name: None,
+            level_filter: LevelFilter::MoreSevereEqual(Level::Info),
+            flush_level_filter: LevelFilter::Off,
+            sinks: vec![],
+        }
+    }
+}
+
+/// A structured description of a sink in [`Config`].
+pub struct SinkConfig {
+    /// The sink.
+    pub sink: Arc<dyn Sink>,
+
+    /// The formatter set to the sink.
+    ///
+    /// Defaults to `None`, which keeps the formatter of the sink.
+    pub formatter: Option<Box<dyn Formatter>>,
+
+    /// The level filter set to the sink.
+    ///
+    /// Defaults to `None`, which keeps the level filter of the sink.
+    pub level_filter: Option<LevelFilter>,
+}
+
+impl SinkConfig {
+    /// Constructs a `SinkConfig` keeping the formatter and the level filter of
+    /// the given sink.
+    #[must_use]
+    pub fn new(sink: Arc<dyn Sink>) -> Self {
+        Self {
+            sink,
+            formatter: None,
+            level_filter: None,
+        }
+    }
+
+    pub(crate) fn apply(self) {
+        if let Some(formatter) = self.formatter {
+            self.sink.set_formatter(formatter);
+        }
+        if let Some(level_filter) = self.level_filter {
+            self.sink.set_level_filter(level_filter);
+        }
+    }
+}
@@ -6,6 +6,7 @@ use std::{
 };
 
 use atomic::Atomic;
+use cfg_if::cfg_if;
 use static_assertions::const_assert;
 use thiserror::Error;
 
@@ -73,6 +74,29 @@ pub enum Error {
     #[error("remove file error: {0}")]
     RemoveFile(io::Error),
 
+    /// The variant returned by [`Sink`]s when an error occurs in changing the
+    /// size of a file.
+    ///
+    /// [`Sink`]: crate::sink::Sink
+    #[error("resize file error: {0}")]
+    ResizeFile(io::Error),
+
+    /// The variant returned when an error occurs in reading the entries of a
+    /// directory.
+    #[error("read directory error: {0}")]
+    ReadDirectory(io::Error),
+
+    /// The variant returned when an error occurs in binding a network socket.
+    #[error("bind socket error: {0}")]
+    BindSocket(io::Error),
+
+    /// The variant returned by [`Sink`]s when an error occurs in connecting a
+    /// network socket to a remote address.
+    ///
+    /// [`Sink`]: crate::sink::Sink
+    #[error("connect socket error: {0}")]
+    ConnectSocket(io::Error),
+
     /// The variant returned by [`from_str`] when the string doesn't match any
     /// of the log levels.
     ///
@@ -80,10 +104,33 @@ pub enum Error {
     #[error("attempted to convert a string that doesn't match an existing log level: {0}")]
     ParseLevel(String),
 
+    /// The variant returned by [`Record::from_json`] when the line is not a
+    /// record written by [`JsonFormatter`].
+    ///
+    /// [`Record::from_json`]: crate::Record::from_json
+    /// [`JsonFormatter`]: crate::formatter::JsonFormatter
+    #[error("parse record error: {0}")]
+    ParseRecord(String),
+
     /// The variant returned if an invalid argument was passed in.
     #[error("invalid argument {0}")]
     InvalidArgument(#[from] InvalidArgumentError),
 
+    /// The variant returned by [`Sink`]s that have been shut down, when a
+    /// record is logged to them afterward.
+    ///
+    /// [`Sink`]: crate::sink::Sink
+    #[error("the sink has been shut down")]
+    SinkClosed,
+
+    /// The variant returned by [`AsyncPoolSink::shutdown`] when the timeout
+    /// elapses before the pending operations have been processed.
+    ///
+    /// [`AsyncPoolSink::shutdown`]: crate::sink::AsyncPoolSink::shutdown
+    #[cfg(feature = "multi-thread")]
+    #[error("timed out shutting down the sink with operations still pending")]
+    ShutdownTimeout,
+
     /// The variant returned by [`Sink`]s when an error occurs in sending to the
     /// channel.
     ///
@@ -117,6 +164,27 @@ pub enum InvalidArgumentError {
     /// Invalid thread pool capacity.
     #[error("'thread pool capacity': {0}")]
     ThreadPoolCapacity(String),
+
+    /// Invalid [`MmapFileSink`] capacity.
+    ///
+    /// [`MmapFileSink`]: crate::sink::MmapFileSink
+    #[error("'mmap capacity': {0}")]
+    MmapCapacity(String),
+
+    /// Invalid [`Backoff`] parameters.
+    ///
+    /// See the documentation of [`BackoffBuilder`] for the input requirements.
+    ///
+    /// [`Backoff`]: crate::sink::Backoff
+    /// [`BackoffBuilder`]: crate::sink::BackoffBuilder
+    #[error("'backoff': {0}")]
+    Backoff(String),
+
+    /// Invalid format string for [`Strftime`] pattern.
+    ///
+    /// [`Strftime`]: crate::formatter::__pattern::Strftime
+    #[error("'strftime format': '{0}' is not a valid format string")]
+    StrftimeFormat(String),
 }
 
 /// This error indicates that an invalid logger name was set.
@@ -153,10 +221,12 @@ impl Display for SetLoggerNameError {
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum SendToChannelError {
-    /// The channel is full.
+    /// The channel is full, or the limit set by
+    /// [`AsyncPoolSinkBuilder::max_in_flight_bytes`] is reached.
     ///
     /// The variant returned only when [`OverflowPolicy::DropIncoming`] is used.
     ///
+    /// [`AsyncPoolSinkBuilder::max_in_flight_bytes`]: crate::sink::AsyncPoolSinkBuilder::max_in_flight_bytes
     /// [`OverflowPolicy::DropIncoming`]: crate::sink::async_sink::OverflowPolicy::DropIncoming
     #[error("the channel is full")]
     Full,
@@ -180,6 +250,92 @@ pub enum SendToChannelErrorDropped {
     Flush,
 }
 
+impl Error {
+    /// Returns `true` if the error was caused by the storage device running
+    /// out of space.
+    ///
+    /// Running out of disk space does not have a dedicated variant, it is
+    /// reported in the variant of the operation that failed (e.g.
+    /// [`Error::WriteRecord`] or [`Error::FlushBuffer`]). This method allows
+    /// wrapper sinks or error handlers to detect it regardless of the
+    /// operation, for example to switch to a fallback sink.
+    #[must_use]
+    pub fn is_disk_full(&self) -> bool {
+        match self {
+            Self::WriteRecord(err)
+            | Self::FlushBuffer(err)
+            | Self::CreateDirectory(err)
+            | Self::OpenFile(err)
+            | Self::RenameFile(err)
+            | Self::ResizeFile(err) => is_disk_full_io_error(err),
+            _ => false,
+        }
+    }
+}
+
+impl Error {
+    /// Returns `true` if the error is likely transient, so that retrying the
+    /// failed operation may succeed.
+    ///
+    /// Errors of writing, flushing and connecting are considered retryable if
+    /// the underlying I/O error is caused by an interruption, a timeout or a
+    /// dropped connection. A full channel is retryable as well. Other errors,
+    /// e.g. invalid arguments, formatting errors or the storage device running
+    /// out of space, are not.
+    ///
+    /// It is used by [`RetrySink`] to decide whether to retry.
+    ///
+    /// [`RetrySink`]: crate::sink::RetrySink
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::WriteRecord(err) | Self::FlushBuffer(err) | Self::ConnectSocket(err) => {
+                is_retryable_io_error(err)
+            }
+            #[cfg(feature = "multi-thread")]
+            Self::SendToChannel(SendToChannelError::Full, _) => true,
+            _ => false,
+        }
+    }
+}
+
+#[must_use]
+fn is_retryable_io_error(err: &io::Error) -> bool {
+    use io::ErrorKind;
+
+    matches!(
+        err.kind(),
+        ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::TimedOut
+            | ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::BrokenPipe
+    )
+}
+
+#[must_use]
+fn is_disk_full_io_error(err: &io::Error) -> bool {
+    // `io::ErrorKind::StorageFull` is not stable on our MSRV, so we check the
+    // raw OS error code instead.
+    cfg_if! {
+        if #[cfg(unix)] {
+            err.raw_os_error() == Some(libc::ENOSPC)
+        } else if #[cfg(windows)] {
+            use windows_sys::Win32::Foundation::{ERROR_DISK_FULL, ERROR_HANDLE_DISK_FULL};
+
+            [ERROR_DISK_FULL, ERROR_HANDLE_DISK_FULL]
+                .iter()
+                .any(|&code| err.raw_os_error() == Some(code as i32))
+        } else {
+            let _ = err;
+            false
+        }
+    }
+}
+
 #[cfg(feature = "multi-thread")]
 impl Error {
     #[must_use]
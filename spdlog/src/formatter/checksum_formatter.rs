@@ -0,0 +1,259 @@
+//! Provides a formatter that appends a checksum to each line of an inner
+//! formatter.
+
+use std::{fmt::Write, path::Path};
+
+use crate::{
+    formatter::{FmtExtraInfo, Formatter},
+    Error, Record, Result, StringBuf,
+};
+
+/// A formatter that appends a checksum of each formatted line, for tamper
+/// detection of audit logs.
+///
+/// The record is formatted by an inner formatter, then the checksum of its
+/// output (excluding the trailing EOL, if any) is appended before the EOL,
+/// separated by a space:
+///
+/// - [`ChecksumFormatter::crc32`] appends `crc32=` followed by the CRC-32
+///   (IEEE) of the line in 8 lowercase hexadecimal digits. It detects
+///   accidental corruption, but not deliberate tampering, since anyone can
+///   recompute it.
+/// - [`ChecksumFormatter::hmac_sha256`] appends `hmac=` followed by the
+///   HMAC-SHA256 of the line with a secret key, truncated to the first 16
+///   bytes, in 32 lowercase hexadecimal digits. Only holders of the key can
+///   produce a valid checksum. It requires crate feature `hmac-sha256`.
+///
+/// Downstream tools can verify a line by splitting it at the last space, and
+/// computing the checksum over the part before it.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::formatter::{ChecksumFormatter, Formatter, FullFormatter};
+///
+/// let formatter = ChecksumFormatter::crc32(Box::new(FullFormatter::new()));
+///
+/// for sink in spdlog::default_logger().sinks() {
+///     sink.set_formatter(formatter.clone_box());
+/// }
+/// ```
+///
+/// Log messages formatted by it look like:
+///
+/// <pre>
+/// [2022-11-02 09:23:12.263] [<font color="#11D116">info</font>] hello, world! crc32=5c1a8d3e
+/// </pre>
+pub struct ChecksumFormatter {
+    algorithm: Algorithm,
+    inner: Box<dyn Formatter>,
+}
+
+#[derive(Clone)]
+enum Algorithm {
+    Crc32,
+    // Keyed already, it's cloned for each line.
+    #[cfg(feature = "hmac-sha256")]
+    HmacSha256(hmac::Hmac<sha2::Sha256>),
+}
+
+#[cfg(feature = "hmac-sha256")]
+const HMAC_TRUNCATED_LEN: usize = 16;
+
+impl ChecksumFormatter {
+    /// Constructs a `ChecksumFormatter` appending the CRC-32 of each line.
+    #[must_use]
+    pub fn crc32(inner: Box<dyn Formatter>) -> Self {
+        Self {
+            algorithm: Algorithm::Crc32,
+            inner,
+        }
+    }
+
+    /// Constructs a `ChecksumFormatter` appending the truncated HMAC-SHA256 of
+    /// each line with the given key.
+    ///
+    /// This function requires crate feature `hmac-sha256`.
+    #[cfg(feature = "hmac-sha256")]
+    #[must_use]
+    pub fn hmac_sha256(key: impl AsRef<[u8]>, inner: Box<dyn Formatter>) -> Self {
+        use hmac::Mac;
+
+        Self {
+            algorithm: Algorithm::HmacSha256(
+                hmac::Hmac::new_from_slice(key.as_ref()).expect("HMAC accepts keys of any length"),
+            ),
+            inner,
+        }
+    }
+
+    fn write_checksum(&self, line: &str, dest: &mut StringBuf) -> Result<()> {
+        match &self.algorithm {
+            Algorithm::Crc32 => {
+                write!(dest, " crc32={:08x}", crc32(line.as_bytes())).map_err(Error::FormatRecord)
+            }
+            #[cfg(feature = "hmac-sha256")]
+            Algorithm::HmacSha256(mac) => {
+                use hmac::Mac;
+
+                let mut mac = mac.clone();
+                mac.update(line.as_bytes());
+                let digest = mac.finalize().into_bytes();
+
+                dest.push_str(" hmac=");
+                for byte in &digest[..HMAC_TRUNCATED_LEN] {
+                    write!(dest, "{:02x}", byte).map_err(Error::FormatRecord)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Formatter for ChecksumFormatter {
+    fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+        self.format_for_target(record, dest, None)
+    }
+
+    fn format_for_target(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        target_path: Option<&Path>,
+    ) -> Result<FmtExtraInfo> {
+        let offset = dest.len();
+
+        // See `PrependFormatter`, the style range of the inner formatter is
+        // relative to the start of its own output.
+        let mut inner_buf = StringBuf::new();
+        let extra_info = self
+            .inner
+            .format_for_target(record, &mut inner_buf, target_path)?;
+
+        let eol_len = if inner_buf.ends_with("\r\n") {
+            2
+        } else if inner_buf.ends_with('\n') {
+            1
+        } else {
+            0
+        };
+        let (line, eol) = inner_buf.split_at(inner_buf.len() - eol_len);
+
+        dest.push_str(line);
+        self.write_checksum(line, dest)?;
+        dest.push_str(eol);
+
+        Ok(match extra_info.style_range() {
+            Some(range) => FmtExtraInfo::builder()
+                .style_range(range.start + offset..range.end + offset)
+                .build(),
+            None => extra_info,
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Formatter> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for ChecksumFormatter {
+    fn clone(&self) -> Self {
+        Self {
+            algorithm: self.algorithm.clone(),
+            inner: self.inner.clone_box(),
+        }
+    }
+}
+
+// CRC-32 (IEEE 802.3), the same as used by zlib and gzip.
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+#[must_use]
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "hmac-sha256")]
+    use crate::formatter::{__pattern, PatternFormatter};
+    use crate::{test_utils::*, Level};
+
+    #[test]
+    fn crc32() {
+        let formatter = ChecksumFormatter::crc32(Box::new(NoModFormatter::new()));
+        let mut dest = StringBuf::new();
+        formatter
+            .format(&Record::new(Level::Info, "123456789"), &mut dest)
+            .unwrap();
+        assert_eq!(dest.as_str(), "123456789 crc32=cbf43926");
+    }
+
+    #[cfg(feature = "hmac-sha256")]
+    #[test]
+    fn hmac_sha256() {
+        // RFC 4231, test case 2
+        let formatter = ChecksumFormatter::hmac_sha256(
+            "Jefe",
+            Box::new(PatternFormatter::new((__pattern::Payload, __pattern::Eol))),
+        );
+        let mut dest = StringBuf::new();
+        formatter
+            .format(
+                &Record::new(Level::Info, "what do ya want for nothing?"),
+                &mut dest,
+            )
+            .unwrap();
+        assert_eq!(
+            dest.as_str(),
+            format!(
+                "what do ya want for nothing? hmac=5bdcc146bf60754e6a042426089575c7{}",
+                crate::EOL
+            )
+        );
+
+        // RFC 4231, test case 6, with a key longer than the block size
+        let formatter = ChecksumFormatter::hmac_sha256(
+            [0xaa_u8; 131],
+            Box::new(PatternFormatter::new((__pattern::Payload, __pattern::Eol))),
+        );
+        let mut dest = StringBuf::new();
+        formatter
+            .format(
+                &Record::new(
+                    Level::Info,
+                    "Test Using Larger Than Block-Size Key - Hash Key First",
+                ),
+                &mut dest,
+            )
+            .unwrap();
+        assert!(dest.ends_with(&format!(
+            " hmac=60e431591ee0b67f0d8a26aacbf5b77f{}",
+            crate::EOL
+        )));
+    }
+}
@@ -0,0 +1,192 @@
+//! Provides a formatter that selects a pattern per record.
+
+use std::{fmt, path::Path};
+
+use crate::{
+    formatter::{FmtExtraInfo, FmtExtraInfoBuilder, Formatter, Pattern, PatternContext},
+    sync::*,
+    Error, Record, Result, StringBuf,
+};
+
+type Selector = Arc<dyn Fn(&Record) -> usize + Send + Sync>;
+
+/// A formatter that picks one of several patterns for each record, using a
+/// user-provided selector.
+///
+/// The selector receives the record and returns the index of the pattern to
+/// use, in the order the patterns were added. This generalizes
+/// [`LevelFormatter`] to arbitrary conditions, e.g. using a different template
+/// for each logger.
+///
+/// If the selector returns an index that is out of range, formatting fails
+/// with [`Error::FormatRecord`].
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{
+///     formatter::{pattern, DynamicPatternFormatter, Formatter},
+///     prelude::*,
+/// };
+///
+/// let formatter = DynamicPatternFormatter::builder()
+///     .pattern(pattern!("[{level}] {payload}{eol}"))
+///     .pattern(pattern!("[{level}] [{logger}] {payload}{eol}"))
+///     .selector(|record| match record.logger_name() {
+///         Some(_) => 1,
+///         None => 0,
+///     })
+///     .build();
+///
+/// for sink in spdlog::default_logger().sinks() {
+///     sink.set_formatter(formatter.clone_box());
+/// }
+/// ```
+///
+/// [`LevelFormatter`]: crate::formatter::LevelFormatter
+#[derive(Clone)]
+pub struct DynamicPatternFormatter {
+    patterns: Vec<Arc<dyn Pattern>>,
+    selector: Selector,
+}
+
+impl DynamicPatternFormatter {
+    /// Constructs a [`DynamicPatternFormatterBuilder`].
+    #[must_use]
+    pub fn builder() -> DynamicPatternFormatterBuilder {
+        DynamicPatternFormatterBuilder::new()
+    }
+}
+
+impl Formatter for DynamicPatternFormatter {
+    fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+        self.format_for_target(record, dest, None)
+    }
+
+    fn format_for_target(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        target_path: Option<&Path>,
+    ) -> Result<FmtExtraInfo> {
+        let pattern = self
+            .patterns
+            .get((self.selector)(record))
+            .ok_or(Error::FormatRecord(fmt::Error))?;
+
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default())
+            .with_target_path(target_path)
+            .with_sink_count(record.sink_count());
+        pattern.format(record, dest, &mut ctx)?;
+        Ok(ctx.fmt_info_builder.build())
+    }
+
+    fn clone_box(&self) -> Box<dyn Formatter> {
+        Box::new(self.clone())
+    }
+}
+
+/// The builder of [`DynamicPatternFormatter`].
+pub struct DynamicPatternFormatterBuilder {
+    patterns: Vec<Arc<dyn Pattern>>,
+    selector: Option<Selector>,
+}
+
+impl DynamicPatternFormatterBuilder {
+    /// Constructs a `DynamicPatternFormatterBuilder`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            patterns: vec![],
+            selector: None,
+        }
+    }
+
+    /// Adds a pattern. Its index is the number of patterns added before it.
+    #[must_use]
+    pub fn pattern<P>(mut self, pattern: P) -> Self
+    where
+        P: Pattern + 'static,
+    {
+        self.patterns.push(Arc::new(pattern));
+        self
+    }
+
+    /// Specifies the function selecting the index of the pattern for each
+    /// record.
+    ///
+    /// This parameter is **optional**, and defaults to a function always
+    /// selecting the first pattern.
+    #[must_use]
+    pub fn selector<F>(mut self, selector: F) -> Self
+    where
+        F: Fn(&Record) -> usize + Send + Sync + 'static,
+    {
+        self.selector = Some(Arc::new(selector));
+        self
+    }
+
+    /// Builds a [`DynamicPatternFormatter`].
+    #[must_use]
+    pub fn build(self) -> DynamicPatternFormatter {
+        DynamicPatternFormatter {
+            patterns: self.patterns,
+            selector: self.selector.unwrap_or_else(|| Arc::new(|_| 0)),
+        }
+    }
+}
+
+impl Default for DynamicPatternFormatterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formatter::__pattern, Level};
+
+    #[test]
+    fn select_by_logger_name() {
+        let formatter = DynamicPatternFormatter::builder()
+            .pattern(("default: ", __pattern::Payload))
+            .pattern(("net: ", __pattern::Payload))
+            .selector(|record| match record.logger_name() {
+                Some("net") => 1,
+                _ => 0,
+            })
+            .build();
+        let mut buf = StringBuf::new();
+
+        let record = Record::builder(Level::Info, "hello").build();
+        formatter.format_into(&record, &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "default: hello");
+
+        let record = Record::builder(Level::Info, "hello")
+            .logger_name("net")
+            .build();
+        formatter.format_into(&record, &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "net: hello");
+
+        let record = Record::builder(Level::Info, "hello")
+            .logger_name("app")
+            .build();
+        formatter.format_into(&record, &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "default: hello");
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        let formatter = DynamicPatternFormatter::builder()
+            .pattern(__pattern::Payload)
+            .selector(|_| 1)
+            .build();
+
+        let record = Record::new(Level::Info, "hello");
+        assert!(matches!(
+            formatter.format(&record, &mut StringBuf::new()),
+            Err(Error::FormatRecord(_))
+        ));
+    }
+}
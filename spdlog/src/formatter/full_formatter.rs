@@ -1,12 +1,16 @@
 //! Provides a full info formatter.
 
-use std::fmt::{self, Write};
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+};
 
 use cfg_if::cfg_if;
 
 use crate::{
-    formatter::{FmtExtraInfo, Formatter, LOCAL_TIME_CACHER},
-    Error, Record, StringBuf, EOL,
+    formatter::{with_local_time, FmtExtraInfo, Formatter},
+    source_location::source_location_or_placeholder,
+    Error, Level, LevelFilter, Record, StringBuf, EOL,
 };
 
 #[rustfmt::skip]
@@ -33,21 +37,116 @@ use crate::{
 ///    <pre>
 ///    [2022-11-02 09:23:12.263] [<font color="#11D116">info</font>] [mod::path, src/main.rs:4] hello, world!
 ///    </pre>
+///
+/// If the payload is empty, the space separating it from the preceding fields
+/// is omitted, so no trailing space is written. The same applies to the
+/// trailing whitespace of the level column separator.
+///
+/// An extra marker can be appended to high-severity records only, see
+/// [`FullFormatter::with_error_marker`].
+///
+/// The bracket and space joins between fields can be replaced with a custom
+/// delimiter, see [`FullFormatter::with_field_separator`].
+///
+/// The level can be rendered in a fixed-width column, see
+/// [`FullFormatter::with_level_column`].
 #[derive(Clone)]
 pub struct FullFormatter {
     with_eol: bool,
+    error_marker: Option<Cow<'static, str>>,
+    field_separator: Option<Cow<'static, str>>,
+    level_column: Option<(usize, Cow<'static, str>)>,
 }
 
 impl FullFormatter {
     /// Constructs a `FullFormatter`.
     #[must_use]
     pub fn new() -> FullFormatter {
-        FullFormatter { with_eol: true }
+        FullFormatter {
+            with_eol: true,
+            error_marker: None,
+            field_separator: None,
+            level_column: None,
+        }
     }
 
     #[must_use]
     pub(crate) fn without_eol() -> Self {
-        Self { with_eol: false }
+        Self {
+            with_eol: false,
+            error_marker: None,
+            field_separator: None,
+            level_column: None,
+        }
+    }
+
+    /// Appends the given marker after the payload of records whose level is
+    /// [`Level::Error`] or more severe.
+    ///
+    /// Records of other levels are formatted as usual. The marker is
+    /// separated from the payload by a space.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spdlog::formatter::FullFormatter;
+    ///
+    /// let formatter = FullFormatter::new().with_error_marker("<<< ALERT");
+    /// // [2022-11-02 09:23:12.263] [error] disk unavailable <<< ALERT
+    /// ```
+    #[must_use]
+    pub fn with_error_marker(mut self, marker: impl Into<Cow<'static, str>>) -> Self {
+        self.error_marker = Some(marker.into());
+        self
+    }
+
+    /// Separates fields with the given delimiter instead of the default
+    /// brackets and spaces.
+    ///
+    /// The order of fields is kept, and no brackets are written around them.
+    /// This is useful for tools that split log lines on a specific delimiter,
+    /// such as a tab or a pipe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spdlog::formatter::FullFormatter;
+    ///
+    /// let formatter = FullFormatter::new().with_field_separator("\t");
+    /// // 2022-11-02 09:23:12.263\tinfo\thello, world!
+    /// ```
+    #[must_use]
+    pub fn with_field_separator(mut self, separator: impl Into<Cow<'static, str>>) -> Self {
+        self.field_separator = Some(separator.into());
+        self
+    }
+
+    /// Renders the level right-aligned in a column of the given width (in
+    /// characters), followed by the given separator, instead of enclosing it
+    /// in brackets or field separators.
+    ///
+    /// The fields following the level start right after the separator, so
+    /// they line up across records. Levels longer than the width are written
+    /// in full. The style range only covers the level text, not the padding
+    /// or the separator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spdlog::formatter::FullFormatter;
+    ///
+    /// let formatter = FullFormatter::new().with_level_column(8, " | ");
+    /// // [2022-11-02 09:23:12.263]     info | hello, world!
+    /// // [2022-11-02 09:23:12.263] critical | something went wrong
+    /// ```
+    #[must_use]
+    pub fn with_level_column(
+        mut self,
+        width: usize,
+        separator: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.level_column = Some((width, separator.into()));
+        self
     }
 
     fn format_impl(
@@ -61,29 +160,55 @@ impl FullFormatter {
             }
         }
 
-        {
-            let mut local_time_cacher = LOCAL_TIME_CACHER.lock();
-            let time = local_time_cacher.get(record.time());
-            dest.push_str("[");
+        // (opening, between fields, before payload, closing without payload)
+        let (open, between, before_payload, close) = match &self.field_separator {
+            Some(separator) => ("", separator.as_ref(), separator.as_ref(), ""),
+            None => ("[", "] [", "] ", "]"),
+        };
+
+        with_local_time(record.time(), |time| {
+            dest.push_str(open);
             dest.push_str(&time.full_second_str());
             dest.push_str(".");
-            write!(dest, "{:03}", time.millisecond())?;
-            dest.push_str("] [");
-        }
+            write!(dest, "{:03}", time.millisecond())
+        })?;
 
         if let Some(logger_name) = record.logger_name() {
+            dest.push_str(between);
             dest.push_str(logger_name);
-            dest.push_str("] [");
         }
 
-        let style_range_begin = dest.len();
-
-        dest.push_str(record.level().as_str());
-
-        let style_range_end = dest.len();
+        let level = record.level().as_str();
+        // The separator of the level column, written once the next field is known,
+        // since its trailing whitespace is trimmed if nothing follows it.
+        let mut level_separator = None;
+        let (style_range_begin, style_range_end) = match &self.level_column {
+            None => {
+                dest.push_str(between);
+                let style_range_begin = dest.len();
+                dest.push_str(level);
+                (style_range_begin, dest.len())
+            }
+            Some((width, separator)) => {
+                dest.push_str(before_payload);
+                for _ in level.len()..*width {
+                    dest.push_str(" ");
+                }
+                let style_range_begin = dest.len();
+                dest.push_str(level);
+                level_separator = Some(separator.as_ref());
+                (style_range_begin, dest.len())
+            }
+        };
 
-        if let Some(srcloc) = record.source_location() {
-            dest.push_str("] [");
+        if let Some(srcloc) = source_location_or_placeholder(record.source_location()) {
+            match level_separator.take() {
+                Some(separator) => {
+                    dest.push_str(separator);
+                    dest.push_str(open);
+                }
+                None => dest.push_str(between),
+            }
             dest.push_str(srcloc.module_path());
             dest.push_str(", ");
             dest.push_str(srcloc.file());
@@ -91,8 +216,28 @@ impl FullFormatter {
             write!(dest, "{}", srcloc.line())?;
         }
 
-        dest.push_str("] ");
-        dest.push_str(record.payload());
+        // Omit the separator for empty payloads, so that no trailing space or
+        // delimiter is left before the EOL.
+        let payload = record.payload();
+        match level_separator {
+            Some(separator) if payload.is_empty() => dest.push_str(separator.trim_end()),
+            Some(separator) => {
+                dest.push_str(separator);
+                dest.push_str(payload);
+            }
+            None if payload.is_empty() => dest.push_str(close),
+            None => {
+                dest.push_str(before_payload);
+                dest.push_str(payload);
+            }
+        }
+
+        if let Some(marker) = &self.error_marker {
+            if LevelFilter::MoreSevereEqual(Level::Error).compare(record.level()) {
+                dest.push_str(" ");
+                dest.push_str(marker);
+            }
+        }
 
         if self.with_eol {
             dest.push_str(EOL);
@@ -144,4 +289,146 @@ mod tests {
         );
         assert_eq!(Some(27..31), extra_info.style_range());
     }
+
+    #[test]
+    fn format_empty_payload() {
+        let record = Record::new(Level::Info, "");
+        let mut buf = StringBuf::new();
+        FullFormatter::new().format(&record, &mut buf).unwrap();
+
+        let local_time: DateTime<Local> = record.time().into();
+        assert_eq!(
+            format!(
+                "[{}] [info]{}",
+                local_time.format("%Y-%m-%d %H:%M:%S.%3f"),
+                EOL
+            ),
+            buf
+        );
+    }
+
+    #[test]
+    fn format_error_marker() {
+        let formatter = FullFormatter::new().with_error_marker("[!]");
+        let mut buf = StringBuf::new();
+
+        formatter
+            .format(&Record::new(Level::Error, "failed"), &mut buf)
+            .unwrap();
+        assert!(buf
+            .as_str()
+            .ends_with(&format!("[error] failed [!]{}", EOL)));
+
+        buf.clear();
+        formatter
+            .format(&Record::new(Level::Critical, "failed"), &mut buf)
+            .unwrap();
+        assert!(buf
+            .as_str()
+            .ends_with(&format!("[critical] failed [!]{}", EOL)));
+
+        buf.clear();
+        formatter
+            .format(&Record::new(Level::Info, "ok"), &mut buf)
+            .unwrap();
+        assert!(buf.as_str().ends_with(&format!("[info] ok{}", EOL)));
+        assert!(!buf.as_str().contains("[!]"));
+    }
+
+    #[test]
+    fn format_field_separator() {
+        let mut record = Record::new(Level::Warn, "test log content");
+        record.set_logger_name("logger-name");
+        let mut buf = StringBuf::new();
+        let extra_info = FullFormatter::new()
+            .with_field_separator("|")
+            .format(&record, &mut buf)
+            .unwrap();
+
+        let local_time: DateTime<Local> = record.time().into();
+        assert_eq!(
+            format!(
+                "{}|logger-name|warn|test log content{}",
+                local_time.format("%Y-%m-%d %H:%M:%S.%3f"),
+                EOL
+            ),
+            buf
+        );
+        assert_eq!(Some(36..40), extra_info.style_range());
+        assert!(!buf.as_str().contains(['[', ']']));
+    }
+
+    #[test]
+    fn format_level_column() {
+        let formatter = FullFormatter::new().with_level_column(5, "| ");
+        let mut buf = StringBuf::new();
+
+        let record = Record::new(Level::Info, "test log content");
+        let extra_info = formatter.format(&record, &mut buf).unwrap();
+        let local_time: DateTime<Local> = record.time().into();
+        assert_eq!(
+            format!(
+                "[{}]  info| test log content{}",
+                local_time.format("%Y-%m-%d %H:%M:%S.%3f"),
+                EOL
+            ),
+            buf
+        );
+        // The style range excludes the padding and the separator.
+        assert_eq!(Some(27..31), extra_info.style_range());
+        assert_eq!(&buf.as_str()[extra_info.style_range().unwrap()], "info");
+
+        buf.clear();
+        let mut record = Record::new(Level::Error, "test log content");
+        record.set_logger_name("logger-name");
+        let extra_info = formatter.format(&record, &mut buf).unwrap();
+        let local_time: DateTime<Local> = record.time().into();
+        assert_eq!(
+            format!(
+                "[{}] [logger-name] error| test log content{}",
+                local_time.format("%Y-%m-%d %H:%M:%S.%3f"),
+                EOL
+            ),
+            buf
+        );
+        assert_eq!(&buf.as_str()[extra_info.style_range().unwrap()], "error");
+
+        buf.clear();
+        let record = Record::new(Level::Warn, "test log content");
+        let extra_info = FullFormatter::new()
+            .with_field_separator("\t")
+            .with_level_column(5, "|")
+            .format(&record, &mut buf)
+            .unwrap();
+        let local_time: DateTime<Local> = record.time().into();
+        assert_eq!(
+            format!(
+                "{}\t warn|test log content{}",
+                local_time.format("%Y-%m-%d %H:%M:%S.%3f"),
+                EOL
+            ),
+            buf
+        );
+        assert_eq!(Some(25..29), extra_info.style_range());
+    }
+
+    #[test]
+    fn format_level_column_empty_payload() {
+        let record = Record::new(Level::Info, "");
+        let mut buf = StringBuf::new();
+        FullFormatter::new()
+            .with_level_column(4, " | ")
+            .format(&record, &mut buf)
+            .unwrap();
+
+        let local_time: DateTime<Local> = record.time().into();
+        assert_eq!(
+            format!(
+                "[{}] info |{}",
+                local_time.format("%Y-%m-%d %H:%M:%S.%3f"),
+                EOL
+            ),
+            buf
+        );
+    }
 }
@@ -1,22 +1,41 @@
 use std::fmt::Write;
 
-///
+/// A formatter writing the time in ISO 8601, the level, the payload, the source
+/// location and the thread ID of each record.
 #[derive(Clone)]
 pub struct CommlibFormatter {
     with_eol: bool,
+    hex_tid: bool,
 }
 
 impl CommlibFormatter {
     /// Constructs a `CommlibFormatter`.
     #[must_use]
     pub fn new() -> CommlibFormatter {
-        CommlibFormatter { with_eol: true }
+        CommlibFormatter {
+            with_eol: true,
+            hex_tid: false,
+        }
     }
 
-    ///
+    /// Constructs a `CommlibFormatter` without appending an EOL to each log
+    /// message.
     #[must_use]
     pub fn without_eol() -> Self {
-        Self { with_eol: false }
+        Self {
+            with_eol: false,
+            hex_tid: false,
+        }
+    }
+
+    /// Writes the thread ID in hexadecimal (e.g. `0xc3c`) instead of decimal,
+    /// to match the thread IDs shown by debuggers.
+    ///
+    /// Thread IDs are written in decimal by default.
+    #[must_use]
+    pub fn with_hex_tid(mut self, hex_tid: bool) -> Self {
+        self.hex_tid = hex_tid;
+        self
     }
 
     fn format_impl(
@@ -31,13 +50,11 @@ impl CommlibFormatter {
         }
 
         // Datetime
-        {
-            let mut local_time_cacher = crate::formatter::LOCAL_TIME_CACHER.lock();
-            let time = local_time_cacher.get(record.time());
+        crate::formatter::with_local_time(record.time(), |time| {
             dest.push_str("[");
-            dest.push_str(&&time.full_iso_8601_str());
+            dest.push_str(&time.full_iso_8601_str());
             dest.push_str("] ");
-        }
+        });
 
         // Level
         let style_range_begin = dest.len();
@@ -61,7 +78,11 @@ impl CommlibFormatter {
 
         // Thread id
         dest.push(' ');
-        write!(dest, "{}", record.tid())?;
+        if self.hex_tid {
+            write!(dest, "{:#x}", record.tid())?;
+        } else {
+            write!(dest, "{}", record.tid())?;
+        }
 
         if self.with_eol {
             dest.push_str(crate::EOL);
@@ -122,4 +143,19 @@ mod tests {
         );
         assert_eq!(Some(38..42), extra_info.style_range());
     }
+
+    #[test]
+    fn format_hex_tid() {
+        let record = crate::Record::builder(Level::Info, "payload")
+            .tid(3132)
+            .build();
+        let format = |formatter: CommlibFormatter| {
+            let mut buf = crate::StringBuf::new();
+            formatter.format(&record, &mut buf).unwrap();
+            String::from(buf.as_str())
+        };
+
+        assert!(format(CommlibFormatter::without_eol()).ends_with(" 3132"));
+        assert!(format(CommlibFormatter::without_eol().with_hex_tid(true)).ends_with(" 0xc3c"));
+    }
 }
@@ -1,7 +1,8 @@
 // TODO: Remove this file, use `PatternFormatter` instead
 //
-// Need to keep waiting for conditional space and brackets to be supported in
-// pattern template strings (optional fields require these, e.g. `logger_name`)
+// Optional groups in pattern template strings now cover the conditional space
+// and brackets around optional fields (e.g. `{?[{logger}] }`), so this can be
+// replaced by `pattern!("[{?{logger}] [}{^{level}}] {payload}{eol}")`.
 
 use std::fmt::{self, Write};
 
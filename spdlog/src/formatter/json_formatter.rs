@@ -0,0 +1,646 @@
+//! Provides a JSON line formatter.
+
+use std::{
+    fmt::{self, Write},
+    iter::Peekable,
+    str::Chars,
+    time::SystemTime,
+};
+
+use chrono::prelude::*;
+
+use crate::{
+    formatter::{FmtExtraInfo, Formatter},
+    utils, Error, Level, Record, StringBuf, EOL,
+};
+
+#[rustfmt::skip]
+/// A log records formatter that emits one JSON object per record.
+///
+/// Log messages formatted by it look like:
+///
+///  - Default:
+///
+///    <pre>
+///    {"timestamp":"2022-11-02T09:23:12.263+08:00","level":"<font color="#11D116">info</font>","logger":"app","payload":"hello, world!","tid":1234}
+///    </pre>
+///
+///  - If crate feature `source-location` is enabled:
+///
+///    <pre>
+///    {"timestamp":"2022-11-02T09:23:12.263+08:00","level":"<font color="#11D116">info</font>","logger":"app","payload":"hello, world!","module_path":"mod::path","file":"src/main.rs","line":4,"tid":1234}
+///    </pre>
+///
+///  - If crate feature `source-location` is enabled, with
+///    [`JsonFormatter::with_nested_caller`]:
+///
+///    <pre>
+///    {"timestamp":"2022-11-02T09:23:12.263+08:00","level":"<font color="#11D116">info</font>","logger":"app","payload":"hello, world!","caller":{"file":"src/main.rs","line":4,"module":"mod::path"},"tid":1234}
+///    </pre>
+///
+/// The `logger` key is omitted if the record has no logger name, and the
+/// source location keys are omitted if the record has no source location. Use
+/// [`JsonFormatter::with_all_keys`] to write them as `null` instead, so that
+/// every line has the same keys.
+///
+/// Strings are escaped as required by JSON, control characters in the payload
+/// are written as `\uXXXX` escapes, so each record always occupies exactly one
+/// line. An indented multi-line output can be enabled with
+/// [`JsonFormatter::with_pretty`].
+///
+/// The style range returned in [`FmtExtraInfo`] covers the level name inside
+/// its quotes, so styled sinks only color the level value.
+///
+/// Lines written by it can be parsed back with [`Record::from_json`].
+#[derive(Clone)]
+pub struct JsonFormatter {
+    pretty: bool,
+    all_keys: bool,
+    nested_caller: bool,
+}
+
+impl JsonFormatter {
+    /// Constructs a `JsonFormatter`.
+    #[must_use]
+    pub fn new() -> JsonFormatter {
+        JsonFormatter {
+            pretty: false,
+            all_keys: false,
+            nested_caller: false,
+        }
+    }
+
+    /// Specifies whether to write each object indented over multiple lines
+    /// instead of a compact single line.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Specifies whether to always write the `logger`, `module_path`, `file`
+    /// and `line` keys, as `null` if the record has no value for them.
+    ///
+    /// This is useful for consumers expecting a fixed schema. The source
+    /// location keys are written even if crate feature `source-location` is
+    /// disabled.
+    ///
+    /// Defaults to `false`, which omits them for compactness.
+    #[must_use]
+    pub fn with_all_keys(mut self, all_keys: bool) -> Self {
+        self.all_keys = all_keys;
+        self
+    }
+
+    /// Specifies whether to write the source location as a nested object
+    /// `"caller":{"file":...,"line":...,"module":...}` instead of the flat
+    /// `module_path`, `file` and `line` keys.
+    ///
+    /// The nested shape matches the schemas expected by many log collectors.
+    /// Like the flat keys, `caller` is omitted if the record has no source
+    /// location, or written as `null` with [`JsonFormatter::with_all_keys`].
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn with_nested_caller(mut self, nested_caller: bool) -> Self {
+        self.nested_caller = nested_caller;
+        self
+    }
+
+    fn format_impl(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+    ) -> Result<FmtExtraInfo, fmt::Error> {
+        let mut writer = ObjectWriter::new(dest, self.pretty);
+
+        let time: DateTime<Local> = record.time().into();
+        writer.key("timestamp")?;
+        utils::write_json_string(
+            writer.dest,
+            &time.to_rfc3339_opts(SecondsFormat::Millis, false),
+        )?;
+
+        writer.key("level")?;
+        let style_range_begin = writer.dest.len() + 1;
+        utils::write_json_string(writer.dest, record.level().as_str())?;
+        let style_range_end = writer.dest.len() - 1;
+
+        match record.logger_name() {
+            Some(logger_name) => {
+                writer.key("logger")?;
+                utils::write_json_string(writer.dest, logger_name)?;
+            }
+            None if self.all_keys => {
+                writer.key("logger")?;
+                writer.dest.push_str("null");
+            }
+            None => {}
+        }
+
+        writer.key("payload")?;
+        utils::write_json_string(writer.dest, record.payload())?;
+
+        let srcloc =
+            crate::source_location::source_location_or_placeholder(record.source_location());
+        match srcloc {
+            Some(srcloc) if self.nested_caller => {
+                writer.key("caller")?;
+                let mut caller = writer.nested();
+                caller.key("file")?;
+                utils::write_json_string(caller.dest, srcloc.file())?;
+                caller.key("line")?;
+                write!(caller.dest, "{}", srcloc.line())?;
+                caller.key("module")?;
+                utils::write_json_string(caller.dest, srcloc.module_path())?;
+                caller.finish();
+            }
+            Some(srcloc) => {
+                writer.key("module_path")?;
+                utils::write_json_string(writer.dest, srcloc.module_path())?;
+                writer.key("file")?;
+                utils::write_json_string(writer.dest, srcloc.file())?;
+                writer.key("line")?;
+                write!(writer.dest, "{}", srcloc.line())?;
+            }
+            None if self.all_keys && self.nested_caller => {
+                writer.key("caller")?;
+                writer.dest.push_str("null");
+            }
+            None if self.all_keys => {
+                for key in ["module_path", "file", "line"] {
+                    writer.key(key)?;
+                    writer.dest.push_str("null");
+                }
+            }
+            None => {}
+        }
+
+        writer.key("tid")?;
+        write!(writer.dest, "{}", record.tid())?;
+
+        writer.finish();
+        dest.push_str(EOL);
+
+        Ok(FmtExtraInfo {
+            style_range: Some(style_range_begin..style_range_end),
+        })
+    }
+}
+
+impl Formatter for JsonFormatter {
+    fn format(&self, record: &Record, dest: &mut StringBuf) -> crate::Result<FmtExtraInfo> {
+        self.format_impl(record, dest).map_err(Error::FormatRecord)
+    }
+
+    fn clone_box(&self) -> Box<dyn Formatter> {
+        Box::new(self.clone())
+    }
+}
+
+impl Default for JsonFormatter {
+    fn default() -> JsonFormatter {
+        JsonFormatter::new()
+    }
+}
+
+struct ObjectWriter<'a> {
+    dest: &'a mut StringBuf,
+    pretty: bool,
+    // The nesting depth, used to indent the pretty output.
+    depth: usize,
+    empty: bool,
+}
+
+impl<'a> ObjectWriter<'a> {
+    fn new(dest: &'a mut StringBuf, pretty: bool) -> Self {
+        Self::with_depth(dest, pretty, 0)
+    }
+
+    fn with_depth(dest: &'a mut StringBuf, pretty: bool, depth: usize) -> Self {
+        dest.push_str("{");
+        Self {
+            dest,
+            pretty,
+            depth,
+            empty: true,
+        }
+    }
+
+    // Starts an object as the value of the last written key.
+    fn nested(&mut self) -> ObjectWriter<'_> {
+        ObjectWriter::with_depth(self.dest, self.pretty, self.depth + 1)
+    }
+
+    fn indent(&mut self, depth: usize) {
+        self.dest.push_str(EOL);
+        for _ in 0..depth {
+            self.dest.push_str("  ");
+        }
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        if !self.empty {
+            self.dest.push_str(",");
+        }
+        self.empty = false;
+        if self.pretty {
+            self.indent(self.depth + 1);
+        }
+        utils::write_json_string(self.dest, key)?;
+        self.dest.push_str(if self.pretty { ": " } else { ":" });
+        Ok(())
+    }
+
+    fn finish(mut self) {
+        if self.pretty && !self.empty {
+            self.indent(self.depth);
+        }
+        self.dest.push_str("}");
+    }
+}
+
+// The fields of a record read back from a line written by `JsonFormatter`, see
+// `Record::from_json`.
+pub(crate) struct JsonRecordFields {
+    pub(crate) time: SystemTime,
+    pub(crate) level: Level,
+    pub(crate) logger_name: Option<String>,
+    pub(crate) payload: String,
+    pub(crate) tid: Option<u64>,
+}
+
+impl JsonRecordFields {
+    pub(crate) fn parse(line: &str) -> crate::Result<Self> {
+        let fields = JsonReader::new(line)
+            .read_object()
+            .map_err(Error::ParseRecord)?;
+        let field = |key: &str| {
+            fields
+                .iter()
+                .find(|(field_key, _)| field_key == key)
+                .map(|(_, value)| value)
+        };
+        let required_str = |key: &str| match field(key) {
+            Some(JsonValue::String(value)) => Ok(value.as_str()),
+            Some(_) => Err(Error::ParseRecord(format!("'{}' is not a string", key))),
+            None => Err(Error::ParseRecord(format!("'{}' is missing", key))),
+        };
+
+        let time = DateTime::parse_from_rfc3339(required_str("timestamp")?)
+            .map_err(|err| Error::ParseRecord(format!("invalid 'timestamp': {}", err)))?
+            .into();
+        let level = required_str("level")?.parse()?;
+        let payload = required_str("payload")?.to_owned();
+        let logger_name = match field("logger") {
+            Some(JsonValue::String(logger_name)) => Some(logger_name.clone()),
+            Some(JsonValue::Null) | None => None,
+            Some(_) => return Err(Error::ParseRecord("'logger' is not a string".into())),
+        };
+        let tid = match field("tid") {
+            Some(JsonValue::Number(tid)) => Some(
+                tid.parse()
+                    .map_err(|_| Error::ParseRecord(format!("invalid 'tid': {}", tid)))?,
+            ),
+            Some(JsonValue::Null) | None => None,
+            Some(_) => return Err(Error::ParseRecord("'tid' is not a number".into())),
+        };
+
+        Ok(Self {
+            time,
+            level,
+            logger_name,
+            payload,
+            tid,
+        })
+    }
+}
+
+enum JsonValue {
+    Null,
+    // The fields are not kept, since no field of a record is read from a nested
+    // object.
+    Object,
+    // Kept as text, since only the caller knows which type it should be.
+    Number(String),
+    String(String),
+}
+
+// Reads a JSON object with string, number, `null` and object values, which is
+// all `JsonFormatter` writes. Other values are not supported.
+struct JsonReader<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> JsonReader<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+        }
+    }
+
+    fn read_object(mut self) -> Result<Vec<(String, JsonValue)>, String> {
+        let fields = self.read_fields()?;
+        self.skip_whitespace();
+        match self.chars.next() {
+            None => Ok(fields),
+            Some(ch) => Err(format!("unexpected '{}' after the object", ch)),
+        }
+    }
+
+    fn read_fields(&mut self) -> Result<Vec<(String, JsonValue)>, String> {
+        let mut fields = vec![];
+        self.expect('{')?;
+        if !self.eat('}') {
+            loop {
+                let key = self.read_string()?;
+                self.expect(':')?;
+                fields.push((key, self.read_value()?));
+                if self.eat('}') {
+                    break;
+                }
+                self.expect(',')?;
+            }
+        }
+        Ok(fields)
+    }
+
+    fn read_value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('"') => self.read_string().map(JsonValue::String),
+            Some('{') => self.read_fields().map(|_| JsonValue::Object),
+            Some(ch) if *ch == '-' || ch.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(ch) = self
+                    .chars
+                    .next_if(|ch| matches!(ch, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    number.push(ch);
+                }
+                Ok(JsonValue::Number(number))
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(ch) = self.chars.next_if(|ch| ch.is_ascii_alphanumeric()) {
+                    word.push(ch);
+                }
+                match word.as_str() {
+                    "null" => Ok(JsonValue::Null),
+                    "" => match self.chars.peek() {
+                        Some(ch) => Err(format!("unsupported value starting with '{}'", ch)),
+                        None => Err("expected a value, found the end".into()),
+                    },
+                    _ => Err(format!("unsupported value '{}'", word)),
+                }
+            }
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        const UNTERMINATED: &str = "unterminated string";
+
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.chars.next().ok_or(UNTERMINATED)? {
+                '"' => return Ok(value),
+                '\\' => match self.chars.next().ok_or(UNTERMINATED)? {
+                    '"' => value.push('"'),
+                    '\\' => value.push('\\'),
+                    '/' => value.push('/'),
+                    'b' => value.push('\u{8}'),
+                    'f' => value.push('\u{c}'),
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    't' => value.push('\t'),
+                    'u' => value.push(self.read_unicode_escape()?),
+                    ch => return Err(format!("invalid escape '\\{}'", ch)),
+                },
+                ch => value.push(ch),
+            }
+        }
+    }
+
+    // Reads the code after `\u`, which is followed by another `\uXXXX` if it's a
+    // high surrogate.
+    fn read_unicode_escape(&mut self) -> Result<char, String> {
+        let code = self.read_hex4()?;
+        let code = if (0xd800..0xdc00).contains(&code) {
+            if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                return Err(format!("unpaired surrogate \\u{:04x}", code));
+            }
+            let low = self.read_hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(format!("unpaired surrogate \\u{:04x}", code));
+            }
+            0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            code
+        };
+        char::from_u32(code).ok_or_else(|| format!("invalid escape \\u{:04x}", code))
+    }
+
+    fn read_hex4(&mut self) -> Result<u32, String> {
+        let hex: String = self.chars.by_ref().take(4).collect();
+        match u32::from_str_radix(&hex, 16) {
+            Ok(code) if hex.len() == 4 => Ok(code),
+            _ => Err(format!("invalid escape \\u{}", hex)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&expected).is_some()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+        match self.chars.peek() {
+            Some(ch) => Err(format!("expected '{}', found '{}'", expected, ch)),
+            None => Err(format!("expected '{}', found the end", expected)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Level, SourceLocation};
+
+    fn expected_timestamp(record: &Record) -> String {
+        let local_time: DateTime<Local> = record.time().into();
+        local_time.to_rfc3339_opts(SecondsFormat::Millis, false)
+    }
+
+    #[test]
+    fn format() {
+        let mut record = Record::new(Level::Warn, "test log content");
+        record.set_logger_name("logger-name");
+        let mut buf = StringBuf::new();
+        let extra_info = JsonFormatter::new().format(&record, &mut buf).unwrap();
+
+        assert_eq!(
+            format!(
+                r#"{{"timestamp":"{}","level":"warn","logger":"logger-name","payload":"test log content","tid":{}}}{}"#,
+                expected_timestamp(&record),
+                record.tid(),
+                EOL
+            ),
+            buf
+        );
+        let style_range = extra_info.style_range().unwrap();
+        assert_eq!(&buf.as_str()[style_range], "warn");
+    }
+
+    #[test]
+    fn escape_payload() {
+        let record = Record::new(Level::Info, "a\"b\\c\nd\te\u{1}f\u{7f}g中");
+        let mut buf = StringBuf::new();
+        JsonFormatter::new().format(&record, &mut buf).unwrap();
+
+        assert!(buf
+            .as_str()
+            .contains(r#""level":"info","payload":"a\"b\\c\nd\te\u0001f\u007fg中""#));
+        assert_eq!(
+            buf.as_str().matches('\n').count(),
+            EOL.matches('\n').count()
+        );
+    }
+
+    #[test]
+    fn pretty() {
+        let record = Record::new(Level::Error, "hi");
+        let mut buf = StringBuf::new();
+        let extra_info = JsonFormatter::new()
+            .with_pretty(true)
+            .format(&record, &mut buf)
+            .unwrap();
+
+        let expected = format!(
+            r#"{{
+  "timestamp": "{}",
+  "level": "error",
+  "payload": "hi",
+  "tid": {}
+}}
+"#,
+            expected_timestamp(&record),
+            record.tid(),
+        )
+        .replace('\n', EOL);
+        assert_eq!(expected, buf);
+        let style_range = extra_info.style_range().unwrap();
+        assert_eq!(&buf.as_str()[style_range], "error");
+    }
+
+    #[test]
+    fn all_keys() {
+        let record = Record::new(Level::Info, "hi");
+        let mut buf = StringBuf::new();
+        JsonFormatter::new()
+            .with_all_keys(true)
+            .format(&record, &mut buf)
+            .unwrap();
+
+        assert_eq!(
+            format!(
+                r#"{{"timestamp":"{}","level":"info","logger":null,"payload":"hi","module_path":null,"file":null,"line":null,"tid":{}}}{}"#,
+                expected_timestamp(&record),
+                record.tid(),
+                EOL
+            ),
+            buf
+        );
+    }
+
+    #[test]
+    fn nested_caller() {
+        let record = Record::builder(Level::Info, "hi")
+            .source_location(Some(SourceLocation::__new(
+                "mod::path",
+                "src/main.rs",
+                4,
+                2,
+            )))
+            .build();
+        let formatter = JsonFormatter::new().with_nested_caller(true);
+
+        let mut buf = StringBuf::new();
+        formatter.format(&record, &mut buf).unwrap();
+        assert_eq!(
+            format!(
+                r#"{{"timestamp":"{}","level":"info","payload":"hi","caller":{{"file":"src/main.rs","line":4,"module":"mod::path"}},"tid":{}}}{}"#,
+                expected_timestamp(&record),
+                record.tid(),
+                EOL
+            ),
+            buf
+        );
+        assert_eq!(Record::from_json(&buf).unwrap().payload(), "hi");
+
+        let mut buf = StringBuf::new();
+        formatter
+            .clone()
+            .with_pretty(true)
+            .format(&record, &mut buf)
+            .unwrap();
+        let expected = format!(
+            r#"{{
+  "timestamp": "{}",
+  "level": "info",
+  "payload": "hi",
+  "caller": {{
+    "file": "src/main.rs",
+    "line": 4,
+    "module": "mod::path"
+  }},
+  "tid": {}
+}}
+"#,
+            expected_timestamp(&record),
+            record.tid(),
+        )
+        .replace('\n', EOL);
+        assert_eq!(expected, buf);
+        assert_eq!(Record::from_json(&buf).unwrap().payload(), "hi");
+    }
+
+    #[test]
+    fn nested_caller_absent() {
+        let record = Record::new(Level::Info, "hi");
+
+        let mut buf = StringBuf::new();
+        JsonFormatter::new()
+            .with_nested_caller(true)
+            .format(&record, &mut buf)
+            .unwrap();
+        assert_eq!(
+            format!(
+                r#"{{"timestamp":"{}","level":"info","payload":"hi","tid":{}}}{}"#,
+                expected_timestamp(&record),
+                record.tid(),
+                EOL
+            ),
+            buf
+        );
+
+        let mut buf = StringBuf::new();
+        JsonFormatter::new()
+            .with_nested_caller(true)
+            .with_all_keys(true)
+            .format(&record, &mut buf)
+            .unwrap();
+        assert!(buf
+            .as_str()
+            .contains(r#""payload":"hi","caller":null,"tid":"#));
+    }
+}
@@ -0,0 +1,187 @@
+//! Provides a formatter that selects an inner formatter per record level.
+
+use std::path::Path;
+
+use crate::{
+    formatter::{FmtExtraInfo, Formatter, FullFormatter},
+    LevelFilter, Record, Result, StringBuf,
+};
+
+/// A formatter that picks one of several inner formatters according to the
+/// level of each record.
+///
+/// Inner formatters are tried in the order they were added, and the first one
+/// whose [`LevelFilter`] accepts the record level is used. If none of them
+/// matches, the fallback formatter is used.
+///
+/// This allows a single sink to, for example, format errors verbosely and
+/// informational messages tersely, without running two sinks over the same
+/// target.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{
+///     formatter::{pattern, Formatter, FullFormatter, LevelFormatter, PatternFormatter},
+///     prelude::*,
+/// };
+///
+/// let formatter = LevelFormatter::builder()
+///     .formatter(
+///         LevelFilter::MoreSevereEqual(Level::Error),
+///         Box::new(FullFormatter::new()),
+///     )
+///     .fallback(Box::new(PatternFormatter::new(pattern!(
+///         "{payload}{eol}"
+///     ))))
+///     .build();
+///
+/// for sink in spdlog::default_logger().sinks() {
+///     sink.set_formatter(formatter.clone_box());
+/// }
+/// ```
+pub struct LevelFormatter {
+    formatters: Vec<(LevelFilter, Box<dyn Formatter>)>,
+    fallback: Box<dyn Formatter>,
+}
+
+impl LevelFormatter {
+    /// Constructs a [`LevelFormatterBuilder`].
+    #[must_use]
+    pub fn builder() -> LevelFormatterBuilder {
+        LevelFormatterBuilder::new()
+    }
+
+    #[must_use]
+    fn select(&self, record: &Record) -> &dyn Formatter {
+        self.formatters
+            .iter()
+            .find(|(level_filter, _)| level_filter.compare(record.level()))
+            .map_or(&*self.fallback, |(_, formatter)| &**formatter)
+    }
+}
+
+impl Formatter for LevelFormatter {
+    fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+        self.select(record).format(record, dest)
+    }
+
+    fn format_for_target(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        target_path: Option<&Path>,
+    ) -> Result<FmtExtraInfo> {
+        self.select(record)
+            .format_for_target(record, dest, target_path)
+    }
+
+    fn clone_box(&self) -> Box<dyn Formatter> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for LevelFormatter {
+    fn clone(&self) -> Self {
+        Self {
+            formatters: self
+                .formatters
+                .iter()
+                .map(|(level_filter, formatter)| (*level_filter, formatter.clone_box()))
+                .collect(),
+            fallback: self.fallback.clone_box(),
+        }
+    }
+}
+
+/// The builder of [`LevelFormatter`].
+pub struct LevelFormatterBuilder {
+    formatters: Vec<(LevelFilter, Box<dyn Formatter>)>,
+    fallback: Option<Box<dyn Formatter>>,
+}
+
+impl LevelFormatterBuilder {
+    /// Constructs a `LevelFormatterBuilder`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            formatters: vec![],
+            fallback: None,
+        }
+    }
+
+    /// Adds a formatter used for records whose level is accepted by
+    /// `level_filter`.
+    ///
+    /// Formatters are tried in the order they were added.
+    #[must_use]
+    pub fn formatter(mut self, level_filter: LevelFilter, formatter: Box<dyn Formatter>) -> Self {
+        self.formatters.push((level_filter, formatter));
+        self
+    }
+
+    /// Specifies the formatter used for records that are not accepted by any
+    /// of the added formatters.
+    ///
+    /// This parameter is **optional**, and defaults to [`FullFormatter`].
+    #[must_use]
+    pub fn fallback(mut self, formatter: Box<dyn Formatter>) -> Self {
+        self.fallback = Some(formatter);
+        self
+    }
+
+    /// Builds a [`LevelFormatter`].
+    #[must_use]
+    pub fn build(self) -> LevelFormatter {
+        LevelFormatter {
+            formatters: self.formatters,
+            fallback: self
+                .fallback
+                .unwrap_or_else(|| Box::new(FullFormatter::new())),
+        }
+    }
+}
+
+impl Default for LevelFormatterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        sink::{Sink, WriteSink},
+        sync::*,
+        test_utils::*,
+    };
+
+    #[test]
+    fn select_by_level() {
+        let formatter = LevelFormatter::builder()
+            .formatter(
+                LevelFilter::MoreSevereEqual(Level::Error),
+                Box::new(FullFormatter::without_eol()),
+            )
+            .fallback(Box::new(NoModFormatter::new()))
+            .build();
+
+        let sink = Arc::new(WriteSink::builder().target(Vec::new()).build().unwrap());
+        sink.set_formatter(Box::new(formatter));
+        let logger = test_logger_builder()
+            .sink(sink.clone())
+            .level_filter(LevelFilter::All)
+            .build()
+            .unwrap();
+
+        info!(logger: logger, "terse;");
+        error!(logger: logger, "verbose");
+
+        let data = String::from_utf8(sink.clone_target()).unwrap();
+        assert!(data.starts_with("terse;["));
+        assert!(data.contains("] [error] "));
+        assert!(data.ends_with("] verbose"));
+    }
+}
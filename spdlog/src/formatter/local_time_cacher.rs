@@ -1,21 +1,95 @@
 use std::{
     cell::{RefCell, RefMut},
+    marker::PhantomData,
     sync::Arc,
-    time::SystemTime,
+    thread::LocalKey,
+    time::{Duration, SystemTime},
 };
 
 use chrono::prelude::*;
-use once_cell::sync::Lazy;
 
 use crate::sync::*;
 
-pub(crate) static LOCAL_TIME_CACHER: Lazy<SpinMutex<LocalTimeCacher>> =
-    Lazy::new(|| SpinMutex::new(LocalTimeCacher::new()));
+// Each thread has its own cachers, so that threads formatting records
+// simultaneously don't contend on a lock. The cached values only change once
+// per second, so caching them per thread costs little.
+thread_local! {
+    static LOCAL_TIME_CACHER: RefCell<LocalTimeCacher> = RefCell::new(LocalTimeCacher::new());
+    static UTC_TIME_CACHER: RefCell<UtcTimeCacher> = RefCell::new(UtcTimeCacher::new());
+}
+
+// Calls the function with the given time in the local timezone, cached by the
+// current thread.
+pub(crate) fn with_local_time<R>(time: SystemTime, f: impl FnOnce(TimeDate) -> R) -> R {
+    with_cached_time(&LOCAL_TIME_CACHER, time, f)
+}
+
+// Calls the function with the given time in UTC, cached by the current thread.
+pub(crate) fn with_utc_time<R>(time: SystemTime, f: impl FnOnce(TimeDate) -> R) -> R {
+    with_cached_time(&UTC_TIME_CACHER, time, f)
+}
+
+fn with_cached_time<Tz, R>(
+    cacher: &'static LocalKey<RefCell<TimeCacher<Tz>>>,
+    time: SystemTime,
+    f: impl FnOnce(TimeDate) -> R,
+) -> R
+where
+    Tz: TimeZone,
+    DateTime<Tz>: From<DateTime<Utc>>,
+{
+    let mut f = Some(f);
+    cacher
+        .try_with(|cacher| (f.take().unwrap())(cacher.borrow_mut().get(time)))
+        // The cacher of the thread has been destroyed, e.g. a record is logged
+        // in the destructor of another thread local. Fall back to no caching.
+        .unwrap_or_else(|_| (f.take().unwrap())(TimeCacher::<Tz>::new().get(time)))
+}
+
+// The number of seconds the resolved UTC offset is reused for, see
+// `set_tz_offset_cache_duration`.
+static TZ_OFFSET_CACHE_SECS: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the duration for which the UTC offset of the local timezone is reused
+/// once resolved.
+///
+/// The datetime patterns and formatters resolve the offset of the local
+/// timezone whenever a record is logged in a new second, which queries the OS
+/// on some platforms. With a non-zero duration, the resolved offset is reused
+/// for the records within the duration instead, so high-rate logging doesn't
+/// repeatedly query the timezone. The duration is truncated to seconds, and
+/// it defaults to zero, i.e. the offset is resolved for every second.
+///
+/// The cached offset may be stale for up to the duration after the offset of
+/// the local timezone changes, e.g. at a DST transition, the time is written
+/// in the previous offset until the cached one expires. Times written in UTC
+/// are not affected.
+///
+/// The duration is global, it affects all formatters.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// spdlog::formatter::set_tz_offset_cache_duration(Duration::from_secs(60));
+/// ```
+pub fn set_tz_offset_cache_duration(duration: Duration) {
+    TZ_OFFSET_CACHE_SECS.store(duration.as_secs(), Ordering::Relaxed);
+}
 
+pub(crate) type LocalTimeCacher = TimeCacher<Local>;
+pub(crate) type UtcTimeCacher = TimeCacher<Utc>;
+
+// Caches the calendar fields of the time in the timezone `Tz`. The time is
+// stored with a fixed offset, so `TimeDate` is shared by all timezones.
 #[derive(Clone)]
-pub(crate) struct LocalTimeCacher {
+pub(crate) struct TimeCacher<Tz> {
     stored_key: CacheKey,
     cache_values: Option<CacheValues>,
+    // The UTC offset and the unix timestamp it was resolved at.
+    resolved_offset: Option<(i64, FixedOffset)>,
+    _phantom: PhantomData<Tz>,
 }
 
 pub(crate) struct TimeDate<'a> {
@@ -32,7 +106,7 @@ enum CacheKey {
 
 #[derive(Clone, Eq, PartialEq)]
 struct CacheValues {
-    local_time: DateTime<Local>,
+    time: DateTime<FixedOffset>,
     is_leap_second: bool,
     full_second_str: RefCell<Option<String>>,
     full_iso_8601_str: RefCell<Option<String>>,
@@ -64,21 +138,30 @@ pub(crate) struct MultiName<T> {
     pub(crate) full: T,
 }
 
-impl LocalTimeCacher {
+impl<Tz> TimeCacher<Tz>
+where
+    Tz: TimeZone,
+    DateTime<Tz>: From<DateTime<Utc>>,
+{
     #[must_use]
-    fn new() -> LocalTimeCacher {
-        LocalTimeCacher {
+    fn new() -> Self {
+        Self {
             stored_key: CacheKey::NonLeap(0),
             cache_values: None,
+            resolved_offset: None,
+            _phantom: PhantomData,
         }
     }
 
     #[must_use]
-    pub(crate) fn get(&mut self, system_time: SystemTime) -> TimeDate {
-        self.get_inner(system_time.into())
+    pub(crate) fn get(&mut self, system_time: SystemTime) -> TimeDate<'_> {
+        self.get_inner(
+            system_time.into(),
+            TZ_OFFSET_CACHE_SECS.load(Ordering::Relaxed),
+        )
     }
 
-    fn get_inner(&mut self, utc_time: DateTime<Utc>) -> TimeDate {
+    fn get_inner(&mut self, utc_time: DateTime<Utc>, offset_cache_secs: u64) -> TimeDate<'_> {
         const LEAP_BOUNDARY: u32 = 1_000_000_000;
 
         let nanosecond = utc_time.nanosecond();
@@ -92,7 +175,8 @@ impl LocalTimeCacher {
 
         let cache_key = CacheKey::new(&utc_time, is_leap_second);
         if self.cache_values.is_none() || self.stored_key != cache_key {
-            self.cache_values = Some(CacheValues::new(utc_time, is_leap_second));
+            let time = self.resolve_fixed_offset(utc_time, offset_cache_secs);
+            self.cache_values = Some(CacheValues::new(time, is_leap_second));
             self.stored_key = cache_key;
         }
 
@@ -104,6 +188,34 @@ impl LocalTimeCacher {
     }
 }
 
+impl<Tz> TimeCacher<Tz>
+where
+    Tz: TimeZone,
+    DateTime<Tz>: From<DateTime<Utc>>,
+{
+    // Converts the time into the timezone `Tz`, reusing the offset resolved
+    // within the last `offset_cache_secs` seconds if any.
+    #[must_use]
+    fn resolve_fixed_offset(
+        &mut self,
+        utc_time: DateTime<Utc>,
+        offset_cache_secs: u64,
+    ) -> DateTime<FixedOffset> {
+        let timestamp = utc_time.timestamp();
+        if let Some((resolved_at, offset)) = self.resolved_offset {
+            let elapsed = timestamp.saturating_sub(resolved_at);
+            if elapsed >= 0 && (elapsed as u64) < offset_cache_secs {
+                return utc_time.with_timezone(&offset);
+            }
+        }
+
+        let time: DateTime<Tz> = utc_time.into();
+        let offset = time.offset().fix();
+        self.resolved_offset = Some((timestamp, offset));
+        time.with_timezone(&offset)
+    }
+}
+
 macro_rules! impl_cache_fields_getter {
     ( $($field:ident: $type:ty),*$(,)? ) => {
         #[must_use]
@@ -112,7 +224,7 @@ macro_rules! impl_cache_fields_getter {
                 .cached
                 .$field
                 .borrow_mut()
-                .get_or_insert_with(|| self.cached.local_time.$field())
+                .get_or_insert_with(|| self.cached.time.$field())
         })*
     };
 }
@@ -124,7 +236,7 @@ macro_rules! impl_cache_fields_str_getter {
             self.cached
                 .$str_field
                 .borrow_mut()
-                .get_or_insert_with(|| Arc::new(format!($fmt, self.cached.local_time.$field())))
+                .get_or_insert_with(|| Arc::new(format!($fmt, self.cached.time.$field())))
                 .clone()
         })*
     };
@@ -148,7 +260,7 @@ impl<'a> TimeDate<'a> {
     pub(crate) fn full_second_str(&self) -> RefMut<'_, str> {
         RefMut::map(self.cached.full_second_str.borrow_mut(), |opt| {
             opt.get_or_insert_with(|| {
-                // `local_time.format("%Y-%m-%d %H:%M:%S")` is slower than this way
+                // `time.format("%Y-%m-%d %H:%M:%S")` is slower than this way
                 format!(
                     "{}-{:02}-{:02} {:02}:{:02}:{:02}",
                     self.year(),
@@ -171,7 +283,7 @@ impl<'a> TimeDate<'a> {
     pub(crate) fn full_iso_8601_str(&self) -> RefMut<'_, str> {
         RefMut::map(self.cached.full_iso_8601_str.borrow_mut(), |opt| {
             opt.get_or_insert_with(|| {
-                // `local_time.format("%Y-%m-%dT%H:%M:%S.%9f%:z")` is slower than this way
+                // `time.format("%Y-%m-%dT%H:%M:%S.%9f%:z")` is slower than this way
                 format!(
                     "{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}{:02}",
                     self.year(),
@@ -211,7 +323,7 @@ impl<'a> TimeDate<'a> {
     pub(crate) fn second(&self) -> u32 {
         *self.cached.second.borrow_mut().get_or_insert_with(|| {
             if !self.cached.is_leap_second {
-                self.cached.local_time.second()
+                self.cached.time.second()
             } else {
                 // https://www.itu.int/dms_pubrec/itu-r/rec/tf/R-REC-TF.460-6-200202-I!!PDF-E.pdf
                 60
@@ -238,7 +350,7 @@ impl<'a> TimeDate<'a> {
                 ];
 
                 let weekday_from_monday_0 =
-                    self.cached.local_time.weekday().num_days_from_monday() as usize;
+                    self.cached.time.weekday().num_days_from_monday() as usize;
 
                 MultiName {
                     short: SHORT[weekday_from_monday_0],
@@ -268,7 +380,7 @@ impl<'a> TimeDate<'a> {
                 "December",
             ];
 
-            let month_index = self.cached.local_time.month() as usize - 1;
+            let month_index = self.cached.time.month() as usize - 1;
 
             MultiName {
                 short: SHORT[month_index],
@@ -277,6 +389,18 @@ impl<'a> TimeDate<'a> {
         })
     }
 
+    // The cached time is shared by all the records in the same second, so its
+    // sub-second part is replaced with the one of the current record.
+    #[must_use]
+    pub(crate) fn time(&self) -> DateTime<FixedOffset> {
+        let nanosecond = if !self.cached.is_leap_second {
+            self.nanosecond
+        } else {
+            self.nanosecond + 1_000_000_000
+        };
+        self.cached.time.with_nanosecond(nanosecond).unwrap()
+    }
+
     #[must_use]
     pub(crate) fn nanosecond(&self) -> u32 {
         self.nanosecond
@@ -322,7 +446,7 @@ impl<'a> TimeDate<'a> {
             .tz_offset_str
             .borrow_mut()
             .get_or_insert_with(|| {
-                let offset_secs = self.cached.local_time.offset().local_minus_utc();
+                let offset_secs = self.cached.time.offset().local_minus_utc();
                 let offset_secs_abs = offset_secs.abs();
 
                 let sign_str = if offset_secs >= 0 { "+" } else { "-" };
@@ -351,9 +475,9 @@ impl CacheKey {
 
 impl CacheValues {
     #[must_use]
-    fn new(utc_time: DateTime<Utc>, is_leap_second: bool) -> Self {
+    fn new(time: DateTime<FixedOffset>, is_leap_second: bool) -> Self {
         CacheValues {
-            local_time: utc_time.into(),
+            time,
             is_leap_second,
             full_second_str: RefCell::new(None),
             full_iso_8601_str: RefCell::new(None),
@@ -432,9 +556,41 @@ mod tests {
 
             println!(" => checking '{datetime}'");
 
-            let result = cacher.get_inner(datetime.and_local_timezone(Utc).unwrap());
+            let result = cacher.get_inner(datetime.and_local_timezone(Utc).unwrap(), 0);
             assert_eq!(result.cached.is_leap_second, leap);
             assert_eq!(result.second(), if !leap { 59 } else { 60 });
         }
     }
+
+    #[test]
+    fn utc() {
+        let datetime = NaiveDate::from_ymd_opt(2022, 6, 30)
+            .unwrap()
+            .and_hms_opt(23, 59, 58)
+            .unwrap()
+            .and_local_timezone(Utc)
+            .unwrap();
+
+        let mut cacher = UtcTimeCacher::new();
+        let result = cacher.get_inner(datetime, 0);
+        assert_eq!(&*result.full_second_str(), "2022-06-30 23:59:58");
+        assert_eq!(*result.tz_offset_str(), "+00:00");
+        assert_eq!(result.time(), datetime);
+    }
+
+    #[test]
+    fn tz_offset_cache() {
+        let start = Utc.with_ymd_and_hms(2022, 6, 30, 12, 0, 0).unwrap();
+        let mut cacher = LocalTimeCacher::new();
+
+        for (secs, resolved_at_secs) in [(0, 0), (1, 0), (30, 0), (59, 0), (60, 60), (61, 60)] {
+            let utc_time = start + chrono::Duration::seconds(secs);
+            let result = cacher.get_inner(utc_time, 60);
+            assert_eq!(result.time(), DateTime::<Local>::from(utc_time));
+            assert_eq!(
+                cacher.resolved_offset.unwrap().0,
+                start.timestamp() + resolved_at_secs
+            );
+        }
+    }
 }
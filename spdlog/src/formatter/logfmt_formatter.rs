@@ -0,0 +1,219 @@
+//! Provides a logfmt formatter.
+
+use std::fmt::{self, Write};
+
+use chrono::prelude::*;
+
+use crate::{
+    formatter::{FmtExtraInfo, Formatter},
+    Error, Record, StringBuf, EOL,
+};
+
+#[rustfmt::skip]
+/// A log records formatter that emits one line of `key=value` pairs per
+/// record, known as [logfmt].
+///
+/// Log messages formatted by it look like:
+///
+///  - Default:
+///
+///    <pre>
+///    ts=2022-11-02T09:23:12.263+08:00 level=<font color="#11D116">info</font> msg="hello, world!" tid=1234
+///    </pre>
+///
+///  - If the logger has a name, and crate feature `source-location` is
+///    enabled:
+///
+///    <pre>
+///    ts=2022-11-02T09:23:12.263+08:00 level=<font color="#11D116">info</font> logger=my-logger msg="hello, world!" module_path=mod::path file=src/main.rs line=4 tid=1234
+///    </pre>
+///
+/// The `logger` and source location keys are omitted if the record has no
+/// logger name or source location.
+///
+/// A value is quoted if it is empty or contains spaces, `=`, quotes or control
+/// characters. Quotes and backslashes in quoted values are escaped with a
+/// backslash, and control characters are written as escapes, so each record
+/// always occupies exactly one line.
+///
+/// The style range returned in [`FmtExtraInfo`] covers the level value, so
+/// styled sinks only color the level.
+///
+/// [logfmt]: https://brandur.org/logfmt
+#[derive(Clone)]
+pub struct LogfmtFormatter {
+    with_eol: bool,
+}
+
+impl LogfmtFormatter {
+    /// Constructs a `LogfmtFormatter`.
+    #[must_use]
+    pub fn new() -> LogfmtFormatter {
+        LogfmtFormatter { with_eol: true }
+    }
+
+    /// Constructs a `LogfmtFormatter` that does not write an EOL at the end of
+    /// each log message.
+    #[must_use]
+    pub fn without_eol() -> LogfmtFormatter {
+        LogfmtFormatter { with_eol: false }
+    }
+
+    fn format_impl(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+    ) -> Result<FmtExtraInfo, fmt::Error> {
+        let time: DateTime<Local> = record.time().into();
+        dest.push_str("ts=");
+        dest.push_str(&time.to_rfc3339_opts(SecondsFormat::Millis, false));
+
+        dest.push_str(" level=");
+        let style_range_begin = dest.len();
+        dest.push_str(record.level().as_str());
+        let style_range_end = dest.len();
+
+        if let Some(logger_name) = record.logger_name() {
+            write_pair(dest, "logger", logger_name);
+        }
+
+        write_pair(dest, "msg", record.payload());
+
+        #[cfg(feature = "source-location")]
+        if let Some(srcloc) =
+            crate::source_location::source_location_or_placeholder(record.source_location())
+        {
+            write_pair(dest, "module_path", srcloc.module_path());
+            write_pair(dest, "file", srcloc.file());
+            write!(dest, " line={}", srcloc.line())?;
+        }
+
+        write!(dest, " tid={}", record.tid())?;
+
+        if self.with_eol {
+            dest.push_str(EOL);
+        }
+
+        Ok(FmtExtraInfo {
+            style_range: Some(style_range_begin..style_range_end),
+        })
+    }
+}
+
+impl Formatter for LogfmtFormatter {
+    fn format(&self, record: &Record, dest: &mut StringBuf) -> crate::Result<FmtExtraInfo> {
+        self.format_impl(record, dest).map_err(Error::FormatRecord)
+    }
+
+    fn clone_box(&self) -> Box<dyn Formatter> {
+        Box::new(self.clone())
+    }
+}
+
+impl Default for LogfmtFormatter {
+    fn default() -> LogfmtFormatter {
+        LogfmtFormatter::new()
+    }
+}
+
+fn write_pair(dest: &mut StringBuf, key: &str, value: &str) {
+    dest.push_str(" ");
+    dest.push_str(key);
+    dest.push_str("=");
+    write_value(dest, value);
+}
+
+fn write_value(dest: &mut StringBuf, value: &str) {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|ch| ch == ' ' || ch == '=' || ch == '"' || ch.is_control());
+    if !needs_quotes {
+        dest.push_str(value);
+        return;
+    }
+
+    dest.push_str("\"");
+    for ch in value.chars() {
+        match ch {
+            '"' => dest.push_str("\\\""),
+            '\\' => dest.push_str("\\\\"),
+            '\n' => dest.push_str("\\n"),
+            '\r' => dest.push_str("\\r"),
+            '\t' => dest.push_str("\\t"),
+            ch if ch.is_control() => write!(dest, "\\u{:04x}", ch as u32).unwrap(),
+            ch => {
+                let mut buf = [0; 4];
+                dest.push_str(ch.encode_utf8(&mut buf));
+            }
+        }
+    }
+    dest.push_str("\"");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Level;
+
+    fn expected_timestamp(record: &Record) -> String {
+        let local_time: DateTime<Local> = record.time().into();
+        local_time.to_rfc3339_opts(SecondsFormat::Millis, false)
+    }
+
+    #[test]
+    fn format() {
+        let mut record = Record::new(Level::Warn, "test log content");
+        record.set_logger_name("logger-name");
+        let mut buf = StringBuf::new();
+        let extra_info = LogfmtFormatter::new().format(&record, &mut buf).unwrap();
+
+        assert_eq!(
+            format!(
+                r#"ts={} level=warn logger=logger-name msg="test log content" tid={}{}"#,
+                expected_timestamp(&record),
+                record.tid(),
+                EOL
+            ),
+            buf
+        );
+        let style_range = extra_info.style_range().unwrap();
+        assert_eq!(&buf.as_str()[style_range], "warn");
+    }
+
+    #[test]
+    fn quote_values() {
+        let check = |payload: &str, expected: &str| {
+            let record = Record::new(Level::Info, payload);
+            let mut buf = StringBuf::new();
+            LogfmtFormatter::without_eol()
+                .format(&record, &mut buf)
+                .unwrap();
+            assert!(
+                buf.as_str().contains(&format!(" msg={} ", expected)),
+                "{}",
+                buf
+            );
+        };
+
+        check("plain", "plain");
+        check("back\\slash", "back\\slash");
+        check("", r#""""#);
+        check("a b", r#""a b""#);
+        check("k=v", r#""k=v""#);
+        check("say \"hi\"", r#""say \"hi\"""#);
+        check("a\\b c", r#""a\\b c""#);
+        check("a\nb\u{1}", r#""a\nb\u0001""#);
+    }
+
+    #[test]
+    fn without_eol() {
+        let record = Record::new(Level::Info, "hi");
+        let mut buf = StringBuf::new();
+        LogfmtFormatter::without_eol()
+            .format(&record, &mut buf)
+            .unwrap();
+
+        assert!(buf.as_str().ends_with(&format!(" tid={}", record.tid())));
+    }
+}
@@ -4,6 +4,8 @@
 //!
 //! [`Sink::set_formatter`]: crate::sink::Sink::set_formatter
 
+mod checksum_formatter;
+mod dynamic_pattern_formatter;
 mod iso8601_datetime_formatter;
 mod full_formatter;
 #[cfg(any(
@@ -11,22 +13,35 @@ mod full_formatter;
     all(doc, not(doctest))
 ))]
 mod journald_formatter;
+mod json_formatter;
+mod level_formatter;
 mod local_time_cacher;
+mod logfmt_formatter;
 mod pattern_formatter;
+mod prepend_formatter;
 
 use std::ops::Range;
 
+pub use checksum_formatter::*;
+pub use dynamic_pattern_formatter::*;
 pub use full_formatter::*;
 #[cfg(any(
     all(target_os = "linux", feature = "native", feature = "libsystemd"),
     all(doc, not(doctest))
 ))]
 pub(crate) use journald_formatter::*;
+pub use json_formatter::*;
+pub use level_formatter::*;
 pub(crate) use local_time_cacher::*;
+pub use local_time_cacher::set_tz_offset_cache_duration;
+pub use logfmt_formatter::*;
 pub use pattern_formatter::*;
+pub use prepend_formatter::*;
 
 pub use iso8601_datetime_formatter::*;
 
+use std::path::Path;
+
 use crate::{Record, Result, StringBuf};
 
 /// A trait for log records formatters.
@@ -38,8 +53,57 @@ use crate::{Record, Result, StringBuf};
 /// [./examples]: https://github.com/SpriteOvO/spdlog-rs/tree/main/spdlog/examples
 pub trait Formatter: Send + Sync {
     /// Formats a log record.
+    ///
+    /// The formatted text is appended to `dest`, existing contents are kept.
     fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo>;
 
+    /// Clears `dest` and then formats a log record into it.
+    ///
+    /// This allows a caller to reuse one buffer across many records, so that
+    /// its allocated capacity is kept instead of allocating a new buffer for
+    /// each record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spdlog::{formatter::Formatter, Record, StringBuf};
+    ///
+    /// fn format_all(formatter: &dyn Formatter, records: &[Record]) -> spdlog::Result<()> {
+    ///     let mut buf = StringBuf::new();
+    ///     for record in records {
+    ///         formatter.format_into(record, &mut buf)?;
+    ///         // `buf` now only contains the current record
+    ///         print!("{}", buf);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    fn format_into(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+        dest.clear();
+        self.format(record, dest)
+    }
+
+    /// Formats a log record that will be written into the given target
+    /// directory.
+    ///
+    /// Sinks writing to files call this function instead of
+    /// [`Formatter::format`], so that formatters can render information about
+    /// the target, e.g. the `{disk_free}` pattern. `target_path` is `None` if
+    /// the sink does not write to the file system.
+    ///
+    /// The default implementation ignores `target_path` and calls
+    /// [`Formatter::format`]. Formatters wrapping other formatters should
+    /// override it to forward `target_path`.
+    fn format_for_target(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        target_path: Option<&Path>,
+    ) -> Result<FmtExtraInfo> {
+        let _ = target_path;
+        self.format(record, dest)
+    }
+
     /// Clones self into a boxed trait object.
     #[must_use]
     fn clone_box(&self) -> Box<dyn Formatter>;
@@ -14,7 +14,9 @@
 #[path = "pattern/mod.rs"]
 pub mod __pattern;
 
-use std::{ops::Range, sync::Arc};
+pub use __pattern::set_app_version;
+
+use std::{ops::Range, path::Path, sync::Arc};
 
 use crate::{
     formatter::{FmtExtraInfo, FmtExtraInfoBuilder, Formatter},
@@ -140,6 +142,72 @@ use crate::{
 /// //            ^^^^^^ <- style range
 /// # );
 /// ```
+///
+/// The style range can be made conditional on the level of the record, by
+/// starting it with `<level>+:`. The style range is then only reported for
+/// records whose level is the same as or more severe than `<level>`, e.g.
+/// `{^error+:{payload}}` styles the payload of error and critical records
+/// only.
+///
+/// # Using Optional Groups
+///
+/// Some placeholders write nothing for some records, e.g. `{logger}` for
+/// records without a logger name, which would leave stray brackets or spaces
+/// around them. Wrap them with the text around them in `{?...}` to write the
+/// whole group only if every placeholder in it writes something:
+/// ```
+/// # use spdlog::{
+/// #     formatter::{pattern, PatternFormatter},
+/// #     info,
+/// # };
+#[doc = include_str!("../../include/doc/test_utils.rs")]
+/// let formatter = PatternFormatter::new(pattern!("{?[{logger}] }{payload}"));
+/// # let (doctest, sink) = doc_test_utils::echo_logger_from_formatter(
+/// #     Box::new(formatter),
+/// #     None
+/// # );
+///
+/// info!(logger: doctest, "Interesting log message");
+/// # assert_eq!(
+/// #     String::from_utf8(sink.clone_target()).unwrap(),
+/// /* Output */ "Interesting log message"
+/// # );
+/// ```
+///
+/// An optional group can contain literal text and placeholders, including
+/// custom patterns, and can be placed inside a style range. Style ranges and
+/// other optional groups cannot be nested inside an optional group.
+///
+/// # Padding and Alignment
+///
+/// Placeholders of patterns that don't take an argument, including custom
+/// patterns, accept a padding spec `:[<|>|^]<width>[!]`, which pads the output
+/// with spaces to at least `width` characters, so that columns line up:
+///
+///  - `<` aligns the output to the left (the default), `>` to the right and
+///    `^` to the center.
+///
+///  - Output longer than the width is written in full, unless the spec ends
+///    with `!`, in which case it's truncated to the first `width` characters.
+///
+/// ```
+/// # use spdlog::{
+/// #     formatter::{pattern, PatternFormatter},
+/// #     info,
+/// # };
+#[doc = include_str!("../../include/doc/test_utils.rs")]
+/// let formatter = PatternFormatter::new(pattern!("[{level:<8}] [{tid:>8}] {payload}"));
+/// # let (doctest, sink) = doc_test_utils::echo_logger_from_formatter(
+/// #     Box::new(formatter),
+/// #     None
+/// # );
+///
+/// info!(logger: doctest, "Interesting log message");
+/// # let output = String::from_utf8(sink.clone_target()).unwrap();
+/// # assert!(output.starts_with("[info    ] ["));
+/// # assert!(output.ends_with("] Interesting log message"));
+/// // [info    ] [    3132] Interesting log message
+/// ```
 /// 
 /// # Using Your Own Patterns
 ///
@@ -331,9 +399,14 @@ use crate::{
 /// | `{time}`              | Time                         | `22:28:02`, `09:53:41`                       |
 /// | `{tz_offset}`         | Timezone offset              | `+08:00`, `+00:00`, `-06:00`                 |
 /// | `{unix_timestamp}`    | Unix timestamp               | `1528834770`                                 |
+/// | `{subsec_seq}`        | Sequence within the second   | `0`, `1`, `2`                                |
+/// | `{strftime:<format>}` | Time in a [`chrono` format]  | `2022/04/01` (for `{strftime:%Y/%m/%d}`)     |
+/// | `{strftime_utc:<format>}` | UTC time in a [`chrono` format] | `2022/04/01` (for `{strftime_utc:%Y/%m/%d}`) |
+/// | `{datetime:<format>}` | Same as `{strftime:<format>}` | `20220401T22:28` (for `{datetime:%Y%m%dT%H:%M}`) |
 /// | `{full}`              | Full log message             | See [`FullFormatter`]                        |
 /// | `{level}`             | Log level                    | `critical`, `error`, `warn`                  |
 /// | `{level_short}`       | Short log level              | `C`, `E`, `W`                                |
+/// | `{level:spdlog}`      | Log level in C++ spdlog      | `critical`, `error`, `warning`               |
 /// | `{source}`            | Source file and line         | `path/to/main.rs:30` [^1]                    |
 /// | `{file_name}`         | Source file name             | `main.rs` [^1]                               |
 /// | `{file}`              | Source file path             | `path/to/main.rs` [^1]                       |
@@ -341,21 +414,44 @@ use crate::{
 /// | `{column}`            | Source file column           | `20` [^1]                                    |
 /// | `{module_path}`       | Source module path           | `mod::module` [^1]                           |
 /// | `{logger}`            | Logger name                  | `my-logger`                                  |
+/// | `{logger:short}`      | Short logger name            | `http` (for `app::net::http`)                |
 /// | `{payload}`           | Log payload                  | `log message`                                |
+/// | `{duration}`          | Duration from the log site   | `1.5s`, `250ms` (see [`Record::duration`])   |
 /// | `{pid}`               | Process ID                   | `3824`                                       |
 /// | `{tid}`               | Thread ID                    | `3132`                                       |
+/// | `{tid:hex}`           | Thread ID in hexadecimal     | `0xc3c`                                      |
+/// | `{thread_prio}`       | Thread scheduling priority   | `0`, `-5` [^3]                               |
+/// | `{disk_free}`         | Free bytes on target's disk  | `52848451584` [^2]                           |
+/// | `{sink_count}`        | Number of sinks of logger    | `3` [^4]                                     |
+/// | `{version}`           | Application version          | `1.2.3` (see [`set_app_version`])            |
+/// | `{const:<literal>}`   | The literal as is            | `us-east-1` (for `{const:us-east-1}`)        |
 /// | `{eol}`               | End of line                  | `\n` (on non-Windows) or `\r\n` (on Windows) |
 /// 
 /// [^1]: Patterns related to source location require that feature
 ///       `source-location` is enabled, otherwise the output is empty.
 ///
+/// [^2]: Only sinks writing to files provide a target directory, for other
+///       sinks the output is empty.
+///
+/// [^3]: The priority of the thread formatting the record, only supported on
+///       Linux (nice value) and Windows, otherwise the output is empty.
+///
+/// [^4]: The number of sinks of the logger that logged the record, for
+///       self-diagnostics. A combined sink (e.g. [`AsyncPoolSink`]) counts as
+///       one sink. For records not logged through a logger, the output is `0`.
+///
 /// [`FullFormatter`]: crate::formatter::FullFormatter
+/// [`AsyncPoolSink`]: crate::sink::AsyncPoolSink
+/// [`set_app_version`]: crate::formatter::set_app_version
+/// [`Record::duration`]: crate::Record::duration
+/// [`chrono` format]: https://docs.rs/chrono/latest/chrono/format/strftime/index.html
 pub use ::spdlog_macros::pattern;
 
 /// A formatter that formats log records according to a specified pattern.
 #[derive(Clone)]
 pub struct PatternFormatter<P> {
     pattern: P,
+    utc: bool,
 }
 
 impl<P> PatternFormatter<P>
@@ -368,7 +464,31 @@ where
     /// calling [`pattern!`] macro.
     #[must_use]
     pub fn new(pattern: P) -> Self {
-        Self { pattern }
+        Self {
+            pattern,
+            utc: false,
+        }
+    }
+
+    /// Specifies whether the datetime patterns write the time in UTC instead
+    /// of the local timezone.
+    ///
+    /// This affects all the datetime patterns, e.g. `{datetime}`, `{hour}` and
+    /// `{strftime:<format>}`, and `{tz_offset}` writes `+00:00` accordingly.
+    /// Defaults to `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spdlog::formatter::{pattern, PatternFormatter};
+    ///
+    /// let formatter =
+    ///     PatternFormatter::new(pattern!("[{date} {time} {tz_offset}] {payload}{eol}")).use_utc(true);
+    /// ```
+    #[must_use]
+    pub fn use_utc(mut self, utc: bool) -> Self {
+        self.utc = utc;
+        self
     }
 }
 
@@ -377,7 +497,19 @@ where
     P: 'static + Clone + Pattern,
 {
     fn format(&self, record: &Record, dest: &mut StringBuf) -> crate::Result<FmtExtraInfo> {
-        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+        self.format_for_target(record, dest, None)
+    }
+
+    fn format_for_target(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        target_path: Option<&Path>,
+    ) -> crate::Result<FmtExtraInfo> {
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default())
+            .with_target_path(target_path)
+            .with_sink_count(record.sink_count())
+            .with_utc(self.utc);
         self.pattern.format(record, dest, &mut ctx)?;
         Ok(ctx.fmt_info_builder.build())
     }
@@ -389,15 +521,68 @@ where
 
 /// Provide context for patterns.
 #[derive(Clone, Debug)]
-pub struct PatternContext {
-    fmt_info_builder: FmtExtraInfoBuilder,
+pub struct PatternContext<'a> {
+    pub(crate) fmt_info_builder: FmtExtraInfoBuilder,
+    target_path: Option<&'a Path>,
+    sink_count: Option<usize>,
+    utc: bool,
+    empty_field_seen: bool,
 }
 
-impl PatternContext {
+impl<'a> PatternContext<'a> {
     /// Create a new `PatternContext` object.
     #[must_use]
-    fn new(fmt_info_builder: FmtExtraInfoBuilder) -> Self {
-        Self { fmt_info_builder }
+    pub(crate) fn new(fmt_info_builder: FmtExtraInfoBuilder) -> Self {
+        Self {
+            fmt_info_builder,
+            target_path: None,
+            sink_count: None,
+            utc: false,
+            empty_field_seen: false,
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn with_target_path(mut self, target_path: Option<&'a Path>) -> Self {
+        self.target_path = target_path;
+        self
+    }
+
+    /// Gets the directory that the formatted message will be written into.
+    ///
+    /// Returns `None` if the sink does not write to the file system. See
+    /// [`Formatter::format_for_target`] for details.
+    #[must_use]
+    pub fn target_path(&self) -> Option<&'a Path> {
+        self.target_path
+    }
+
+    #[must_use]
+    pub(crate) fn with_sink_count(mut self, sink_count: Option<usize>) -> Self {
+        self.sink_count = sink_count;
+        self
+    }
+
+    /// Gets the number of sinks of the logger that logged the record, which is
+    /// written by the `{sink_count}` pattern.
+    ///
+    /// Returns `None` if the record was not logged through a logger.
+    #[must_use]
+    pub fn sink_count(&self) -> Option<usize> {
+        self.sink_count
+    }
+
+    #[must_use]
+    pub(crate) fn with_utc(mut self, utc: bool) -> Self {
+        self.utc = utc;
+        self
+    }
+
+    /// Determines if the time should be written in UTC instead of the local
+    /// timezone, see [`PatternFormatter::use_utc`].
+    #[must_use]
+    pub fn is_utc(&self) -> bool {
+        self.utc
     }
 
     /// Set the style range of the log message written by the patterns.
@@ -432,8 +617,9 @@ pub trait Pattern: Send + Sync {
     /// Format this pattern against the given log record and write the formatted
     /// message into the output buffer.
     ///
-    /// **For implementors:** the `ctx` parameter is reserved for future use.
-    /// For now, please ignore it.
+    /// **For implementors:** the `ctx` parameter provides information about
+    /// the formatting, e.g. [`PatternContext::target_path`]. Patterns that do
+    /// not need it can ignore it.
     fn format(
         &self,
         record: &Record,
@@ -465,7 +651,7 @@ impl Pattern for str {
     }
 }
 
-impl<'a, T> Pattern for &'a T
+impl<T> Pattern for &T
 where
     T: ?Sized + Pattern,
 {
@@ -479,7 +665,7 @@ where
     }
 }
 
-impl<'a, T> Pattern for &'a mut T
+impl<T> Pattern for &mut T
 where
     T: ?Sized + Pattern,
 {
@@ -1205,6 +1391,7 @@ tuple_pattern! {
 }
 
 #[cfg(test)]
+#[allow(missing_docs)]
 pub mod tests {
     use super::*;
     use crate::{Level, SourceLocation};
@@ -1249,14 +1436,14 @@ pub mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn test_pattern_ref_as_pattern() {
-        #[allow(clippy::needless_borrow)]
         test_pattern(&String::from("literal"), "literal", None);
     }
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args)]
     fn test_pattern_mut_as_pattern() {
-        #[allow(clippy::needless_borrow)]
         test_pattern(&mut String::from("literal"), "literal", None);
     }
 
@@ -1328,4 +1515,19 @@ pub mod tests {
     fn test_unit_as_pattern() {
         test_pattern((), "", None);
     }
+
+    #[test]
+    fn test_format_into_reused_buffer() {
+        let formatter = PatternFormatter::new(("[", __pattern::Level, "] ", __pattern::Payload));
+        let mut buf = StringBuf::new();
+
+        let record = Record::new(Level::Info, "first");
+        formatter.format_into(&record, &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "[info] first");
+
+        let record = Record::new(Level::Error, "second");
+        let fmt_info = formatter.format_into(&record, &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "[error] second");
+        assert_eq!(fmt_info.style_range(), None);
+    }
 }
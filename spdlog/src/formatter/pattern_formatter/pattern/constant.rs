@@ -0,0 +1,40 @@
+use std::borrow::Cow;
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    Record, StringBuf,
+};
+
+/// A pattern that writes a constant literal into the output, e.g. a region
+/// code.
+///
+/// It's what the `{const:<literal>}` placeholder creates, which reads better
+/// than plain template text when the value is a field of the output rather
+/// than decoration. The literal cannot contain `{` or `}` there, plain
+/// template text with `{{` and `}}` can be used instead.
+#[derive(Clone, Debug, Default)]
+pub struct Constant {
+    value: Cow<'static, str>,
+}
+
+impl Constant {
+    /// Constructs a `Constant` pattern writing the given literal.
+    #[must_use]
+    pub fn new(value: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            value: value.into(),
+        }
+    }
+}
+
+impl Pattern for Constant {
+    fn format(
+        &self,
+        _record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        dest.push_str(&self.value);
+        Ok(())
+    }
+}
@@ -1,10 +1,22 @@
-use std::{fmt::Write, marker::PhantomData};
+use std::{
+    borrow::Cow,
+    fmt::Write,
+    marker::PhantomData,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Utc,
+};
 
 use crate::{
+    error::InvalidArgumentError,
     formatter::{
-        local_time_cacher::LOCAL_TIME_CACHER,
+        local_time_cacher::{self, TimeDate},
         pattern_formatter::{Pattern, PatternContext},
     },
+    sync::SpinMutex,
     Error, Record, StringBuf,
 };
 
@@ -18,13 +30,12 @@ impl Pattern for AbbrWeekdayName {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let name = LOCAL_TIME_CACHER
-            .lock()
-            .get(record.time())
-            .weekday_name()
-            .short;
+        let name = with_cached_time(record, ctx.is_utc(), |cached_time| {
+            cached_time.weekday_name()
+        })
+        .short;
 
         dest.push_str(name);
         Ok(())
@@ -41,13 +52,12 @@ impl Pattern for WeekdayName {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let name = LOCAL_TIME_CACHER
-            .lock()
-            .get(record.time())
-            .weekday_name()
-            .full;
+        let name = with_cached_time(record, ctx.is_utc(), |cached_time| {
+            cached_time.weekday_name()
+        })
+        .full;
 
         dest.push_str(name);
         Ok(())
@@ -64,13 +74,10 @@ impl Pattern for AbbrMonthName {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let name = LOCAL_TIME_CACHER
-            .lock()
-            .get(record.time())
-            .month_name()
-            .short;
+        let name =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.month_name()).short;
 
         dest.push_str(name);
         Ok(())
@@ -87,13 +94,10 @@ impl Pattern for MonthName {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let name = LOCAL_TIME_CACHER
-            .lock()
-            .get(record.time())
-            .month_name()
-            .full;
+        let name =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.month_name()).full;
 
         dest.push_str(name);
         Ok(())
@@ -110,7 +114,7 @@ impl Pattern for FullDateTime {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
         let (
             abbr_weekday_name,
@@ -120,9 +124,7 @@ impl Pattern for FullDateTime {
             minute_str,
             second_str,
             year_str,
-        ) = {
-            let mut time_cacher_lock = LOCAL_TIME_CACHER.lock();
-            let cached_time = time_cacher_lock.get(record.time());
+        ) = with_cached_time(record, ctx.is_utc(), |cached_time| {
             (
                 cached_time.weekday_name().short,
                 cached_time.month_name().short,
@@ -132,9 +134,9 @@ impl Pattern for FullDateTime {
                 cached_time.second_str(),
                 cached_time.year_str(),
             )
-        };
+        });
 
-        (|| {
+        {
             dest.push_str(abbr_weekday_name);
             dest.push(' ');
             dest.push_str(abbr_month_name);
@@ -148,7 +150,7 @@ impl Pattern for FullDateTime {
             dest.push_str(&second_str);
             dest.push(' ');
             dest.push_str(&year_str)
-        })();
+        }
         Ok(())
     }
 }
@@ -163,9 +165,11 @@ impl Pattern for ShortYear {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let year_short_str = LOCAL_TIME_CACHER.lock().get(record.time()).year_short_str();
+        let year_short_str = with_cached_time(record, ctx.is_utc(), |cached_time| {
+            cached_time.year_short_str()
+        });
         dest.push_str(&year_short_str);
         Ok(())
     }
@@ -181,9 +185,9 @@ impl Pattern for Year {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let year_str = LOCAL_TIME_CACHER.lock().get(record.time()).year_str();
+        let year_str = with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.year_str());
         dest.push_str(&year_str);
         Ok(())
     }
@@ -199,25 +203,24 @@ impl Pattern for Date {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let (month_str, day_str, year_str) = {
-            let mut local_cacher_lock = LOCAL_TIME_CACHER.lock();
-            let cached_time = local_cacher_lock.get(record.time());
-            (
-                cached_time.month_str(),
-                cached_time.day_str(),
-                cached_time.year_str(),
-            )
-        };
-
-        (|| {
+        let (month_str, day_str, year_str) =
+            with_cached_time(record, ctx.is_utc(), |cached_time| {
+                (
+                    cached_time.month_str(),
+                    cached_time.day_str(),
+                    cached_time.year_str(),
+                )
+            });
+
+        {
             dest.push_str(&year_str);
             dest.push('-');
             dest.push_str(&month_str);
             dest.push('-');
             dest.push_str(&day_str)
-        })();
+        }
         Ok(())
     }
 }
@@ -232,25 +235,24 @@ impl Pattern for ShortDate {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let (month_str, day_str, year_short_str) = {
-            let mut local_cacher_lock = LOCAL_TIME_CACHER.lock();
-            let cached_time = local_cacher_lock.get(record.time());
-            (
-                cached_time.month_str(),
-                cached_time.day_str(),
-                cached_time.year_short_str(),
-            )
-        };
-
-        (|| {
+        let (month_str, day_str, year_short_str) =
+            with_cached_time(record, ctx.is_utc(), |cached_time| {
+                (
+                    cached_time.month_str(),
+                    cached_time.day_str(),
+                    cached_time.year_short_str(),
+                )
+            });
+
+        {
             dest.push_str(&month_str);
             dest.push('/');
             dest.push_str(&day_str);
             dest.push('/');
             dest.push_str(&year_short_str)
-        })();
+        }
         Ok(())
     }
 }
@@ -265,9 +267,10 @@ impl Pattern for Month {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let month_str = LOCAL_TIME_CACHER.lock().get(record.time()).month_str();
+        let month_str =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.month_str());
         dest.push_str(&month_str);
         Ok(())
     }
@@ -283,9 +286,9 @@ impl Pattern for Day {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let day_str = LOCAL_TIME_CACHER.lock().get(record.time()).day_str();
+        let day_str = with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.day_str());
         dest.push_str(&day_str);
         Ok(())
     }
@@ -301,9 +304,9 @@ impl Pattern for Hour {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let hour_str = LOCAL_TIME_CACHER.lock().get(record.time()).hour_str();
+        let hour_str = with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.hour_str());
         dest.push_str(&hour_str);
         Ok(())
     }
@@ -319,9 +322,10 @@ impl Pattern for Hour12 {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let hour_12_str = LOCAL_TIME_CACHER.lock().get(record.time()).hour12_str();
+        let hour_12_str =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.hour12_str());
         dest.push_str(&hour_12_str);
         Ok(())
     }
@@ -337,9 +341,10 @@ impl Pattern for Minute {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let minute_str = LOCAL_TIME_CACHER.lock().get(record.time()).minute_str();
+        let minute_str =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.minute_str());
         dest.push_str(&minute_str);
         Ok(())
     }
@@ -355,9 +360,10 @@ impl Pattern for Second {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let second_str = LOCAL_TIME_CACHER.lock().get(record.time()).second_str();
+        let second_str =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.second_str());
         dest.push_str(&second_str);
         Ok(())
     }
@@ -377,9 +383,11 @@ impl Pattern for Millisecond {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let millisecond = LOCAL_TIME_CACHER.lock().get(record.time()).millisecond();
+        let millisecond = with_cached_time(record, ctx.is_utc(), |cached_time| {
+            cached_time.millisecond()
+        });
         write!(dest, "{:03}", millisecond).map_err(Error::FormatRecord)
     }
 }
@@ -394,9 +402,10 @@ impl Pattern for Microsecond {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let nanosecond = LOCAL_TIME_CACHER.lock().get(record.time()).nanosecond();
+        let nanosecond =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.nanosecond());
         write!(dest, "{:06}", nanosecond / 1_000).map_err(Error::FormatRecord)
     }
 }
@@ -411,9 +420,10 @@ impl Pattern for Nanosecond {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let nanosecond = LOCAL_TIME_CACHER.lock().get(record.time()).nanosecond();
+        let nanosecond =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.nanosecond());
         write!(dest, "{:09}", nanosecond).map_err(Error::FormatRecord)
     }
 }
@@ -428,9 +438,10 @@ impl Pattern for AmPm {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let am_pm_str = LOCAL_TIME_CACHER.lock().get(record.time()).am_pm_str();
+        let am_pm_str =
+            with_cached_time(record, ctx.is_utc(), |cached_time| cached_time.am_pm_str());
         dest.push_str(am_pm_str);
         Ok(())
     }
@@ -446,20 +457,19 @@ impl Pattern for Time12 {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let (hour_str, minute_str, second_str, am_pm_str) = {
-            let mut time_cacher_lock = LOCAL_TIME_CACHER.lock();
-            let cached_time = time_cacher_lock.get(record.time());
-            (
-                cached_time.hour12_str(),
-                cached_time.minute_str(),
-                cached_time.second_str(),
-                cached_time.am_pm_str(),
-            )
-        };
-
-        (|| {
+        let (hour_str, minute_str, second_str, am_pm_str) =
+            with_cached_time(record, ctx.is_utc(), |cached_time| {
+                (
+                    cached_time.hour12_str(),
+                    cached_time.minute_str(),
+                    cached_time.second_str(),
+                    cached_time.am_pm_str(),
+                )
+            });
+
+        {
             dest.push_str(&hour_str);
             dest.push(':');
             dest.push_str(&minute_str);
@@ -467,7 +477,7 @@ impl Pattern for Time12 {
             dest.push_str(&second_str);
             dest.push(' ');
             dest.push_str(am_pm_str)
-        })();
+        }
         Ok(())
     }
 }
@@ -482,19 +492,17 @@ impl Pattern for ShortTime {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let (hour_str, minute_str) = {
-            let mut time_cacher_lock = LOCAL_TIME_CACHER.lock();
-            let cached_time = time_cacher_lock.get(record.time());
+        let (hour_str, minute_str) = with_cached_time(record, ctx.is_utc(), |cached_time| {
             (cached_time.hour_str(), cached_time.minute_str())
-        };
+        });
 
-        (|| {
+        {
             dest.push_str(&hour_str);
             dest.push(':');
             dest.push_str(&minute_str)
-        })();
+        }
         Ok(())
     }
 }
@@ -509,25 +517,24 @@ impl Pattern for Time {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let (hour_str, minute_str, second_str) = {
-            let mut time_cacher_lock = LOCAL_TIME_CACHER.lock();
-            let cached_time = time_cacher_lock.get(record.time());
-            (
-                cached_time.hour_str(),
-                cached_time.minute_str(),
-                cached_time.second_str(),
-            )
-        };
-
-        (|| {
+        let (hour_str, minute_str, second_str) =
+            with_cached_time(record, ctx.is_utc(), |cached_time| {
+                (
+                    cached_time.hour_str(),
+                    cached_time.minute_str(),
+                    cached_time.second_str(),
+                )
+            });
+
+        {
             dest.push_str(&hour_str);
             dest.push(':');
             dest.push_str(&minute_str);
             dest.push(':');
             dest.push_str(&second_str)
-        })();
+        }
         Ok(())
     }
 }
@@ -542,9 +549,11 @@ impl Pattern for TzOffset {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let tz_offset_str = LOCAL_TIME_CACHER.lock().get(record.time()).tz_offset_str();
+        let tz_offset_str = with_cached_time(record, ctx.is_utc(), |cached_time| {
+            cached_time.tz_offset_str()
+        });
         dest.push_str(&tz_offset_str);
         Ok(())
     }
@@ -560,13 +569,314 @@ impl Pattern for UnixTimestamp {
         &self,
         record: &Record,
         dest: &mut StringBuf,
-        _ctx: &mut PatternContext,
+        ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        let unix_timestamp_str = LOCAL_TIME_CACHER
-            .lock()
-            .get(record.time())
-            .unix_timestamp_str();
+        let unix_timestamp_str = with_cached_time(record, ctx.is_utc(), |cached_time| {
+            cached_time.unix_timestamp_str()
+        });
         dest.push_str(&unix_timestamp_str);
         Ok(())
     }
 }
+
+/// A pattern that writes the sequence number of log records within their
+/// second into the output. Examples: `0`, `1`, `2`.
+///
+/// The sequence is `0` for the first record of a second, and increments for
+/// each following record of the same second. This helps ordering records that
+/// share the same timestamp in high-rate logs.
+///
+/// The state is owned by the pattern, so each formatter counts separately, and
+/// a cloned pattern starts counting from scratch.
+#[derive(Default)]
+pub struct SubsecSeq {
+    // The last seen second and the next sequence number within it.
+    state: SpinMutex<Option<(u64, u64)>>,
+}
+
+impl Clone for SubsecSeq {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Pattern for SubsecSeq {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        let second = unix_second(record.time());
+        let seq = {
+            let mut state = self.state.lock();
+            let seq = match *state {
+                Some((last_second, next_seq)) if last_second == second => next_seq,
+                _ => 0,
+            };
+            *state = Some((second, seq + 1));
+            seq
+        };
+        write!(dest, "{}", seq).map_err(Error::FormatRecord)
+    }
+}
+
+// Calls the function with the cached time of the record, in UTC if `utc` is
+// `true`, otherwise in the local timezone.
+fn with_cached_time<R>(record: &Record, utc: bool, f: impl FnOnce(TimeDate) -> R) -> R {
+    if utc {
+        local_time_cacher::with_utc_time(record.time(), f)
+    } else {
+        local_time_cacher::with_local_time(record.time(), f)
+    }
+}
+
+#[must_use]
+fn unix_second(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// A pattern that writes the time of log records into the output, formatted
+/// by a [`chrono` format string]. Example: `2022/04/01` for `%Y/%m/%d`.
+///
+/// It complements the other datetime patterns for formats they do not cover.
+/// The time is in the local timezone, or in UTC if constructed by
+/// [`Strftime::new_utc`] or used by a [`PatternFormatter`] with
+/// [`PatternFormatter::use_utc`].
+///
+/// In [`pattern!`] templates, it is written as `{strftime:<format>}` or
+/// `{strftime_utc:<format>}`, e.g. `{strftime:%Y/%m/%d %H:%M}`. The local
+/// time form can also be written as `{datetime:<format>}`, e.g.
+/// `{datetime:%Y%m%dT%H%M%S}`. The format string is validated at compile-time
+/// there.
+///
+/// All specifiers of `chrono` are supported, including `%.3f`-like fractional
+/// seconds and `%z` timezone offsets. The format string is parsed once when
+/// the pattern is constructed rather than for each record.
+///
+/// [`chrono` format string]: https://docs.rs/chrono/latest/chrono/format/strftime/index.html
+/// [`pattern!`]: crate::formatter::pattern
+/// [`PatternFormatter`]: crate::formatter::PatternFormatter
+/// [`PatternFormatter::use_utc`]: crate::formatter::PatternFormatter::use_utc
+#[derive(Clone)]
+pub struct Strftime {
+    items: Vec<Item<'static>>,
+    utc: bool,
+}
+
+impl Strftime {
+    /// Constructs a `Strftime` pattern formatting the local time with the given
+    /// format string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the format string is invalid, use [`Strftime::try_new`] to
+    /// handle the error instead.
+    #[must_use]
+    pub fn new(format: impl Into<Cow<'static, str>>) -> Self {
+        Self::try_new(format, false).unwrap()
+    }
+
+    /// Constructs a `Strftime` pattern formatting the UTC time with the given
+    /// format string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the format string is invalid, use [`Strftime::try_new`] to
+    /// handle the error instead.
+    #[must_use]
+    pub fn new_utc(format: impl Into<Cow<'static, str>>) -> Self {
+        Self::try_new(format, true).unwrap()
+    }
+
+    /// Constructs a `Strftime` pattern with the given format string, formatting
+    /// the UTC time if `utc` is `true`, otherwise the local time.
+    ///
+    /// # Errors
+    ///
+    /// If the format string is invalid, [`Error::InvalidArgument`] will be
+    /// returned.
+    pub fn try_new(format: impl Into<Cow<'static, str>>, utc: bool) -> crate::Result<Self> {
+        let format = format.into();
+        let items: Vec<_> = StrftimeItems::new(&format).map(owned_item).collect();
+        if items.iter().any(|item| matches!(item, Item::Error)) {
+            return Err(Error::InvalidArgument(
+                InvalidArgumentError::StrftimeFormat(format.into_owned()),
+            ));
+        }
+        Ok(Self { items, utc })
+    }
+}
+
+#[must_use]
+fn owned_item(item: Item) -> Item<'static> {
+    match item {
+        Item::Literal(literal) => Item::OwnedLiteral(literal.into()),
+        Item::OwnedLiteral(literal) => Item::OwnedLiteral(literal),
+        Item::Space(space) => Item::OwnedSpace(space.into()),
+        Item::OwnedSpace(space) => Item::OwnedSpace(space),
+        Item::Numeric(numeric, pad) => Item::Numeric(numeric, pad),
+        Item::Fixed(fixed) => Item::Fixed(fixed),
+        Item::Error => Item::Error,
+    }
+}
+
+impl Pattern for Strftime {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        let result = if self.utc || ctx.is_utc() {
+            let utc_time: DateTime<Utc> = record.time().into();
+            write!(dest, "{}", utc_time.format_with_items(self.items.iter()))
+        } else {
+            let local_time = with_cached_time(record, false, |cached_time| cached_time.time());
+            write!(dest, "{}", local_time.format_with_items(self.items.iter()))
+        };
+        result.map_err(Error::FormatRecord)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use chrono::prelude::*;
+
+    use super::*;
+    use crate::{formatter::FmtExtraInfoBuilder, Level};
+
+    #[test]
+    fn strftime() {
+        let mut record = Record::new(Level::Info, "payload");
+        record.set_time(
+            Local
+                .with_ymd_and_hms(2012, 3, 4, 5, 6, 7)
+                .unwrap()
+                .with_nanosecond(8_000_000)
+                .unwrap()
+                .into(),
+        );
+
+        let mut dest = StringBuf::new();
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+        Strftime::new("%Y/%m/%d %H:%M:%S%.3f")
+            .format(&record, &mut dest, &mut ctx)
+            .unwrap();
+        assert_eq!(dest.as_str(), "2012/03/04 05:06:07.008");
+    }
+
+    #[test]
+    fn strftime_matches_chrono() {
+        let local_time = Local
+            .with_ymd_and_hms(2021, 12, 31, 23, 4, 5)
+            .unwrap()
+            .with_nanosecond(678_901_234)
+            .unwrap();
+        let utc_time: DateTime<Utc> = local_time.into();
+        let mut record = Record::new(Level::Info, "payload");
+        record.set_time(local_time.into());
+
+        let specs = [
+            "%Y-%m-%d %H:%M:%S",
+            "%a %b %e %T %Y",
+            "%I:%M %p",
+            "%j %U %u",
+            "%s.%f",
+            "%.3f %z",
+            "%% literal",
+        ];
+        for spec in specs {
+            for (pattern, expected) in [
+                (Strftime::new(spec), local_time.format(spec).to_string()),
+                (Strftime::new_utc(spec), utc_time.format(spec).to_string()),
+            ] {
+                let mut dest = StringBuf::new();
+                let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+                pattern.format(&record, &mut dest, &mut ctx).unwrap();
+                assert_eq!(dest.as_str(), expected, "spec: {}", spec);
+            }
+        }
+    }
+
+    #[test]
+    fn utc() {
+        let utc_time = Utc.with_ymd_and_hms(2021, 12, 31, 23, 4, 5).unwrap();
+        let mut record = Record::new(Level::Info, "payload");
+        record.set_time(utc_time.into());
+
+        let mut dest = StringBuf::new();
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default()).with_utc(true);
+        (Date, " ", Time, " ", TzOffset, " ", Strftime::new("%H"))
+            .format(&record, &mut dest, &mut ctx)
+            .unwrap();
+        assert_eq!(dest.as_str(), "2021-12-31 23:04:05 +00:00 23");
+    }
+
+    #[test]
+    fn strftime_invalid_format() {
+        assert!(matches!(
+            Strftime::try_new("%Y-%Q", false),
+            Err(Error::InvalidArgument(
+                InvalidArgumentError::StrftimeFormat(_)
+            ))
+        ));
+        assert!(Strftime::try_new("%Y-%m", true).is_ok());
+    }
+
+    #[test]
+    fn subsec_seq() {
+        let pattern = SubsecSeq::default();
+        let mut record = Record::new(Level::Info, "payload");
+        let mut format_at = |time: SystemTime| {
+            record.set_time(time);
+            let mut dest = StringBuf::new();
+            let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+            pattern.format(&record, &mut dest, &mut ctx).unwrap();
+            String::from(dest.as_str())
+        };
+
+        let second = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(format_at(second), "0");
+        assert_eq!(format_at(second + Duration::from_millis(1)), "1");
+        assert_eq!(format_at(second + Duration::from_millis(999)), "2");
+
+        let next_second = second + Duration::from_secs(1);
+        assert_eq!(format_at(next_second), "0");
+        assert_eq!(format_at(next_second + Duration::from_millis(500)), "1");
+
+        // A cloned pattern starts counting from scratch.
+        assert_eq!(*pattern.clone().state.lock(), None);
+    }
+
+    #[test]
+    fn nanosecond_precision() {
+        let format = |pattern: &dyn Pattern, record: &Record| {
+            let mut dest = StringBuf::new();
+            let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+            pattern.format(record, &mut dest, &mut ctx).unwrap();
+            String::from(dest.as_str())
+        };
+
+        let mut record = Record::new(Level::Info, "payload");
+        let second = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        // Two records in the same second share the cached values of the
+        // cacher, their sub-second parts must still be kept independently.
+        record.set_time(second + Duration::from_nanos(123_456_789));
+        assert_eq!(format(&Nanosecond, &record), "123456789");
+        assert_eq!(format(&Microsecond, &record), "123456");
+        assert_eq!(format(&Millisecond::default(), &record), "123");
+
+        record.set_time(second + Duration::from_nanos(1));
+        assert_eq!(format(&Nanosecond, &record), "000000001");
+
+        // Also survives the conversion for asynchronous sinks.
+        record.set_time(second + Duration::from_nanos(987_654_321));
+        let owned = record.to_owned();
+        assert_eq!(format(&Nanosecond, &owned.as_ref()), "987654321");
+    }
+}
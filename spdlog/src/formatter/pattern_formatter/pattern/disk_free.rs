@@ -0,0 +1,114 @@
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    utils, Error, Record, StringBuf,
+};
+
+/// A pattern that writes the number of free bytes on the file system of the
+/// sink's target directory into the output. Example: `52848451584`.
+///
+/// Only sinks writing to files provide a target directory, see
+/// [`PatternContext::target_path`]. For other sinks, or if querying the file
+/// system fails, this pattern writes nothing.
+///
+/// # Implementation
+///
+/// On unix-like systems such as Linux and macOS, this pattern writes the
+/// available space reported by `statvfs`.
+///
+/// On Windows, this pattern writes the available space reported by
+/// `GetDiskFreeSpaceExW`.
+///
+/// On other systems, this pattern writes nothing.
+///
+/// The value is queried at most once per second for each target directory and
+/// thread, so it may lag behind the actual free space.
+#[derive(Clone, Default)]
+pub struct DiskFree;
+
+impl Pattern for DiskFree {
+    fn format(
+        &self,
+        _record: &Record,
+        dest: &mut StringBuf,
+        ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        match ctx.target_path().and_then(cached_disk_free_space) {
+            Some(free_bytes) => write!(dest, "{}", free_bytes).map_err(Error::FormatRecord),
+            None => Ok(()),
+        }
+    }
+}
+
+const CACHE_DURATION: Duration = Duration::from_secs(1);
+
+struct CachedDiskFree {
+    dir: PathBuf,
+    queried_at: Instant,
+    free_bytes: Option<u64>,
+}
+
+thread_local! {
+    // Querying the file system is a syscall, which is too expensive to make for
+    // every record.
+    static CACHE: RefCell<Vec<CachedDiskFree>> = const { RefCell::new(Vec::new()) };
+}
+
+#[must_use]
+fn cached_disk_free_space(dir: &Path) -> Option<u64> {
+    let query = || utils::disk_free_space(dir).ok();
+
+    CACHE
+        .try_with(|cache| {
+            let mut cache = cache.borrow_mut();
+            let now = Instant::now();
+            match cache.iter_mut().find(|cached| cached.dir == dir) {
+                Some(cached) => {
+                    if now.duration_since(cached.queried_at) >= CACHE_DURATION {
+                        cached.queried_at = now;
+                        cached.free_bytes = query();
+                    }
+                    cached.free_bytes
+                }
+                None => {
+                    let free_bytes = query();
+                    cache.push(CachedDiskFree {
+                        dir: dir.to_owned(),
+                        queried_at: now,
+                        free_bytes,
+                    });
+                    free_bytes
+                }
+            }
+        })
+        // The thread local has been destroyed, e.g. logging in a destructor
+        .unwrap_or_else(|_| query())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formatter::FmtExtraInfoBuilder, test_utils::*, Level};
+
+    #[test]
+    fn format() {
+        let record = Record::new(Level::Info, "payload");
+
+        let mut buf = StringBuf::new();
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default())
+            .with_target_path(Some(TEST_LOGS_PATH.as_path()));
+        DiskFree.format(&record, &mut buf, &mut ctx).unwrap();
+        buf.as_str().parse::<u64>().unwrap();
+
+        let mut buf = StringBuf::new();
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+        DiskFree.format(&record, &mut buf, &mut ctx).unwrap();
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt::Write;
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    Error, Record, StringBuf,
+};
+
+/// A pattern that writes the duration attached to log records at the log site
+/// into the output. Examples: `1.5s`, `250ms`, `12.3µs`.
+///
+/// The duration is written with a human-readable unit, in the same way as the
+/// `Debug` output of [`Duration`]. If the record has no duration attached, this
+/// pattern writes nothing. See [`Record::duration`].
+///
+/// [`Duration`]: std::time::Duration
+#[derive(Clone, Default)]
+pub struct RecordDuration;
+
+impl Pattern for RecordDuration {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        match record.duration() {
+            Some(duration) => write!(dest, "{:?}", duration).map_err(Error::FormatRecord),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{formatter::FmtExtraInfoBuilder, Level};
+
+    #[test]
+    fn format() {
+        let format = |record: &Record| {
+            let mut dest = StringBuf::new();
+            let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+            RecordDuration.format(record, &mut dest, &mut ctx).unwrap();
+            String::from(dest.as_str())
+        };
+
+        let record = Record::builder(Level::Info, "payload")
+            .duration(Some(Duration::from_millis(1500)))
+            .build();
+        assert_eq!(format(&record), "1.5s");
+
+        let record = Record::builder(Level::Info, "payload")
+            .duration(Some(Duration::from_micros(250)))
+            .build();
+        assert_eq!(format(&record), "250µs");
+
+        assert_eq!(format(&Record::new(Level::Info, "payload")), "");
+    }
+}
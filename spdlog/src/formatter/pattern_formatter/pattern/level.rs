@@ -22,6 +22,10 @@ impl Pattern for Level {
 
 /// A pattern that writes the level in a shorter form of a log record into the
 /// output. Examples: `C`, `E`, `W`.
+///
+/// Each level is written as a distinct uppercase ASCII character, the first
+/// letter of its name: `C` (critical), `E` (error), `W` (warn), `I` (info),
+/// `D` (debug) and `T` (trace).
 #[derive(Clone, Default)]
 pub struct ShortLevel;
 
@@ -36,3 +40,67 @@ impl Pattern for ShortLevel {
         Ok(())
     }
 }
+
+/// A pattern that writes the level of a log record into the output, using the
+/// same names as C++ spdlog. Examples: `critical`, `error`, `warning`.
+///
+/// The only difference from [`Level`] is that [`Warn`] is written as `warning`
+/// instead of `warn`, which keeps logs comparable with C++ spdlog's output.
+///
+/// [`Warn`]: crate::Level::Warn
+#[derive(Clone, Default)]
+pub struct SpdlogLevel;
+
+impl SpdlogLevel {
+    /// Constructs a `SpdlogLevel` pattern for the `{level:spdlog}` placeholder.
+    ///
+    /// The variant has been checked by the `pattern!` macro.
+    #[must_use]
+    pub fn with_variant(_variant: &str) -> Self {
+        Self
+    }
+}
+
+impl Pattern for SpdlogLevel {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        dest.push_str(spdlog_level_name(record.level()));
+        Ok(())
+    }
+}
+
+#[must_use]
+fn spdlog_level_name(level: crate::Level) -> &'static str {
+    match level {
+        crate::Level::Warn => "warning",
+        level => level.as_str(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spdlog_level_names() {
+        use crate::Level;
+
+        // The names used by C++ spdlog, see `SPDLOG_LEVEL_NAMES` in its
+        // `common.h`.
+        let expected = [
+            (Level::Critical, "critical"),
+            (Level::Error, "error"),
+            (Level::Warn, "warning"),
+            (Level::Info, "info"),
+            (Level::Debug, "debug"),
+            (Level::Trace, "trace"),
+        ];
+        for (level, name) in expected {
+            assert_eq!(spdlog_level_name(level), name);
+        }
+    }
+}
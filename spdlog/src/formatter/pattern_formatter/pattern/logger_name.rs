@@ -19,3 +19,65 @@ impl Pattern for LoggerName {
         Ok(())
     }
 }
+
+/// A pattern that writes the last component of the logger's name into the
+/// output, i.e. the part after the last `::`. Example: `http` for a logger
+/// named `app::net::http`.
+///
+/// Names without `::` are written unchanged.
+#[derive(Clone, Default)]
+pub struct ShortLoggerName;
+
+impl ShortLoggerName {
+    /// Constructs a `ShortLoggerName` pattern for the `{logger:short}` placeholder.
+    ///
+    /// The variant has been checked by the `pattern!` macro.
+    #[must_use]
+    pub fn with_variant(_variant: &str) -> Self {
+        Self
+    }
+}
+
+impl Pattern for ShortLoggerName {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        dest.push_str(short_logger_name(record.logger_name().unwrap_or("")));
+        Ok(())
+    }
+}
+
+#[must_use]
+fn short_logger_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formatter::FmtExtraInfoBuilder, Level};
+
+    #[test]
+    fn short_name() {
+        let format = |logger_name: Option<&'static str>| {
+            let mut record = Record::builder(Level::Info, "payload");
+            if let Some(logger_name) = logger_name {
+                record = record.logger_name(logger_name);
+            }
+            let mut dest = StringBuf::new();
+            let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+            ShortLoggerName
+                .format(&record.build(), &mut dest, &mut ctx)
+                .unwrap();
+            String::from(dest.as_str())
+        };
+
+        assert_eq!(format(Some("app::net::http")), "http");
+        assert_eq!(format(Some("my-logger")), "my-logger");
+        assert_eq!(format(Some("")), "");
+        assert_eq!(format(None), "");
+    }
+}
@@ -1,23 +1,37 @@
 //! This module provides all the built-in patterns.
 
+mod constant;
 mod datetime;
+mod disk_free;
+mod duration;
 mod eol;
 mod full;
 mod level;
 mod logger_name;
+mod optional;
+mod padding;
 mod payload;
 mod process_id;
+mod sink_count;
 mod srcloc;
 mod style_range;
 mod thread_id;
+mod version;
 
+pub use constant::*;
 pub use datetime::*;
+pub use disk_free::*;
+pub use duration::*;
 pub use eol::*;
 pub use full::*;
 pub use level::*;
 pub use logger_name::*;
+pub use optional::*;
+pub use padding::*;
 pub use payload::*;
 pub use process_id::*;
+pub use sink_count::*;
 pub use srcloc::*;
 pub use style_range::*;
 pub use thread_id::*;
+pub use version::*;
@@ -0,0 +1,122 @@
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    Record, StringBuf,
+};
+
+/// A pattern that wraps another pattern and discards its output if any field
+/// inside it writes nothing.
+///
+/// Fields are the patterns wrapped in [`OptionalField`], other patterns (e.g.
+/// literal text) don't affect the decision. It's what the `{?...}` group in
+/// template strings creates, so that the brackets and spaces around an
+/// optional field like the logger name are omitted together with it.
+#[derive(Clone, Debug, Default)]
+pub struct Optional<P> {
+    inner: P,
+}
+
+impl<P> Optional<P>
+where
+    P: Pattern,
+{
+    /// Create a new `Optional` pattern that wraps the given inner pattern.
+    #[must_use]
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+}
+
+impl<P> Pattern for Optional<P>
+where
+    P: Pattern,
+{
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        let start = dest.len();
+        let outer_empty_field_seen = std::mem::replace(&mut ctx.empty_field_seen, false);
+
+        self.inner.format(record, dest, ctx)?;
+
+        if ctx.empty_field_seen {
+            dest.truncate(start);
+        }
+        ctx.empty_field_seen = outer_empty_field_seen;
+
+        Ok(())
+    }
+}
+
+/// A pattern that wraps a field inside an [`Optional`] pattern, and reports to
+/// it if the field writes nothing.
+#[derive(Clone, Debug, Default)]
+pub struct OptionalField<P> {
+    inner: P,
+}
+
+impl<P> OptionalField<P>
+where
+    P: Pattern,
+{
+    /// Create a new `OptionalField` pattern that wraps the given inner pattern.
+    #[must_use]
+    pub fn new(inner: P) -> Self {
+        Self { inner }
+    }
+}
+
+impl<P> Pattern for OptionalField<P>
+where
+    P: Pattern,
+{
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        let start = dest.len();
+
+        self.inner.format(record, dest, ctx)?;
+
+        if dest.len() == start {
+            ctx.empty_field_seen = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        formatter::{__pattern::LoggerName, FmtExtraInfoBuilder},
+        Level,
+    };
+
+    #[test]
+    fn omit_if_field_empty() {
+        let pattern = (
+            Optional::new(("[", OptionalField::new(LoggerName), "] ")),
+            "payload",
+        );
+        let format = |logger_name: Option<&'static str>| {
+            let mut record = Record::builder(Level::Info, "payload");
+            if let Some(logger_name) = logger_name {
+                record = record.logger_name(logger_name);
+            }
+            let mut dest = StringBuf::new();
+            let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+            pattern
+                .format(&record.build(), &mut dest, &mut ctx)
+                .unwrap();
+            String::from(dest.as_str())
+        };
+
+        assert_eq!(format(Some("my-logger")), "[my-logger] payload");
+        assert_eq!(format(None), "payload");
+    }
+}
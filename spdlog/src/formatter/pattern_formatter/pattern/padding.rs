@@ -0,0 +1,122 @@
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    Record, StringBuf,
+};
+
+/// The alignment of the content padded by a [`Padding`] pattern.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PaddingAlign {
+    /// Pads spaces after the content.
+    Left,
+    /// Pads spaces before the content.
+    Right,
+    /// Pads spaces on both sides of the content, the extra space (if any) goes
+    /// after it.
+    Center,
+}
+
+/// A pattern that wraps another pattern and pads the content formatted by it
+/// with spaces to a minimum width.
+///
+/// The width is counted in `char`s. Content longer than the width is written
+/// in full, unless truncation is enabled, in which case only the first `width`
+/// `char`s are written regardless of the alignment.
+///
+/// It's what the `{name:<width}` placeholder spec creates.
+#[derive(Clone, Debug)]
+pub struct Padding<P> {
+    inner: P,
+    width: usize,
+    align: PaddingAlign,
+    truncate: bool,
+}
+
+impl<P> Padding<P>
+where
+    P: Pattern,
+{
+    /// Create a new `Padding` pattern that wraps the given inner pattern.
+    #[must_use]
+    pub fn new(inner: P, width: usize, align: PaddingAlign, truncate: bool) -> Self {
+        Self {
+            inner,
+            width,
+            align,
+            truncate,
+        }
+    }
+}
+
+impl<P> Pattern for Padding<P>
+where
+    P: Pattern,
+{
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        let mut content = StringBuf::new();
+        self.inner.format(record, &mut content, ctx)?;
+        let content = content.as_str();
+
+        let len = content.chars().count();
+        if len >= self.width {
+            if self.truncate && len > self.width {
+                let end = content
+                    .char_indices()
+                    .nth(self.width)
+                    .map_or(content.len(), |(index, _)| index);
+                dest.push_str(&content[..end]);
+            } else {
+                dest.push_str(content);
+            }
+            return Ok(());
+        }
+
+        let padding = self.width - len;
+        let (before, after) = match self.align {
+            PaddingAlign::Left => (0, padding),
+            PaddingAlign::Right => (padding, 0),
+            PaddingAlign::Center => (padding / 2, padding - padding / 2),
+        };
+        push_spaces(dest, before);
+        dest.push_str(content);
+        push_spaces(dest, after);
+        Ok(())
+    }
+}
+
+fn push_spaces(dest: &mut StringBuf, count: usize) {
+    for _ in 0..count {
+        dest.push_str(" ");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formatter::FmtExtraInfoBuilder, Level};
+
+    #[test]
+    fn pad_and_truncate() {
+        let record = Record::new(Level::Info, "payload");
+        let format = |content: &'static str, width, align, truncate| {
+            let mut dest = StringBuf::new();
+            let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+            Padding::new(content, width, align, truncate)
+                .format(&record, &mut dest, &mut ctx)
+                .unwrap();
+            String::from(dest.as_str())
+        };
+
+        assert_eq!(format("info", 8, PaddingAlign::Left, false), "info    ");
+        assert_eq!(format("info", 8, PaddingAlign::Right, false), "    info");
+        assert_eq!(format("info", 7, PaddingAlign::Center, false), " info  ");
+        assert_eq!(format("critical", 4, PaddingAlign::Left, false), "critical");
+        assert_eq!(format("critical", 4, PaddingAlign::Right, true), "crit");
+        assert_eq!(format("日志信息", 3, PaddingAlign::Left, true), "日志信");
+        assert_eq!(format("日志", 3, PaddingAlign::Right, true), " 日志");
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::Write;
+
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    Error, Record, StringBuf,
+};
+
+/// A pattern that writes the number of sinks of the logger that logged the
+/// record into the output. Example: `3`.
+///
+/// The count is provided by [`PatternContext::sink_count`], this pattern writes
+/// `0` if it's unavailable.
+#[derive(Clone, Default)]
+pub struct SinkCount;
+
+impl Pattern for SinkCount {
+    fn format(
+        &self,
+        _record: &Record,
+        dest: &mut StringBuf,
+        ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        write!(dest, "{}", ctx.sink_count().unwrap_or(0)).map_err(Error::FormatRecord)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formatter::FmtExtraInfoBuilder, Level};
+
+    #[test]
+    fn format() {
+        let record = Record::new(Level::Info, "payload");
+
+        let mut buf = StringBuf::new();
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default()).with_sink_count(Some(3));
+        SinkCount.format(&record, &mut buf, &mut ctx).unwrap();
+        assert_eq!(buf.as_str(), "3");
+
+        let mut buf = StringBuf::new();
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+        SinkCount.format(&record, &mut buf, &mut ctx).unwrap();
+        assert_eq!(buf.as_str(), "0");
+    }
+}
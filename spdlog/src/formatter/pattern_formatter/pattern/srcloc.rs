@@ -2,6 +2,7 @@ use std::fmt::Write;
 
 use crate::{
     formatter::pattern_formatter::{Pattern, PatternContext},
+    source_location::source_location_or_placeholder,
     Error, Record, StringBuf,
 };
 
@@ -17,12 +18,12 @@ impl Pattern for Source {
         dest: &mut StringBuf,
         _ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        if let Some(loc) = record.source_location() {
-            (|| {
+        if let Some(loc) = source_location_or_placeholder(record.source_location()) {
+            {
                 dest.push_str(loc.file());
                 dest.push(':');
                 write!(dest, "{}", loc.line()).unwrap();
-            })();
+            }
         }
         Ok(())
     }
@@ -40,7 +41,7 @@ impl Pattern for SourceFilename {
         dest: &mut StringBuf,
         _ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        if let Some(loc) = record.source_location() {
+        if let Some(loc) = source_location_or_placeholder(record.source_location()) {
             dest.push_str(loc.file_name());
         }
         Ok(())
@@ -59,7 +60,7 @@ impl Pattern for SourceFile {
         dest: &mut StringBuf,
         _ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        if let Some(loc) = record.source_location() {
+        if let Some(loc) = source_location_or_placeholder(record.source_location()) {
             dest.push_str(loc.file());
         }
         Ok(())
@@ -77,7 +78,7 @@ impl Pattern for SourceLine {
         dest: &mut StringBuf,
         _ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        if let Some(loc) = record.source_location() {
+        if let Some(loc) = source_location_or_placeholder(record.source_location()) {
             write!(dest, "{}", loc.line()).map_err(Error::FormatRecord)?;
         }
         Ok(())
@@ -95,7 +96,7 @@ impl Pattern for SourceColumn {
         dest: &mut StringBuf,
         _ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        if let Some(loc) = record.source_location() {
+        if let Some(loc) = source_location_or_placeholder(record.source_location()) {
             write!(dest, "{}", loc.column()).map_err(Error::FormatRecord)?;
         }
         Ok(())
@@ -114,7 +115,7 @@ impl Pattern for SourceModulePath {
         dest: &mut StringBuf,
         _ctx: &mut PatternContext,
     ) -> crate::Result<()> {
-        if let Some(loc) = record.source_location() {
+        if let Some(loc) = source_location_or_placeholder(record.source_location()) {
             dest.push_str(loc.module_path());
         }
         Ok(())
@@ -1,13 +1,17 @@
 use crate::{
     formatter::pattern_formatter::{Pattern, PatternContext},
-    Record, StringBuf,
+    Level, LevelFilter, Record, StringBuf,
 };
 
 /// A pattern that wraps another pattern and apply style ranges to the content
 /// formatted by the wrapped pattern.
+///
+/// If a minimum level is given, the style range is only applied to records
+/// whose level is the same as or more severe than it.
 #[derive(Clone, Debug, Default)]
 pub struct StyleRange<P> {
     inner: P,
+    min_level: Option<Level>,
 }
 
 impl<P> StyleRange<P>
@@ -17,7 +21,21 @@ where
     /// Create a new `StyleRange` pattern that wraps the given inner pattern.
     #[must_use]
     pub fn new(inner: P) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            min_level: None,
+        }
+    }
+
+    /// Create a new `StyleRange` pattern that wraps the given inner pattern,
+    /// applying the style range only to records whose level is the same as or
+    /// more severe than `min_level`.
+    #[must_use]
+    pub fn with_min_level(inner: P, min_level: Level) -> Self {
+        Self {
+            inner,
+            min_level: Some(min_level),
+        }
     }
 }
 
@@ -36,7 +54,12 @@ where
         self.inner.format(record, dest, ctx)?;
 
         let style_range_end = dest.len();
-        ctx.set_style_range(style_range_start..style_range_end);
+        let level_matched = self.min_level.map_or(true, |min_level| {
+            LevelFilter::MoreSevereEqual(min_level).compare(record.level())
+        });
+        if level_matched {
+            ctx.set_style_range(style_range_start..style_range_end);
+        }
 
         Ok(())
     }
@@ -29,4 +29,132 @@ impl Pattern for ThreadId {
     ) -> crate::Result<()> {
         write!(dest, "{}", record.tid()).map_err(Error::FormatRecord)
     }
-}
\ No newline at end of file
+}
+
+/// A pattern that writes the current thread's ID in hexadecimal into the
+/// output. Example: `0xc3c`.
+///
+/// This is useful for matching thread IDs shown in hexadecimal by debuggers.
+/// The ID is the same as the one written by [`ThreadId`].
+#[derive(Clone, Default)]
+pub struct ThreadIdHex;
+
+impl ThreadIdHex {
+    /// Constructs a `ThreadIdHex` pattern for the `{tid:hex}` placeholder.
+    ///
+    /// The variant has been checked by the `pattern!` macro.
+    #[must_use]
+    pub fn with_variant(_variant: &str) -> Self {
+        Self
+    }
+}
+
+impl Pattern for ThreadIdHex {
+    fn format(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        write!(dest, "{:#x}", record.tid()).map_err(Error::FormatRecord)
+    }
+}
+
+/// A pattern that writes the scheduling priority of the current thread into the
+/// output. Example: `0`, `-5`.
+///
+/// Note that it's the priority of the thread formatting the record, which is
+/// not the thread that logged it if the record is formatted on another thread,
+/// e.g. in a sink combined by [`AsyncPoolSink`].
+///
+/// # Implementation
+///
+/// On Linux, this pattern writes the nice value of the thread returned by
+/// `getpriority` to the output.
+///
+/// On Windows, this pattern writes the return value of `GetThreadPriority` to
+/// the output.
+///
+/// On other platforms, or if the priority cannot be queried, the output is
+/// empty.
+///
+/// [`AsyncPoolSink`]: crate::sink::AsyncPoolSink
+#[derive(Clone, Default)]
+pub struct ThreadPriority;
+
+impl Pattern for ThreadPriority {
+    fn format(
+        &self,
+        _record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        match get_current_thread_priority() {
+            Some(priority) => write!(dest, "{}", priority).map_err(Error::FormatRecord),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[must_use]
+fn get_current_thread_priority() -> Option<i32> {
+    // On Linux, the nice value is per-thread, and `who` being 0 refers to the
+    // calling thread. `-1` is also a valid priority, so `errno` is cleared
+    // beforehand to tell errors apart.
+    unsafe {
+        *libc::__errno_location() = 0;
+        let priority = libc::getpriority(libc::PRIO_PROCESS, 0);
+        (priority != -1 || *libc::__errno_location() == 0).then(|| priority)
+    }
+}
+
+#[cfg(target_os = "windows")]
+#[must_use]
+fn get_current_thread_priority() -> Option<i32> {
+    use winapi::um::{
+        processthreadsapi::{GetCurrentThread, GetThreadPriority},
+        winbase::THREAD_PRIORITY_ERROR_RETURN,
+    };
+
+    let priority = unsafe { GetThreadPriority(GetCurrentThread()) };
+    (priority != THREAD_PRIORITY_ERROR_RETURN as i32).then(|| priority)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+#[must_use]
+fn get_current_thread_priority() -> Option<i32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formatter::FmtExtraInfoBuilder, Level};
+
+    #[test]
+    fn format() {
+        let record = Record::builder(Level::Info, "payload").tid(3132).build();
+        let format = |pattern: &dyn Pattern| {
+            let mut dest = StringBuf::new();
+            let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+            pattern.format(&record, &mut dest, &mut ctx).unwrap();
+            String::from(dest.as_str())
+        };
+
+        assert_eq!(format(&ThreadId), "3132");
+        assert_eq!(format(&ThreadIdHex), "0xc3c");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn thread_priority() {
+        let record = Record::new(Level::Info, "payload");
+        let mut dest = StringBuf::new();
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+        ThreadPriority.format(&record, &mut dest, &mut ctx).unwrap();
+
+        let priority: i32 = dest.as_str().parse().unwrap();
+        assert!((-20..=19).contains(&priority));
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    formatter::pattern_formatter::{Pattern, PatternContext},
+    sync::*,
+    Record, StringBuf,
+};
+
+static APP_VERSION: SpinRwLock<&str> = SpinRwLock::new("");
+
+/// Sets the application version written by the `{version}` pattern.
+///
+/// spdlog-rs cannot know the version of the crate using it, so the version
+/// must be set by the application, typically at the start of the program. It
+/// is usually `env!("CARGO_PKG_VERSION")`. If it is not set, the pattern
+/// writes nothing.
+///
+/// The version is global, it affects all formatters using the pattern.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::formatter::{pattern, set_app_version, PatternFormatter};
+///
+/// set_app_version(env!("CARGO_PKG_VERSION"));
+///
+/// let formatter = PatternFormatter::new(pattern!("[{version}] {payload}{eol}"));
+/// ```
+pub fn set_app_version(version: &'static str) {
+    *APP_VERSION.write() = version;
+}
+
+/// A pattern that writes the version of the application into the output.
+/// Example: `1.2.3`.
+///
+/// The version is set by [`set_app_version`].
+///
+/// [`set_app_version`]: crate::formatter::set_app_version
+#[derive(Clone, Default)]
+pub struct AppVersion;
+
+impl Pattern for AppVersion {
+    fn format(
+        &self,
+        _record: &Record,
+        dest: &mut StringBuf,
+        _ctx: &mut PatternContext,
+    ) -> crate::Result<()> {
+        dest.push_str(*APP_VERSION.read());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formatter::FmtExtraInfoBuilder, Level};
+
+    #[test]
+    fn format() {
+        set_app_version("1.2.3");
+
+        let mut dest = StringBuf::new();
+        let mut ctx = PatternContext::new(FmtExtraInfoBuilder::default());
+        AppVersion
+            .format(&Record::new(Level::Info, "payload"), &mut dest, &mut ctx)
+            .unwrap();
+        assert_eq!(dest.as_str(), "1.2.3");
+    }
+}
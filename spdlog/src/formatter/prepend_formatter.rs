@@ -0,0 +1,164 @@
+//! Provides a formatter that prepends a prefix to an inner formatter.
+
+use std::{borrow::Cow, path::Path};
+
+use crate::{
+    formatter::{FmtExtraInfo, Formatter},
+    sync::*,
+    Record, Result, StringBuf,
+};
+
+type PrefixFn = Arc<dyn Fn(&Record, &mut StringBuf) + Send + Sync>;
+
+#[derive(Clone)]
+enum Prefix {
+    Static(Cow<'static, str>),
+    Fn(PrefixFn),
+}
+
+/// A formatter that writes a prefix and then delegates to an inner formatter.
+///
+/// The prefix is either a fixed string, or written by a closure for each
+/// record. The style range returned by the inner formatter is shifted by the
+/// length of the prefix, so that it still covers the same text.
+///
+/// This allows reusing an existing formatter, e.g. [`FullFormatter`], while
+/// adding something like a data-center id to the front of every line.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::formatter::{Formatter, FullFormatter, PrependFormatter};
+///
+/// let formatter = PrependFormatter::new("[dc-1] ", Box::new(FullFormatter::new()));
+///
+/// for sink in spdlog::default_logger().sinks() {
+///     sink.set_formatter(formatter.clone_box());
+/// }
+/// ```
+///
+/// Log messages formatted by it look like:
+///
+/// <pre>
+/// [dc-1] [2022-11-02 09:23:12.263] [<font color="#11D116">info</font>] hello, world!
+/// </pre>
+///
+/// [`FullFormatter`]: crate::formatter::FullFormatter
+pub struct PrependFormatter {
+    prefix: Prefix,
+    inner: Box<dyn Formatter>,
+}
+
+impl PrependFormatter {
+    /// Constructs a `PrependFormatter` writing a fixed prefix.
+    #[must_use]
+    pub fn new(prefix: impl Into<Cow<'static, str>>, inner: Box<dyn Formatter>) -> Self {
+        Self {
+            prefix: Prefix::Static(prefix.into()),
+            inner,
+        }
+    }
+
+    /// Constructs a `PrependFormatter` whose prefix is written by the given
+    /// function for each record.
+    ///
+    /// The function should only append to the buffer it receives.
+    #[must_use]
+    pub fn with_fn<F>(prefix_fn: F, inner: Box<dyn Formatter>) -> Self
+    where
+        F: Fn(&Record, &mut StringBuf) + Send + Sync + 'static,
+    {
+        Self {
+            prefix: Prefix::Fn(Arc::new(prefix_fn)),
+            inner,
+        }
+    }
+}
+
+impl Formatter for PrependFormatter {
+    fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+        self.format_for_target(record, dest, None)
+    }
+
+    fn format_for_target(
+        &self,
+        record: &Record,
+        dest: &mut StringBuf,
+        target_path: Option<&Path>,
+    ) -> Result<FmtExtraInfo> {
+        match &self.prefix {
+            Prefix::Static(prefix) => dest.push_str(prefix),
+            Prefix::Fn(prefix_fn) => prefix_fn(record, dest),
+        }
+        let offset = dest.len();
+
+        // The inner formatter is given an empty buffer, so that its style range
+        // is relative to the start of its own output, regardless of how it
+        // computes the range.
+        let mut inner_buf = StringBuf::new();
+        let extra_info = self
+            .inner
+            .format_for_target(record, &mut inner_buf, target_path)?;
+        dest.push_str(inner_buf.as_str());
+
+        Ok(match extra_info.style_range() {
+            Some(range) => FmtExtraInfo::builder()
+                .style_range(range.start + offset..range.end + offset)
+                .build(),
+            None => extra_info,
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn Formatter> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for PrependFormatter {
+    fn clone(&self) -> Self {
+        Self {
+            prefix: self.prefix.clone(),
+            inner: self.inner.clone_box(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{formatter::FullFormatter, Level};
+
+    #[test]
+    fn prepend_to_full_formatter() {
+        let record = Record::new(Level::Warn, "test log content");
+
+        let mut full_buf = StringBuf::new();
+        let full_info = FullFormatter::new().format(&record, &mut full_buf).unwrap();
+
+        let formatter = PrependFormatter::new("[dc-1] ", Box::new(FullFormatter::new()));
+        let mut buf = StringBuf::new();
+        let info = formatter.format(&record, &mut buf).unwrap();
+
+        assert_eq!(buf.as_str(), format!("[dc-1] {}", full_buf.as_str()));
+
+        let full_range = full_info.style_range().unwrap();
+        let range = info.style_range().unwrap();
+        assert_eq!(range, full_range.start + 7..full_range.end + 7);
+        assert_eq!(&buf.as_str()[range], "warn");
+    }
+
+    #[test]
+    fn prefix_fn() {
+        let formatter = PrependFormatter::with_fn(
+            |record, dest| dest.push_str(&format!("<{}> ", record.level() as u16)),
+            Box::new(FullFormatter::new()),
+        );
+        let mut buf = StringBuf::new();
+        let info = formatter
+            .format(&Record::new(Level::Error, "test log content"), &mut buf)
+            .unwrap();
+
+        assert!(buf.as_str().starts_with("<1> ["));
+        assert_eq!(&buf.as_str()[info.style_range().unwrap()], "error");
+    }
+}
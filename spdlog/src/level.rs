@@ -9,6 +9,8 @@ use crate::Error;
 pub(crate) const LOG_LEVEL_NAMES: [&str; Level::count()] =
     ["critical", "error", "warn", "info", "debug", "trace"];
 
+// Each short name is a distinct single ASCII character, so it can be used as a
+// key identifying the level, e.g. when parsing logs.
 const LOG_LEVEL_SHORT_NAMES: [&str; Level::count()] = ["C", "E", "W", "I", "D", "T"];
 
 /// An enum representing log levels.
@@ -135,7 +137,7 @@ impl Level {
 
     #[must_use]
     #[inline(always)]
-    pub(crate) fn as_short_str(&self) -> &'static str {
+    pub(crate) const fn as_short_str(&self) -> &'static str {
         LOG_LEVEL_SHORT_NAMES[*self as usize]
     }
 
@@ -368,6 +370,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn short_names_are_distinct_chars() {
+        let expected = [
+            (Level::Critical, "C"),
+            (Level::Error, "E"),
+            (Level::Warn, "W"),
+            (Level::Info, "I"),
+            (Level::Debug, "D"),
+            (Level::Trace, "T"),
+        ];
+        assert_eq!(expected.len(), Level::count());
+        for (level, short_name) in expected {
+            assert_eq!(level.as_short_str(), short_name);
+        }
+
+        let mut short_chars: Vec<char> = Level::iter()
+            .map(|level| {
+                let short_name = level.as_short_str();
+                assert_eq!(short_name.len(), 1);
+                short_name.chars().next().unwrap()
+            })
+            .collect();
+        short_chars.sort_unstable();
+        short_chars.dedup();
+        assert_eq!(short_chars.len(), Level::count());
+    }
+
     #[test]
     fn level_filter_from_str_for_env() {
         assert_eq!(
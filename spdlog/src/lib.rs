@@ -139,6 +139,18 @@
 //!    features need to be enabled as well. See the documentation of the
 //!    component for these details.
 //!
+//!  - `debug-http` enables [`sink::DebugHttpSink`], which serves recent logs
+//!    over HTTP for debugging.
+//!
+//!  - `mmap` enables [`sink::MmapFileSink`], which writes logs into a
+//!    memory-mapped file for very high throughput (unix-like systems only).
+//!
+//!  - `gzip` enables [`sink::Compression::Gzip`], which compresses the files
+//!    closed by [`sink::DateAndHourRotatingFileSink`] rotations.
+//!
+//!  - `hmac-sha256` enables [`formatter::ChecksumFormatter::hmac_sha256`],
+//!    which appends a keyed checksum to each formatted line.
+//!
 //! # Supported Rust Versions
 //!
 //! <!--
@@ -207,6 +219,9 @@
 #![cfg_attr(all(doc, CHANNEL_NIGHTLY), feature(doc_auto_cfg))]
 #![warn(missing_docs)]
 
+mod capture;
+mod clock;
+mod config;
 mod env_level;
 pub mod error;
 pub mod formatter;
@@ -229,6 +244,9 @@ mod test_utils;
 mod thread_pool;
 mod utils;
 
+pub use capture::*;
+pub use clock::*;
+pub use config::*;
 pub use error::{Error, ErrorHandler, Result};
 pub use level::*;
 #[cfg(feature = "log")]
@@ -256,6 +274,7 @@ use std::{
 
 use cfg_if::cfg_if;
 use error::EnvLevelError;
+use formatter::{Formatter, FullFormatter};
 use sink::{Sink, StdStream, StdStreamSink};
 use sync::*;
 
@@ -360,7 +379,8 @@ fn default_logger_ref() -> &'static ArcSwap<Logger> {
 ///    ```
 ///
 /// Users can replace the default logger with [`set_default_logger`] or
-/// [`swap_default_logger`].
+/// [`swap_default_logger`]. Inside [`capture`], a capturing logger is returned
+/// instead on the capturing thread.
 ///
 /// # Examples
 ///
@@ -382,6 +402,9 @@ fn default_logger_ref() -> &'static ArcSwap<Logger> {
 /// ```
 #[must_use]
 pub fn default_logger() -> Arc<Logger> {
+    if let Some(logger) = capture::capture_logger() {
+        return logger;
+    }
     default_logger_ref().load().clone()
 }
 
@@ -421,6 +444,150 @@ pub fn set_default_logger(logger: Arc<Logger>) {
     swap_default_logger(logger);
 }
 
+static DEFAULT_FORMATTER: Lazy<SpinRwLock<Option<Box<dyn Formatter>>>> =
+    Lazy::new(|| SpinRwLock::new(None));
+
+/// Sets the formatter that sinks use by default.
+///
+/// Sinks built afterward adopt a clone of the given formatter, unless a
+/// formatter is specified explicitly on their builders. Sinks already built
+/// are not affected, and sinks with a dedicated default formatter (e.g.
+/// [`JournaldSink`]) keep using it.
+///
+/// If it's never called, sinks use [`FullFormatter`] by default.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::{
+///     formatter::{pattern, PatternFormatter},
+///     sink::{StdStream, StdStreamSink},
+/// };
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// spdlog::set_default_formatter(Box::new(PatternFormatter::new(pattern!(
+///     "[{level}] {payload}{eol}"
+/// ))));
+///
+/// // The sink formats records with the pattern above.
+/// let sink = StdStreamSink::builder()
+///     .std_stream(StdStream::Stdout)
+///     .build()?;
+/// # Ok(()) }
+/// ```
+///
+/// [`JournaldSink`]: crate::sink::JournaldSink
+pub fn set_default_formatter(formatter: Box<dyn Formatter>) {
+    *DEFAULT_FORMATTER.write() = Some(formatter);
+}
+
+// Returns a clone of the formatter set by `set_default_formatter`, or a
+// `FullFormatter` if none was set.
+#[must_use]
+pub(crate) fn default_formatter() -> Box<dyn Formatter> {
+    match &*DEFAULT_FORMATTER.read() {
+        Some(formatter) => formatter.clone_box(),
+        None => Box::new(FullFormatter::new()),
+    }
+}
+
+/// A snapshot of the global logging configuration.
+///
+/// Created by [`snapshot_config`] and consumed by [`restore_config`].
+#[must_use = "a snapshot does nothing unless it is passed to `restore_config`"]
+pub struct ConfigSnapshot {
+    default_logger: Arc<Logger>,
+    level_filter: LevelFilter,
+    flush_level_filter: LevelFilter,
+    sink_level_filters: Vec<LevelFilter>,
+}
+
+/// Takes a snapshot of the global logging configuration.
+///
+/// The snapshot captures the default logger, its level filter, its flush level
+/// filter and the level filters of its sinks. It can be passed to
+/// [`restore_config`] later to undo changes made in the meantime, which is
+/// mainly useful in tests that tweak the default logger.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::prelude::*;
+///
+/// let snapshot = spdlog::snapshot_config();
+///
+/// spdlog::default_logger().set_level_filter(LevelFilter::Off);
+/// # let new_logger = spdlog::default_logger();
+/// spdlog::set_default_logger(new_logger);
+///
+/// spdlog::restore_config(snapshot);
+/// ```
+pub fn snapshot_config() -> ConfigSnapshot {
+    let default_logger = default_logger();
+
+    ConfigSnapshot {
+        level_filter: default_logger.level_filter(),
+        flush_level_filter: default_logger.flush_level_filter(),
+        sink_level_filters: default_logger
+            .sinks()
+            .iter()
+            .map(|sink| sink.level_filter())
+            .collect(),
+        default_logger,
+    }
+}
+
+/// Restores the global logging configuration from a snapshot taken by
+/// [`snapshot_config`].
+///
+/// The captured default logger is set back as the default logger, and its
+/// level filter, flush level filter and the level filters of its sinks are
+/// reset to the captured values.
+pub fn restore_config(snapshot: ConfigSnapshot) {
+    let logger = snapshot.default_logger;
+
+    logger.set_level_filter(snapshot.level_filter);
+    logger.set_flush_level_filter(snapshot.flush_level_filter);
+    for (sink, level_filter) in logger.sinks().iter().zip(snapshot.sink_level_filters) {
+        sink.set_level_filter(level_filter);
+    }
+
+    set_default_logger(logger);
+}
+
+/// Builds a logger from the given [`Config`] and sets it as the default logger.
+///
+/// The formatters and level filters described in the config are set to their
+/// sinks once the logger has been built. The old default logger is dropped, use
+/// [`snapshot_config`] beforehand if it needs to be restored later.
+///
+/// # Errors
+///
+/// Returns an error if the logger cannot be built, e.g. the name in the config
+/// is invalid. In that case, neither the sinks nor the default logger are
+/// modified.
+///
+/// # Examples
+///
+/// See the documentation of [`Config`].
+pub fn init_with(config: Config) -> Result<()> {
+    let mut builder = Logger::builder();
+    builder
+        .level_filter(config.level_filter)
+        .flush_level_filter(config.flush_level_filter)
+        .sinks(config.sinks.iter().map(|sink| sink.sink.clone()));
+    if let Some(name) = config.name {
+        builder.name(name);
+    }
+    let logger = builder.build_default()?;
+
+    // Nothing can fail from here on, so the config is either applied as a whole or
+    // not at all.
+    config.sinks.into_iter().for_each(SinkConfig::apply);
+    set_default_logger(Arc::new(logger));
+    Ok(())
+}
+
 /// Initialize environment variable level filters from environment variable
 /// `SPDLOG_RS_LEVEL`.
 ///
@@ -458,7 +625,7 @@ pub fn set_default_logger(logger: Arc<Logger>) {
 /// - Specifies the level filter for ***loggers with the specified name***.
 ///
 ///   Possible inputs: `logger-name=info`, `network=warn`, `core=info`,
-/// `gui=critical`, etc.
+///   `gui=critical`, etc.
 ///
 /// ---
 ///
@@ -485,15 +652,15 @@ pub fn set_default_logger(logger: Arc<Logger>) {
 /// - `off,*=ERROR`
 ///
 ///   Specifies the level filter for the default logger as `LevelFilter::Off`,
-/// the rest of loggers as `LevelFilter::MoreSevereEqual(Level::Error)`.
+///   the rest of loggers as `LevelFilter::MoreSevereEqual(Level::Error)`.
 ///
 /// ---
 ///
 /// - `gui=warn,network=trace`
 ///
 ///   Specifies the level filter for loggers with name "gui" as
-/// `LevelFilter::MoreSevereEqual(Level::Warn)`, loggers with name "network" as
-/// `LevelFilter::MoreSevereEqual(Level::Trace)`.
+///   `LevelFilter::MoreSevereEqual(Level::Warn)`, loggers with name "network" as
+///   `LevelFilter::MoreSevereEqual(Level::Trace)`.
 ///
 /// ---
 ///
@@ -704,6 +871,7 @@ pub fn __log(
     logger: &Logger,
     level: Level,
     srcloc: Option<SourceLocation>,
+    duration: Option<std::time::Duration>,
     fmt_args: std::fmt::Arguments,
 ) {
     // use `Cow` to avoid allocation as much as we can
@@ -712,7 +880,10 @@ pub fn __log(
         None => fmt_args.to_string().into(),
     };
 
-    let mut builder = Record::builder(level, payload).source_location(srcloc);
+    let mut builder = Record::builder(level, payload)
+        .source_location(srcloc)
+        .duration(duration)
+        .sink_count(logger.sinks().len());
     if let Some(logger_name) = logger.name() {
         builder = builder.logger_name(logger_name);
     }
@@ -721,12 +892,20 @@ pub fn __log(
 
 #[cfg(test)]
 mod tests {
+    use std::thread;
+
     use test_utils::*;
 
     use super::*;
 
+    // Tests in this module replace the global default logger, so they must not
+    // run concurrently.
+    static DEFAULT_LOGGER_LOCK: SpinMutex<()> = SpinMutex::new(());
+
     #[test]
     fn test_default_logger() {
+        let _guard = DEFAULT_LOGGER_LOCK.lock();
+
         let test_sink = Arc::new(CounterSink::new());
 
         let test_logger = Arc::new(
@@ -755,4 +934,124 @@ mod tests {
             vec!["hello".to_string(), "rust".to_string()]
         );
     }
+
+    #[test]
+    fn test_snapshot_config() {
+        let _guard = DEFAULT_LOGGER_LOCK.lock();
+
+        let test_sink = Arc::new(CounterSink::new());
+        let test_logger = Arc::new(
+            test_logger_builder()
+                .sink(test_sink.clone())
+                .level_filter(LevelFilter::All)
+                .build()
+                .unwrap(),
+        );
+        set_default_logger(test_logger.clone());
+
+        let snapshot = snapshot_config();
+
+        test_logger.set_level_filter(LevelFilter::Off);
+        test_logger.set_flush_level_filter(LevelFilter::All);
+        test_sink.set_level_filter(LevelFilter::Off);
+        set_default_logger(Arc::new(Logger::builder().build().unwrap()));
+        info!("dropped");
+
+        restore_config(snapshot);
+
+        assert!(Arc::ptr_eq(&default_logger(), &test_logger));
+        assert_eq!(test_logger.level_filter(), LevelFilter::All);
+        assert_eq!(test_logger.flush_level_filter(), LevelFilter::Off);
+        assert_eq!(test_sink.level_filter(), LevelFilter::All);
+
+        info!("restored");
+        assert_eq!(test_sink.log_count(), 1);
+        assert_eq!(test_sink.payloads(), vec!["restored".to_string()]);
+    }
+
+    #[test]
+    fn test_capture() {
+        let _guard = DEFAULT_LOGGER_LOCK.lock();
+        let snapshot = snapshot_config();
+
+        let test_sink = Arc::new(CounterSink::new());
+        set_default_logger(Arc::new(
+            test_logger_builder()
+                .sink(test_sink.clone())
+                .build()
+                .unwrap(),
+        ));
+
+        info!("outside before");
+        let lines = capture(|| {
+            info!("inside");
+            trace!("inside verbose");
+
+            // Other threads keep logging to the actual default logger.
+            thread::spawn(|| info!("other thread")).join().unwrap();
+
+            assert_eq!(capture(|| info!("nested")), ["[info] nested"]);
+        });
+        info!("outside after");
+
+        assert_eq!(lines, ["[info] inside", "[trace] inside verbose"]);
+        assert_eq!(
+            test_sink.payloads(),
+            vec![
+                "outside before".to_string(),
+                "other thread".to_string(),
+                "outside after".to_string()
+            ]
+        );
+
+        restore_config(snapshot);
+    }
+
+    #[test]
+    fn test_init_with() {
+        let _guard = DEFAULT_LOGGER_LOCK.lock();
+        let snapshot = snapshot_config();
+
+        let test_sink = Arc::new(CounterSink::new());
+        init_with(Config {
+            name: Some("configured".to_string()),
+            level_filter: LevelFilter::All,
+            sinks: vec![SinkConfig {
+                level_filter: Some(LevelFilter::MoreSevereEqual(Level::Info)),
+                ..SinkConfig::new(test_sink.clone())
+            }],
+            ..Config::default()
+        })
+        .unwrap();
+
+        let logger = default_logger();
+        assert_eq!(logger.name(), Some("configured"));
+        assert_eq!(logger.level_filter(), LevelFilter::All);
+        assert_eq!(logger.sinks().len(), 1);
+        assert_eq!(
+            test_sink.level_filter(),
+            LevelFilter::MoreSevereEqual(Level::Info)
+        );
+
+        info!("hello");
+        assert_eq!(test_sink.payloads(), vec!["hello".to_string()]);
+
+        assert!(init_with(Config {
+            name: Some("invalid,name".to_string()),
+            sinks: vec![SinkConfig {
+                level_filter: Some(LevelFilter::Off),
+                ..SinkConfig::new(test_sink.clone())
+            }],
+            ..Config::default()
+        })
+        .is_err());
+        assert!(Arc::ptr_eq(&default_logger(), &logger));
+        // The sinks are not modified if the logger fails to build
+        assert_eq!(
+            test_sink.level_filter(),
+            LevelFilter::MoreSevereEqual(Level::Info)
+        );
+
+        restore_config(snapshot);
+    }
 }
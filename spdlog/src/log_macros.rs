@@ -19,17 +19,50 @@
 ///     data.0, data.1, private_data);
 /// ```
 ///
+/// A [`Duration`] measured by the caller can be attached to the record with
+/// `duration:`, after `logger:` if both are present. It is available as
+/// [`Record::duration`] and rendered by the `{duration}` pattern.
+///
+/// ```
+/// use std::time::Instant;
+///
+/// use spdlog::{log, Level};
+///
+/// # let app_events = spdlog::default_logger();
+/// let start = Instant::now();
+/// // handle the request ...
+/// log!(duration: start.elapsed(), Level::Info, "request handled");
+/// log!(logger: app_events, duration: start.elapsed(), Level::Info, "request handled");
+/// ```
+///
 /// [`Level`]: crate::Level
+/// [`Duration`]: std::time::Duration
+/// [`Record::duration`]: crate::Record::duration
 #[macro_export]
 macro_rules! log {
-    (logger: $logger:expr, $level:expr, $($arg:tt)+) => ({
+    (@inner $logger:expr, $duration:expr, $level:expr, $($arg:tt)+) => ({
         let logger = &$logger;
         const LEVEL: $crate::Level = $level;
         const SHOULD_LOG: bool = $crate::STATIC_LEVEL_FILTER.__compare_const(LEVEL);
         if SHOULD_LOG && logger.should_log(LEVEL) {
-            $crate::__log(logger, LEVEL, $crate::source_location_current!(), format_args!($($arg)+));
+            $crate::__log(
+                logger,
+                LEVEL,
+                $crate::source_location_current!(),
+                $duration,
+                format_args!($($arg)+),
+            );
         }
     });
+    (logger: $logger:expr, duration: $duration:expr, $level:expr, $($arg:tt)+) => (
+        $crate::log!(@inner $logger, Some($duration), $level, $($arg)+)
+    );
+    (logger: $logger:expr, $level:expr, $($arg:tt)+) => (
+        $crate::log!(@inner $logger, None, $level, $($arg)+)
+    );
+    (duration: $duration:expr, $level:expr, $($arg:tt)+) => (
+        $crate::log!(logger: $crate::default_logger(), duration: $duration, $level, $($arg)+)
+    );
     ($level:expr, $($arg:tt)+) => ($crate::log!(logger: $crate::default_logger(), $level, $($arg)+))
 }
 
@@ -48,9 +81,15 @@ macro_rules! log {
 /// ```
 #[macro_export]
 macro_rules! critical {
+    (logger: $logger:expr, duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(logger: $logger, duration: $duration, $crate::Level::Critical, $($arg)+)
+    );
     (logger: $logger:expr, $($arg:tt)+) => (
         $crate::log!(logger: $logger, $crate::Level::Critical, $($arg)+)
     );
+    (duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(duration: $duration, $crate::Level::Critical, $($arg)+)
+    );
     ($($arg:tt)+) => (
         $crate::log!($crate::Level::Critical, $($arg)+)
     )
@@ -71,9 +110,15 @@ macro_rules! critical {
 /// ```
 #[macro_export]
 macro_rules! error {
+    (logger: $logger:expr, duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(logger: $logger, duration: $duration, $crate::Level::Error, $($arg)+)
+    );
     (logger: $logger:expr, $($arg:tt)+) => (
         $crate::log!(logger: $logger, $crate::Level::Error, $($arg)+)
     );
+    (duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(duration: $duration, $crate::Level::Error, $($arg)+)
+    );
     ($($arg:tt)+) => (
         $crate::log!($crate::Level::Error, $($arg)+)
     )
@@ -94,9 +139,15 @@ macro_rules! error {
 /// ```
 #[macro_export]
 macro_rules! warn {
+    (logger: $logger:expr, duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(logger: $logger, duration: $duration, $crate::Level::Warn, $($arg)+)
+    );
     (logger: $logger:expr, $($arg:tt)+) => (
         $crate::log!(logger: $logger, $crate::Level::Warn, $($arg)+)
     );
+    (duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(duration: $duration, $crate::Level::Warn, $($arg)+)
+    );
     ($($arg:tt)+) => (
         $crate::log!($crate::Level::Warn, $($arg)+)
     )
@@ -119,9 +170,15 @@ macro_rules! warn {
 /// ```
 #[macro_export]
 macro_rules! info {
+    (logger: $logger:expr, duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(logger: $logger, duration: $duration, $crate::Level::Info, $($arg)+)
+    );
     (logger: $logger:expr, $($arg:tt)+) => (
         $crate::log!(logger: $logger, $crate::Level::Info, $($arg)+)
     );
+    (duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(duration: $duration, $crate::Level::Info, $($arg)+)
+    );
     ($($arg:tt)+) => (
         $crate::log!($crate::Level::Info, $($arg)+)
     )
@@ -143,9 +200,15 @@ macro_rules! info {
 /// ```
 #[macro_export]
 macro_rules! debug {
+    (logger: $logger:expr, duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(logger: $logger, duration: $duration, $crate::Level::Debug, $($arg)+)
+    );
     (logger: $logger:expr, $($arg:tt)+) => (
         $crate::log!(logger: $logger, $crate::Level::Debug, $($arg)+)
     );
+    (duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(duration: $duration, $crate::Level::Debug, $($arg)+)
+    );
     ($($arg:tt)+) => (
         $crate::log!($crate::Level::Debug, $($arg)+)
     )
@@ -169,9 +232,15 @@ macro_rules! debug {
 /// ```
 #[macro_export]
 macro_rules! trace {
+    (logger: $logger:expr, duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(logger: $logger, duration: $duration, $crate::Level::Trace, $($arg)+)
+    );
     (logger: $logger:expr, $($arg:tt)+) => (
         $crate::log!(logger: $logger, $crate::Level::Trace, $($arg)+)
     );
+    (duration: $duration:expr, $($arg:tt)+) => (
+        $crate::log!(duration: $duration, $crate::Level::Trace, $($arg)+)
+    );
     ($($arg:tt)+) => (
         $crate::log!($crate::Level::Trace, $($arg)+)
     )
@@ -69,6 +69,22 @@ pub struct Logger {
     flush_level_filter: Atomic<LevelFilter>,
     error_handler: SpinRwLock<Option<ErrorHandler>>,
     periodic_flusher: Mutex<Option<(Duration, PeriodicWorker)>>,
+    inject_name: bool,
+    flush_on_level_change: bool,
+}
+
+/// Identifies a sink of a [`Logger`].
+///
+/// It is the position of the sink in [`Logger::sinks`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct SinkId(usize);
+
+impl SinkId {
+    /// Gets the index of the sink in [`Logger::sinks`].
+    #[must_use]
+    pub fn index(self) -> usize {
+        self.0
+    }
 }
 
 impl Logger {
@@ -82,6 +98,8 @@ impl Logger {
             sinks: vec![],
             flush_level_filter: LevelFilter::Off,
             error_handler: None,
+            inject_name: true,
+            flush_on_level_change: false,
         }
     }
 
@@ -149,7 +167,41 @@ impl Logger {
         if !self.should_log(record.level()) {
             return;
         }
-        self.sink_record(record);
+        self.with_injected_name(record, |record| self.sink_record(record));
+    }
+
+    /// Logs a record, and returns the errors that occurred in sinks.
+    ///
+    /// Unlike [`Logger::log`], which passes sink errors to the error handler,
+    /// this function collects them and returns them with the [`SinkId`] of the
+    /// failing sink, for callers who must know whether a record was written.
+    /// The record is still passed to every sink even if some of them fail.
+    ///
+    /// Errors that occurred in the automatic flushing triggered by
+    /// [`Logger::flush_level_filter`] are still passed to the error handler.
+    pub fn try_log(&self, record: &Record) -> StdResult<(), Vec<(SinkId, Error)>> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        self.with_injected_name(record, |record| {
+            let errors: Vec<_> = self
+                .sinks
+                .iter()
+                .enumerate()
+                .filter_map(|(index, sink)| sink.log(record).err().map(|err| (SinkId(index), err)))
+                .collect();
+
+            if self.should_flush(record) {
+                self.flush();
+            }
+
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            }
+        })
     }
 
     /// Flushes any buffered records.
@@ -165,6 +217,26 @@ impl Logger {
         self.flush_sinks();
     }
 
+    /// Flushes any buffered records, and returns the first error that occurred.
+    ///
+    /// Like [`Logger::flush`], every sink is flushed even if flushing a
+    /// previous sink failed, so that one broken sink does not leave the others
+    /// unflushed. The errors after the first one are passed to the error
+    /// handler.
+    pub fn try_flush(&self) -> Result<()> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            if let Err(err) = sink.flush() {
+                if result.is_ok() {
+                    result = Err(err);
+                } else {
+                    self.handle_error(err);
+                }
+            }
+        }
+        result
+    }
+
     /// Gets the flush level filter.
     #[must_use]
     #[inline(always)]
@@ -210,11 +282,20 @@ impl Logger {
 
     /// Sets the log filter level.
     ///
+    /// If [`LoggerBuilder::flush_on_level_change`] is enabled and the new level
+    /// filter lets through any level that the old one did not (i.e. the logger
+    /// becomes more verbose), the sinks are flushed.
+    ///
     /// # Examples
     ///
     /// See [`Logger::should_log`].
     pub fn set_level_filter(&self, level_filter: LevelFilter) {
-        self.level_filter.store(level_filter, Ordering::Relaxed);
+        let old = self.level_filter.swap(level_filter, Ordering::Relaxed);
+        if self.flush_on_level_change
+            && Level::iter().any(|level| level_filter.compare(level) && !old.compare(level))
+        {
+            self.flush();
+        }
     }
 
     /// Sets periodic flush.
@@ -393,6 +474,22 @@ impl Logger {
             flush_level_filter: Atomic::new(self.flush_level_filter()),
             periodic_flusher: Mutex::new(None),
             error_handler: SpinRwLock::new(*self.error_handler.read()),
+            inject_name: self.inject_name,
+            flush_on_level_change: self.flush_on_level_change,
+        }
+    }
+
+    // Calls `f` with the record, or with a copy of it carrying the logger name
+    // if name injection is enabled and the record has no name.
+    #[inline(always)]
+    fn with_injected_name<R>(&self, record: &Record, f: impl FnOnce(&Record) -> R) -> R {
+        match &self.name {
+            Some(name) if self.inject_name && record.logger_name().is_none() => {
+                let mut record = record.clone();
+                record.set_logger_name(name);
+                f(&record)
+            }
+            _ => f(record),
         }
     }
 
@@ -466,6 +563,8 @@ pub struct LoggerBuilder {
     sinks: Sinks,
     flush_level_filter: LevelFilter,
     error_handler: Option<ErrorHandler>,
+    inject_name: bool,
+    flush_on_level_change: bool,
 }
 
 impl LoggerBuilder {
@@ -491,6 +590,9 @@ impl LoggerBuilder {
     /// and cannot start or end with a whitespace.
     ///
     /// Otherwise, [`LoggerBuilder::build`] will return an error.
+    ///
+    /// The name is also given to records that are logged without one, see
+    /// [`LoggerBuilder::inject_name`].
     #[inline(always)]
     pub fn name<S>(&mut self, name: S) -> &mut Self
     where
@@ -550,6 +652,41 @@ impl LoggerBuilder {
         self
     }
 
+    /// Sets whether the logger injects its own name into records that do not
+    /// have one.
+    ///
+    /// This parameter is **optional**, and defaults to `true`.
+    ///
+    /// Records created by log macros always carry the name of the logger they
+    /// are logged through, but records built elsewhere (e.g. obtained by
+    /// [`RecordOwned::as_ref`]) may not have a name. When enabled, such records
+    /// are given the name of this logger before being passed to sinks, so that
+    /// the name appears in the formatted output. Records that already have a
+    /// name are left unchanged. It has no effect if the logger has no name.
+    ///
+    /// [`RecordOwned::as_ref`]: crate::RecordOwned::as_ref
+    #[inline(always)]
+    pub fn inject_name(&mut self, inject_name: bool) -> &mut Self {
+        self.inject_name = inject_name;
+        self
+    }
+
+    /// Sets whether the logger flushes its sinks when its level filter is
+    /// made more verbose by [`Logger::set_level_filter`].
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    ///
+    /// This is useful when the level is raised at runtime to capture an
+    /// incident, records buffered in sinks before the change are written out,
+    /// so that the transition is clean. Changes that don't let through any new
+    /// level (e.g. lowering the level back) do not flush. It is opt-in because
+    /// flushing may add latency to the change.
+    #[inline(always)]
+    pub fn flush_on_level_change(&mut self, flush_on_level_change: bool) -> &mut Self {
+        self.flush_on_level_change = flush_on_level_change;
+        self
+    }
+
     /// Builds a [`Logger`].
     pub fn build(&mut self) -> Result<Logger> {
         self.build_inner(self.preset_level(false))
@@ -581,10 +718,13 @@ impl LoggerBuilder {
             flush_level_filter: Atomic::new(self.flush_level_filter),
             error_handler: SpinRwLock::new(self.error_handler),
             periodic_flusher: Mutex::new(None),
+            inject_name: self.inject_name,
+            flush_on_level_change: self.flush_on_level_change,
         };
 
         if let Some(preset_level) = preset_level {
-            logger.set_level_filter(preset_level);
+            // Nothing has been logged yet, so there is nothing to flush.
+            logger.level_filter.store(preset_level, Ordering::Relaxed);
         }
 
         Ok(logger)
@@ -622,6 +762,140 @@ mod tests {
         assert_sync::<Logger>();
     }
 
+    #[test]
+    fn try_log_errors() {
+        let sinks = [Arc::new(CounterSink::new()), Arc::new(CounterSink::new())];
+        let logger = Logger::builder()
+            .sink(sinks[0].clone())
+            .sink(Arc::new(FailingSink))
+            .sink(sinks[1].clone())
+            .error_handler(|err| panic!("unexpected error: {}", err))
+            .build()
+            .unwrap();
+
+        let errors = logger
+            .try_log(&Record::new(Level::Info, "test"))
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0.index(), 1);
+        assert!(matches!(errors[0].1, Error::WriteRecord(_)));
+        assert_eq!(sinks[0].log_count(), 1);
+        assert_eq!(sinks[1].log_count(), 1);
+
+        let logger = test_logger_builder()
+            .sink(sinks[0].clone())
+            .build()
+            .unwrap();
+        assert!(logger.try_log(&Record::new(Level::Info, "test")).is_ok());
+        assert_eq!(sinks[0].log_count(), 2);
+    }
+
+    #[test]
+    fn inject_name() {
+        use crate::{formatter::FullFormatter, sink::WriteSink};
+
+        let build_sink = || {
+            Arc::new(
+                WriteSink::builder()
+                    .target(Vec::new())
+                    .formatter(Box::new(FullFormatter::new()))
+                    .build()
+                    .unwrap(),
+            )
+        };
+        let record = Record::new(Level::Info, "no name");
+
+        let sink = build_sink();
+        let logger = test_logger_builder()
+            .name("named")
+            .sink(sink.clone())
+            .build()
+            .unwrap();
+        logger.log(&record);
+        let output = String::from_utf8(sink.clone_target()).unwrap();
+        assert!(output.contains("[named] [info]"));
+
+        let sink = build_sink();
+        let logger = test_logger_builder()
+            .name("named")
+            .inject_name(false)
+            .sink(sink.clone())
+            .build()
+            .unwrap();
+        logger.log(&record);
+        let output = String::from_utf8(sink.clone_target()).unwrap();
+        assert!(!output.contains("[named]"));
+
+        let sink = build_sink();
+        let logger = test_logger_builder()
+            .name("named")
+            .sink(sink.clone())
+            .build()
+            .unwrap();
+        logger.log(
+            &Record::builder(Level::Info, "own name")
+                .logger_name("own")
+                .build(),
+        );
+        let output = String::from_utf8(sink.clone_target()).unwrap();
+        assert!(output.contains("[own] [info]"));
+    }
+
+    #[test]
+    fn flush_on_level_change() {
+        let test_sink = Arc::new(CounterSink::new());
+        let test_logger = test_logger_builder()
+            .sink(test_sink.clone())
+            .build()
+            .unwrap();
+        test_logger.set_level_filter(LevelFilter::All);
+        assert_eq!(test_sink.flush_count(), 0);
+
+        let test_sink = Arc::new(CounterSink::new());
+        let test_logger = test_logger_builder()
+            .sink(test_sink.clone())
+            .flush_on_level_change(true)
+            .build()
+            .unwrap();
+        info!(logger: test_logger, "buffered");
+        assert_eq!(test_sink.log_count(), 1);
+        assert_eq!(test_sink.flush_count(), 0);
+
+        test_logger.set_level_filter(LevelFilter::All);
+        assert_eq!(test_sink.flush_count(), 1);
+
+        // Setting the same level filter is not a change.
+        test_logger.set_level_filter(LevelFilter::All);
+        assert_eq!(test_sink.flush_count(), 1);
+
+        // Becoming less verbose doesn't flush.
+        test_logger.set_level_filter(LevelFilter::MoreSevereEqual(Level::Warn));
+        assert_eq!(test_sink.flush_count(), 1);
+        test_logger.set_level_filter(LevelFilter::Equal(Level::Error));
+        assert_eq!(test_sink.flush_count(), 1);
+
+        // Letting through a level that was filtered out flushes, even if another
+        // level is filtered out now.
+        test_logger.set_level_filter(LevelFilter::Equal(Level::Info));
+        assert_eq!(test_sink.flush_count(), 2);
+    }
+
+    #[test]
+    fn try_flush_all_sinks() {
+        let sinks = [Arc::new(CounterSink::new()), Arc::new(CounterSink::new())];
+        let logger = Logger::builder()
+            .sink(sinks[0].clone())
+            .sink(Arc::new(FailingSink))
+            .sink(sinks[1].clone())
+            .error_handler(|err| panic!("unexpected error: {}", err))
+            .build()
+            .unwrap();
+
+        assert!(matches!(logger.try_flush(), Err(Error::FlushBuffer(_))));
+        assert_eq!(sinks[0].flush_count(), 1);
+        assert_eq!(sinks[1].flush_count(), 1);
+    }
+
     #[test]
     fn flush_level() {
         let test_sink = Arc::new(CounterSink::new());
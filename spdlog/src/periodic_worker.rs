@@ -22,7 +22,7 @@ impl PeriodicWorker {
             active: active.clone(),
             thread: Some(thread::spawn(move || loop {
                 let guard = active.0.lock_expect();
-                let (_, res) = active
+                let (_guard, res) = active
                     .1
                     .wait_timeout_while(guard, interval, |active| *active)
                     .unwrap();
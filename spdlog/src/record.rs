@@ -3,10 +3,10 @@
 use std::{
     borrow::{Borrow, Cow},
     cell::RefCell,
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
-use crate::{Level, SourceLocation};
+use crate::{formatter::JsonRecordFields, Level, SourceLocation};
 
 /// Represents a log record.
 ///
@@ -35,6 +35,12 @@ struct RecordInner {
     source_location: Option<SourceLocation>,
     time: SystemTime,
     tid: u64,
+    duration: Option<Duration>,
+    preformatted: bool,
+    // The number of sinks of the logger that created the record, saturated. `u16`
+    // fits in the padding, to keep `RecordOwned` (carried by
+    // `Error::SendToChannel`) small.
+    sink_count: Option<u16>,
 }
 
 impl<'a> Record<'a> {
@@ -54,6 +60,9 @@ impl<'a> Record<'a> {
                 source_location: None,
                 time: SystemTime::now(),
                 tid: get_current_tid(),
+                duration: None,
+                preformatted: false,
+                sink_count: None,
             }),
         }
     }
@@ -73,8 +82,8 @@ impl<'a> Record<'a> {
     #[must_use]
     pub fn to_owned(&self) -> RecordOwned {
         RecordOwned {
-            logger_name: self.logger_name.map(|n| n.into()),
-            payload: self.payload.to_string(),
+            logger_name: self.logger_name.map(Box::from),
+            payload: Box::from(&*self.payload),
             inner: self.inner.clone().into_owned(),
         }
     }
@@ -116,6 +125,36 @@ impl<'a> Record<'a> {
         self.inner.tid
     }
 
+    /// Gets the duration attached at the log site.
+    ///
+    /// It is set by passing `duration: <expr>` to log macros, e.g. to report
+    /// the time a request handler took. Returns `None` if no duration was
+    /// attached.
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.inner.duration
+    }
+
+    /// Returns `true` if the payload is a message that has already been
+    /// formatted, which sinks should write verbatim instead of passing the
+    /// record to their formatter.
+    ///
+    /// Such records are logged by the default implementation of
+    /// [`Sink::log_preformatted`].
+    ///
+    /// [`Sink::log_preformatted`]: crate::sink::Sink::log_preformatted
+    #[must_use]
+    pub fn is_preformatted(&self) -> bool {
+        self.inner.preformatted
+    }
+
+    // Gets the number of sinks of the logger that created the record, `None` if
+    // it was not created by a logger.
+    #[must_use]
+    pub(crate) fn sink_count(&self) -> Option<usize> {
+        self.inner.sink_count.map(usize::from)
+    }
+
     // When adding more getters, also add to `RecordOwned`
 
     #[cfg(feature = "log")]
@@ -140,14 +179,84 @@ impl<'a> Record<'a> {
                 // For records from `log` crate, they never seem to come from different threads, so
                 // getting the current TID here should be correct
                 tid: get_current_tid(),
+                duration: None,
+                preformatted: false,
+                sink_count: Some(saturate_sink_count(logger.sinks().len())),
             }),
         }
     }
 
-    #[cfg(test)]
+    /// Parses a line written by [`JsonFormatter`] back into a record, e.g. to
+    /// replay logs.
+    ///
+    /// The time, level, logger name, payload and TID are restored. The time
+    /// is only as precise as the written timestamp, i.e. milliseconds. The
+    /// logger name and TID are optional, a record without a logger name
+    /// restores as such and one without a TID gets the current TID. Other keys
+    /// are ignored, the source location is not restored.
+    ///
+    /// A [`RecordOwned`] is returned since the parsed strings are owned, use
+    /// [`RecordOwned::as_ref`] to get a `Record` from it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ParseRecord`] if the line is not a JSON object with
+    /// the `timestamp`, `level` and `payload` keys, or [`Error::ParseLevel`] if
+    /// the level is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spdlog::{Level, Record};
+    ///
+    /// let line = r#"{"timestamp":"2022-11-02T09:23:12.263+08:00","level":"info","logger":"app","payload":"hello, world!"}"#;
+    /// let record = Record::from_json(line)?;
+    /// assert_eq!(record.level(), Level::Info);
+    /// assert_eq!(record.logger_name(), Some("app"));
+    /// assert_eq!(record.payload(), "hello, world!");
+    /// # Ok::<(), spdlog::Error>(())
+    /// ```
+    ///
+    /// [`JsonFormatter`]: crate::formatter::JsonFormatter
+    /// [`Error::ParseRecord`]: crate::Error::ParseRecord
+    /// [`Error::ParseLevel`]: crate::Error::ParseLevel
+    pub fn from_json(line: &str) -> crate::Result<RecordOwned> {
+        let fields = JsonRecordFields::parse(line.trim_end())?;
+
+        let mut record = Record::new(fields.level, fields.payload);
+        if let Some(logger_name) = &fields.logger_name {
+            record.set_logger_name(logger_name);
+        }
+        record.set_time(fields.time);
+        if let Some(tid) = fields.tid {
+            record.inner.to_mut().tid = tid;
+        }
+        Ok(record.to_owned())
+    }
+
+    /// Replaces the payload of the record.
+    ///
+    /// This allows a record to be reused in hot loops (e.g. benchmarks)
+    /// without reconstructing it for each iteration. Other fields are
+    /// unaffected.
+    pub fn set_payload<S>(&mut self, payload: S)
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        self.payload = payload.into();
+    }
+
+    pub(crate) fn set_logger_name(&mut self, logger_name: &'a str) {
+        self.logger_name = Some(logger_name);
+    }
+
     pub(crate) fn set_time(&mut self, new: SystemTime) {
         self.inner.to_mut().time = new;
     }
+
+    pub(crate) fn set_preformatted(&mut self) {
+        self.inner.to_mut().preformatted = true;
+    }
 }
 
 /// [`Record`] without lifetimes version.
@@ -155,8 +264,10 @@ impl<'a> Record<'a> {
 // Rust naming convention. Use `record.to_owned()` instead.
 #[derive(Clone, Debug)]
 pub struct RecordOwned {
-    logger_name: Option<String>,
-    payload: String,
+    // `Box<str>` rather than `String`, to keep `RecordOwned` (carried by
+    // `Error::SendToChannel`) small.
+    logger_name: Option<Box<str>>,
+    payload: Box<str>,
     inner: RecordInner,
 }
 
@@ -167,7 +278,7 @@ impl RecordOwned {
 
     /// References as [`Record`] cheaply.
     #[must_use]
-    pub fn as_ref(&self) -> Record {
+    pub fn as_ref(&self) -> Record<'_> {
         Record {
             logger_name: self.logger_name.as_deref(),
             payload: Cow::Borrowed(&self.payload),
@@ -205,6 +316,21 @@ impl RecordOwned {
         self.inner.time
     }
 
+    /// Gets the duration attached at the log site.
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.inner.duration
+    }
+
+    /// Returns `true` if the payload is a message that has already been
+    /// formatted.
+    ///
+    /// See [`Record::is_preformatted`].
+    #[must_use]
+    pub fn is_preformatted(&self) -> bool {
+        self.inner.preformatted
+    }
+
     // When adding more getters, also add to `Record`
 }
 
@@ -250,6 +376,28 @@ impl<'a> RecordBuilder<'a> {
         self
     }
 
+    /// Sets the duration attached at the log site.
+    #[must_use]
+    pub(crate) fn duration(mut self, duration: Option<Duration>) -> Self {
+        self.record.inner.to_mut().duration = duration;
+        self
+    }
+
+    /// Sets the number of sinks of the logger creating the record.
+    #[must_use]
+    pub(crate) fn sink_count(mut self, sink_count: usize) -> Self {
+        self.record.inner.to_mut().sink_count = Some(saturate_sink_count(sink_count));
+        self
+    }
+
+    /// Sets the thread ID.
+    #[cfg(test)]
+    #[must_use]
+    pub(crate) fn tid(mut self, tid: u64) -> Self {
+        self.record.inner.to_mut().tid = tid;
+        self
+    }
+
     /// Builds a [`Record`].
     #[must_use]
     pub(crate) fn build(self) -> Record<'a> {
@@ -257,6 +405,11 @@ impl<'a> RecordBuilder<'a> {
     }
 }
 
+#[must_use]
+fn saturate_sink_count(sink_count: usize) -> u16 {
+    u16::try_from(sink_count).unwrap_or(u16::MAX)
+}
+
 /// Get current thread id
 pub fn get_current_tid() -> u64 {
     #[cfg(target_os = "linux")]
@@ -286,8 +439,82 @@ pub fn get_current_tid() -> u64 {
     }
 
     thread_local! {
-        static TID: RefCell<Option<u64>> = RefCell::new(None);
+        static TID: RefCell<Option<u64>> = const { RefCell::new(None) };
     }
 
     TID.with(|tid| *tid.borrow_mut().get_or_insert_with(get_current_tid_inner))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        formatter::{Formatter, JsonFormatter},
+        test_utils::*,
+        StringBuf,
+    };
+
+    #[test]
+    fn set_payload() {
+        let mut record = Record::new(Level::Info, "first");
+        let formatter = NoModFormatter::new();
+
+        let mut buf = StringBuf::new();
+        formatter.format(&record, &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "first");
+
+        record.set_payload(String::from("second"));
+        assert_eq!(record.payload(), "second");
+
+        let mut buf = StringBuf::new();
+        formatter.format(&record, &mut buf).unwrap();
+        assert_eq!(buf.as_str(), "second");
+    }
+
+    #[test]
+    fn from_json() {
+        let mut record = Record::builder(Level::Warn, "a\"b\\c\nd\u{1}e中😀")
+            .tid(3132)
+            .build();
+        record.set_logger_name("logger-name");
+        record.set_time(SystemTime::UNIX_EPOCH + Duration::from_millis(1_667_351_000_123));
+
+        for formatter in [JsonFormatter::new(), JsonFormatter::new().with_pretty(true)] {
+            let mut buf = StringBuf::new();
+            formatter.format(&record, &mut buf).unwrap();
+
+            let parsed = Record::from_json(&buf).unwrap();
+            assert_eq!(parsed.level(), record.level());
+            assert_eq!(parsed.time(), record.time());
+            assert_eq!(parsed.logger_name(), record.logger_name());
+            assert_eq!(parsed.payload(), record.payload());
+            assert_eq!(parsed.as_ref().tid(), record.tid());
+        }
+
+        let parsed = Record::from_json(
+            r#"{"timestamp":"2022-11-02T09:23:12.263Z","level":"info","payload":"hi"}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.logger_name(), None);
+        assert_eq!(parsed.payload(), "hi");
+
+        for line in [
+            "",
+            "not json",
+            r#"{"level":"info","payload":"hi"}"#,
+            r#"{"timestamp":"2022-11-02T09:23:12.263Z","level":"info","payload":1}"#,
+            r#"{"timestamp":"2022-11-02T09:23:12.263Z","level":"info","payload":"hi"} trailing"#,
+        ] {
+            assert!(matches!(
+                Record::from_json(line),
+                Err(crate::Error::ParseRecord(_))
+            ));
+        }
+        assert!(matches!(
+            Record::from_json(
+                r#"{"timestamp":"2022-11-02T09:23:12.263Z","level":"loud","payload":"hi"}"#
+            ),
+            Err(crate::Error::ParseLevel(_))
+        ));
+    }
+}
@@ -1,9 +1,19 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::RecvTimeoutError;
+
 use crate::{
-    default_error_handler, default_thread_pool,
+    clock, default_error_handler, default_thread_pool,
+    error::{SendToChannelError, SendToChannelErrorDropped},
     formatter::Formatter,
-    sink::{helper, OverflowPolicy, Sink, Sinks},
+    sink::{
+        helper, AfterShutdownPolicy, FlushPolicy, FlushPolicyState, OverflowPolicy, Sink, Sinks,
+    },
     sync::*,
-    Error, ErrorHandler, LevelFilter, Record, RecordOwned, Result, ThreadPool,
+    Error, ErrorHandler, LevelFilter, Record, RecordOwned, Result, StringBuf, ThreadPool,
 };
 
 /// A [combined sink], logging and flushing [asynchronously]
@@ -17,6 +27,23 @@ use crate::{
 /// Errors that occur in `log` and `flush` will not be returned directly,
 /// instead the error handler will be called.
 ///
+/// By default records are formatted by the internal sinks on a worker thread
+/// of the thread pool, see [`AsyncPoolSinkBuilder::format_on_enqueue`] for
+/// formatting them on the logging thread instead.
+///
+/// # Dropping
+///
+/// When dropped, the sink is shut down by [`AsyncPoolSink::shutdown`] with a
+/// timeout of 5 seconds, so that records are not lost when the program exits.
+/// That is, dropping blocks the current thread until the operations sent
+/// before have been processed, for at most 5 seconds. Errors are passed to the
+/// error handler.
+///
+/// If the sink is dropped on a worker thread of its own thread pool (e.g. it's
+/// an internal sink of another `AsyncPoolSink` using the same thread pool),
+/// dropping doesn't block, because the pending operations can only be
+/// processed after the current one. They are still processed afterward.
+///
 /// # Examples
 ///
 /// See [./examples] directory.
@@ -27,7 +54,16 @@ use crate::{
 // The names `AsyncSink` and `AsyncRuntimeSink` is reserved for future use.
 pub struct AsyncPoolSink {
     level_filter: Atomic<LevelFilter>,
+    // Only used if `AsyncPoolSinkBuilder::format_on_enqueue` is enabled.
+    formatter: SpinRwLock<Box<dyn Formatter>>,
+    format_on_enqueue: bool,
     overflow_policy: OverflowPolicy,
+    max_in_flight_bytes: usize,
+    flush_policy: FlushPolicyState,
+    after_shutdown: AfterShutdownPolicy,
+    closed: AtomicBool,
+    dropped_after_shutdown: AtomicUsize,
+    overflow_count: AtomicU64,
     thread_pool: Arc<ThreadPool>,
     backend: Arc<Backend>,
 }
@@ -38,10 +74,16 @@ impl AsyncPoolSink {
     pub fn builder() -> AsyncPoolSinkBuilder {
         AsyncPoolSinkBuilder {
             level_filter: helper::SINK_DEFAULT_LEVEL_FILTER,
+            formatter: None,
+            format_on_enqueue: false,
             overflow_policy: OverflowPolicy::Block,
+            max_in_flight_bytes: 0,
+            flush_policy: FlushPolicy::Never,
+            after_shutdown: AfterShutdownPolicy::ReturnError,
             sinks: Sinks::new(),
             thread_pool: None,
             error_handler: None,
+            retain_failed: 0,
         }
     }
 
@@ -56,8 +98,253 @@ impl AsyncPoolSink {
         self.backend.error_handler.swap(handler, Ordering::Relaxed);
     }
 
+    /// Flushes the internal sinks and blocks until it's done.
+    ///
+    /// Unlike [`Sink::flush`], which only sends a flush operation to the thread
+    /// pool and returns immediately, this function waits until all the
+    /// operations sent before it (including logging records) and the flush
+    /// itself have been processed. It's useful to make sure everything has been
+    /// written before the program exits.
+    ///
+    /// The flush operation is always sent with [`OverflowPolicy::Block`],
+    /// regardless of the overflow policy of this sink. Errors that occur while
+    /// flushing the internal sinks are still passed to the error handler rather
+    /// than returned.
+    pub fn flush_blocking(&self) -> Result<()> {
+        let (done_sender, done_receiver) = crossbeam::channel::bounded(1);
+
+        self.thread_pool.assign_task(
+            Task::Flush {
+                backend: self.clone_backend(),
+                done: Some(done_sender),
+            },
+            OverflowPolicy::Block,
+            || self.count_overflow(),
+        )?;
+
+        // The sender is dropped without sending if the flush is never processed
+        // (e.g. an internal sink panicked), so this never blocks forever.
+        done_receiver
+            .recv()
+            .map_err(|_| Self::flush_not_processed_error())
+    }
+
+    /// Shuts down the sink, flushing the internal sinks and blocking for at
+    /// most the given timeout until it's done.
+    ///
+    /// Records logged after this call are handled according to the policy
+    /// specified by [`AsyncPoolSinkBuilder::after_shutdown`], and flushing
+    /// does nothing (or flushes the fallback sink). Records logged
+    /// concurrently with this call may still be processed.
+    ///
+    /// Returns [`Error::ShutdownTimeout`] if the timeout elapses before the
+    /// operations sent before this call and the flush have been processed, they
+    /// are still processed afterward in the background. The sink is shut down
+    /// either way, calling it again has no effect and returns `Ok(())`.
+    ///
+    /// This is called when the sink is dropped, see the [type-level
+    /// documentation](AsyncPoolSink#dropping).
+    pub fn shutdown(&self, timeout: Duration) -> Result<()> {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let deadline = Instant::now().checked_add(timeout);
+        let (done_sender, done_receiver) = crossbeam::channel::bounded(1);
+
+        self.thread_pool.assign_task_timeout(
+            Task::Flush {
+                backend: self.clone_backend(),
+                done: Some(done_sender),
+            },
+            timeout,
+        )?;
+
+        let res = match deadline {
+            Some(deadline) => done_receiver.recv_deadline(deadline),
+            None => done_receiver
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match res {
+            Ok(()) => Ok(()),
+            Err(RecvTimeoutError::Timeout) => Err(Error::ShutdownTimeout),
+            // See `flush_blocking`.
+            Err(RecvTimeoutError::Disconnected) => Err(Self::flush_not_processed_error()),
+        }
+    }
+
+    // Shuts down the sink without waiting, the flush is processed afterward in the
+    // background.
+    fn shutdown_nonblocking(&self) -> Result<()> {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // Blocking here may wait on the current thread, so the flush is dropped
+        // instead if the channel is full.
+        self.thread_pool.assign_task(
+            Task::Flush {
+                backend: self.clone_backend(),
+                done: None,
+            },
+            OverflowPolicy::DropIncoming,
+            || self.count_overflow(),
+        )
+    }
+
+    #[must_use]
+    fn flush_not_processed_error() -> Error {
+        Error::SendToChannel(
+            SendToChannelError::Disconnected,
+            SendToChannelErrorDropped::Flush,
+        )
+    }
+
+    /// Gets the number of records dropped because the channel of the thread
+    /// pool was full, with [`OverflowPolicy::DropIncoming`] or
+    /// [`OverflowPolicy::DropOldest`].
+    ///
+    /// Records dropped by [`AsyncPoolSinkBuilder::max_in_flight_bytes`] are
+    /// also counted.
+    #[must_use]
+    pub fn dropped_records(&self) -> usize {
+        self.backend.dropped_records.load(Ordering::Relaxed)
+    }
+
+    /// Gets the number of records dropped because they were logged after
+    /// [`AsyncPoolSink::shutdown`] with [`AfterShutdownPolicy::Drop`].
+    #[must_use]
+    pub fn dropped_after_shutdown(&self) -> usize {
+        self.dropped_after_shutdown.load(Ordering::Relaxed)
+    }
+
+    fn log_after_shutdown(&self, record: &Record) -> Result<()> {
+        match &self.after_shutdown {
+            AfterShutdownPolicy::ReturnError => Err(Error::SinkClosed),
+            AfterShutdownPolicy::Drop => {
+                self.dropped_after_shutdown.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            AfterShutdownPolicy::Fallback(sink) => sink.log(record),
+        }
+    }
+
+    /// Gets the number of operations queued in the thread pool, waiting to be
+    /// processed.
+    ///
+    /// The queue is shared by all sinks using the same [`ThreadPool`], so
+    /// operations of other sinks are included. The lengths are read without
+    /// locking, see [`ThreadPool::queue_len`], so it's suitable for exporting
+    /// as a metric periodically.
+    #[must_use]
+    pub fn queue_len(&self) -> usize {
+        self.thread_pool.queue_len()
+    }
+
+    /// Gets the capacity of the queue of the thread pool.
+    ///
+    /// See [`ThreadPoolBuilder::capacity`].
+    ///
+    /// [`ThreadPoolBuilder::capacity`]: crate::ThreadPoolBuilder::capacity
+    #[must_use]
+    pub fn queue_capacity(&self) -> usize {
+        self.thread_pool.queue_capacity()
+    }
+
+    /// Gets the number of times an operation of this sink overflowed, i.e. the
+    /// queue was full (or the limit specified by
+    /// [`AsyncPoolSinkBuilder::max_in_flight_bytes`] was reached) when it was
+    /// sent.
+    ///
+    /// Overflows are counted regardless of the overflow policy, with
+    /// [`OverflowPolicy::Block`] each of them made a logging thread wait, with
+    /// other policies each of them dropped a record (see
+    /// [`AsyncPoolSink::dropped_records`]). This is a cheap read, suitable for
+    /// exporting as a metric periodically.
+    #[must_use]
+    pub fn overflow_count(&self) -> u64 {
+        self.overflow_count.load(Ordering::Relaxed)
+    }
+
+    fn count_overflow(&self) {
+        self.overflow_count.fetch_add(1, Ordering::Relaxed);
+    }
+
     fn assign_task(&self, task: Task) -> Result<()> {
-        self.thread_pool.assign_task(task, self.overflow_policy)
+        self.thread_pool
+            .assign_task(task, self.overflow_policy, || self.count_overflow())
+    }
+
+    // Reserves the payload size of the record against `max_in_flight_bytes`, the
+    // returned size is released once the record has been processed.
+    fn reserve_in_flight_bytes(&self, record: &Record) -> Result<usize> {
+        if self.max_in_flight_bytes == 0 {
+            return Ok(0);
+        }
+
+        let bytes = record.payload().len();
+        // A record larger than the limit is still accepted when nothing is in
+        // flight, otherwise it could never be sent.
+        let fits =
+            |in_flight: usize| in_flight == 0 || in_flight + bytes <= self.max_in_flight_bytes;
+
+        let mut in_flight = self.backend.in_flight_bytes.lock_expect();
+        if !fits(*in_flight) {
+            self.count_overflow();
+        }
+        match self.overflow_policy {
+            OverflowPolicy::Block => {
+                while !fits(*in_flight) {
+                    in_flight = self
+                        .backend
+                        .in_flight_released
+                        .wait(in_flight)
+                        .expect("lock is poisoned");
+                }
+            }
+            OverflowPolicy::DropIncoming => {
+                if !fits(*in_flight) {
+                    return Err(self.drop_incoming(record));
+                }
+            }
+            OverflowPolicy::DropOldest => {
+                while !fits(*in_flight) {
+                    // Discarding the evicted record releases its bytes, which locks
+                    // `in_flight_bytes` again.
+                    drop(in_flight);
+                    match self.thread_pool.evict_oldest(&self.backend) {
+                        Some(evicted) => evicted.discard(),
+                        None => return Err(self.drop_incoming(record)),
+                    }
+                    in_flight = self.backend.in_flight_bytes.lock_expect();
+                }
+            }
+        }
+        *in_flight += bytes;
+        Ok(bytes)
+    }
+
+    #[must_use]
+    fn drop_incoming(&self, record: &Record) -> Error {
+        self.backend.dropped_records.fetch_add(1, Ordering::Relaxed);
+        Error::SendToChannel(
+            SendToChannelError::Full,
+            SendToChannelErrorDropped::Record(record.to_owned()),
+        )
+    }
+
+    // Formats the record with the formatter of this sink, into a record marked as
+    // preformatted, which is passed to `Sink::log_preformatted` of the internal
+    // sinks.
+    fn format_preformatted<'a>(&self, record: &Record<'a>) -> Result<Record<'a>> {
+        let mut dest = StringBuf::new();
+        self.formatter.read().format(record, &mut dest)?;
+
+        let mut record = record.clone();
+        record.set_payload(dest.to_string());
+        record.set_preformatted();
+        Ok(record)
     }
 
     #[must_use]
@@ -69,26 +356,61 @@ impl AsyncPoolSink {
 impl Sink for AsyncPoolSink {
     fn log(&self, record: &Record) -> Result<()> {
         if self.should_log(record.level()) {
+            if self.closed.load(Ordering::SeqCst) {
+                return self.log_after_shutdown(record);
+            }
+
+            let formatted;
+            let record = if self.format_on_enqueue && !record.is_preformatted() {
+                formatted = self.format_preformatted(record)?;
+                &formatted
+            } else {
+                record
+            };
+
+            let bytes = self.reserve_in_flight_bytes(record)?;
             self.assign_task(Task::Log {
                 backend: self.clone_backend(),
                 record: record.to_owned(),
+                bytes,
+            })
+            .map_err(|err| {
+                self.backend.release_in_flight_bytes(bytes);
+                if matches!(err, Error::SendToChannel(SendToChannelError::Full, _)) {
+                    self.backend.dropped_records.fetch_add(1, Ordering::Relaxed);
+                }
+                err
             })?;
+
+            if self.flush_policy.should_flush(record.level()) {
+                self.flush()?;
+            }
         }
         Ok(())
     }
 
     fn flush(&self) -> Result<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            return match &self.after_shutdown {
+                AfterShutdownPolicy::Fallback(sink) => sink.flush(),
+                _ => Ok(()),
+            };
+        }
+
         self.assign_task(Task::Flush {
             backend: self.clone_backend(),
+            done: None,
         })
     }
 
     /// For [`AsyncPoolSink`], the function performs the same call to all
-    /// internal sinks.
+    /// internal sinks, and also sets the formatter used by
+    /// [`AsyncPoolSinkBuilder::format_on_enqueue`].
     fn set_formatter(&self, formatter: Box<dyn Formatter>) {
         for sink in &self.backend.sinks {
             sink.set_formatter(formatter.clone_box())
         }
+        *self.formatter.write() = formatter;
     }
 
     helper::common_impl! {
@@ -98,15 +420,47 @@ impl Sink for AsyncPoolSink {
             error_handler: backend.error_handler,
         }
     }
+
+    /// For [`AsyncPoolSink`], the function returns the records that failed to
+    /// be logged by any internal sink, up to the capacity specified by
+    /// [`AsyncPoolSinkBuilder::retain_failed`].
+    ///
+    /// Records that are still queued in the thread pool are not included, call
+    /// [`AsyncPoolSink::flush_blocking`] first to wait for them to be
+    /// processed.
+    fn drain_pending(&self) -> Vec<RecordOwned> {
+        self.backend.failed.lock_expect().drain(..).collect()
+    }
+}
+
+impl Drop for AsyncPoolSink {
+    fn drop(&mut self) {
+        let res = if self.thread_pool.is_current_thread_worker() {
+            self.shutdown_nonblocking()
+        } else {
+            self.shutdown(SHUTDOWN_TIMEOUT_ON_DROP)
+        };
+        if let Err(err) = res {
+            self.backend.handle_error(err);
+        }
+    }
 }
 
+const SHUTDOWN_TIMEOUT_ON_DROP: Duration = Duration::from_secs(5);
+
 /// The builder of [`AsyncPoolSink`].
 pub struct AsyncPoolSinkBuilder {
     level_filter: LevelFilter,
+    formatter: Option<Box<dyn Formatter>>,
+    format_on_enqueue: bool,
     sinks: Sinks,
     overflow_policy: OverflowPolicy,
+    max_in_flight_bytes: usize,
+    flush_policy: FlushPolicy,
+    after_shutdown: AfterShutdownPolicy,
     thread_pool: Option<Arc<ThreadPool>>,
     error_handler: Option<ErrorHandler>,
+    retain_failed: usize,
 }
 
 impl AsyncPoolSinkBuilder {
@@ -139,6 +493,68 @@ impl AsyncPoolSinkBuilder {
         self
     }
 
+    /// Specifies the maximum total size (in bytes) of the payloads of the
+    /// records sent to the thread pool but not yet processed.
+    ///
+    /// When an incoming record would exceed the limit, it is handled according
+    /// to the overflow policy, the same as when the channel of the thread pool
+    /// is full. With [`OverflowPolicy::DropOldest`], the oldest queued records
+    /// of this sink are dropped until the incoming one fits, records that are
+    /// being processed by a worker thread can't be dropped, so if they alone
+    /// exceed the limit, the incoming record is dropped instead. This bounds the memory used under backpressure even if a few
+    /// records are huge, which the capacity of the channel (a number of
+    /// operations) alone cannot. A single record larger than the limit is still
+    /// accepted when no other record is in flight.
+    ///
+    /// This parameter is **optional**, and defaults to `0` for no limit.
+    #[must_use]
+    pub fn max_in_flight_bytes(mut self, max_in_flight_bytes: usize) -> Self {
+        self.max_in_flight_bytes = max_in_flight_bytes;
+        self
+    }
+
+    /// Specifies whether records are formatted on the logging thread when they
+    /// are logged, rather than by the internal sinks on a worker thread of the
+    /// thread pool.
+    ///
+    /// If enabled, each record is formatted immediately by the formatter of
+    /// this sink (see [`AsyncPoolSinkBuilder::formatter`]), and only the
+    /// resulting text is queued, which the internal sinks receive by
+    /// [`Sink::log_preformatted`] and write as-is. This moves the cost of
+    /// formatting from the worker threads to the logging threads, and patterns
+    /// that read the context of the current thread (e.g. `{thread_prio}`, or
+    /// custom patterns reading the name of the current thread) render the
+    /// logging thread rather than the worker thread. The formatters of the
+    /// internal sinks are not used for records logged by this sink.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[must_use]
+    pub fn format_on_enqueue(mut self, format_on_enqueue: bool) -> Self {
+        self.format_on_enqueue = format_on_enqueue;
+        self
+    }
+
+    /// Specifies when a flush operation is sent to the internal sinks after
+    /// logging a record.
+    ///
+    /// This parameter is **optional**, and defaults to [`FlushPolicy::Never`].
+    #[must_use]
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
+    /// Specifies how records logged after [`AsyncPoolSink::shutdown`] are
+    /// handled.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`AfterShutdownPolicy::ReturnError`].
+    #[must_use]
+    pub fn after_shutdown(mut self, policy: AfterShutdownPolicy) -> Self {
+        self.after_shutdown = policy;
+        self
+    }
+
     /// Specifies a custom thread pool.
     ///
     /// This parameter is **optional**, and defaults to the built-in thread
@@ -151,18 +567,47 @@ impl AsyncPoolSinkBuilder {
         self
     }
 
+    /// Specifies the maximum number of records retained when internal sinks
+    /// fail to log them, so that they can be retrieved later by
+    /// [`Sink::drain_pending`].
+    ///
+    /// When exceeded, the oldest retained record is dropped. Errors are still
+    /// passed to the error handler regardless of this parameter.
+    ///
+    /// This parameter is **optional**, and defaults to `0` (no records are
+    /// retained).
+    #[must_use]
+    pub fn retain_failed(mut self, capacity: usize) -> Self {
+        self.retain_failed = capacity;
+        self
+    }
+
     /// Builds a [`AsyncPoolSink`].
     pub fn build(self) -> Result<AsyncPoolSink> {
         let backend = Arc::new(Backend {
             sinks: self.sinks.clone(),
             error_handler: Atomic::new(self.error_handler),
+            failed: Mutex::new(VecDeque::new()),
+            failed_capacity: self.retain_failed,
+            in_flight_bytes: Mutex::new(0),
+            in_flight_released: Condvar::new(),
+            dropped_records: AtomicUsize::new(0),
+            drop_oldest: self.overflow_policy == OverflowPolicy::DropOldest,
         });
 
         let thread_pool = self.thread_pool.unwrap_or_else(default_thread_pool);
 
         Ok(AsyncPoolSink {
             level_filter: Atomic::new(self.level_filter),
+            formatter: SpinRwLock::new(self.formatter.unwrap_or_else(crate::default_formatter)),
+            format_on_enqueue: self.format_on_enqueue,
             overflow_policy: self.overflow_policy,
+            max_in_flight_bytes: self.max_in_flight_bytes,
+            flush_policy: FlushPolicyState::new(self.flush_policy, clock::default_clock()),
+            after_shutdown: self.after_shutdown,
+            closed: AtomicBool::new(false),
+            dropped_after_shutdown: AtomicUsize::new(0),
+            overflow_count: AtomicU64::new(0),
             thread_pool,
             backend,
         })
@@ -173,20 +618,63 @@ impl AsyncPoolSinkBuilder {
         formatter: None,
         error_handler: error_handler,
     });
+
+    helper::common_impl! {
+        /// Specifies a formatter, used to format records on the logging thread
+        /// if [`AsyncPoolSinkBuilder::format_on_enqueue`] is enabled.
+        ///
+        /// This parameter is **optional**, and defaults to the formatter set
+        /// by [`set_default_formatter`], or [`FullFormatter`] if none was set.
+        ///
+        /// [`set_default_formatter`]: crate::set_default_formatter
+        /// [`FullFormatter`]: crate::formatter::FullFormatter
+        @SinkBuilderCustomInner@formatter: formatter
+    }
 }
 
 pub(crate) struct Backend {
     sinks: Sinks,
     error_handler: helper::SinkErrorHandler,
+    failed: Mutex<VecDeque<RecordOwned>>,
+    failed_capacity: usize,
+    // Only tracked if `AsyncPoolSinkBuilder::max_in_flight_bytes` is set.
+    in_flight_bytes: Mutex<usize>,
+    in_flight_released: Condvar,
+    dropped_records: AtomicUsize,
+    // Whether the sink uses `OverflowPolicy::DropOldest`, whose tasks are queued
+    // separately in the thread pool.
+    drop_oldest: bool,
 }
 
 impl Backend {
-    fn log(&self, record: &Record) {
+    fn log(&self, record: RecordOwned) {
+        let mut has_failed = false;
         for sink in &self.sinks {
-            if let Err(err) = sink.log(record) {
+            let res = if record.is_preformatted() {
+                sink.log_preformatted(record.payload().as_bytes(), record.level())
+            } else {
+                sink.log(&record.as_ref())
+            };
+            if let Err(err) = res {
+                has_failed = true;
                 self.handle_error(err);
             }
         }
+
+        if has_failed && self.failed_capacity > 0 {
+            let mut failed = self.failed.lock_expect();
+            if failed.len() >= self.failed_capacity {
+                failed.pop_front();
+            }
+            failed.push_back(record);
+        }
+    }
+
+    fn release_in_flight_bytes(&self, bytes: usize) {
+        if bytes > 0 {
+            *self.in_flight_bytes.lock_expect() -= bytes;
+            self.in_flight_released.notify_all();
+        }
     }
 
     fn flush(&self) {
@@ -208,9 +696,13 @@ pub(crate) enum Task {
     Log {
         backend: Arc<Backend>,
         record: RecordOwned,
+        // Released from `Backend::in_flight_bytes` after logging.
+        bytes: usize,
     },
     Flush {
         backend: Arc<Backend>,
+        // Notified after flushing, used by `AsyncPoolSink::flush_blocking`.
+        done: Option<crossbeam::channel::Sender<()>>,
     },
 }
 
@@ -218,14 +710,63 @@ impl Task {
     // calls this function in async threads
     pub(crate) fn exec(self) {
         match self {
-            Task::Log { backend, record } => {
-                backend.log(&record.as_ref());
+            Task::Log {
+                backend,
+                record,
+                bytes,
+            } => {
+                backend.log(record);
+                backend.release_in_flight_bytes(bytes);
             }
-            Task::Flush { backend } => {
+            Task::Flush { backend, done } => {
                 backend.flush();
+                if let Some(done) = done {
+                    let _ = done.send(());
+                }
             }
         }
     }
+
+    // Returns whether the task is queued in the evicting queue of the thread pool,
+    // rather than its channel.
+    //
+    // All tasks of a sink go to the same queue, so that they are processed in order.
+    #[must_use]
+    pub(crate) fn uses_evicting_queue(&self) -> bool {
+        self.backend().drop_oldest
+    }
+
+    // Returns whether the task can be evicted to make room for a record of the sink
+    // of `backend`, for `OverflowPolicy::DropOldest`.
+    //
+    // Only log tasks of the same sink can be evicted, so that a sink under pressure
+    // never drops records of other sinks sharing the thread pool, and flushes are
+    // never lost.
+    #[must_use]
+    pub(crate) fn is_evictable_for(&self, backend: &Arc<Backend>) -> bool {
+        match self {
+            Task::Log { backend: own, .. } => Arc::ptr_eq(own, backend),
+            Task::Flush { .. } => false,
+        }
+    }
+
+    // Drops the task without executing it, for `OverflowPolicy::DropOldest`.
+    pub(crate) fn discard(self) {
+        match self {
+            Task::Log { backend, bytes, .. } => {
+                backend.dropped_records.fetch_add(1, Ordering::Relaxed);
+                backend.release_in_flight_bytes(bytes);
+            }
+            Task::Flush { .. } => unreachable!("flush tasks are never evicted"),
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn backend(&self) -> &Arc<Backend> {
+        match self {
+            Task::Log { backend, .. } | Task::Flush { backend, .. } => backend,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -269,18 +810,21 @@ mod tests {
             assert_eq!(counter_sink.flush_count(), 0);
         }
 
+        // The sink was shut down and flushed when dropped
+        assert_eq!(counter_sink.flush_count(), 1);
+
         {
             let logger = build_logger();
 
             error!(logger: logger, "");
             sleep(Duration::from_millis(50));
             assert_eq!(counter_sink.log_count(), 3);
-            assert_eq!(counter_sink.flush_count(), 1);
+            assert_eq!(counter_sink.flush_count(), 2);
 
             critical!(logger: logger, "");
             sleep(Duration::from_millis(50));
             assert_eq!(counter_sink.log_count(), 4);
-            assert_eq!(counter_sink.flush_count(), 2);
+            assert_eq!(counter_sink.flush_count(), 3);
         }
     }
 
@@ -361,4 +905,518 @@ mod tests {
         assert_eq!(counter_sink.log_count(), 2);
         assert_eq!(counter_sink.flush_count(), 1);
     }
+
+    #[test]
+    fn flush_blocking() {
+        let counter_sink = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(50))));
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().build().unwrap());
+        let async_pool_sink = Arc::new(
+            AsyncPoolSink::builder()
+                .sink(counter_sink.clone())
+                .thread_pool(thread_pool)
+                .build()
+                .unwrap(),
+        );
+        let logger = Logger::builder()
+            .sink(async_pool_sink.clone())
+            .level_filter(LevelFilter::All)
+            .build()
+            .unwrap();
+
+        for i in 0..5 {
+            info!(logger: logger, "{}", i);
+        }
+        assert!(counter_sink.log_count() < 5);
+
+        async_pool_sink.flush_blocking().unwrap();
+        assert_eq!(counter_sink.log_count(), 5);
+        assert_eq!(counter_sink.flush_count(), 1);
+        assert_eq!(counter_sink.payloads(), ["0", "1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn after_shutdown() {
+        let thread_pool = Arc::new(ThreadPool::builder().build().unwrap());
+        let build = |policy: AfterShutdownPolicy, counter_sink: Arc<CounterSink>| {
+            AsyncPoolSink::builder()
+                .sink(counter_sink)
+                .thread_pool(thread_pool.clone())
+                .after_shutdown(policy)
+                .build()
+                .unwrap()
+        };
+        let record = Record::new(Level::Info, "late");
+
+        // Return an error
+        let counter_sink = Arc::new(CounterSink::new());
+        let sink = build(AfterShutdownPolicy::ReturnError, counter_sink.clone());
+        sink.log(&Record::new(Level::Info, "early")).unwrap();
+        sink.shutdown(Duration::from_secs(5)).unwrap();
+        assert_eq!(counter_sink.payloads(), ["early"]);
+        assert!(matches!(sink.log(&record), Err(Error::SinkClosed)));
+        sink.flush().unwrap();
+        sink.shutdown(Duration::from_secs(5)).unwrap();
+
+        // Drop and count
+        let counter_sink = Arc::new(CounterSink::new());
+        let sink = build(AfterShutdownPolicy::Drop, counter_sink.clone());
+        sink.shutdown(Duration::from_secs(5)).unwrap();
+        sink.log(&record).unwrap();
+        sink.log(&record).unwrap();
+        assert_eq!(sink.dropped_after_shutdown(), 2);
+        assert_eq!(counter_sink.log_count(), 0);
+
+        // Route to a fallback sink
+        let counter_sink = Arc::new(CounterSink::new());
+        let fallback = Arc::new(CounterSink::new());
+        let sink = build(
+            AfterShutdownPolicy::Fallback(fallback.clone()),
+            counter_sink.clone(),
+        );
+        sink.shutdown(Duration::from_secs(5)).unwrap();
+        sink.log(&record).unwrap();
+        sink.flush().unwrap();
+        assert_eq!(fallback.payloads(), ["late"]);
+        assert_eq!(fallback.flush_count(), 1);
+        assert_eq!(counter_sink.log_count(), 0);
+        assert_eq!(counter_sink.flush_count(), 1);
+    }
+
+    #[test]
+    fn shutdown() {
+        let counter_sink = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(100))));
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().build().unwrap());
+        let build = || {
+            AsyncPoolSink::builder()
+                .sink(counter_sink.clone())
+                .thread_pool(thread_pool.clone())
+                .build()
+                .unwrap()
+        };
+
+        // Timed out with records still pending
+        let sink = build();
+        for _ in 0..5 {
+            sink.log(&Record::new(Level::Info, "")).unwrap();
+        }
+        assert!(matches!(
+            sink.shutdown(Duration::from_millis(150)),
+            Err(Error::ShutdownTimeout)
+        ));
+        assert!(counter_sink.log_count() < 5);
+        // Shutting down again has no effect
+        sink.shutdown(Duration::ZERO).unwrap();
+        assert!(matches!(
+            sink.log(&Record::new(Level::Info, "")),
+            Err(Error::SinkClosed)
+        ));
+        sink.flush_blocking().unwrap();
+        assert_eq!(counter_sink.log_count(), 5);
+
+        // Drained in time
+        let sink = build();
+        for _ in 0..2 {
+            sink.log(&Record::new(Level::Info, "")).unwrap();
+        }
+        sink.shutdown(Duration::from_secs(5)).unwrap();
+        assert_eq!(counter_sink.log_count(), 7);
+
+        // Without a deadline
+        let sink = build();
+        sink.log(&Record::new(Level::Info, "")).unwrap();
+        sink.shutdown(Duration::MAX).unwrap();
+        assert_eq!(counter_sink.log_count(), 8);
+
+        // Drained when dropped
+        let sink = build();
+        sink.log(&Record::new(Level::Info, "")).unwrap();
+        drop(sink);
+        assert_eq!(counter_sink.log_count(), 9);
+    }
+
+    #[test]
+    fn drop_on_worker_thread() {
+        // Drops the inner sink when a record is logged, i.e. on a worker thread.
+        struct DropOnLogSink {
+            inner: Mutex<Option<AsyncPoolSink>>,
+        }
+
+        impl Sink for DropOnLogSink {
+            fn log(&self, _record: &Record) -> Result<()> {
+                drop(self.inner.lock_expect().take());
+                Ok(())
+            }
+
+            fn flush(&self) -> Result<()> {
+                Ok(())
+            }
+
+            fn level_filter(&self) -> LevelFilter {
+                LevelFilter::All
+            }
+
+            fn set_level_filter(&self, _level_filter: LevelFilter) {}
+
+            fn set_formatter(&self, _formatter: Box<dyn Formatter>) {}
+
+            fn set_error_handler(&self, _handler: Option<ErrorHandler>) {}
+        }
+
+        let counter_sink = Arc::new(CounterSink::new());
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().build().unwrap());
+        let inner = AsyncPoolSink::builder()
+            .sink(counter_sink.clone())
+            .thread_pool(thread_pool.clone())
+            .build()
+            .unwrap();
+        inner.log(&Record::new(Level::Info, "inner")).unwrap();
+        let outer = AsyncPoolSink::builder()
+            .sink(Arc::new(DropOnLogSink {
+                inner: Mutex::new(Some(inner)),
+            }))
+            .thread_pool(thread_pool)
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        outer.log(&Record::new(Level::Info, "outer")).unwrap();
+        outer.flush_blocking().unwrap();
+        // Dropping the inner sink didn't wait on the worker thread itself
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        drop(outer);
+        assert_eq!(counter_sink.payloads(), ["inner"]);
+        assert_eq!(counter_sink.flush_count(), 1);
+    }
+
+    #[test]
+    fn max_in_flight_bytes() {
+        let counter_sink = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(200))));
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().capacity(100).build().unwrap());
+        let async_pool_sink = AsyncPoolSink::builder()
+            .sink(counter_sink.clone())
+            .thread_pool(thread_pool)
+            .overflow_policy(OverflowPolicy::DropIncoming)
+            .max_in_flight_bytes(1024)
+            .build()
+            .unwrap();
+
+        // Far below the capacity of the channel, but beyond the byte limit
+        let payload = "x".repeat(400);
+        let results = (0..4)
+            .map(|_| async_pool_sink.log(&Record::new(Level::Info, payload.as_str())))
+            .collect::<Vec<_>>();
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        assert!(matches!(
+            results[2],
+            Err(Error::SendToChannel(
+                SendToChannelError::Full,
+                SendToChannelErrorDropped::Record(_)
+            ))
+        ));
+        assert!(results[3].is_err());
+
+        async_pool_sink.flush_blocking().unwrap();
+        assert_eq!(counter_sink.log_count(), 2);
+
+        // The bytes are released after the records are processed
+        assert!(async_pool_sink
+            .log(&Record::new(Level::Info, payload.as_str()))
+            .is_ok());
+    }
+
+    #[test]
+    fn max_in_flight_bytes_drop_oldest() {
+        let counter_sink = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(200))));
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().capacity(100).build().unwrap());
+        let async_pool_sink = AsyncPoolSink::builder()
+            .sink(counter_sink.clone())
+            .thread_pool(thread_pool)
+            .overflow_policy(OverflowPolicy::DropOldest)
+            .max_in_flight_bytes(1024)
+            .build()
+            .unwrap();
+        let payload = |i: usize| format!("{i}{}", "x".repeat(399));
+
+        // Keep the worker busy, the record being processed can't be dropped
+        async_pool_sink
+            .log(&Record::new(Level::Info, payload(0)))
+            .unwrap();
+        sleep(Duration::from_millis(50));
+
+        // The oldest queued records are dropped to make room for the incoming ones
+        for i in 1..5 {
+            async_pool_sink
+                .log(&Record::new(Level::Info, payload(i)))
+                .unwrap();
+        }
+        async_pool_sink.flush_blocking().unwrap();
+        assert_eq!(counter_sink.payloads(), [payload(0), payload(4)]);
+        assert_eq!(async_pool_sink.dropped_records(), 3);
+
+        // If only records being processed exceed the limit, the incoming one is dropped
+        async_pool_sink
+            .log(&Record::new(Level::Info, "x".repeat(1000)))
+            .unwrap();
+        sleep(Duration::from_millis(50));
+        assert!(matches!(
+            async_pool_sink.log(&Record::new(Level::Info, payload(5))),
+            Err(Error::SendToChannel(
+                SendToChannelError::Full,
+                SendToChannelErrorDropped::Record(_)
+            ))
+        ));
+        assert_eq!(async_pool_sink.dropped_records(), 4);
+    }
+
+    #[test]
+    fn overflow_policy() {
+        let test = |overflow_policy: OverflowPolicy| {
+            let counter_sink = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(200))));
+            // The default thread pool is not used here to avoid race when tests are run in
+            // parallel.
+            let thread_pool = Arc::new(ThreadPool::builder().capacity(1).build().unwrap());
+            let async_pool_sink = AsyncPoolSink::builder()
+                .sink(counter_sink.clone())
+                .thread_pool(thread_pool)
+                .overflow_policy(overflow_policy)
+                .build()
+                .unwrap();
+
+            // Keep the worker busy, so that the channel is full after one more record
+            async_pool_sink.log(&Record::new(Level::Info, "0")).unwrap();
+            sleep(Duration::from_millis(50));
+
+            let results = ["1", "2", "3"]
+                .iter()
+                .map(|payload| async_pool_sink.log(&Record::new(Level::Info, *payload)))
+                .collect::<Vec<_>>();
+            async_pool_sink.flush_blocking().unwrap();
+            assert_eq!(async_pool_sink.dropped_records(), 2);
+            (results, counter_sink.payloads())
+        };
+
+        let (results, payloads) = test(OverflowPolicy::DropIncoming);
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1],
+            Err(Error::SendToChannel(SendToChannelError::Full, _))
+        ));
+        assert!(results[2].is_err());
+        assert_eq!(payloads, ["0", "1"]);
+
+        let (results, payloads) = test(OverflowPolicy::DropOldest);
+        assert!(results.iter().all(|res| res.is_ok()));
+        assert_eq!(payloads, ["0", "3"]);
+    }
+
+    #[test]
+    fn drop_oldest_shared_thread_pool() {
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().capacity(3).build().unwrap());
+        let build = |counter_sink: Arc<CounterSink>| {
+            AsyncPoolSink::builder()
+                .sink(counter_sink)
+                .thread_pool(thread_pool.clone())
+                .overflow_policy(OverflowPolicy::DropOldest)
+                .build()
+                .unwrap()
+        };
+        let counter_sink_a = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(200))));
+        let counter_sink_b = Arc::new(CounterSink::new());
+        let sink_a = build(counter_sink_a.clone());
+        let sink_b = build(counter_sink_b.clone());
+
+        // Keep the worker busy
+        sink_a.log(&Record::new(Level::Info, "a0")).unwrap();
+        sleep(Duration::from_millis(50));
+
+        // Fill the queue: [flush a, b1, a1]
+        sink_a.flush().unwrap();
+        sink_b.log(&Record::new(Level::Info, "b1")).unwrap();
+        sink_a.log(&Record::new(Level::Info, "a1")).unwrap();
+
+        // Each sink only evicts its own oldest record, never a flush or a record of
+        // the other sink
+        sink_a.log(&Record::new(Level::Info, "a2")).unwrap();
+        sink_a.log(&Record::new(Level::Info, "a3")).unwrap();
+        sink_b.log(&Record::new(Level::Info, "b2")).unwrap();
+        sink_a.log(&Record::new(Level::Info, "a4")).unwrap();
+        assert_eq!(sink_a.dropped_records(), 3);
+        assert_eq!(sink_b.dropped_records(), 1);
+
+        sink_a.flush_blocking().unwrap();
+        sink_b.flush_blocking().unwrap();
+        assert_eq!(counter_sink_a.payloads(), ["a0", "a4"]);
+        assert_eq!(counter_sink_a.flush_count(), 2);
+        assert_eq!(counter_sink_b.payloads(), ["b2"]);
+        assert_eq!(counter_sink_b.flush_count(), 1);
+
+        // Nothing of the sink to evict, the incoming record is dropped instead
+        sink_a.log(&Record::new(Level::Info, "a5")).unwrap();
+        sleep(Duration::from_millis(50));
+        for _ in 0..3 {
+            sink_b.flush().unwrap();
+        }
+        assert!(matches!(
+            sink_b.log(&Record::new(Level::Info, "b3")),
+            Err(Error::SendToChannel(SendToChannelError::Full, _))
+        ));
+        assert_eq!(sink_b.dropped_records(), 2);
+        assert_eq!(sink_a.dropped_records(), 3);
+
+        sink_b.flush_blocking().unwrap();
+        assert_eq!(counter_sink_b.payloads(), ["b2"]);
+        assert_eq!(counter_sink_b.flush_count(), 5);
+    }
+
+    #[test]
+    fn drop_oldest_with_other_policies() {
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().capacity(2).build().unwrap());
+        let build = |counter_sink: Arc<CounterSink>, overflow_policy| {
+            AsyncPoolSink::builder()
+                .sink(counter_sink)
+                .thread_pool(thread_pool.clone())
+                .overflow_policy(overflow_policy)
+                .build()
+                .unwrap()
+        };
+        let counter_sink_block = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(20))));
+        let counter_sink_drop = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(20))));
+        let sink_block = build(counter_sink_block.clone(), OverflowPolicy::Block);
+        let sink_drop = build(counter_sink_drop.clone(), OverflowPolicy::DropOldest);
+
+        // Both queues are served by the worker, and the records of the sink that
+        // blocks are never evicted
+        for i in 0..5 {
+            sink_block
+                .log(&Record::new(Level::Info, i.to_string()))
+                .unwrap();
+            sink_drop
+                .log(&Record::new(Level::Info, i.to_string()))
+                .unwrap();
+        }
+        assert_eq!(sink_block.dropped_records(), 0);
+        sink_block.flush_blocking().unwrap();
+        sink_drop.flush_blocking().unwrap();
+
+        assert_eq!(counter_sink_block.payloads(), ["0", "1", "2", "3", "4"]);
+        assert_eq!(
+            counter_sink_drop.log_count() + sink_drop.dropped_records(),
+            5
+        );
+        assert_eq!(counter_sink_drop.payloads().last().unwrap(), "4");
+        assert_eq!(sink_block.queue_len(), 0);
+    }
+
+    #[test]
+    fn queue_metrics() {
+        let counter_sink = Arc::new(CounterSink::with_delay(Some(Duration::from_millis(200))));
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().capacity(2).build().unwrap());
+        let async_pool_sink = AsyncPoolSink::builder()
+            .sink(counter_sink.clone())
+            .thread_pool(thread_pool)
+            .overflow_policy(OverflowPolicy::DropIncoming)
+            .build()
+            .unwrap();
+
+        assert_eq!(async_pool_sink.queue_capacity(), 2);
+        assert_eq!(async_pool_sink.queue_len(), 0);
+        assert_eq!(async_pool_sink.overflow_count(), 0);
+
+        // Keep the worker busy, and then fill the queue
+        async_pool_sink.log(&Record::new(Level::Info, "0")).unwrap();
+        sleep(Duration::from_millis(50));
+        async_pool_sink.log(&Record::new(Level::Info, "1")).unwrap();
+        async_pool_sink.log(&Record::new(Level::Info, "2")).unwrap();
+        assert_eq!(async_pool_sink.queue_len(), 2);
+        assert_eq!(async_pool_sink.overflow_count(), 0);
+
+        assert!(async_pool_sink.log(&Record::new(Level::Info, "3")).is_err());
+        assert!(async_pool_sink.log(&Record::new(Level::Info, "4")).is_err());
+        assert_eq!(async_pool_sink.overflow_count(), 2);
+        assert_eq!(async_pool_sink.dropped_records(), 2);
+
+        async_pool_sink.flush_blocking().unwrap();
+        assert_eq!(async_pool_sink.queue_len(), 0);
+        assert_eq!(counter_sink.payloads(), ["0", "1", "2"]);
+    }
+
+    #[test]
+    fn drain_pending() {
+        struct FailingSink;
+
+        impl Sink for FailingSink {
+            fn log(&self, record: &Record) -> Result<()> {
+                match record.payload() {
+                    "ok" => Ok(()),
+                    _ => Err(Error::WriteRecord(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        "network is down",
+                    ))),
+                }
+            }
+
+            fn flush(&self) -> Result<()> {
+                Ok(())
+            }
+
+            fn level_filter(&self) -> LevelFilter {
+                LevelFilter::All
+            }
+
+            fn set_level_filter(&self, _level_filter: LevelFilter) {}
+
+            fn set_formatter(&self, _formatter: Box<dyn Formatter>) {}
+
+            fn set_error_handler(&self, _handler: Option<ErrorHandler>) {}
+        }
+
+        // The default thread pool is not used here to avoid race when tests are run in
+        // parallel.
+        let thread_pool = Arc::new(ThreadPool::builder().build().unwrap());
+        let async_pool_sink = Arc::new(
+            AsyncPoolSink::builder()
+                .sink(Arc::new(FailingSink))
+                .thread_pool(thread_pool)
+                .retain_failed(2)
+                .error_handler(|_| {})
+                .build()
+                .unwrap(),
+        );
+        let logger = Logger::builder()
+            .sink(async_pool_sink.clone())
+            .level_filter(LevelFilter::All)
+            .build()
+            .unwrap();
+
+        info!(logger: logger, "a");
+        info!(logger: logger, "ok");
+        info!(logger: logger, "b");
+        info!(logger: logger, "c");
+        async_pool_sink.flush_blocking().unwrap();
+
+        let pending = async_pool_sink.drain_pending();
+        assert_eq!(
+            pending.iter().map(|r| r.payload()).collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+        assert!(async_pool_sink.drain_pending().is_empty());
+    }
 }
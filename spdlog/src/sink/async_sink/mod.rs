@@ -2,18 +2,80 @@ mod async_pool_sink;
 
 pub use async_pool_sink::*;
 
+use crate::{sink::Sink, sync::*};
+
 /// Overflow policy for [asynchronous sinks].
 ///
 /// When the channel is full, an incoming operation is handled according to the
 /// specified policy.
 ///
+/// The policy trades the latency of the logging threads against the loss of
+/// records:
+///
+/// - [`Block`] never loses records, but a logging thread may be blocked for as
+///   long as the internal sinks need to catch up, so a slow sink slows down
+///   the application under load.
+/// - [`DropIncoming`] and [`DropOldest`] never block, so logging has a bounded
+///   latency regardless of the internal sinks, but records are lost when they
+///   are produced faster than they can be processed. Dropped records are
+///   counted, see [`AsyncPoolSink::dropped_records`].
+///
+/// [`Block`] is the default, so that records are not lost silently.
+///
 /// [asynchronous sinks]: index.html#asynchronous-combined-sink
+/// [`Block`]: OverflowPolicy::Block
+/// [`DropIncoming`]: OverflowPolicy::DropIncoming
+/// [`DropOldest`]: OverflowPolicy::DropOldest
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 #[non_exhaustive]
 pub enum OverflowPolicy {
     /// Blocks until the channel is not full.
     Block,
     /// Drops the incoming operation.
+    ///
+    /// The dropped record is returned in [`Error::SendToChannel`].
+    ///
+    /// [`Error::SendToChannel`]: crate::Error::SendToChannel
     DropIncoming,
-    // DropOldest, // waiting for https://github.com/crossbeam-rs/crossbeam/issues/400
+    /// Drops the oldest record of the same sink in the channel to make room for
+    /// the incoming operation.
+    ///
+    /// This keeps the most recent records, which are usually the most relevant
+    /// ones when troubleshooting. The operations of sinks using this policy
+    /// are queued separately from the channel of the [`ThreadPool`], with the
+    /// same capacity, which may be shared by multiple sinks. Only records of
+    /// the sink that overflowed are dropped, and flush operations are never
+    /// dropped. If the queue holds no record of the sink, the incoming
+    /// operation is dropped instead, the same as
+    /// [`OverflowPolicy::DropIncoming`].
+    ///
+    /// Unlike the lock-free channel, the separate queue is protected by a
+    /// lock, so this policy has a higher overhead per operation.
+    ///
+    /// The limit specified by [`AsyncPoolSinkBuilder::max_in_flight_bytes`]
+    /// is enforced the same way, by dropping the oldest queued records of the
+    /// sink until the incoming record fits.
+    ///
+    /// [`ThreadPool`]: crate::ThreadPool
+    DropOldest,
+}
+
+/// Policy for records logged to [asynchronous sinks] after they have been shut
+/// down.
+///
+/// See [`AsyncPoolSink::shutdown`].
+///
+/// [asynchronous sinks]: index.html#asynchronous-combined-sink
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum AfterShutdownPolicy {
+    /// Returns [`Error::SinkClosed`].
+    ///
+    /// [`Error::SinkClosed`]: crate::Error::SinkClosed
+    ReturnError,
+    /// Drops the record silently, and counts it. The count can be retrieved by
+    /// [`AsyncPoolSink::dropped_after_shutdown`].
+    Drop,
+    /// Logs the record to the given sink synchronously, on the calling thread.
+    Fallback(Arc<dyn Sink>),
 }
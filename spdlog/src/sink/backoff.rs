@@ -0,0 +1,306 @@
+//! Provides a retry backoff policy for sinks.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{error::InvalidArgumentError, Error, Result};
+
+/// An exponential backoff policy for retrying failed operations.
+///
+/// It is intended to be consumed by sinks that need to retry operations, e.g.
+/// reconnecting to a remote endpoint, so that they don't have to hand-roll the
+/// timing logic.
+///
+/// The first delay is `initial`, each following delay is the previous one
+/// multiplied by `multiplier`, and no delay exceeds `max`. If `jitter` is
+/// non-zero, each delay is randomly reduced by up to that fraction of itself,
+/// to avoid many clients retrying in lockstep.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use spdlog::sink::Backoff;
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let backoff = Backoff::builder()
+///     .initial(Duration::from_millis(100))
+///     .max(Duration::from_secs(1))
+///     .multiplier(2.0)
+///     .build()?;
+///
+/// let delays = backoff.delays().take(6).collect::<Vec<_>>();
+/// assert_eq!(
+///     delays,
+///     [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis)
+/// );
+/// # Ok(()) }
+/// ```
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    multiplier: f64,
+    jitter: f64,
+}
+
+impl Backoff {
+    /// Constructs a [`BackoffBuilder`].
+    #[must_use]
+    pub fn builder() -> BackoffBuilder {
+        BackoffBuilder {
+            backoff: Self::default(),
+        }
+    }
+
+    /// Gets the first delay.
+    #[must_use]
+    pub fn initial(&self) -> Duration {
+        self.initial
+    }
+
+    /// Gets the upper limit of delays.
+    #[must_use]
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Gets the factor by which each delay grows.
+    #[must_use]
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    /// Gets the maximum fraction by which each delay is randomly reduced.
+    #[must_use]
+    pub fn jitter(&self) -> f64 {
+        self.jitter
+    }
+
+    /// Returns an infinite iterator over the successive delays of this policy.
+    #[must_use]
+    pub fn delays(&self) -> BackoffDelays {
+        BackoffDelays::new(*self)
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.0,
+        }
+    }
+}
+
+/// The builder of [`Backoff`].
+#[derive(Clone, Debug)]
+pub struct BackoffBuilder {
+    backoff: Backoff,
+}
+
+impl BackoffBuilder {
+    /// Specifies the first delay.
+    ///
+    /// This parameter is **optional**, and defaults to 100 milliseconds.
+    #[must_use]
+    pub fn initial(mut self, initial: Duration) -> Self {
+        self.backoff.initial = initial;
+        self
+    }
+
+    /// Specifies the upper limit of delays. It must not be less than the first
+    /// delay.
+    ///
+    /// This parameter is **optional**, and defaults to 30 seconds.
+    #[must_use]
+    pub fn max(mut self, max: Duration) -> Self {
+        self.backoff.max = max;
+        self
+    }
+
+    /// Specifies the factor by which each delay grows. Range: [1.0, +inf).
+    ///
+    /// This parameter is **optional**, and defaults to `2.0`.
+    #[must_use]
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.backoff.multiplier = multiplier;
+        self
+    }
+
+    /// Specifies the maximum fraction by which each delay is randomly reduced.
+    /// Range: [0.0, 1.0].
+    ///
+    /// This parameter is **optional**, and defaults to `0.0` for no jitter.
+    #[must_use]
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.backoff.jitter = jitter;
+        self
+    }
+
+    /// Builds a [`Backoff`].
+    ///
+    /// # Errors
+    ///
+    /// If any of the parameters is out of range,
+    /// [`Error::InvalidArgument`] will be returned.
+    pub fn build(self) -> Result<Backoff> {
+        let backoff = self.backoff;
+
+        if backoff.initial > backoff.max {
+            return Err(Self::invalid(format!(
+                "expect `initial` ({:?}) to be less than or equal to `max` ({:?})",
+                backoff.initial, backoff.max
+            )));
+        }
+        if !(1.0..f64::INFINITY).contains(&backoff.multiplier) {
+            return Err(Self::invalid(format!(
+                "expect `multiplier` to be [1.0, +inf) but got {}",
+                backoff.multiplier
+            )));
+        }
+        if !(0.0..=1.0).contains(&backoff.jitter) {
+            return Err(Self::invalid(format!(
+                "expect `jitter` to be [0.0, 1.0] but got {}",
+                backoff.jitter
+            )));
+        }
+
+        Ok(backoff)
+    }
+
+    #[must_use]
+    fn invalid(message: String) -> Error {
+        Error::InvalidArgument(InvalidArgumentError::Backoff(message))
+    }
+}
+
+/// An infinite iterator over the successive delays of a [`Backoff`].
+///
+/// Call [`BackoffDelays::reset`] after the retried operation succeeds, so that
+/// the next failure starts over from the first delay.
+#[derive(Clone, Debug)]
+pub struct BackoffDelays {
+    backoff: Backoff,
+    next: Duration,
+    rng_state: u64,
+}
+
+impl BackoffDelays {
+    #[must_use]
+    fn new(backoff: Backoff) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+
+        Self {
+            backoff,
+            next: backoff.initial,
+            // xorshift requires a non-zero state
+            rng_state: seed | 1,
+        }
+    }
+
+    /// Restarts the sequence from the first delay.
+    pub fn reset(&mut self) {
+        self.next = self.backoff.initial;
+    }
+
+    // xorshift64, good enough for jitter and avoids a dependency.
+    #[must_use]
+    fn next_random(&mut self) -> f64 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        (x >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+impl Iterator for BackoffDelays {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next;
+
+        let grown = (current.as_nanos() as f64 * self.backoff.multiplier).round();
+        self.next = if grown >= self.backoff.max.as_nanos() as f64 {
+            self.backoff.max
+        } else {
+            Duration::from_nanos(grown as u64)
+        };
+
+        if self.backoff.jitter > 0.0 {
+            let reduction = self.backoff.jitter * self.next_random();
+            Some(current.mul_f64(1.0 - reduction))
+        } else {
+            Some(current)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_and_is_capped() {
+        let backoff = Backoff::builder()
+            .initial(Duration::from_millis(10))
+            .max(Duration::from_millis(100))
+            .multiplier(3.0)
+            .build()
+            .unwrap();
+
+        let mut delays = backoff.delays();
+        assert_eq!(
+            delays.by_ref().take(6).collect::<Vec<_>>(),
+            [10, 30, 90, 100, 100, 100].map(Duration::from_millis)
+        );
+
+        delays.reset();
+        assert_eq!(delays.next(), Some(Duration::from_millis(10)));
+        assert_eq!(delays.next(), Some(Duration::from_millis(30)));
+    }
+
+    #[test]
+    fn jitter_stays_in_range() {
+        let backoff = Backoff::builder()
+            .initial(Duration::from_millis(100))
+            .max(Duration::from_millis(400))
+            .multiplier(2.0)
+            .jitter(0.5)
+            .build()
+            .unwrap();
+
+        let bases = [100, 200, 400, 400, 400, 400].map(Duration::from_millis);
+        for (delay, base) in backoff.delays().zip(bases) {
+            assert!(delay <= base);
+            assert!(delay >= base / 2);
+        }
+    }
+
+    #[test]
+    fn invalid_params() {
+        let invalid = |builder: BackoffBuilder| {
+            matches!(
+                builder.build(),
+                Err(Error::InvalidArgument(InvalidArgumentError::Backoff(_)))
+            )
+        };
+
+        assert!(invalid(
+            Backoff::builder()
+                .initial(Duration::from_secs(2))
+                .max(Duration::from_secs(1))
+        ));
+        assert!(invalid(Backoff::builder().multiplier(0.5)));
+        assert!(invalid(Backoff::builder().multiplier(f64::NAN)));
+        assert!(invalid(Backoff::builder().jitter(-0.1)));
+        assert!(invalid(Backoff::builder().jitter(1.5)));
+        assert!(Backoff::builder().build().is_ok());
+    }
+}
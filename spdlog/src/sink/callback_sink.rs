@@ -0,0 +1,251 @@
+//! Provides a callback sink.
+
+use std::convert::Infallible;
+
+use crate::{
+    sink::{helper, Sink},
+    Record, Result, StringBuf,
+};
+
+/// The callback type invoked by [`CallbackSink`] with each record.
+pub type RecordCallback = Box<dyn Fn(&Record) + Send + Sync>;
+
+/// The callback type invoked by [`CallbackSink`] with each formatted record.
+pub type FormattedCallback = Box<dyn Fn(&str) + Send + Sync>;
+
+/// The callback of a [`CallbackSink`], see [`CallbackSinkBuilder`].
+pub enum Callback {
+    /// Invoked with the record, the formatter of the sink is not used.
+    Record(RecordCallback),
+    /// Invoked with the record formatted by the formatter of the sink.
+    Formatted(FormattedCallback),
+}
+
+/// A sink that invokes a user closure for each record.
+///
+/// This is useful to integrate with application-specific code, e.g.
+/// telemetry, without implementing the whole [`Sink`] trait. The closure is
+/// only invoked for records passing the level filter of the sink.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::{
+///     atomic::{AtomicUsize, Ordering},
+///     Arc,
+/// };
+///
+/// use spdlog::{prelude::*, sink::CallbackSink};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// static ERRORS: AtomicUsize = AtomicUsize::new(0);
+///
+/// let sink = CallbackSink::builder()
+///     .callback(|record| {
+///         if matches!(record.level(), Level::Critical | Level::Error) {
+///             ERRORS.fetch_add(1, Ordering::Relaxed);
+///         }
+///     })
+///     .build()?;
+/// # let logger = Logger::builder().sink(Arc::new(sink)).build()?;
+///
+/// # error!(logger: logger, "something went wrong");
+/// # warn!(logger: logger, "something seems wrong");
+/// # assert_eq!(ERRORS.load(Ordering::Relaxed), 1);
+/// # Ok(()) }
+/// ```
+pub struct CallbackSink {
+    common_impl: helper::CommonImpl,
+    callback: Callback,
+}
+
+impl CallbackSink {
+    /// Constructs a builder of `CallbackSink`.
+    #[must_use]
+    pub fn builder() -> CallbackSinkBuilder<()> {
+        CallbackSinkBuilder {
+            common_builder_impl: helper::CommonBuilderImpl::new(),
+            callback: (),
+        }
+    }
+}
+
+impl Sink for CallbackSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        match &self.callback {
+            Callback::Record(callback) => callback(record),
+            Callback::Formatted(callback) => {
+                let mut string_buf = StringBuf::new();
+                self.common_impl.format(record, &mut string_buf)?;
+                callback(string_buf.as_str());
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    helper::common_impl!(@Sink: common_impl);
+}
+
+// --------------------------------------------------
+
+/// The builder of [`CallbackSink`].
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+/// # Examples
+///
+/// - Building a [`CallbackSink`] receiving records.
+///
+///   ```
+///   use spdlog::sink::CallbackSink;
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: CallbackSink = CallbackSink::builder()
+///       .callback(|record| println!("{}", record.payload())) // required
+///       .build()?;
+///   # Ok(()) }
+///   ```
+///
+/// - Building a [`CallbackSink`] receiving formatted records.
+///
+///   ```
+///   use spdlog::sink::CallbackSink;
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: CallbackSink = CallbackSink::builder()
+///       .on_formatted(|formatted| print!("{}", formatted)) // required
+///       .build()?;
+///   # Ok(()) }
+///   ```
+///
+/// - If any required parameters are missing, a compile-time error will be
+///   raised.
+///
+///   ```compile_fail,E0061
+///   use spdlog::sink::CallbackSink;
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: CallbackSink = CallbackSink::builder()
+///       // .callback(|record| println!("{}", record.payload())) // required
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct CallbackSinkBuilder<ArgCallback> {
+    common_builder_impl: helper::CommonBuilderImpl,
+    callback: ArgCallback,
+}
+
+impl<ArgCallback> CallbackSinkBuilder<ArgCallback> {
+    /// Specifies a closure invoked with each record.
+    ///
+    /// The formatter of the sink is not used.
+    ///
+    /// This parameter is **required**, unless [`on_formatted`] is specified
+    /// instead.
+    ///
+    /// [`on_formatted`]: CallbackSinkBuilder::on_formatted
+    #[must_use]
+    pub fn callback<F>(self, callback: F) -> CallbackSinkBuilder<Callback>
+    where
+        F: Fn(&Record) + Send + Sync + 'static,
+    {
+        CallbackSinkBuilder {
+            common_builder_impl: self.common_builder_impl,
+            callback: Callback::Record(Box::new(callback)),
+        }
+    }
+
+    /// Specifies a closure invoked with each record formatted by the formatter
+    /// of the sink.
+    ///
+    /// This parameter is **required**, unless [`callback`] is specified
+    /// instead.
+    ///
+    /// [`callback`]: CallbackSinkBuilder::callback
+    #[must_use]
+    pub fn on_formatted<F>(self, callback: F) -> CallbackSinkBuilder<Callback>
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        CallbackSinkBuilder {
+            common_builder_impl: self.common_builder_impl,
+            callback: Callback::Formatted(Box::new(callback)),
+        }
+    }
+
+    helper::common_impl!(@SinkBuilder: common_builder_impl);
+}
+
+impl CallbackSinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `callback` or `on_formatted`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl CallbackSinkBuilder<Callback> {
+    /// Builds a [`CallbackSink`].
+    pub fn build(self) -> Result<CallbackSink> {
+        Ok(CallbackSink {
+            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
+            callback: self.callback,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, sync::*, test_utils::*};
+
+    #[test]
+    fn invoke_callback() {
+        let records = Arc::new(Mutex::new(vec![]));
+        let sink = {
+            let records = records.clone();
+            CallbackSink::builder()
+                .callback(move |record| {
+                    records
+                        .lock_expect()
+                        .push(format!("{}: {}", record.level(), record.payload()))
+                })
+                .level_filter(LevelFilter::MoreSevereEqual(Level::Info))
+                .build()
+                .unwrap()
+        };
+        let logger = test_logger_builder().sink(Arc::new(sink)).build().unwrap();
+
+        info!(logger: logger, "hello");
+        debug!(logger: logger, "filtered");
+        error!(logger: logger, "world");
+
+        assert_eq!(*records.lock_expect(), ["info: hello", "error: world"]);
+    }
+
+    #[test]
+    fn invoke_formatted_callback() {
+        let formatted = Arc::new(Mutex::new(String::new()));
+        let sink = {
+            let formatted = formatted.clone();
+            CallbackSink::builder()
+                .on_formatted(move |string| formatted.lock_expect().push_str(string))
+                .formatter(Box::new(NoModFormatter::new()))
+                .build()
+                .unwrap()
+        };
+        let logger = test_logger_builder().sink(Arc::new(sink)).build().unwrap();
+
+        info!(logger: logger, "hello ");
+        warn!(logger: logger, "world");
+
+        assert_eq!(*formatted.lock_expect(), "hello world");
+    }
+}
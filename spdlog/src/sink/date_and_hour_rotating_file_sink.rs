@@ -1,23 +1,32 @@
 //! Provides a date and hour rotating file sink.
 
 use std::{
-    fs::File,
-    io::{BufWriter, Write},
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    mem,
     path::{Path, PathBuf},
-    time::SystemTime,
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
 };
 
 use chrono::prelude::*;
 
 use crate::{
-    sink::{helper, Sink},
+    clock::{self, Clock},
+    sink::{file_sink::UTF8_BOM, helper, FlushPolicy, FlushPolicyState, Sink},
     sync::*,
-    utils, Error, Record, Result, StringBuf,
+    utils, Error, Record, Result, StringBuf, EOL,
 };
 
+// Written instead of truncating a non-empty file on `rotate_on_open`, to mark
+// where the records of the new run start.
+const ROTATE_ON_OPEN_SEPARATOR: &str = "---------- reopened ----------";
+
 trait Rotator {
+    // Returns the path of the file closed by a rotation, if any.
     #[allow(clippy::ptr_arg)]
-    fn log(&self, record: &Record, string_buf: &StringBuf) -> Result<()>;
+    fn log(&self, string_buf: &StringBuf) -> Result<Option<PathBuf>>;
     fn flush(&self) -> Result<()>;
     fn drop_flush(&mut self) -> Result<()> {
         self.flush()
@@ -26,12 +35,67 @@ trait Rotator {
 
 struct RotatorTimePoint {
     base_path: PathBuf,
-    inner: SpinMutex<RotatorTimePointInner>,
+    granularity: RotationGranularity,
+    max_files: usize,
+    write_bom: bool,
+    clock: Arc<dyn Clock>,
+    // See `RotatorFileSize::inner` of `RotatingFileSink`.
+    inner: Mutex<RotatorTimePointInner>,
 }
 
 struct RotatorTimePointInner {
     file: BufWriter<File>,
+    file_path: PathBuf,
     rotation_time_point: SystemTime,
+    // The closed files counted by `max_files`, the oldest first. It's listed from
+    // the directory once when opening, and kept up to date on rotations since.
+    retained_files: VecDeque<PathBuf>,
+}
+
+/// The length of the periods that a [`DateAndHourRotatingFileSink`] rotates
+/// at.
+///
+/// The file of a period is placed in the directory of its date, with a suffix
+/// identifying the period within the day. Supposes the given base path is
+/// `/path/to/base_file.log`, the file names for the time `2012-03-04 05:06`
+/// are listed in the documentation of each variant.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum RotationGranularity {
+    /// Rotates every minute, e.g. `/path/to/20120304/base_file_0506.log`.
+    Minute,
+    /// Rotates every ten minutes, e.g. `/path/to/20120304/base_file_0500.log`.
+    TenMinutes,
+    /// Rotates every hour, e.g. `/path/to/20120304/base_file_05.log`.
+    Hourly,
+    /// Rotates every day at midnight, e.g. `/path/to/20120304/base_file.log`.
+    Daily,
+}
+
+/// Compression applied to the log files closed by a rotation.
+///
+/// See [`DateAndHourRotatingFileSinkBuilder::compress`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum Compression {
+    /// The files are kept as they are.
+    None,
+    /// The files are compressed to `<name>.gz` with gzip, and the original
+    /// files are removed.
+    ///
+    /// This variant requires crate feature `gzip`.
+    #[cfg(feature = "gzip")]
+    Gzip,
+}
+
+impl Compression {
+    #[must_use]
+    fn compress_fn(self) -> Option<fn(&Path) -> Result<()>> {
+        match self {
+            Self::None => None,
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Some(gzip_file),
+        }
+    }
 }
 
 /// A sink with a collection of files as the target, rotating according to the
@@ -63,7 +127,9 @@ struct RotatorTimePointInner {
 ///
 /// # Rotation Policy
 ///
-/// The only policy is rotating file with both date and hour
+/// Files are rotated at the start of each period, which is one hour by
+/// default. See [`RotationGranularity`] for other periods and the file names
+/// used by each of them.
 ///
 /// # Examples
 ///
@@ -73,6 +139,15 @@ struct RotatorTimePointInner {
 pub struct DateAndHourRotatingFileSink {
     common_impl: helper::CommonImpl,
     rotator: RotatorTimePoint,
+    compression: Compression,
+    compression_threads: Mutex<Vec<CompressionThread>>,
+    flush_policy: FlushPolicyState,
+}
+
+// A thread started by `compress_in_background`.
+struct CompressionThread {
+    handle: JoinHandle<()>,
+    finished: Arc<AtomicBool>,
 }
 
 /// The builder of [`DateAndHourRotatingFileSink`].
@@ -87,7 +162,12 @@ pub struct DateAndHourRotatingFileSink {
 ///   # fn main() -> Result<(), spdlog::Error> {
 ///   let sink: DateAndHourRotatingFileSink = DateAndHourRotatingFileSink::builder()
 ///       .base_path("/path/to/base_log_file") // required
+///       // .rotation_granularity(RotationGranularity::Daily) // optional, defaults to `Hourly`
+///       // .max_files(100) // optional, defaults to `0` for no limit
 ///       // .rotate_on_open(true) // optional, defaults to `false`
+///       // .force_truncate(true) // optional, defaults to `false`
+///       // .compress(Compression::None) // optional, defaults to `Compression::None`
+///       // .flush_policy(FlushPolicy::EveryN(100)) // optional, defaults to `FlushPolicy::Never`
 ///       .build()?;
 ///   # Ok(()) }
 ///   ```
@@ -105,21 +185,18 @@ pub struct DateAndHourRotatingFileSink {
 ///       .build()?;
 ///   # Ok(()) }
 ///   ```
-///
-///   ```compile_fail,E0061
-///   use spdlog::sink::{DateAndHourRotatingFileSink};
-///
-///   # fn main() -> Result<(), spdlog::Error> {
-///   let sink: DateAndHourRotatingFileSink = DateAndHourRotatingFileSink::builder()
-///       .base_path("/path/to/base_log_file") // required
-///       .rotate_on_open(true) // optional, defaults to `false`
-///       .build()?;
-///   # Ok(()) }
-///   ```
 pub struct DateAndHourRotatingFileSinkBuilder<ArgBP> {
     common_builder_impl: helper::CommonBuilderImpl,
     base_path: ArgBP,
+    rotation_granularity: RotationGranularity,
+    max_files: usize,
     rotate_on_open: bool,
+    force_truncate: bool,
+    write_bom: bool,
+    compression: Compression,
+    flush_policy: FlushPolicy,
+    max_create_dir_depth: Option<usize>,
+    clock: Arc<dyn Clock>,
 }
 
 impl DateAndHourRotatingFileSink {
@@ -129,11 +206,158 @@ impl DateAndHourRotatingFileSink {
         DateAndHourRotatingFileSinkBuilder {
             common_builder_impl: helper::CommonBuilderImpl::new(),
             base_path: (),
+            rotation_granularity: RotationGranularity::Hourly,
+            max_files: 0,
             rotate_on_open: false,
+            force_truncate: false,
+            write_bom: false,
+            compression: Compression::None,
+            flush_policy: FlushPolicy::Never,
+            max_create_dir_depth: None,
+            clock: clock::default_clock(),
+        }
+    }
+
+    /// Lists the existing log files for the given base path, sorted from the
+    /// oldest to the newest.
+    ///
+    /// Only files whose paths match the naming scheme of this sink are
+    /// returned, i.e. `/path/to/yyyymmdd/base_file_hh.log` for the base path
+    /// `/path/to/base_file.log`, or the names used by the other
+    /// [`RotationGranularity`] variants, optionally suffixed with `.gz` if
+    /// compressed. See [`DateAndHourRotatingFileSinkBuilder::base_path`]
+    /// for details.
+    ///
+    /// # Errors
+    ///
+    /// If an error occurs reading the directories, [`Error::ReadDirectory`]
+    /// will be returned. A missing directory is not an error, an empty list is
+    /// returned instead.
+    pub fn list_files(base_path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let base_path = base_path.as_ref();
+
+        let mut files = vec![];
+        for date_dir in read_dir_paths(utils::target_dir(base_path))? {
+            if !date_dir.is_dir() {
+                continue;
+            }
+            for file in read_dir_paths(&date_dir)? {
+                if file.is_file() && RotatorTimePoint::is_file_path(base_path, &file) {
+                    files.push(file);
+                }
+            }
         }
+        // Dates and hours are zero-padded, so the lexical order is chronological
+        files.sort();
+        Ok(files)
     }
 }
 
+fn read_dir_paths(dir: &Path) -> Result<Vec<PathBuf>> {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()
+            .map_err(Error::ReadDirectory),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(Error::ReadDirectory(err)),
+    }
+}
+
+impl DateAndHourRotatingFileSink {
+    // Compresses on a separate thread, so that logging is not blocked by it. Errors
+    // are reported to the error handler, since there is no caller to return them
+    // to.
+    fn compress_in_background(&self, file_path: PathBuf) {
+        let compress = match self.compression.compress_fn() {
+            Some(compress) => compress,
+            None => return,
+        };
+
+        let error_handler = self.common_impl.error_handler.load(Ordering::Relaxed);
+        let finished = Arc::new(AtomicBool::new(false));
+        let handle = thread::spawn({
+            let finished = finished.clone();
+            move || {
+                if let Err(err) = compress(&file_path) {
+                    match error_handler {
+                        Some(handler) => handler(err),
+                        None => crate::default_error_handler("DateAndHourRotatingFileSink", err),
+                    }
+                }
+                finished.store(true, Ordering::Release);
+            }
+        });
+
+        // Finished threads are joined when a new one starts, so that their handles
+        // don't pile up. Others are joined when the sink is dropped.
+        let finished_threads = {
+            let mut threads = self.compression_threads.lock_expect();
+            let (finished_threads, running_threads): (Vec<_>, Vec<_>) = mem::take(&mut *threads)
+                .into_iter()
+                .partition(|thread| thread.finished.load(Ordering::Acquire));
+            *threads = running_threads;
+            threads.push(CompressionThread { handle, finished });
+            finished_threads
+        };
+        for thread in finished_threads {
+            // A panic has been reported by the panic hook already
+            let _ = thread.handle.join();
+        }
+    }
+
+    // Removes the temporary files left by compressions that were interrupted, e.g.
+    // by a crash. Their original files are kept in that case, see `gzip_file`.
+    fn remove_partial_files(base_path: &Path) -> Result<()> {
+        for date_dir in read_dir_paths(utils::target_dir(base_path))? {
+            if !date_dir.is_dir() {
+                continue;
+            }
+            for file in read_dir_paths(&date_dir)? {
+                let is_partial = file
+                    .to_str()
+                    .and_then(|path| path.strip_suffix(".gz.partial"))
+                    .map_or(false, |path| {
+                        RotatorTimePoint::is_file_path(base_path, Path::new(path))
+                    });
+                if is_partial && file.is_file() {
+                    fs::remove_file(&file).map_err(Error::RemoveFile)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// The file is compressed to a temporary file which is renamed once complete, and
+// the original file is removed last, so that an interrupted compression leaves
+// the original file or the complete `.gz` file, never a truncated one.
+#[cfg(feature = "gzip")]
+fn gzip_file(file_path: &Path) -> Result<()> {
+    use flate2::write::GzEncoder;
+
+    let mut gz_path = file_path.as_os_str().to_owned();
+    gz_path.push(".gz");
+    let mut partial_path = gz_path.clone();
+    partial_path.push(".partial");
+
+    let mut file = match File::open(file_path) {
+        Ok(file) => file,
+        // Already removed, e.g. by `max_files`
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(Error::OpenFile(err)),
+    };
+    let partial_file = File::create(&partial_path).map_err(Error::OpenFile)?;
+
+    let mut encoder = GzEncoder::new(partial_file, flate2::Compression::default());
+    io::copy(&mut file, &mut encoder).map_err(Error::WriteRecord)?;
+    let partial_file = encoder.finish().map_err(Error::WriteRecord)?;
+    partial_file.sync_all().map_err(Error::FlushBuffer)?;
+
+    fs::rename(&partial_path, &gz_path).map_err(Error::RenameFile)?;
+    fs::remove_file(file_path).map_err(Error::RemoveFile)
+}
+
 impl Sink for DateAndHourRotatingFileSink {
     fn log(&self, record: &Record) -> Result<()> {
         if !self.should_log(record.level()) {
@@ -141,12 +365,17 @@ impl Sink for DateAndHourRotatingFileSink {
         }
 
         let mut string_buf = StringBuf::new();
-        self.common_impl
-            .formatter
-            .read()
-            .format(record, &mut string_buf)?;
+        self.common_impl.format(record, &mut string_buf)?;
+
+        if let Some(closed_file_path) = self.rotator.log(&string_buf)? {
+            self.compress_in_background(closed_file_path);
+        }
+
+        if self.flush_policy.should_flush(record.level()) {
+            self.rotator.flush()?;
+        }
 
-        self.rotator.log(record, &string_buf)
+        Ok(())
     }
 
     fn flush(&self) -> Result<()> {
@@ -162,56 +391,176 @@ impl Drop for DateAndHourRotatingFileSink {
             self.common_impl
                 .non_returnable_error("DateAndHourRotatingFileSink", err)
         }
+
+        // Waits for compressions in progress, so that they are not cut off if the
+        // process exits after dropping the sink.
+        for thread in mem::take(&mut *self.compression_threads.lock_expect()) {
+            let _ = thread.handle.join();
+        }
     }
 }
 
 impl RotatorTimePoint {
-    fn new(base_path: PathBuf, truncate: bool) -> Result<Self> {
-        let now = SystemTime::now();
-        let file_path = Self::calc_file_path(base_path.as_path(), now);
-        let file = utils::open_file(file_path, truncate)?;
-
-        let inner = RotatorTimePointInner {
-            file: BufWriter::new(file),
-            rotation_time_point: Self::next_rotation_time_point(now),
+    fn new(
+        base_path: PathBuf,
+        granularity: RotationGranularity,
+        max_files: usize,
+        rotate_on_open: bool,
+        force_truncate: bool,
+        write_bom: bool,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self> {
+        let now = clock.now();
+        let file_path = Self::calc_file_path(base_path.as_path(), granularity, now);
+
+        // The file of the current time point may have been written by a previous run,
+        // its contents are kept unless truncation is forced.
+        let non_empty = fs::metadata(&file_path).map_or(false, |metadata| metadata.len() > 0);
+        let truncate = rotate_on_open && (force_truncate || !non_empty);
+        let mut file = BufWriter::new(utils::open_file(&file_path, truncate)?);
+        if write_bom && (truncate || !non_empty) {
+            file.write_all(UTF8_BOM).map_err(Error::WriteRecord)?;
+        }
+        if rotate_on_open && !truncate {
+            // The previous run may have stopped in the middle of a line
+            if !ends_with_line_feed(&file_path).map_err(Error::OpenFile)? {
+                file.write_all(EOL.as_bytes()).map_err(Error::WriteRecord)?;
+            }
+            file.write_all(ROTATE_ON_OPEN_SEPARATOR.as_bytes())
+                .and_then(|_| file.write_all(EOL.as_bytes()))
+                .map_err(Error::WriteRecord)?;
+        }
+
+        let retained_files = if max_files == 0 {
+            VecDeque::new()
+        } else {
+            DateAndHourRotatingFileSink::list_files(&base_path)?
+                .into_iter()
+                .filter(|path| !is_same_file_path(path, &file_path))
+                .collect()
         };
 
-        let res = Self {
-            base_path,
-            inner: SpinMutex::new(inner),
+        let mut inner = RotatorTimePointInner {
+            file,
+            file_path,
+            rotation_time_point: Self::next_rotation_time_point(granularity, now),
+            retained_files,
         };
+        remove_files(&inner.take_excess_files(max_files))?;
 
-        Ok(res)
+        Ok(Self {
+            base_path,
+            granularity,
+            max_files,
+            write_bom,
+            clock,
+            inner: Mutex::new(inner),
+        })
     }
 
     // a little expensive, should only be called when rotation is needed or in
     // constructor.
     #[must_use]
-    fn next_rotation_time_point(now: SystemTime) -> SystemTime {
-        let now: DateTime<Utc> = now.into();
+    fn next_rotation_time_point(granularity: RotationGranularity, now: SystemTime) -> SystemTime {
+        Self::next_rotation_time_point_in(&Local, granularity, now)
+    }
 
-        let mut rotation_time = now
-            .with_minute(0)
-            .unwrap()
-            .with_second(0)
-            .unwrap()
-            .with_nanosecond(0)
-            .unwrap();
+    // The boundary is the start of the next period in the given time zone, the
+    // same one used by `calc_file_path_in`, so that the period in the file name
+    // always matches the period the file was opened in. The elapsed time of the
+    // current period is subtracted from the instant instead of setting the fields
+    // of the local time, which may be ambiguous around DST transitions.
+    #[must_use]
+    fn next_rotation_time_point_in<Tz: TimeZone>(
+        tz: &Tz,
+        granularity: RotationGranularity,
+        now: SystemTime,
+    ) -> SystemTime {
+        let local_time = DateTime::<Utc>::from(now).with_timezone(tz);
+        let (minute, second) = (local_time.minute(), local_time.second());
+
+        let (elapsed_secs, period_secs) = match granularity {
+            RotationGranularity::Minute => (second, 60),
+            RotationGranularity::TenMinutes => (minute % 10 * 60 + second, 10 * 60),
+            RotationGranularity::Hourly => (minute * 60 + second, 60 * 60),
+            RotationGranularity::Daily => {
+                // A day is not 24 hours long around DST transitions, so the next
+                // midnight is looked up in the time zone if it exists.
+                let next_midnight = local_time
+                    .date_naive()
+                    .succ_opt()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    .and_then(|midnight| tz.from_local_datetime(&midnight).earliest());
+                if let Some(next_midnight) = next_midnight {
+                    return next_midnight.into();
+                }
+                (
+                    local_time.hour() * 60 * 60 + minute * 60 + second,
+                    24 * 60 * 60,
+                )
+            }
+        };
 
-        rotation_time = rotation_time
-            .checked_add_signed(chrono::Duration::hours(1))
-            .unwrap();
+        let elapsed = Duration::from_secs(u64::from(elapsed_secs))
+            + Duration::from_nanos(u64::from(local_time.nanosecond()));
 
-        rotation_time.into()
+        now - elapsed + Duration::from_secs(period_secs)
     }
 
+    // Checks whether the given path has the form of the paths computed by
+    // `calc_file_path`, regardless of the time and the granularity. The parent
+    // directory of the date directory is not checked.
     #[must_use]
-    fn calc_file_path(base_path: impl AsRef<Path>, system_time: SystemTime) -> PathBuf {
+    fn is_file_path(base_path: &Path, path: &Path) -> bool {
+        let is_digits =
+            |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+
+        let date_matches = path
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| is_digits(name, 8));
+
+        let stem = base_path.file_stem().and_then(|stem| stem.to_str());
+        let extension = base_path.extension().and_then(|ext| ext.to_str());
+        let file_name_matches = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.strip_suffix(".gz").unwrap_or(name))
+            .and_then(|name| name.strip_prefix(stem?))
+            .and_then(|rest| match extension {
+                Some(extension) => rest.strip_suffix(extension)?.strip_suffix('.'),
+                None => Some(rest),
+            })
+            .map_or(false, |suffix| match suffix.strip_prefix('_') {
+                Some(time) => is_digits(time, 2) || is_digits(time, 4),
+                None => suffix.is_empty(),
+            });
+
+        date_matches && file_name_matches
+    }
+
+    #[must_use]
+    fn calc_file_path(
+        base_path: impl AsRef<Path>,
+        granularity: RotationGranularity,
+        system_time: SystemTime,
+    ) -> PathBuf {
+        Self::calc_file_path_in(&Local, base_path, granularity, system_time)
+    }
+
+    #[must_use]
+    fn calc_file_path_in<Tz: TimeZone>(
+        tz: &Tz,
+        base_path: impl AsRef<Path>,
+        granularity: RotationGranularity,
+        system_time: SystemTime,
+    ) -> PathBuf {
         let base_path = base_path.as_ref();
         let mut file_name = base_path.file_stem().unwrap().to_owned();
         let externsion = base_path.extension();
 
-        let local_time: chrono::DateTime<chrono::Local> = system_time.into();
+        let local_time = DateTime::<Utc>::from(system_time).with_timezone(tz);
 
         // append yyyymmdd to base_path
         let date_path = format!(
@@ -221,8 +570,21 @@ impl RotatorTimePoint {
             local_time.day()
         );
 
-        // append hour to filename
-        file_name.push(format!("_{:02}", local_time.hour()));
+        // append the period within the day to filename
+        match granularity {
+            RotationGranularity::Minute => file_name.push(format!(
+                "_{:02}{:02}",
+                local_time.hour(),
+                local_time.minute()
+            )),
+            RotationGranularity::TenMinutes => file_name.push(format!(
+                "_{:02}{:02}",
+                local_time.hour(),
+                local_time.minute() / 10 * 10
+            )),
+            RotationGranularity::Hourly => file_name.push(format!("_{:02}", local_time.hour())),
+            RotationGranularity::Daily => {}
+        }
 
         let mut path = base_path.to_owned();
         path.pop();
@@ -236,29 +598,120 @@ impl RotatorTimePoint {
     }
 }
 
+impl RotatorTimePointInner {
+    // Takes the oldest files out of `retained_files`, so that at most `max_files`
+    // files remain, including the current one.
+    #[must_use]
+    fn take_excess_files(&mut self, max_files: usize) -> Vec<PathBuf> {
+        if max_files == 0 {
+            return vec![];
+        }
+        let excess = (self.retained_files.len() + 1).saturating_sub(max_files);
+        self.retained_files.drain(..excess).collect()
+    }
+}
+
+// `EOL` ends with a line feed on all platforms.
+fn ends_with_line_feed(path: &Path) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    if file.seek(SeekFrom::End(0))? == 0 {
+        return Ok(false);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last_byte = [0];
+    file.read_exact(&mut last_byte)?;
+    Ok(last_byte[0] == b'\n')
+}
+
+// Paths listed may be prefixed differently from the computed ones (e.g. with
+// `./`), so only the date directory and the file name are compared.
+#[must_use]
+fn is_same_file_path(lhs: &Path, rhs: &Path) -> bool {
+    lhs.file_name() == rhs.file_name()
+        && lhs.parent().and_then(Path::file_name) == rhs.parent().and_then(Path::file_name)
+}
+
+// Deletes the given files, then removes the date directories left empty.
+fn remove_files(files: &[PathBuf]) -> Result<()> {
+    for file in files {
+        match fs::remove_file(file) {
+            Ok(()) => {}
+            // A closed file may have been replaced by its compressed file since
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let mut gz_path = file.as_os_str().to_owned();
+                gz_path.push(".gz");
+                match fs::remove_file(gz_path) {
+                    Ok(()) => {}
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(Error::RemoveFile(err)),
+                }
+            }
+            Err(err) => return Err(Error::RemoveFile(err)),
+        }
+
+        if let Some(date_dir) = file.parent() {
+            if read_dir_paths(date_dir)?.is_empty() {
+                fs::remove_dir(date_dir).map_err(Error::RemoveFile)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 impl Rotator for RotatorTimePoint {
-    fn log(&self, record: &Record, string_buf: &StringBuf) -> Result<()> {
-        let mut inner = self.inner.lock();
+    fn log(&self, string_buf: &StringBuf) -> Result<Option<PathBuf>> {
+        let mut inner = self.inner.lock_expect();
 
-        let record_time = record.time();
-        let should_rotate = record_time >= inner.rotation_time_point;
+        let now = self.clock.now();
+        let should_rotate = now >= inner.rotation_time_point;
 
+        let mut closed_file_path = None;
         if should_rotate {
-            let file_path = Some(Self::calc_file_path(&self.base_path, record_time));
-            inner.file = BufWriter::new(utils::open_file(file_path.as_ref().unwrap(), true)?);
-            inner.rotation_time_point = Self::next_rotation_time_point(record_time);
+            let file_path = Self::calc_file_path(&self.base_path, self.granularity, now);
+
+            // Coalesced like a spurious rotation in `RotatingFileSink`
+            let rotated = file_path != inner.file_path;
+            if rotated {
+                // The previous file is flushed and closed by dropping its writer
+                inner.file = BufWriter::new(utils::open_file(&file_path, true)?);
+                if self.write_bom {
+                    inner.file.write_all(UTF8_BOM).map_err(Error::WriteRecord)?;
+                }
+                let closed = mem::replace(&mut inner.file_path, file_path);
+                if self.max_files != 0 {
+                    // The new file may have been closed earlier, e.g. if the clock
+                    // went backwards
+                    let current_file_path = inner.file_path.clone();
+                    inner
+                        .retained_files
+                        .retain(|path| !is_same_file_path(path, &current_file_path));
+                    inner.retained_files.push_back(closed.clone());
+                }
+                closed_file_path = Some(closed);
+            }
+            inner.rotation_time_point = Self::next_rotation_time_point(self.granularity, now);
         }
+        let excess_files = inner.take_excess_files(self.max_files);
 
         inner
             .file
             .write_all(string_buf.as_bytes())
             .map_err(Error::WriteRecord)?;
+        drop(inner);
 
-        Ok(())
+        // Removed without holding the lock, so that other threads logging are not
+        // blocked by the file system.
+        remove_files(&excess_files)?;
+
+        Ok(closed_file_path)
     }
 
     fn flush(&self) -> Result<()> {
-        self.inner.lock().file.flush().map_err(Error::FlushBuffer)
+        self.inner
+            .lock_expect()
+            .file
+            .flush()
+            .map_err(Error::FlushBuffer)
     }
 }
 
@@ -291,23 +744,147 @@ impl<ArgBP> DateAndHourRotatingFileSinkBuilder<ArgBP> {
         DateAndHourRotatingFileSinkBuilder {
             common_builder_impl: self.common_builder_impl,
             base_path: base_path.into(),
+            rotation_granularity: self.rotation_granularity,
+            max_files: self.max_files,
             rotate_on_open: self.rotate_on_open,
+            force_truncate: self.force_truncate,
+            write_bom: self.write_bom,
+            compression: self.compression,
+            flush_policy: self.flush_policy,
+            max_create_dir_depth: self.max_create_dir_depth,
+            clock: self.clock,
         }
     }
 
+    /// Specifies the length of the periods to rotate at.
+    ///
+    /// See [`RotationGranularity`] for the file names used by each of them.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`RotationGranularity::Hourly`].
+    #[must_use]
+    pub fn rotation_granularity(mut self, rotation_granularity: RotationGranularity) -> Self {
+        self.rotation_granularity = rotation_granularity;
+        self
+    }
+
+    /// Specifies the maximum number of files.
+    ///
+    /// If the number of existing files exceeds this parameter, the oldest files
+    /// will be deleted on the next rotation, and so will the date directories
+    /// left empty. The current file is never deleted.
+    ///
+    /// Pass `0` for no limit.
+    ///
+    /// This parameter is **optional**, and defaults to `0`.
+    #[must_use]
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
     /// Specifies whether to rotate files once when constructing
     /// `DateAndHourRotatingFileSink`.
     ///
-    /// It may truncate the contents of the existing file if the parameter is `true`
-    /// , since the file name is a time point and not an index.
+    /// Since the file name is a time point and not an index, the file of the
+    /// current time point may already exist. If it is not empty, a separator
+    /// line is written and new records are appended to it, unless
+    /// [`force_truncate`] is set, in which case its contents are truncated.
     ///
     /// This parameter is **optional**, and defaults to `false`.
+    ///
+    /// [`force_truncate`]: DateAndHourRotatingFileSinkBuilder::force_truncate
     #[must_use]
     pub fn rotate_on_open(mut self, rotate_on_open: bool) -> Self {
         self.rotate_on_open = rotate_on_open;
         self
     }
 
+    /// Specifies whether [`rotate_on_open`] truncates the existing file of the
+    /// current time point instead of appending to it.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    ///
+    /// [`rotate_on_open`]: DateAndHourRotatingFileSinkBuilder::rotate_on_open
+    #[must_use]
+    pub fn force_truncate(mut self, force_truncate: bool) -> Self {
+        self.force_truncate = force_truncate;
+        self
+    }
+
+    /// Specifies whether to write a UTF-8 BOM at the start of each log file.
+    ///
+    /// The BOM is only written into files created or truncated by the sink,
+    /// or existing empty files, never in the middle of a file being appended
+    /// to, e.g. by [`rotate_on_open`]. Some Windows tools rely on it to detect
+    /// the encoding of the file.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    ///
+    /// [`rotate_on_open`]: DateAndHourRotatingFileSinkBuilder::rotate_on_open
+    #[must_use]
+    pub fn write_bom(mut self, write_bom: bool) -> Self {
+        self.write_bom = write_bom;
+        self
+    }
+
+    /// Specifies the compression applied to a log file after a rotation closes
+    /// it.
+    ///
+    /// The compression runs on a background thread, so that logging is not
+    /// blocked by it. Errors that occur are passed to the error handler of the
+    /// sink. Dropping the sink waits for the compressions in progress, and
+    /// building it removes the temporary files of the ones interrupted before.
+    ///
+    /// This parameter is **optional**, and defaults to [`Compression::None`].
+    #[must_use]
+    pub fn compress(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Specifies when the sink flushes itself after logging a record.
+    ///
+    /// This parameter is **optional**, and defaults to [`FlushPolicy::Never`].
+    #[must_use]
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
+    /// Specifies the maximum number of levels of missing parent directories
+    /// that may be created for the base path.
+    ///
+    /// If opening the log file requires creating more levels of directories,
+    /// building fails with [`Error::CreateDirectory`] instead, to avoid
+    /// accidentally creating a deep directory tree from a malformed path. Pass
+    /// `0` to require the parent directory to already exist. The date
+    /// directories the files are placed in are not counted.
+    ///
+    /// This parameter is **optional**, and defaults to no limit.
+    #[must_use]
+    pub fn max_create_dir_depth(mut self, max_depth: usize) -> Self {
+        self.max_create_dir_depth = Some(max_depth);
+        self
+    }
+
+    /// Specifies the clock providing the current time.
+    ///
+    /// The clock decides when rotations happen and the files they open, by the
+    /// time a record is written rather than its [`Record::time`], and when
+    /// [`FlushPolicy::EveryDuration`] flushes. A [`MockClock`] makes them
+    /// deterministic in tests.
+    ///
+    /// This parameter is **optional**, and defaults to [`SystemClock`].
+    ///
+    /// [`MockClock`]: crate::MockClock
+    /// [`SystemClock`]: crate::SystemClock
+    #[must_use]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     helper::common_impl!(@SinkBuilder: common_builder_impl);
 }
 
@@ -317,13 +894,33 @@ impl DateAndHourRotatingFileSinkBuilder<PathBuf> {
     /// # Errors
     ///
     /// If an error occurs opening the file, [`Error::CreateDirectory`] or [`Error::OpenFile`]
-    /// will be returned.
+    /// will be returned. If an error occurs deleting old files,
+    /// [`Error::ReadDirectory`] or [`Error::RemoveFile`] will be returned.
     pub fn build(self) -> Result<DateAndHourRotatingFileSink> {
-        let rotator = RotatorTimePoint::new(self.base_path, self.rotate_on_open)?;
+        if let Some(max_depth) = self.max_create_dir_depth {
+            utils::check_create_dir_depth(&self.base_path, max_depth)?;
+        }
+        let common_impl = helper::CommonImpl::from_builder(self.common_builder_impl)
+            .with_target_path(&self.base_path);
+        if self.compression != Compression::None {
+            DateAndHourRotatingFileSink::remove_partial_files(&self.base_path)?;
+        }
+        let rotator = RotatorTimePoint::new(
+            self.base_path,
+            self.rotation_granularity,
+            self.max_files,
+            self.rotate_on_open,
+            self.force_truncate,
+            self.write_bom,
+            self.clock.clone(),
+        )?;
 
         let res = DateAndHourRotatingFileSink {
-            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
+            common_impl,
             rotator,
+            compression: self.compression,
+            compression_threads: Mutex::new(vec![]),
+            flush_policy: FlushPolicyState::new(self.flush_policy, self.clock.clone()),
         };
 
         Ok(res)
@@ -332,6 +929,8 @@ impl DateAndHourRotatingFileSinkBuilder<PathBuf> {
 
 #[cfg(test)]
 mod tests {
+    use std::{fs, time::Duration};
+
     use super::*;
     use crate::{prelude::*, test_utils::*, Level, Record};
 
@@ -355,10 +954,14 @@ mod tests {
             let system_time = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
 
             let calc_date_and_hour = |base_path| {
-                RotatorTimePoint::calc_file_path(base_path, system_time)
-                    .to_str()
-                    .unwrap()
-                    .to_string()
+                RotatorTimePoint::calc_file_path(
+                    base_path,
+                    RotationGranularity::Hourly,
+                    system_time,
+                )
+                .to_str()
+                .unwrap()
+                .to_string()
             };
 
             #[cfg(not(windows))]
@@ -380,22 +983,97 @@ mod tests {
             run();
         }
 
+        #[test]
+        fn rotation_time_point_matches_file_path() {
+            // A non-whole-hour offset, so that the local hours and the UTC hours
+            // start at different instants
+            let tz = FixedOffset::east_opt(5 * 60 * 60 + 30 * 60).unwrap();
+            let now: SystemTime = tz.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+
+            let file_name = |time| {
+                RotatorTimePoint::calc_file_path_in(
+                    &tz,
+                    "test.log",
+                    RotationGranularity::Hourly,
+                    time,
+                )
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+            };
+
+            let rotation_time = RotatorTimePoint::next_rotation_time_point_in(
+                &tz,
+                RotationGranularity::Hourly,
+                now,
+            );
+            assert_eq!(
+                rotation_time,
+                tz.with_ymd_and_hms(2012, 3, 4, 6, 0, 0).unwrap().into()
+            );
+            assert_eq!(file_name(now), "test_05.log");
+            assert_eq!(
+                file_name(rotation_time - Duration::from_nanos(1)),
+                "test_05.log"
+            );
+            assert_eq!(file_name(rotation_time), "test_06.log");
+        }
+
+        #[test]
+        fn granularity() {
+            let tz = FixedOffset::east_opt(5 * 60 * 60 + 30 * 60).unwrap();
+            let now: SystemTime = tz.with_ymd_and_hms(2012, 3, 4, 5, 16, 7).unwrap().into();
+
+            let check = |granularity, file_name: &str, (hour, minute)| {
+                let path = RotatorTimePoint::calc_file_path_in(&tz, "test.log", granularity, now);
+                assert_eq!(path, Path::new("20120304").join(file_name));
+
+                let next = RotatorTimePoint::next_rotation_time_point_in(&tz, granularity, now);
+                let expected: SystemTime = if hour == 24 {
+                    tz.with_ymd_and_hms(2012, 3, 5, 0, 0, 0).unwrap().into()
+                } else {
+                    tz.with_ymd_and_hms(2012, 3, 4, hour, minute, 0)
+                        .unwrap()
+                        .into()
+                };
+                assert_eq!(next, expected);
+
+                // The next period is named differently
+                let next_path =
+                    RotatorTimePoint::calc_file_path_in(&tz, "test.log", granularity, next);
+                assert_ne!(next_path, path);
+                assert!(RotatorTimePoint::is_file_path(Path::new("test.log"), &path));
+            };
+
+            check(RotationGranularity::Minute, "test_0516.log", (5, 17));
+            check(RotationGranularity::TenMinutes, "test_0510.log", (5, 20));
+            check(RotationGranularity::Hourly, "test_05.log", (6, 0));
+            check(RotationGranularity::Daily, "test.log", (24, 0));
+        }
+
         #[test]
         fn rotate() {
-            let build = |rotate_on_open| {
+            let start_time: SystemTime =
+                Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+
+            let build = |rotate_on_open, clock: Arc<crate::MockClock>| {
                 fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
                 fs::create_dir(LOGS_PATH.as_path()).unwrap();
 
                 let hourly_sink = DateAndHourRotatingFileSink::builder()
                     .base_path(LOGS_PATH.join("hourly.log"))
                     .rotate_on_open(rotate_on_open)
+                    .clock(clock.clone())
                     .build()
                     .unwrap();
 
-                let local_time_now = Local::now();
                 let daily_sink = DateAndHourRotatingFileSink::builder()
                     .base_path(LOGS_PATH.join("daily.log"))
+                    .rotation_granularity(RotationGranularity::Daily)
                     .rotate_on_open(rotate_on_open)
+                    .clock(clock)
                     .build()
                     .unwrap();
 
@@ -405,34 +1083,24 @@ mod tests {
                 logger
             };
 
-            let exist_files = |file_name_prefix| {
-                let paths = fs::read_dir(LOGS_PATH.clone()).unwrap();
-
-                paths.fold(0_usize, |count, entry| {
-                    if entry
-                        .unwrap()
-                        .file_name()
-                        .to_string_lossy()
-                        .starts_with(file_name_prefix)
-                    {
-                        count + 1
-                    } else {
-                        count
-                    }
-                })
+            // The files are placed in date directories
+            let exist_files = |base_file_name| {
+                DateAndHourRotatingFileSink::list_files(LOGS_PATH.join(base_file_name))
+                    .unwrap()
+                    .len()
             };
 
-            let exist_hourly_files = || exist_files("hourly");
-            let exist_daily_files = || exist_files("daily");
+            let exist_hourly_files = || exist_files("hourly.log");
+            let exist_daily_files = || exist_files("daily.log");
 
             const SECOND_1: Duration = Duration::from_secs(1);
             const HOUR_1: Duration = Duration::from_secs(60 * 60);
             const DAY_1: Duration = Duration::from_secs(60 * 60 * 24);
 
             {
-                let logger = build(true);
-                let mut record = Record::new(Level::Info, "test log message");
-                let initial_time = record.time();
+                let clock = Arc::new(crate::MockClock::new(start_time));
+                let logger = build(true, clock.clone());
+                let record = Record::new(Level::Info, "test log message");
 
                 assert_eq!(exist_hourly_files(), 1);
                 assert_eq!(exist_daily_files(), 1);
@@ -441,22 +1109,22 @@ mod tests {
                 assert_eq!(exist_hourly_files(), 1);
                 assert_eq!(exist_daily_files(), 1);
 
-                record.set_time(record.time() + HOUR_1 + SECOND_1);
+                clock.advance(HOUR_1 + SECOND_1);
                 logger.log(&record);
                 assert_eq!(exist_hourly_files(), 2);
                 assert_eq!(exist_daily_files(), 1);
 
-                record.set_time(record.time() + HOUR_1 + SECOND_1);
+                clock.advance(HOUR_1 + SECOND_1);
                 logger.log(&record);
                 assert_eq!(exist_hourly_files(), 3);
                 assert_eq!(exist_daily_files(), 1);
 
-                record.set_time(record.time() + SECOND_1);
+                clock.advance(SECOND_1);
                 logger.log(&record);
                 assert_eq!(exist_hourly_files(), 3);
                 assert_eq!(exist_daily_files(), 1);
 
-                record.set_time(initial_time + DAY_1 + SECOND_1);
+                clock.set(start_time + DAY_1 + SECOND_1);
                 logger.log(&record);
                 assert_eq!(exist_hourly_files(), 4);
                 assert_eq!(exist_daily_files(), 2);
@@ -464,17 +1132,377 @@ mod tests {
         }
     }
 
+    #[test]
+    fn coalesce_same_file_rotation() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("date_and_hour_coalesce_same_file_rotation");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let sink = DateAndHourRotatingFileSink::builder()
+            .base_path(LOGS_PATH.join("hourly.log"))
+            .clock(Arc::new(crate::MockClock::new(start_time)))
+            .build()
+            .unwrap();
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+
+        let record = Record::new(Level::Info, "a");
+        sink.log(&record).unwrap();
+
+        // Trigger a spurious rotation, the computed path is still the current file
+        sink.rotator.inner.lock_expect().rotation_time_point = SystemTime::UNIX_EPOCH;
+        let record = Record::new(Level::Info, "b");
+        sink.log(&record).unwrap();
+        sink.flush().unwrap();
+
+        let file_path = LOGS_PATH.join("20120304").join("hourly_05.log");
+        assert_eq!(fs::read_to_string(file_path).unwrap(), "ab");
+    }
+
+    #[test]
+    fn rotate_on_open_existing_file() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("date_and_hour_rotate_on_open_existing_file");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let file_path = LOGS_PATH.join("20120304").join("hourly_05.log");
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        fs::write(&file_path, "previous").unwrap();
+
+        let restart = |force_truncate| {
+            let sink = DateAndHourRotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("hourly.log"))
+                .rotate_on_open(true)
+                .force_truncate(force_truncate)
+                .clock(Arc::new(crate::MockClock::new(start_time)))
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+
+            let record = Record::new(Level::Info, "new");
+            sink.log(&record).unwrap();
+            sink.flush().unwrap();
+        };
+
+        let expected = format!("previous{}{}{}new", EOL, ROTATE_ON_OPEN_SEPARATOR, EOL);
+        restart(false);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), expected);
+
+        fs::write(&file_path, format!("previous{}", EOL)).unwrap();
+        restart(false);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), expected);
+
+        restart(true);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "new");
+    }
+
+    #[test]
+    fn write_bom() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("date_and_hour_write_bom");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let clock = Arc::new(crate::MockClock::new(start_time));
+        let build = || {
+            clock.set(start_time);
+            let sink = DateAndHourRotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("hourly.log"))
+                .write_bom(true)
+                .clock(clock.clone())
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+            sink
+        };
+        let log = |sink: &DateAndHourRotatingFileSink, payload, offset| {
+            clock.set(start_time + Duration::from_secs(offset));
+            let record = Record::new(Level::Info, payload);
+            sink.log(&record).unwrap();
+            sink.flush().unwrap();
+        };
+        let read = |hour| {
+            let file_name = format!("hourly_{:02}.log", hour);
+            fs::read(LOGS_PATH.join("20120304").join(file_name)).unwrap()
+        };
+
+        let sink = build();
+        log(&sink, "a", 0);
+        log(&sink, "b", 60 * 60);
+        assert_eq!(read(5), b"\xEF\xBB\xBFa");
+        assert_eq!(read(6), b"\xEF\xBB\xBFb");
+        drop(sink);
+
+        // Appending to the existing file does not write the BOM again
+        let sink = build();
+        log(&sink, "c", 0);
+        assert_eq!(read(5), b"\xEF\xBB\xBFac");
+    }
+
+    #[test]
+    fn flush_policy() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("date_and_hour_flush_policy");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let sink = DateAndHourRotatingFileSink::builder()
+            .base_path(LOGS_PATH.join("hourly.log"))
+            .flush_policy(FlushPolicy::EveryN(2))
+            .clock(Arc::new(crate::MockClock::new(start_time)))
+            .build()
+            .unwrap();
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+
+        let file_path = LOGS_PATH.join("20120304").join("hourly_05.log");
+        let log = |payload| {
+            let record = Record::new(Level::Info, payload);
+            sink.log(&record).unwrap();
+            fs::read_to_string(&file_path).unwrap()
+        };
+
+        assert_eq!(log("a"), "");
+        assert_eq!(log("b"), "ab");
+        assert_eq!(log("c"), "ab");
+        assert_eq!(log("d"), "abcd");
+    }
+
+    #[test]
+    fn max_create_dir_depth() {
+        let base = BASE_LOGS_PATH.join("date_and_hour_max_create_dir_depth");
+        if base.exists() {
+            fs::remove_dir_all(&base).unwrap();
+        }
+        let base_path = base.join("a/app.log");
+
+        let result = DateAndHourRotatingFileSink::builder()
+            .base_path(&base_path)
+            .max_create_dir_depth(1)
+            .build();
+        assert!(matches!(result, Err(Error::CreateDirectory(_))));
+        assert!(!base.exists());
+
+        // The date directory is not counted
+        DateAndHourRotatingFileSink::builder()
+            .base_path(&base_path)
+            .max_create_dir_depth(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            DateAndHourRotatingFileSink::list_files(&base_path)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn list_files() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("date_and_hour_list_files");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let base_path = LOGS_PATH.join("app.log");
+        assert!(DateAndHourRotatingFileSink::list_files(&base_path)
+            .unwrap()
+            .is_empty());
+
+        let create = |path: &str| {
+            let path = LOGS_PATH.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            File::create(path).unwrap();
+        };
+        create("20120305/app_00.log");
+        create("20120304/app_23.log");
+        create("20120304/app_05.log");
+        // Not matching the naming scheme
+        create("app.log");
+        create("20120304/app_5.log");
+        create("20120304/app_05.txt");
+        create("20120304/other_05.log");
+        create("2012030/app_05.log");
+
+        assert_eq!(
+            DateAndHourRotatingFileSink::list_files(&base_path).unwrap(),
+            [
+                LOGS_PATH.join("20120304/app_05.log"),
+                LOGS_PATH.join("20120304/app_23.log"),
+                LOGS_PATH.join("20120305/app_00.log"),
+            ]
+        );
+    }
+
+    #[test]
+    fn max_files() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("date_and_hour_max_files");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let create = |path: &str| {
+            let path = LOGS_PATH.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            File::create(path).unwrap();
+        };
+        create("20120301/app_00.log");
+        create("20120302/app_00.log");
+        create("20120302/app_01.log");
+        create("20120302/other_00.log");
+
+        let base_path = LOGS_PATH.join("app.log");
+        let list_files = || DateAndHourRotatingFileSink::list_files(&base_path).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let clock = Arc::new(crate::MockClock::new(start_time));
+        let sink = DateAndHourRotatingFileSink::builder()
+            .base_path(&base_path)
+            .max_files(2)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        // The emptied date directory is removed, the one still containing an
+        // unrelated file is kept
+        assert_eq!(
+            list_files(),
+            [
+                LOGS_PATH.join("20120302/app_01.log"),
+                LOGS_PATH.join("20120304/app_05.log"),
+            ]
+        );
+        assert!(!LOGS_PATH.join("20120301").exists());
+
+        clock.set(start_time + Duration::from_secs(60 * 60));
+        let record = Record::new(Level::Info, "test log message");
+        sink.log(&record).unwrap();
+
+        assert_eq!(
+            list_files(),
+            [
+                LOGS_PATH.join("20120304/app_05.log"),
+                LOGS_PATH.join("20120304/app_06.log"),
+            ]
+        );
+        assert!(LOGS_PATH.join("20120302/other_00.log").exists());
+
+        // `0` for no limit
+        create("20120301/app_00.log");
+        let _sink = DateAndHourRotatingFileSink::builder()
+            .base_path(&base_path)
+            .clock(Arc::new(crate::MockClock::new(start_time)))
+            .build()
+            .unwrap();
+        assert_eq!(list_files().len(), 3);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compress_gzip() {
+        use std::io::Read;
+
+        use flate2::read::GzDecoder;
+
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("date_and_hour_compress_gzip");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        // Left by an interrupted compression
+        let partial_path = LOGS_PATH.join("20120303").join("hourly_23.log.gz.partial");
+        let other_partial_path = LOGS_PATH.join("20120303").join("other_23.log.gz.partial");
+        fs::create_dir_all(partial_path.parent().unwrap()).unwrap();
+        File::create(&partial_path).unwrap();
+        File::create(&other_partial_path).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let clock = Arc::new(crate::MockClock::new(start_time));
+        let sink = DateAndHourRotatingFileSink::builder()
+            .base_path(LOGS_PATH.join("hourly.log"))
+            .compress(Compression::Gzip)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+        assert!(!partial_path.exists());
+        assert!(other_partial_path.exists());
+
+        let record = Record::new(Level::Info, "first hour");
+        sink.log(&record).unwrap();
+
+        clock.set(start_time + Duration::from_secs(60 * 60));
+        let record = Record::new(Level::Info, "second hour");
+        sink.log(&record).unwrap();
+
+        // Waits for the compression to finish
+        drop(sink);
+
+        let file_path = LOGS_PATH.join("20120304").join("hourly_05.log");
+        let gz_path = LOGS_PATH.join("20120304").join("hourly_05.log.gz");
+        assert!(!file_path.exists());
+
+        let mut content = String::new();
+        GzDecoder::new(File::open(gz_path).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "first hour");
+
+        assert_eq!(
+            DateAndHourRotatingFileSink::list_files(LOGS_PATH.join("hourly.log")).unwrap(),
+            [
+                LOGS_PATH.join("20120304/hourly_05.log.gz"),
+                LOGS_PATH.join("20120304/hourly_06.log"),
+            ]
+        );
+    }
+
     #[test]
     fn test_builder_optional_params() {
         // workaround for the missing `no_run` attribute
         let _ = || {
             let _: Result<DateAndHourRotatingFileSink> = DateAndHourRotatingFileSink::builder()
                 .base_path("/path/to/base_log_file")
+                // .max_files(100)
                 // .rotate_on_open(true)
                 .build();
 
             let _: Result<DateAndHourRotatingFileSink> = DateAndHourRotatingFileSink::builder()
                 .base_path("/path/to/base_log_file")
+                .rotation_granularity(RotationGranularity::Daily)
+                .max_files(100)
                 // .rotate_on_open(true)
                 .build();
 
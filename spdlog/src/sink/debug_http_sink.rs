@@ -0,0 +1,330 @@
+//! Provides a sink serving recent log messages over HTTP.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    sink::{helper, Sink},
+    sync::*,
+    utils, Error, Record, Result, StringBuf,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const MAX_REQUEST_HEAD_LEN: usize = 8 * 1024;
+
+/// A sink that keeps recent log messages in memory and serves them over HTTP.
+///
+/// This sink is intended for quick debugging, it is not a production-grade
+/// HTTP server. It listens on `127.0.0.1` and answers `GET` requests on a
+/// background thread:
+///
+///  - `/logs` responds with the buffered messages as plain text, in the order
+///    they were logged.
+///  - `/logs?format=json` responds with a JSON array of the buffered messages,
+///    with the trailing EOL of each message removed.
+///
+/// Other requests are answered with `404 Not Found`.
+///
+/// At most `capacity` messages are kept, when the limit is reached the oldest
+/// message is discarded.
+///
+/// The socket is bound when the sink is built, and the background thread is
+/// stopped when the sink is dropped.
+///
+/// This sink requires the crate feature `debug-http`.
+pub struct DebugHttpSink {
+    common_impl: helper::CommonImpl,
+    buffer: Arc<LineBuffer>,
+    local_addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl DebugHttpSink {
+    /// Constructs a builder of `DebugHttpSink`.
+    #[must_use]
+    pub fn builder() -> DebugHttpSinkBuilder {
+        DebugHttpSinkBuilder {
+            common_builder_impl: helper::CommonBuilderImpl::new(),
+            port: 0,
+            capacity: 1000,
+        }
+    }
+
+    /// Gets the local address that the sink is listening on.
+    ///
+    /// This is useful to find out the actual port if the sink was built with
+    /// port `0`.
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Gets the buffered log messages, from oldest to newest.
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        self.buffer.lines.lock_expect().iter().cloned().collect()
+    }
+}
+
+impl Sink for DebugHttpSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let mut string_buf = StringBuf::new();
+        self.common_impl.format(record, &mut string_buf)?;
+
+        self.buffer.push(string_buf.to_string());
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    helper::common_impl!(@Sink: common_impl);
+}
+
+impl Drop for DebugHttpSink {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        if let Some(server) = self.server.take() {
+            server.join().expect("failed to join the server thread");
+        }
+    }
+}
+
+struct LineBuffer {
+    lines: Mutex<VecDeque<String>>,
+    capacity: usize,
+}
+
+impl LineBuffer {
+    fn push(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock_expect();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    #[must_use]
+    fn plain_text(&self) -> String {
+        self.lines
+            .lock_expect()
+            .iter()
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[must_use]
+    fn json(&self) -> String {
+        let lines = self.lines.lock_expect();
+        let mut json = String::from("[");
+        for (i, line) in lines.iter().enumerate() {
+            if i != 0 {
+                json.push(',');
+            }
+            utils::write_json_string(&mut json, line.trim_end_matches(&['\r', '\n'][..])).unwrap();
+        }
+        json.push(']');
+        json
+    }
+}
+
+// The listener is non-blocking and polled, so that the thread notices the
+// shutdown flag without relying on a connection to wake it up.
+fn serve(listener: TcpListener, buffer: Arc<LineBuffer>, shutdown: Arc<AtomicBool>) {
+    while !shutdown.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // Errors only affect the current client, there is nobody to report
+                // them to, so they are ignored.
+                let _ = stream
+                    .set_nonblocking(false)
+                    .and_then(|_| respond(stream, &buffer));
+            }
+            // Mostly `WouldBlock` for no pending connection, other errors are
+            // retried after the same interval instead of spinning.
+            Err(_) => thread::sleep(ACCEPT_POLL_INTERVAL),
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, buffer: &LineBuffer) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let head = read_request_head(&mut stream)?;
+    let request_line = head.lines().next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/logs")) => ("200 OK", "text/plain", buffer.plain_text()),
+        (Some("GET"), Some("/logs?format=json")) => ("200 OK", "application/json", buffer.json()),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: {}; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\
+         \r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+fn read_request_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_HEAD_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head is too long",
+            ));
+        }
+        let len = stream.read(&mut buf)?;
+        if len == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..len]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// The builder of [`DebugHttpSink`].
+///
+/// # Examples
+///
+/// - Building a [`DebugHttpSink`] listening on `127.0.0.1:8080`.
+///
+///   ```no_run
+///   use spdlog::{prelude::*, sink::DebugHttpSink};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: DebugHttpSink = DebugHttpSink::builder()
+///       .port(8080)
+///       // .capacity(100) // optional
+///       // .level_filter(LevelFilter::MoreSevere(Level::Info)) // optional
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct DebugHttpSinkBuilder {
+    common_builder_impl: helper::CommonBuilderImpl,
+    port: u16,
+    capacity: usize,
+}
+
+impl DebugHttpSinkBuilder {
+    /// Specifies the port to listen on.
+    ///
+    /// This parameter is **optional**, and defaults to `0`, which lets the
+    /// operating system pick a free port. See [`DebugHttpSink::local_addr`].
+    #[must_use]
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Specifies the maximum number of log messages to keep.
+    ///
+    /// This parameter is **optional**, and defaults to `1000`.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    helper::common_impl!(@SinkBuilder: common_builder_impl);
+
+    /// Builds a [`DebugHttpSink`].
+    ///
+    /// # Errors
+    ///
+    /// If an error occurs binding the socket or starting the server thread,
+    /// [`Error::BindSocket`] will be returned.
+    pub fn build(self) -> Result<DebugHttpSink> {
+        let listener =
+            TcpListener::bind((Ipv4Addr::LOCALHOST, self.port)).map_err(Error::BindSocket)?;
+        let local_addr = listener.local_addr().map_err(Error::BindSocket)?;
+        listener.set_nonblocking(true).map_err(Error::BindSocket)?;
+
+        let buffer = Arc::new(LineBuffer {
+            lines: Mutex::new(VecDeque::new()),
+            capacity: self.capacity,
+        });
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let server = {
+            let buffer = buffer.clone();
+            let shutdown = shutdown.clone();
+            thread::Builder::new()
+                .name("spdlog-debug-http".into())
+                .spawn(move || serve(listener, buffer, shutdown))
+                .map_err(Error::BindSocket)?
+        };
+
+        let sink = DebugHttpSink {
+            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
+            buffer,
+            local_addr,
+            shutdown,
+            server: Some(server),
+        };
+        Ok(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    fn fetch(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serve_logs() {
+        let sink = Arc::new(DebugHttpSink::builder().capacity(2).build().unwrap());
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+        let logger = test_logger_builder().sink(sink.clone()).build().unwrap();
+
+        info!(logger: logger, "dropped\n");
+        info!(logger: logger, "hello \"DebugHttpSink\"\n");
+        info!(logger: logger, "second line\n");
+
+        let response = fetch(sink.local_addr(), "/logs");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nhello \"DebugHttpSink\"\nsecond line\n"));
+
+        let response = fetch(sink.local_addr(), "/logs?format=json");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(r#"["hello \"DebugHttpSink\"","second line"]"#));
+
+        let response = fetch(sink.local_addr(), "/other");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    convert::Infallible,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    formatter::Formatter,
+    sink::{helper, Sink},
+    sync::*,
+    ErrorHandler, Level, LevelFilter, Record, Result,
+};
+
+/// A [combined sink] that collapses repeated identical messages into a single
+/// record, forwarding the rest to its internal sink.
+///
+/// A record is skipped if its payload is the same as the payload of the last
+/// forwarded record, and it's logged within `max_skip_duration` after it. When
+/// a different message arrives, or the same message arrives after the
+/// duration, a record `Skipped N duplicate messages..` is forwarded before it
+/// if any records were skipped, at the level of the skipped records.
+///
+/// The duration is measured between the [`Record::time`] of the records, not
+/// the time they are processed by the sink.
+///
+/// This is the equivalent of `dup_filter_sink` in C++ spdlog.
+///
+/// [combined sink]: index.html#combined-sink
+pub struct DupFilterSink {
+    level_filter: Atomic<LevelFilter>,
+    sink: Arc<dyn Sink>,
+    max_skip_duration: Duration,
+    state: SpinMutex<DupFilterState>,
+}
+
+struct DupFilterState {
+    last_payload: Option<String>,
+    last_time: SystemTime,
+    last_level: Level,
+    skipped: usize,
+}
+
+impl DupFilterSink {
+    /// Constructs a builder of `DupFilterSink`.
+    #[must_use]
+    pub fn builder() -> DupFilterSinkBuilder<(), ()> {
+        DupFilterSinkBuilder {
+            level_filter: helper::SINK_DEFAULT_LEVEL_FILTER,
+            sink: (),
+            max_skip_duration: (),
+        }
+    }
+
+    /// Gets a reference to the internal sink.
+    #[must_use]
+    pub fn sink(&self) -> &Arc<dyn Sink> {
+        &self.sink
+    }
+
+    #[must_use]
+    fn is_duplicate(&self, state: &DupFilterState, record: &Record) -> bool {
+        state.last_payload.as_deref() == Some(record.payload())
+            && record
+                .time()
+                .duration_since(state.last_time)
+                .map_or(true, |elapsed| elapsed < self.max_skip_duration)
+    }
+}
+
+impl Sink for DupFilterSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let mut state = self.state.lock();
+        if self.is_duplicate(&state, record) {
+            state.skipped += 1;
+            state.last_level = record.level();
+            return Ok(());
+        }
+
+        let skipped = std::mem::take(&mut state.skipped);
+        let summary_res = if skipped > 0 {
+            let mut summary = Record::new(
+                state.last_level,
+                format!("Skipped {} duplicate messages..", skipped),
+            );
+            if let Some(logger_name) = record.logger_name() {
+                summary.set_logger_name(logger_name);
+            }
+            summary.set_time(record.time());
+            self.sink.log(&summary)
+        } else {
+            Ok(())
+        };
+
+        state.last_payload = Some(record.payload().to_string());
+        state.last_time = record.time();
+        state.last_level = record.level();
+
+        let res = self.sink.log(record);
+        summary_res.and(res)
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.sink.flush()
+    }
+
+    /// For [`DupFilterSink`], the function performs the same call to the
+    /// internal sink.
+    fn set_formatter(&self, formatter: Box<dyn Formatter>) {
+        self.sink.set_formatter(formatter)
+    }
+
+    /// For [`DupFilterSink`], the function performs the same call to the
+    /// internal sink.
+    fn set_error_handler(&self, handler: Option<ErrorHandler>) {
+        self.sink.set_error_handler(handler)
+    }
+
+    helper::common_impl! {
+        @SinkCustom {
+            level_filter: level_filter,
+            formatter: None,
+            error_handler: None,
+        }
+    }
+}
+
+/// The builder of [`DupFilterSink`].
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+/// # Examples
+///
+/// - Building a [`DupFilterSink`].
+///
+///   ```
+///   use std::{sync::Arc, time::Duration};
+///
+///   use spdlog::sink::{DupFilterSink, StdStream, StdStreamSink};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let stdout = Arc::new(StdStreamSink::builder().std_stream(StdStream::Stdout).build()?);
+///   let sink: DupFilterSink = DupFilterSink::builder()
+///       .sink(stdout) // required
+///       .max_skip_duration(Duration::from_secs(5)) // required
+///       .build()?;
+///   # Ok(()) }
+///   ```
+///
+/// - If any required parameters are missing, a compile-time error will be
+///   raised.
+///
+///   ```compile_fail,E0061
+///   use std::{sync::Arc, time::Duration};
+///
+///   use spdlog::sink::{DupFilterSink, StdStream, StdStreamSink};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let stdout = Arc::new(StdStreamSink::builder().std_stream(StdStream::Stdout).build()?);
+///   let sink: DupFilterSink = DupFilterSink::builder()
+///       .sink(stdout) // required
+///       // .max_skip_duration(Duration::from_secs(5)) // required
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct DupFilterSinkBuilder<ArgSink, ArgDuration> {
+    level_filter: LevelFilter,
+    sink: ArgSink,
+    max_skip_duration: ArgDuration,
+}
+
+impl<ArgSink, ArgDuration> DupFilterSinkBuilder<ArgSink, ArgDuration> {
+    /// Specifies the internal sink that records are forwarded to.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn sink(self, sink: Arc<dyn Sink>) -> DupFilterSinkBuilder<Arc<dyn Sink>, ArgDuration> {
+        DupFilterSinkBuilder {
+            level_filter: self.level_filter,
+            sink,
+            max_skip_duration: self.max_skip_duration,
+        }
+    }
+
+    /// Specifies the duration after the last forwarded record, within which
+    /// records with the same payload are skipped.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn max_skip_duration(
+        self,
+        max_skip_duration: Duration,
+    ) -> DupFilterSinkBuilder<ArgSink, Duration> {
+        DupFilterSinkBuilder {
+            level_filter: self.level_filter,
+            sink: self.sink,
+            max_skip_duration,
+        }
+    }
+
+    helper::common_impl!(@SinkBuilderCustom {
+        level_filter: level_filter,
+        formatter: None,
+        error_handler: None,
+    });
+}
+
+impl<ArgDuration> DupFilterSinkBuilder<(), ArgDuration> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `sink`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl DupFilterSinkBuilder<Arc<dyn Sink>, ()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `max_skip_duration`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl DupFilterSinkBuilder<Arc<dyn Sink>, Duration> {
+    /// Builds a [`DupFilterSink`].
+    pub fn build(self) -> Result<DupFilterSink> {
+        Ok(DupFilterSink {
+            level_filter: Atomic::new(self.level_filter),
+            sink: self.sink,
+            max_skip_duration: self.max_skip_duration,
+            state: SpinMutex::new(DupFilterState {
+                last_payload: None,
+                last_time: SystemTime::UNIX_EPOCH,
+                last_level: Level::Info,
+                skipped: 0,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn collapse_duplicates() {
+        let counter = Arc::new(CounterSink::new());
+        let sink = DupFilterSink::builder()
+            .sink(counter.clone())
+            .max_skip_duration(Duration::from_secs(5))
+            .build()
+            .unwrap();
+
+        let start_time = SystemTime::now();
+        let log = |payload: &'static str, secs: u64| {
+            let mut record = Record::new(Level::Warn, payload);
+            record.set_time(start_time + Duration::from_secs(secs));
+            sink.log(&record).unwrap();
+        };
+
+        log("hello", 0);
+        log("hello", 1);
+        log("hello", 2);
+        log("world", 3);
+        log("world", 4);
+        // Out of the window of the last forwarded "world" at 3s.
+        log("world", 9);
+        log("world", 10);
+
+        assert_eq!(
+            counter.payloads(),
+            vec![
+                "hello",
+                "Skipped 2 duplicate messages..",
+                "world",
+                "Skipped 1 duplicate messages..",
+                "world",
+            ]
+        );
+    }
+}
@@ -2,17 +2,25 @@
 
 use std::{
     convert::Infallible,
-    fs::File,
-    io::{BufWriter, Write},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
     path::{Path, PathBuf},
 };
 
 use crate::{
-    sink::{helper, Sink},
+    clock,
+    sink::{helper, FlushPolicy, FlushPolicyState, Sink},
     sync::*,
-    utils, Error, Record, Result, StringBuf,
+    utils, Error, Level, Record, Result, StringBuf,
 };
 
+pub(crate) const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The alignment required by [`FileSinkBuilder::direct_io`] for the size of
+/// each formatted log message, in bytes.
+#[cfg(any(target_os = "linux", all(doc, not(doctest))))]
+pub const DIRECT_IO_ALIGNMENT: usize = 4096;
+
 /// A sink with a file as the target.
 ///
 /// # Examples
@@ -22,7 +30,8 @@ use crate::{
 /// [./examples]: https://github.com/SpriteOvO/spdlog-rs/tree/main/spdlog/examples
 pub struct FileSink {
     common_impl: helper::CommonImpl,
-    file: SpinMutex<BufWriter<File>>,
+    file: SpinMutex<FileWriter>,
+    flush_policy: FlushPolicyState,
 }
 
 impl FileSink {
@@ -32,6 +41,10 @@ impl FileSink {
         FileSinkBuilder {
             path: (),
             truncate: false,
+            flush_policy: FlushPolicy::Never,
+            max_create_dir_depth: None,
+            write_bom: false,
+            direct_io: false,
             common_builder_impl: helper::CommonBuilderImpl::new(),
         }
     }
@@ -68,16 +81,31 @@ impl Sink for FileSink {
         }
 
         let mut string_buf = StringBuf::new();
-        self.common_impl
-            .formatter
-            .read()
-            .format(record, &mut string_buf)?;
-
-        self.file
-            .lock()
-            .write_all(string_buf.as_bytes())
+        self.common_impl.format(record, &mut string_buf)?;
+
+        let mut file = self.file.lock();
+        file.write_all(string_buf.as_bytes())
             .map_err(Error::WriteRecord)?;
 
+        if self.flush_policy.should_flush(record.level()) {
+            file.flush().map_err(Error::FlushBuffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn log_preformatted(&self, bytes: &[u8], level: Level) -> Result<()> {
+        if !self.should_log(level) {
+            return Ok(());
+        }
+
+        let mut file = self.file.lock();
+        file.write_all(bytes).map_err(Error::WriteRecord)?;
+
+        if self.flush_policy.should_flush(level) {
+            file.flush().map_err(Error::FlushBuffer)?;
+        }
+
         Ok(())
     }
 
@@ -97,6 +125,81 @@ impl Drop for FileSink {
     }
 }
 
+// The writer of the file, which buffers writes unless the file is opened for
+// direct I/O.
+enum FileWriter {
+    Buffered(BufWriter<File>),
+    #[cfg(target_os = "linux")]
+    Direct(DirectFile),
+}
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Buffered(writer) => writer.write(buf),
+            #[cfg(target_os = "linux")]
+            Self::Direct(writer) => writer.write(buf),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Buffered(writer) => writer.write_all(buf),
+            #[cfg(target_os = "linux")]
+            Self::Direct(writer) => writer.write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Buffered(writer) => writer.flush(),
+            #[cfg(target_os = "linux")]
+            Self::Direct(writer) => writer.flush(),
+        }
+    }
+}
+
+// A file opened with `O_DIRECT`. The memory written from must be aligned too,
+// so each write is copied into an aligned region of a bounce buffer first.
+#[cfg(target_os = "linux")]
+struct DirectFile {
+    file: File,
+    bounce_buf: Vec<u8>,
+}
+
+#[cfg(target_os = "linux")]
+impl Write for DirectFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() % DIRECT_IO_ALIGNMENT != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "direct I/O requires writes of a multiple of {} bytes, got {} bytes",
+                    DIRECT_IO_ALIGNMENT,
+                    buf.len()
+                ),
+            ));
+        }
+
+        let required_len = buf.len() + DIRECT_IO_ALIGNMENT;
+        if self.bounce_buf.len() < required_len {
+            self.bounce_buf.resize(required_len, 0);
+        }
+        let offset = self.bounce_buf.as_ptr().align_offset(DIRECT_IO_ALIGNMENT);
+        let aligned = &mut self.bounce_buf[offset..offset + buf.len()];
+        aligned.copy_from_slice(buf);
+
+        // A partial write would leave the rest unaligned, so write it all here.
+        self.file.write_all(aligned)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Nothing is buffered in user space.
+        Ok(())
+    }
+}
+
 // --------------------------------------------------
 
 /// The builder of [`FileSink`].
@@ -133,6 +236,10 @@ pub struct FileSinkBuilder<ArgPath> {
     common_builder_impl: helper::CommonBuilderImpl,
     path: ArgPath,
     truncate: bool,
+    flush_policy: FlushPolicy,
+    max_create_dir_depth: Option<usize>,
+    write_bom: bool,
+    direct_io: bool,
 }
 
 impl<ArgPath> FileSinkBuilder<ArgPath> {
@@ -148,6 +255,10 @@ impl<ArgPath> FileSinkBuilder<ArgPath> {
             common_builder_impl: self.common_builder_impl,
             path: path.into(),
             truncate: self.truncate,
+            flush_policy: self.flush_policy,
+            max_create_dir_depth: self.max_create_dir_depth,
+            write_bom: self.write_bom,
+            direct_io: self.direct_io,
         }
     }
 
@@ -160,6 +271,67 @@ impl<ArgPath> FileSinkBuilder<ArgPath> {
         self
     }
 
+    /// Specifies when the sink flushes itself after logging a record.
+    ///
+    /// This parameter is **optional**, and defaults to [`FlushPolicy::Never`].
+    #[must_use]
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
+    /// Specifies the maximum number of levels of missing parent directories
+    /// that may be created for the log file.
+    ///
+    /// If opening the file requires creating more levels of directories,
+    /// building fails with [`Error::CreateDirectory`] instead, to avoid
+    /// accidentally creating a deep directory tree from a malformed path. Pass
+    /// `0` to require the parent directory to already exist.
+    ///
+    /// This parameter is **optional**, and defaults to no limit.
+    #[must_use]
+    pub fn max_create_dir_depth(mut self, max_depth: usize) -> Self {
+        self.max_create_dir_depth = Some(max_depth);
+        self
+    }
+
+    /// Specifies whether to write a UTF-8 BOM at the start of the log file.
+    ///
+    /// The BOM is only written if the file is newly created, truncated, or
+    /// empty, it is never written in the middle of a file being appended to.
+    /// Some Windows tools rely on it to detect the encoding of the file.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[must_use]
+    pub fn write_bom(mut self, write_bom: bool) -> Self {
+        self.write_bom = write_bom;
+        self
+    }
+
+    /// Specifies whether to open the file with `O_DIRECT`, so that writes
+    /// bypass the page cache and nothing is buffered in user space.
+    ///
+    /// This is intended for benchmarking the true throughput of the disk, and
+    /// is much slower than the default buffered I/O otherwise.
+    ///
+    /// `O_DIRECT` requires the size of each write and the file offset to be
+    /// aligned to the block size of the device, so the formatter must pad each
+    /// formatted log message to a multiple of [`DIRECT_IO_ALIGNMENT`] bytes,
+    /// otherwise logging fails with [`Error::WriteRecord`]. For the same
+    /// reason, a file being appended to must already have an aligned size, and
+    /// [`FileSinkBuilder::write_bom`] cannot be used.
+    ///
+    /// If the file system does not support `O_DIRECT` (e.g. tmpfs), building
+    /// fails with [`Error::OpenFile`].
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[cfg(any(target_os = "linux", all(doc, not(doctest))))]
+    #[must_use]
+    pub fn direct_io(mut self, direct_io: bool) -> Self {
+        self.direct_io = direct_io;
+        self
+    }
+
     helper::common_impl!(@SinkBuilder: common_builder_impl);
 }
 
@@ -180,13 +352,209 @@ impl FileSinkBuilder<PathBuf> {
     /// If an error occurs opening the file, [`Error::CreateDirectory`] or
     /// [`Error::OpenFile`] will be returned.
     pub fn build(self) -> Result<FileSink> {
-        let file = utils::open_file(self.path, self.truncate)?;
+        if let Some(max_depth) = self.max_create_dir_depth {
+            utils::check_create_dir_depth(&self.path, max_depth)?;
+        }
+        let is_new_file =
+            self.truncate || fs::metadata(&self.path).map_or(true, |metadata| metadata.len() == 0);
+        #[cfg(target_os = "linux")]
+        let mut writer = if self.direct_io {
+            FileWriter::Direct(DirectFile {
+                file: utils::open_file_direct(&self.path, self.truncate)?,
+                bounce_buf: vec![],
+            })
+        } else {
+            FileWriter::Buffered(BufWriter::new(utils::open_file(&self.path, self.truncate)?))
+        };
+        #[cfg(not(target_os = "linux"))]
+        let mut writer =
+            FileWriter::Buffered(BufWriter::new(utils::open_file(&self.path, self.truncate)?));
+
+        if self.write_bom && is_new_file {
+            writer.write_all(UTF8_BOM).map_err(Error::WriteRecord)?;
+        }
 
         let sink = FileSink {
-            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
-            file: SpinMutex::new(BufWriter::new(file)),
+            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl)
+                .with_target_path(&self.path),
+            file: SpinMutex::new(writer),
+            flush_policy: FlushPolicyState::new(self.flush_policy, clock::default_clock()),
         };
 
         Ok(sink)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    static BASE_LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+        let path = TEST_LOGS_PATH.join("file_sink");
+        fs::create_dir_all(&path).unwrap();
+        path
+    });
+
+    fn build_logger(file_name: &str, flush_policy: FlushPolicy) -> (PathBuf, Logger) {
+        let path = BASE_LOGS_PATH.join(file_name);
+        let sink = FileSink::builder()
+            .path(&path)
+            .truncate(true)
+            .flush_policy(flush_policy)
+            .formatter(Box::new(NoModFormatter::new()))
+            .build()
+            .unwrap();
+        let logger = test_logger_builder()
+            .sink(Arc::new(sink))
+            .level_filter(LevelFilter::All)
+            .build()
+            .unwrap();
+        (path, logger)
+    }
+
+    #[test]
+    fn flush_policy_on_level() {
+        let (path, logger) = build_logger("on_level.log", FlushPolicy::OnLevel(Level::Warn));
+
+        info!(logger: logger, "a");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        warn!(logger: logger, "b");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ab");
+
+        debug!(logger: logger, "c");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ab");
+
+        error!(logger: logger, "d");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abcd");
+    }
+
+    #[test]
+    fn flush_policy_every_n() {
+        let (path, logger) = build_logger("every_n.log", FlushPolicy::EveryN(2));
+
+        info!(logger: logger, "a");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        info!(logger: logger, "b");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ab");
+
+        info!(logger: logger, "c");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ab");
+
+        info!(logger: logger, "d");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abcd");
+    }
+
+    #[test]
+    fn write_bom() {
+        let path = BASE_LOGS_PATH.join("write_bom.log");
+        if path.exists() {
+            fs::remove_file(&path).unwrap();
+        }
+
+        let log_with_new_sink = |payload: &str| {
+            let sink = FileSink::builder()
+                .path(&path)
+                .write_bom(true)
+                .formatter(Box::new(NoModFormatter::new()))
+                .build()
+                .unwrap();
+            sink.log(&Record::new(Level::Info, payload)).unwrap();
+        };
+
+        log_with_new_sink("a");
+        assert_eq!(fs::read(&path).unwrap(), b"\xEF\xBB\xBFa");
+
+        // Appending to the existing file does not write the BOM again.
+        log_with_new_sink("b");
+        assert_eq!(fs::read(&path).unwrap(), b"\xEF\xBB\xBFab");
+    }
+
+    #[test]
+    fn log_preformatted() {
+        let path = BASE_LOGS_PATH.join("log_preformatted.log");
+        let sink = FileSink::builder()
+            .path(&path)
+            .truncate(true)
+            .level_filter(LevelFilter::MoreSevereEqual(Level::Info))
+            .build()
+            .unwrap();
+
+        sink.log_preformatted(b"[relayed] [info] first\n", Level::Info)
+            .unwrap();
+        sink.log_preformatted(b"filtered\n", Level::Debug).unwrap();
+        sink.log_preformatted(b"[relayed] [error] second\n", Level::Error)
+            .unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            b"[relayed] [info] first\n[relayed] [error] second\n"
+        );
+    }
+
+    #[test]
+    fn max_create_dir_depth() {
+        let base = BASE_LOGS_PATH.join("max_create_dir_depth");
+        if base.exists() {
+            fs::remove_dir_all(&base).unwrap();
+        }
+        let path = base.join("a/b/file.log");
+
+        let result = FileSink::builder()
+            .path(&path)
+            .max_create_dir_depth(2)
+            .build();
+        assert!(matches!(result, Err(Error::CreateDirectory(_))));
+        assert!(!base.exists());
+
+        FileSink::builder()
+            .path(&path)
+            .max_create_dir_depth(3)
+            .build()
+            .unwrap();
+        assert!(path.exists());
+
+        // `0` only requires the parent directory to exist
+        FileSink::builder()
+            .path(base.join("a/b/another.log"))
+            .max_create_dir_depth(0)
+            .build()
+            .unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn direct_io() {
+        let path = BASE_LOGS_PATH.join("direct_io.log");
+        let sink = match FileSink::builder()
+            .path(&path)
+            .truncate(true)
+            .direct_io(true)
+            .formatter(Box::new(NoModFormatter::new()))
+            .build()
+        {
+            Ok(sink) => sink,
+            // The file system of the test directory doesn't support `O_DIRECT` (e.g.
+            // tmpfs), nothing to test here.
+            Err(Error::OpenFile(err)) if err.kind() == io::ErrorKind::InvalidInput => return,
+            Err(err) => panic!("{}", err),
+        };
+        let logger = test_logger_builder().sink(Arc::new(sink)).build().unwrap();
+
+        let aligned = "a".repeat(DIRECT_IO_ALIGNMENT);
+        assert!(logger
+            .try_log(&Record::new(Level::Info, aligned.as_str()))
+            .is_ok());
+        assert!(logger
+            .try_log(&Record::new(Level::Info, "unaligned"))
+            .is_err());
+
+        // Written without explicit flushing
+        assert_eq!(fs::read_to_string(&path).unwrap(), aligned);
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    formatter::Formatter,
+    sink::{helper, Sink, Sinks},
+    sync::*,
+    ErrorHandler, LevelFilter, Record, Result,
+};
+
+/// The function type used by [`FirstSeenSink`] to compute the key of a record.
+pub type FirstSeenKeyFn = Box<dyn Fn(&Record) -> u64 + Send + Sync>;
+
+/// A [combined sink] that only forwards the first record for each distinct
+/// key.
+///
+/// The key of a record is computed by a user-provided function, and defaults
+/// to a hash of the record payload. Records whose key has been seen before are
+/// dropped silently. This is useful for "log this once per distinct value"
+/// cases, e.g. logging a configuration value only when it changes to a value
+/// that has not been logged yet.
+///
+/// To keep memory bounded, at most `capacity` keys are remembered. When the
+/// limit is reached, the least recently seen key is forgotten, so a record
+/// with that key will be forwarded again the next time it occurs.
+///
+/// [combined sink]: index.html#combined-sink
+pub struct FirstSeenSink {
+    level_filter: Atomic<LevelFilter>,
+    sinks: Sinks,
+    key_fn: FirstSeenKeyFn,
+    seen: Mutex<LruKeys>,
+}
+
+impl FirstSeenSink {
+    /// Constructs a builder of `FirstSeenSink`.
+    #[must_use]
+    pub fn builder() -> FirstSeenSinkBuilder {
+        FirstSeenSinkBuilder {
+            level_filter: helper::SINK_DEFAULT_LEVEL_FILTER,
+            sinks: Sinks::new(),
+            key_fn: None,
+            capacity: 1024,
+        }
+    }
+
+    /// Gets a reference to internal sinks in the combined sink.
+    #[must_use]
+    pub fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &self.sinks
+    }
+
+    /// Forgets all the keys that have been seen.
+    pub fn clear(&self) {
+        self.seen.lock_expect().clear();
+    }
+
+    #[must_use]
+    fn default_key(record: &Record) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        record.payload().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Sink for FirstSeenSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let key = (self.key_fn)(record);
+        if !self.seen.lock_expect().insert(key) {
+            return Ok(());
+        }
+
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            let res = sink.log(record);
+            if result.is_ok() {
+                result = res;
+            }
+        }
+        result
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            let res = sink.flush();
+            if result.is_ok() {
+                result = res;
+            }
+        }
+        result
+    }
+
+    /// For [`FirstSeenSink`], the function performs the same call to all
+    /// internal sinks.
+    fn set_formatter(&self, formatter: Box<dyn Formatter>) {
+        for sink in &self.sinks {
+            sink.set_formatter(formatter.clone_box())
+        }
+    }
+
+    /// For [`FirstSeenSink`], the function performs the same call to all
+    /// internal sinks.
+    fn set_error_handler(&self, handler: Option<ErrorHandler>) {
+        for sink in &self.sinks {
+            sink.set_error_handler(handler)
+        }
+    }
+
+    helper::common_impl! {
+        @SinkCustom {
+            level_filter: level_filter,
+            formatter: None,
+            error_handler: None,
+        }
+    }
+}
+
+/// The builder of [`FirstSeenSink`].
+///
+/// # Examples
+///
+/// - Forwarding each distinct payload only once.
+///
+///   ```
+///   use spdlog::{prelude::*, sink::FirstSeenSink};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   # let stdout_sink = spdlog::default_logger().sinks()[0].clone();
+///   let sink: FirstSeenSink = FirstSeenSink::builder()
+///       .sink(stdout_sink)
+///       // .key(|record| record.payload().len() as u64) // optional
+///       // .capacity(4096) // optional
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct FirstSeenSinkBuilder {
+    level_filter: LevelFilter,
+    sinks: Sinks,
+    key_fn: Option<FirstSeenKeyFn>,
+    capacity: usize,
+}
+
+impl FirstSeenSinkBuilder {
+    /// Add a [`Sink`].
+    #[must_use]
+    pub fn sink(mut self, sink: Arc<dyn Sink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Add multiple [`Sink`]s.
+    #[must_use]
+    pub fn sinks<I>(mut self, sinks: I) -> Self
+    where
+        I: IntoIterator<Item = Arc<dyn Sink>>,
+    {
+        self.sinks.append(&mut sinks.into_iter().collect());
+        self
+    }
+
+    /// Specifies the function computing the key of a record.
+    ///
+    /// Records with equal keys are considered the same, only the first one of
+    /// them is forwarded.
+    ///
+    /// This parameter is **optional**, and defaults to a hash of the record
+    /// payload.
+    #[must_use]
+    pub fn key<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&Record) -> u64 + Send + Sync + 'static,
+    {
+        self.key_fn = Some(Box::new(key_fn));
+        self
+    }
+
+    /// Specifies the maximum number of keys remembered. When exceeded, the
+    /// least recently seen key is forgotten.
+    ///
+    /// A capacity of `0` is treated the same as `1`.
+    ///
+    /// This parameter is **optional**, and defaults to `1024`.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Builds a [`FirstSeenSink`].
+    pub fn build(self) -> Result<FirstSeenSink> {
+        Ok(FirstSeenSink {
+            level_filter: Atomic::new(self.level_filter),
+            sinks: self.sinks,
+            key_fn: self
+                .key_fn
+                .unwrap_or_else(|| Box::new(FirstSeenSink::default_key)),
+            seen: Mutex::new(LruKeys::new(self.capacity.max(1))),
+        })
+    }
+
+    helper::common_impl!(@SinkBuilderCustom {
+        level_filter: level_filter,
+        formatter: None,
+        error_handler: None,
+    });
+}
+
+// A set of keys evicting the least recently seen one when full.
+struct LruKeys {
+    capacity: usize,
+    tick: u64,
+    // key -> the tick it was last seen
+    keys: HashMap<u64, u64>,
+    // tick -> key, ordered from the least recently seen
+    order: BTreeMap<u64, u64>,
+}
+
+impl LruKeys {
+    #[must_use]
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            keys: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    // Marks the key as the most recently seen one, returns `true` if it was not
+    // in the set.
+    fn insert(&mut self, key: u64) -> bool {
+        self.tick += 1;
+
+        if let Some(last_seen) = self.keys.insert(key, self.tick) {
+            self.order.remove(&last_seen);
+            self.order.insert(self.tick, key);
+            return false;
+        }
+
+        if self.keys.len() > self.capacity {
+            // `BTreeMap::pop_first` is not stable on our MSRV.
+            let (&oldest_tick, &oldest) = self.order.iter().next().unwrap();
+            self.order.remove(&oldest_tick);
+            self.keys.remove(&oldest);
+        }
+        self.order.insert(self.tick, key);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.keys.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    #[test]
+    fn forward_first_only() {
+        let counter_sink = Arc::new(CounterSink::new());
+        let sink = Arc::new(
+            FirstSeenSink::builder()
+                .sink(counter_sink.clone())
+                .build()
+                .unwrap(),
+        );
+        let logger = test_logger_builder().sink(sink).build().unwrap();
+
+        info!(logger: logger, "config: a");
+        info!(logger: logger, "config: a");
+        info!(logger: logger, "config: b");
+
+        assert_eq!(counter_sink.log_count(), 2);
+        assert_eq!(counter_sink.payloads(), ["config: a", "config: b"]);
+    }
+
+    #[test]
+    fn evict_least_recently_seen() {
+        let counter_sink = Arc::new(CounterSink::new());
+        let sink = Arc::new(
+            FirstSeenSink::builder()
+                .sink(counter_sink.clone())
+                .capacity(2)
+                .build()
+                .unwrap(),
+        );
+        let logger = test_logger_builder().sink(sink.clone()).build().unwrap();
+
+        info!(logger: logger, "a");
+        info!(logger: logger, "b");
+        info!(logger: logger, "a"); // `a` becomes the most recently seen
+        info!(logger: logger, "c"); // evicts `b`
+        info!(logger: logger, "a");
+        info!(logger: logger, "b");
+
+        assert_eq!(counter_sink.payloads(), ["a", "b", "c", "b"]);
+
+        sink.clear();
+        info!(logger: logger, "a");
+        assert_eq!(counter_sink.log_count(), 5);
+    }
+}
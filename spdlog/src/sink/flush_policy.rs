@@ -0,0 +1,150 @@
+//! Provides a flush policy shared by sinks.
+
+use std::time::{Duration, SystemTime};
+
+use crate::{clock::Clock, sync::*, Level, LevelFilter};
+
+/// Specifies when a sink flushes itself after logging a record.
+///
+/// It is consumed by the builders of sinks that buffer their output, e.g.
+/// [`FileSinkBuilder::flush_policy`]. Regardless of the policy, a sink is
+/// still flushed when [`Sink::flush`] is called explicitly (e.g. by the
+/// flush level filter or the flush period of a [`Logger`]).
+///
+/// [`FileSinkBuilder::flush_policy`]: crate::sink::FileSinkBuilder::flush_policy
+/// [`Sink::flush`]: crate::sink::Sink::flush
+/// [`Logger`]: crate::Logger
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum FlushPolicy {
+    /// Never flushes automatically.
+    Never,
+    /// Flushes after logging a record whose level is equal to or more severe
+    /// than the given level.
+    OnLevel(Level),
+    /// Flushes after logging a record if at least the given duration has
+    /// elapsed since the last automatic flush.
+    ///
+    /// Note that no background timer is involved, the elapsed time is only
+    /// checked when a record is logged, by the clock of the sink (see e.g.
+    /// [`RotatingFileSinkBuilder::clock`]).
+    ///
+    /// [`RotatingFileSinkBuilder::clock`]: crate::sink::RotatingFileSinkBuilder::clock
+    EveryDuration(Duration),
+    /// Flushes after every N logged records.
+    ///
+    /// `EveryN(0)` is treated the same as `EveryN(1)`.
+    EveryN(usize),
+}
+
+pub(crate) struct FlushPolicyState {
+    policy: FlushPolicy,
+    log_counter: AtomicUsize,
+    clock: Arc<dyn Clock>,
+    last_flush: SpinMutex<SystemTime>,
+}
+
+impl FlushPolicyState {
+    #[must_use]
+    pub(crate) fn new(policy: FlushPolicy, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            policy,
+            log_counter: AtomicUsize::new(0),
+            last_flush: SpinMutex::new(clock.now()),
+            clock,
+        }
+    }
+
+    // Called once for each record that has been logged by the sink.
+    #[must_use]
+    pub(crate) fn should_flush(&self, level: Level) -> bool {
+        match self.policy {
+            FlushPolicy::Never => false,
+            FlushPolicy::OnLevel(flush_level) => {
+                LevelFilter::MoreSevereEqual(flush_level).compare(level)
+            }
+            FlushPolicy::EveryDuration(duration) => {
+                let mut last_flush = self.last_flush.lock();
+                let now = self.clock.now();
+                match now.duration_since(*last_flush) {
+                    Ok(elapsed) if elapsed >= duration => {
+                        *last_flush = now;
+                        true
+                    }
+                    Ok(_) => false,
+                    // The clock went backwards, the elapsed time is counted from now on
+                    Err(_) => {
+                        *last_flush = now;
+                        false
+                    }
+                }
+            }
+            FlushPolicy::EveryN(n) => {
+                let count = self.log_counter.fetch_add(1, Ordering::Relaxed) + 1;
+                count % n.max(1) == 0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clock, MockClock};
+
+    #[test]
+    fn on_level() {
+        let state =
+            FlushPolicyState::new(FlushPolicy::OnLevel(Level::Warn), clock::default_clock());
+        assert!(!state.should_flush(Level::Trace));
+        assert!(!state.should_flush(Level::Info));
+        assert!(state.should_flush(Level::Warn));
+        assert!(state.should_flush(Level::Critical));
+    }
+
+    #[test]
+    fn every_n() {
+        let state = FlushPolicyState::new(FlushPolicy::EveryN(3), clock::default_clock());
+        let results = (0..7)
+            .map(|_| state.should_flush(Level::Info))
+            .collect::<Vec<_>>();
+        assert_eq!(results, [false, false, true, false, false, true, false]);
+
+        let state = FlushPolicyState::new(FlushPolicy::EveryN(0), clock::default_clock());
+        assert!(state.should_flush(Level::Info));
+        assert!(state.should_flush(Level::Info));
+    }
+
+    #[test]
+    fn every_duration() {
+        let clock = Arc::new(MockClock::new(SystemTime::UNIX_EPOCH));
+        let state = FlushPolicyState::new(
+            FlushPolicy::EveryDuration(Duration::from_secs(3600)),
+            clock.clone(),
+        );
+        assert!(!state.should_flush(Level::Critical));
+        clock.advance(Duration::from_secs(3599));
+        assert!(!state.should_flush(Level::Critical));
+        clock.advance(Duration::from_secs(1));
+        assert!(state.should_flush(Level::Trace));
+        assert!(!state.should_flush(Level::Trace));
+
+        // The clock went backwards
+        clock.set(SystemTime::UNIX_EPOCH);
+        assert!(!state.should_flush(Level::Trace));
+        clock.advance(Duration::from_secs(3600));
+        assert!(state.should_flush(Level::Trace));
+
+        let state = FlushPolicyState::new(
+            FlushPolicy::EveryDuration(Duration::ZERO),
+            clock::default_clock(),
+        );
+        assert!(state.should_flush(Level::Trace));
+    }
+
+    #[test]
+    fn never() {
+        let state = FlushPolicyState::new(FlushPolicy::Never, clock::default_clock());
+        assert!(!state.should_flush(Level::Critical));
+    }
+}
@@ -0,0 +1,205 @@
+use std::{convert::Infallible, fmt, io, marker::PhantomData};
+
+use crate::{
+    sink::{helper, Sink},
+    sync::*,
+    Error, Record, Result, StringBuf,
+};
+
+/// A sink that appends log messages to a shared [`fmt::Write`] object, e.g. a
+/// [`String`].
+///
+/// Unlike [`WriteSink`], which targets [`io::Write`], this sink targets
+/// [`fmt::Write`]. The target is shared via `Arc<Mutex<W>>`, so the caller
+/// can keep a handle and read the accumulated content at any time, e.g. when
+/// building a report.
+///
+/// Since [`fmt::Write`] has no buffering concept, flushing this sink does
+/// nothing.
+///
+/// [`WriteSink`]: crate::sink::WriteSink
+pub struct FmtWriteSink<W>
+where
+    W: fmt::Write + Send + ?Sized,
+{
+    common_impl: helper::CommonImpl,
+    target: Arc<Mutex<W>>,
+}
+
+impl<W> FmtWriteSink<W>
+where
+    W: fmt::Write + Send + ?Sized,
+{
+    /// Constructs a builder of `FmtWriteSink`.
+    #[must_use]
+    pub fn builder() -> FmtWriteSinkBuilder<W, ()> {
+        FmtWriteSinkBuilder {
+            common_builder_impl: helper::CommonBuilderImpl::new(),
+            target: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Gets the shared target.
+    #[must_use]
+    pub fn target(&self) -> &Arc<Mutex<W>> {
+        &self.target
+    }
+}
+
+impl<W> Sink for FmtWriteSink<W>
+where
+    W: fmt::Write + Send + ?Sized,
+{
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let mut string_buf = StringBuf::new();
+        self.common_impl.format(record, &mut string_buf)?;
+
+        self.target
+            .lock_expect()
+            .write_str(string_buf.as_str())
+            .map_err(|err| Error::WriteRecord(io::Error::new(io::ErrorKind::Other, err)))?;
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    helper::common_impl!(@Sink: common_impl);
+}
+
+/// The builder of [`FmtWriteSink`].
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+/// # Examples
+///
+/// - Building a [`FmtWriteSink`].
+///
+///   ```
+///   use std::sync::{Arc, Mutex};
+///
+///   use spdlog::{prelude::*, sink::FmtWriteSink};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let report = Arc::new(Mutex::new(String::new()));
+///   let sink: FmtWriteSink<_> = FmtWriteSink::builder()
+///       .target(report.clone()) // required
+///       // .level_filter(LevelFilter::MoreSevere(Level::Info)) // optional
+///       .build()?;
+///   # Ok(()) }
+///   ```
+///
+/// - If any required parameters are missing, a compile-time error will be
+///   raised.
+///
+///   ```compile_fail,E0061
+///   use spdlog::{prelude::*, sink::FmtWriteSink};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: FmtWriteSink<String> = FmtWriteSink::builder()
+///       // .target(report) // required
+///       .level_filter(LevelFilter::MoreSevere(Level::Info)) // optional
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct FmtWriteSinkBuilder<W, ArgW>
+where
+    W: ?Sized,
+{
+    common_builder_impl: helper::CommonBuilderImpl,
+    target: Option<Arc<Mutex<W>>>,
+    _phantom: PhantomData<ArgW>,
+}
+
+impl<W, ArgW> FmtWriteSinkBuilder<W, ArgW>
+where
+    W: fmt::Write + Send + ?Sized,
+{
+    /// Specifies the shared target that implemented [`fmt::Write`] trait, log
+    /// messages will be appended to the target.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn target(self, target: Arc<Mutex<W>>) -> FmtWriteSinkBuilder<W, PhantomData<W>> {
+        FmtWriteSinkBuilder {
+            common_builder_impl: self.common_builder_impl,
+            target: Some(target),
+            _phantom: PhantomData,
+        }
+    }
+
+    helper::common_impl!(@SinkBuilder: common_builder_impl);
+}
+
+impl<W> FmtWriteSinkBuilder<W, ()>
+where
+    W: fmt::Write + Send + ?Sized,
+{
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `target`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl<W> FmtWriteSinkBuilder<W, PhantomData<W>>
+where
+    W: fmt::Write + Send + ?Sized,
+{
+    /// Builds a [`FmtWriteSink`].
+    pub fn build(self) -> Result<FmtWriteSink<W>> {
+        let sink = FmtWriteSink {
+            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
+            target: self.target.unwrap(),
+        };
+        Ok(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    #[test]
+    fn append_to_shared_string() {
+        let report = Arc::new(Mutex::new(String::from("report:\n")));
+        let sink = Arc::new(
+            FmtWriteSink::builder()
+                .target(report.clone())
+                .formatter(Box::new(NoModFormatter::new()))
+                .build()
+                .unwrap(),
+        );
+        let logger = test_logger_builder()
+            .sink(sink)
+            .level_filter(LevelFilter::All)
+            .build()
+            .unwrap();
+
+        info!(logger: logger, "hello\n");
+        warn!(logger: logger, "FmtWriteSink\n");
+
+        assert_eq!(*report.lock_expect(), "report:\nhello\nFmtWriteSink\n");
+    }
+
+    #[test]
+    fn dyn_target() {
+        let report = Arc::new(Mutex::new(String::new()));
+        let target: Arc<Mutex<dyn fmt::Write + Send>> = report.clone();
+        let sink = FmtWriteSink::builder()
+            .target(target)
+            .formatter(Box::new(NoModFormatter::new()))
+            .build()
+            .unwrap();
+
+        sink.log(&Record::new(Level::Info, "hello")).unwrap();
+        assert_eq!(*report.lock_expect(), "hello");
+    }
+}
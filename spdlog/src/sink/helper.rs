@@ -1,10 +1,12 @@
+use std::path::{Path, PathBuf};
+
 use cfg_if::cfg_if;
 
 use crate::{
-    formatter::{Formatter, FullFormatter},
+    formatter::{FmtExtraInfo, Formatter},
     prelude::*,
     sync::*,
-    Error, ErrorHandler,
+    utils, Error, ErrorHandler, Record, Result, StringBuf,
 };
 
 pub(crate) type SinkErrorHandler = Atomic<Option<ErrorHandler>>;
@@ -18,16 +20,35 @@ cfg_if! {
 
 pub(crate) const SINK_DEFAULT_LEVEL_FILTER: LevelFilter = LevelFilter::All;
 
+// Writes the payload as-is, used for records logged by the default
+// `Sink::log_preformatted`, which have been formatted already.
+#[derive(Clone)]
+struct PassThroughFormatter;
+
+impl Formatter for PassThroughFormatter {
+    fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+        dest.push_str(record.payload());
+        Ok(FmtExtraInfo::default())
+    }
+
+    fn clone_box(&self) -> Box<dyn Formatter> {
+        Box::new(self.clone())
+    }
+}
+
 pub(crate) struct CommonImpl {
     pub(crate) level_filter: Atomic<LevelFilter>,
     pub(crate) formatter: SpinRwLock<Box<dyn Formatter>>,
     pub(crate) error_handler: SinkErrorHandler,
+    pub(crate) eol_override: Option<String>,
+    pub(crate) target_path: Option<PathBuf>,
+    pub(crate) paused: AtomicBool,
 }
 
 impl CommonImpl {
     #[must_use]
     pub(crate) fn from_builder(common_builder_impl: CommonBuilderImpl) -> Self {
-        Self::from_builder_with_formatter(common_builder_impl, || Box::new(FullFormatter::new()))
+        Self::from_builder_with_formatter(common_builder_impl, crate::default_formatter)
     }
 
     #[must_use]
@@ -39,6 +60,9 @@ impl CommonImpl {
             level_filter: Atomic::new(common_builder_impl.level_filter),
             formatter: SpinRwLock::new(common_builder_impl.formatter.unwrap_or_else(fallback)),
             error_handler: Atomic::new(common_builder_impl.error_handler),
+            eol_override: common_builder_impl.eol_override,
+            target_path: None,
+            paused: AtomicBool::new(false),
         }
     }
 
@@ -49,9 +73,48 @@ impl CommonImpl {
             level_filter: Atomic::new(LevelFilter::All),
             formatter: SpinRwLock::new(formatter),
             error_handler: Atomic::new(None),
+            eol_override: None,
+            target_path: None,
+            paused: AtomicBool::new(false),
         }
     }
 
+    // Sets the directory the sink writes into, which is passed to the formatter.
+    #[must_use]
+    pub(crate) fn with_target_path(mut self, target_path: impl AsRef<Path>) -> Self {
+        self.target_path = Some(utils::target_dir(target_path.as_ref()).to_path_buf());
+        self
+    }
+
+    // Formats the record with the sink's formatter, then applies the EOL override
+    // if any.
+    pub(crate) fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+        if record.is_preformatted() {
+            return PassThroughFormatter.format(record, dest);
+        }
+
+        let extra_info =
+            self.formatter
+                .read()
+                .format_for_target(record, dest, self.target_path.as_deref())?;
+
+        if let Some(eol) = &self.eol_override {
+            let trailing_eol_len = if dest.ends_with("\r\n") {
+                2
+            } else if dest.ends_with('\n') {
+                1
+            } else {
+                0
+            };
+            if trailing_eol_len != 0 {
+                dest.truncate(dest.len() - trailing_eol_len);
+                dest.push_str(eol);
+            }
+        }
+
+        Ok(extra_info)
+    }
+
     pub(crate) fn non_returnable_error(&self, from: impl AsRef<str>, err: Error) {
         match self.error_handler.load(Ordering::Relaxed) {
             Some(handler) => handler(err),
@@ -64,6 +127,7 @@ pub(crate) struct CommonBuilderImpl {
     pub(crate) level_filter: LevelFilter,
     pub(crate) formatter: Option<Box<dyn Formatter>>,
     pub(crate) error_handler: Option<ErrorHandler>,
+    pub(crate) eol_override: Option<String>,
 }
 
 impl CommonBuilderImpl {
@@ -73,6 +137,7 @@ impl CommonBuilderImpl {
             level_filter: SINK_DEFAULT_LEVEL_FILTER,
             formatter: None,
             error_handler: None,
+            eol_override: None,
         }
     }
 }
@@ -86,6 +151,7 @@ macro_rules! common_impl {
             formatter: $($field).+.formatter,
             error_handler: $($field).+.error_handler,
         });
+        $crate::sink::helper::common_impl!(@SinkCustomInner@paused: $($field).+.paused);
     };
     ( @SinkCustom {
         level_filter: $($level_filter:ident).+,
@@ -119,12 +185,27 @@ macro_rules! common_impl {
         }
     };
 
+    ( @SinkCustomInner@paused: $($field:ident).+ ) => {
+        fn pause(&self) {
+            self.$($field).+.store(true, $crate::sync::Ordering::Relaxed);
+        }
+
+        fn resume(&self) {
+            self.$($field).+.store(false, $crate::sync::Ordering::Relaxed);
+        }
+
+        fn is_paused(&self) -> bool {
+            self.$($field).+.load($crate::sync::Ordering::Relaxed)
+        }
+    };
+
     // SinkBuiler
 
     ( @SinkBuilder: $($field:ident).+ ) => {
         $crate::sink::helper::common_impl!(@SinkBuilderCustomInner@level_filter: $($field).+.level_filter);
         $crate::sink::helper::common_impl!(@SinkBuilderCustomInner@formatter: $($field).+.formatter);
         $crate::sink::helper::common_impl!(@SinkBuilderCustomInner@error_handler: $($field).+.error_handler);
+        $crate::sink::helper::common_impl!(@SinkBuilderCustomInner@eol_override: $($field).+.eol_override);
     };
     ( @SinkBuilderCustom {
         level_filter: $($level_filter:ident).+,
@@ -159,8 +240,10 @@ macro_rules! common_impl {
         $crate::sink::helper::common_impl! {
             /// Specifies a formatter.
             ///
-            /// This parameter is **optional**, and defaults to [`FullFormatter`].
+            /// This parameter is **optional**, and defaults to the formatter set
+            /// by [`set_default_formatter`], or [`FullFormatter`] if none was set.
             ///
+            /// [`set_default_formatter`]: crate::set_default_formatter
             /// [`FullFormatter`]: crate::formatter::FullFormatter
             @SinkBuilderCustomInner@formatter: $($field).+
         }
@@ -192,5 +275,23 @@ macro_rules! common_impl {
             self
         }
     };
+    ( @SinkBuilderCustomInner@eol_override: $($field:ident).+ ) => {
+        /// Specifies an end of line that replaces the trailing one of each
+        /// formatted log message, independent of the formatter.
+        ///
+        /// If a formatted log message ends with `\n` or `\r\n`, it will be
+        /// replaced with the given string before writing, otherwise the message
+        /// is written as is.
+        ///
+        /// This parameter is **optional**, and defaults to no override.
+        #[must_use]
+        pub fn eol_override<S>(mut self, eol: S) -> Self
+        where
+            S: Into<String>,
+        {
+            self.$($field).+ = Some(eol.into());
+            self
+        }
+    };
 }
 pub(crate) use common_impl;
@@ -110,10 +110,7 @@ impl Sink for JournaldSink {
         }
 
         let mut string_buf = StringBuf::new();
-        self.common_impl
-            .formatter
-            .read()
-            .format(record, &mut string_buf)?;
+        self.common_impl.format(record, &mut string_buf)?;
 
         let kvs = [
             format!("MESSAGE={}", string_buf),
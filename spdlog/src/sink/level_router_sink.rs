@@ -0,0 +1,188 @@
+use crate::{
+    formatter::Formatter,
+    sink::{helper, Sink},
+    sync::*,
+    ErrorHandler, LevelFilter, Record, Result,
+};
+
+/// A [combined sink] that dispatches each record to the internal sinks routed
+/// for its level.
+///
+/// Each internal sink is paired with a [`LevelFilter`], a record is forwarded
+/// to every sink whose filter accepts the level of the record. Routes may
+/// overlap, in which case the record is forwarded to all matching sinks, in
+/// the order the routes were added. Records matching no route are dropped.
+///
+/// This allows e.g. writing errors to one file and less severe records to
+/// another within a single logger.
+///
+/// [combined sink]: index.html#combined-sink
+pub struct LevelRouterSink {
+    level_filter: Atomic<LevelFilter>,
+    routes: Vec<(LevelFilter, Arc<dyn Sink>)>,
+}
+
+impl LevelRouterSink {
+    /// Constructs a builder of `LevelRouterSink`.
+    #[must_use]
+    pub fn builder() -> LevelRouterSinkBuilder {
+        LevelRouterSinkBuilder {
+            level_filter: helper::SINK_DEFAULT_LEVEL_FILTER,
+            routes: vec![],
+        }
+    }
+
+    /// Gets a reference to the routes of the combined sink, each of which is a
+    /// level filter and the internal sink it routes to.
+    #[must_use]
+    pub fn routes(&self) -> &[(LevelFilter, Arc<dyn Sink>)] {
+        &self.routes
+    }
+}
+
+impl Sink for LevelRouterSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let mut result = Ok(());
+        for (level_filter, sink) in &self.routes {
+            if level_filter.compare(record.level()) {
+                let res = sink.log(record);
+                if result.is_ok() {
+                    result = res;
+                }
+            }
+        }
+        result
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut result = Ok(());
+        for (_, sink) in &self.routes {
+            let res = sink.flush();
+            if result.is_ok() {
+                result = res;
+            }
+        }
+        result
+    }
+
+    /// For [`LevelRouterSink`], the function performs the same call to all
+    /// internal sinks.
+    fn set_formatter(&self, formatter: Box<dyn Formatter>) {
+        for (_, sink) in &self.routes {
+            sink.set_formatter(formatter.clone_box())
+        }
+    }
+
+    /// For [`LevelRouterSink`], the function performs the same call to all
+    /// internal sinks.
+    fn set_error_handler(&self, handler: Option<ErrorHandler>) {
+        for (_, sink) in &self.routes {
+            sink.set_error_handler(handler)
+        }
+    }
+
+    helper::common_impl! {
+        @SinkCustom {
+            level_filter: level_filter,
+            formatter: None,
+            error_handler: None,
+        }
+    }
+}
+
+/// The builder of [`LevelRouterSink`].
+///
+/// # Examples
+///
+/// - Writing errors to one file, and info and debug records to another.
+///
+///   ```no_run
+///   use std::sync::Arc;
+///
+///   use spdlog::{
+///       prelude::*,
+///       sink::{FileSink, LevelRouterSink},
+///   };
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let errors = Arc::new(FileSink::builder().path("error.log").build()?);
+///   let infos = Arc::new(FileSink::builder().path("info.log").build()?);
+///   let sink: LevelRouterSink = LevelRouterSink::builder()
+///       .route(LevelFilter::MoreSevereEqual(Level::Error), errors)
+///       .route(LevelFilter::Equal(Level::Info), infos.clone())
+///       .route(LevelFilter::Equal(Level::Debug), infos)
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct LevelRouterSinkBuilder {
+    level_filter: LevelFilter,
+    routes: Vec<(LevelFilter, Arc<dyn Sink>)>,
+}
+
+impl LevelRouterSinkBuilder {
+    /// Add a route, forwarding records whose level is accepted by the given
+    /// level filter to the given [`Sink`].
+    #[must_use]
+    pub fn route(mut self, level_filter: LevelFilter, sink: Arc<dyn Sink>) -> Self {
+        self.routes.push((level_filter, sink));
+        self
+    }
+
+    /// Builds a [`LevelRouterSink`].
+    pub fn build(self) -> Result<LevelRouterSink> {
+        Ok(LevelRouterSink {
+            level_filter: Atomic::new(self.level_filter),
+            routes: self.routes,
+        })
+    }
+
+    helper::common_impl!(@SinkBuilderCustom {
+        level_filter: level_filter,
+        formatter: None,
+        error_handler: None,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    #[test]
+    fn route_by_level() {
+        let errors = Arc::new(CounterSink::new());
+        let infos = Arc::new(CounterSink::new());
+        let all = Arc::new(CounterSink::new());
+        let sink = Arc::new(
+            LevelRouterSink::builder()
+                .route(LevelFilter::MoreSevereEqual(Level::Error), errors.clone())
+                .route(LevelFilter::Equal(Level::Info), infos.clone())
+                .route(LevelFilter::Equal(Level::Debug), infos.clone())
+                .route(LevelFilter::All, all.clone())
+                .build()
+                .unwrap(),
+        );
+        let logger = test_logger_builder().sink(sink.clone()).build().unwrap();
+        logger.set_level_filter(LevelFilter::All);
+
+        critical!(logger: logger, "critical");
+        error!(logger: logger, "error");
+        warn!(logger: logger, "warn");
+        info!(logger: logger, "info");
+        debug!(logger: logger, "debug");
+        trace!(logger: logger, "trace");
+
+        assert_eq!(errors.payloads(), vec!["critical", "error"]);
+        assert_eq!(infos.payloads(), vec!["info", "debug"]);
+        assert_eq!(all.log_count(), 6);
+
+        logger.flush();
+        assert_eq!(errors.flush_count(), 1);
+        assert_eq!(infos.flush_count(), 2);
+        assert_eq!(all.flush_count(), 1);
+    }
+}
@@ -0,0 +1,515 @@
+//! Provides a memory-mapped file sink.
+
+use std::{
+    convert::Infallible,
+    fs::{self, File, OpenOptions},
+    io,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    ptr,
+    sync::PoisonError,
+};
+
+use crate::{
+    error::InvalidArgumentError,
+    sink::{helper, rotating_file_sink::RotatorFileSize, Sink},
+    sync::*,
+    Error, Record, Result, StringBuf,
+};
+
+/// A sink that writes log messages into a memory-mapped file.
+///
+/// The log file is created with the size of the region and mapped into memory,
+/// so writing a record is a memory copy instead of a system call. Concurrent
+/// records reserve their space in the region with an atomic write cursor and
+/// are copied in parallel.
+///
+/// When a record does not fit in the rest of the region, the file is truncated
+/// to the written length and rotated, and a new region is started. Rotated
+/// files are named like the ones of [`RotatingFileSink`] with a file size
+/// rotation policy, i.e. the newest rotated file of `app.log` is `app_1.log`.
+/// An existing non-empty file at the path is also rotated when the sink is
+/// built. When the sink is dropped, the file is truncated to the written
+/// length.
+///
+/// Flushing the sink calls `msync` to write the mapped pages to the disk.
+/// Without flushing, the operating system writes them back on its own, and
+/// already written records survive a crash of the process, but not of the
+/// operating system.
+///
+/// This sink is only available on unix-like systems, and requires the crate
+/// feature `mmap`.
+///
+/// [`RotatingFileSink`]: crate::sink::RotatingFileSink
+pub struct MmapFileSink {
+    common_impl: helper::CommonImpl,
+    base_path: PathBuf,
+    capacity: usize,
+    max_files: usize,
+    // Rotations finish the old region and create a new one under the write lock,
+    // which performs file I/O, so a blocking lock is used rather than a spin one.
+    region: RwLock<Option<MmapRegion>>,
+    cursor: AtomicUsize,
+    // Whether the old region was finished but the files were not rotated yet,
+    // only accessed under the write lock of `region`.
+    rotation_pending: AtomicBool,
+}
+
+impl MmapFileSink {
+    /// Constructs a builder of `MmapFileSink`.
+    #[must_use]
+    pub fn builder() -> MmapFileSinkBuilder<()> {
+        MmapFileSinkBuilder {
+            common_builder_impl: helper::CommonBuilderImpl::new(),
+            path: (),
+            capacity: 16 * 1024 * 1024,
+            max_files: 2,
+        }
+    }
+
+    // Reserves `len` bytes in the current region, returns the offset of them.
+    #[must_use]
+    fn reserve(&self, len: usize) -> Option<usize> {
+        let mut current = self.cursor.load(Ordering::Acquire);
+        loop {
+            let end = current
+                .checked_add(len)
+                .filter(|end| *end <= self.capacity)?;
+            match self.cursor.compare_exchange_weak(
+                current,
+                end,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return Some(current),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    // Finishes the current region and starts a new one, unless another thread
+    // has already done it and `len` bytes fit now.
+    //
+    // If a previous rotation failed, the files are rotated again before the new
+    // region is created, since creating it truncates the file at the base path.
+    fn rotate(&self, len: usize) -> Result<()> {
+        let mut region = self.region.write_expect();
+
+        if region.is_some() && self.cursor.load(Ordering::Acquire) + len <= self.capacity {
+            return Ok(());
+        }
+        if let Some(old) = region.take() {
+            self.rotation_pending.store(true, Ordering::Relaxed);
+            old.finish(self.cursor.load(Ordering::Acquire))?;
+        }
+        if self.rotation_pending.load(Ordering::Relaxed) {
+            rotate_files(&self.base_path, self.max_files)?;
+            self.rotation_pending.store(false, Ordering::Relaxed);
+        }
+
+        *region = Some(MmapRegion::create(&self.base_path, self.capacity)?);
+        self.cursor.store(0, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl Sink for MmapFileSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let mut string_buf = StringBuf::new();
+        self.common_impl.format(record, &mut string_buf)?;
+        let bytes = string_buf.as_bytes();
+
+        if bytes.len() > self.capacity {
+            return Err(Error::WriteRecord(io::Error::new(
+                io::ErrorKind::Other,
+                "the record is larger than the mapped region",
+            )));
+        }
+
+        loop {
+            {
+                let region = self.region.read_expect();
+                if let Some(region) = region.as_ref() {
+                    if let Some(offset) = self.reserve(bytes.len()) {
+                        // SAFETY: The range is inside the mapping, and it is reserved by this
+                        // thread only, the mapping is kept alive by the read lock.
+                        unsafe {
+                            ptr::copy_nonoverlapping(
+                                bytes.as_ptr(),
+                                region.ptr.add(offset),
+                                bytes.len(),
+                            );
+                        }
+                        return Ok(());
+                    }
+                }
+            }
+            self.rotate(bytes.len())?;
+        }
+    }
+
+    fn flush(&self) -> Result<()> {
+        match self.region.read_expect().as_ref() {
+            Some(region) => region.sync().map_err(Error::FlushBuffer),
+            None => Ok(()),
+        }
+    }
+
+    helper::common_impl!(@Sink: common_impl);
+}
+
+impl Drop for MmapFileSink {
+    fn drop(&mut self) {
+        let region = self
+            .region
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(region) = region.take() {
+            if let Err(err) = region.finish(*self.cursor.get_mut()) {
+                self.common_impl.non_returnable_error("MmapFileSink", err)
+            }
+        }
+    }
+}
+
+fn rotate_files(base_path: &Path, max_files: usize) -> Result<()> {
+    for i in (1..max_files).rev() {
+        let src = RotatorFileSize::calc_file_path(base_path, i - 1);
+        if !src.exists() {
+            continue;
+        }
+
+        let dst = RotatorFileSize::calc_file_path(base_path, i);
+        if dst.exists() {
+            fs::remove_file(&dst).map_err(Error::RemoveFile)?;
+        }
+
+        fs::rename(src, dst).map_err(Error::RenameFile)?;
+    }
+    Ok(())
+}
+
+struct MmapRegion {
+    file: File,
+    ptr: *mut u8,
+    len: usize,
+}
+
+// SAFETY: The mapping is owned by the region, writes to it are coordinated by
+// the atomic cursor of the sink.
+unsafe impl Send for MmapRegion {}
+unsafe impl Sync for MmapRegion {}
+
+impl MmapRegion {
+    fn create(path: &Path, len: usize) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent).map_err(Error::CreateDirectory)?;
+            }
+        }
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(Error::OpenFile)?;
+        file.set_len(len as u64).map_err(Error::ResizeFile)?;
+
+        // SAFETY: The file is opened for reading and writing, and has been
+        // extended to `len` bytes.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::OpenFile(io::Error::last_os_error()));
+        }
+
+        Ok(Self {
+            file,
+            ptr: ptr as *mut u8,
+            len,
+        })
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        // SAFETY: `ptr` and `len` describe a live mapping.
+        if unsafe { libc::msync(self.ptr as *mut libc::c_void, self.len, libc::MS_SYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Syncs the mapping and truncates the file to the written length.
+    fn finish(self, written: usize) -> Result<()> {
+        self.sync().map_err(Error::FlushBuffer)?;
+        self.file.set_len(written as u64).map_err(Error::ResizeFile)
+    }
+}
+
+impl Drop for MmapRegion {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `len` describe a live mapping, which is not used
+        // after this.
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+/// The builder of [`MmapFileSink`].
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+/// # Examples
+///
+/// - Building a [`MmapFileSink`].
+///
+///   ```no_run
+///   use spdlog::sink::MmapFileSink;
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: MmapFileSink = MmapFileSink::builder()
+///       .path("/path/to/log_file") // required
+///       // .capacity(64 * 1024 * 1024) // optional, defaults to 16 MiB
+///       // .max_files(10) // optional, defaults to `2`
+///       .build()?;
+///   # Ok(()) }
+///   ```
+///
+/// - If any required parameters are missing, a compile-time error will be
+///   raised.
+///
+///   ```compile_fail,E0061
+///   use spdlog::sink::MmapFileSink;
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: MmapFileSink = MmapFileSink::builder()
+///       // .path("/path/to/log_file") // required
+///       .capacity(64 * 1024 * 1024) // optional, defaults to 16 MiB
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct MmapFileSinkBuilder<ArgPath> {
+    common_builder_impl: helper::CommonBuilderImpl,
+    path: ArgPath,
+    capacity: usize,
+    max_files: usize,
+}
+
+impl<ArgPath> MmapFileSinkBuilder<ArgPath> {
+    /// The path of the log file.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn path<P>(self, path: P) -> MmapFileSinkBuilder<PathBuf>
+    where
+        P: Into<PathBuf>,
+    {
+        MmapFileSinkBuilder {
+            common_builder_impl: self.common_builder_impl,
+            path: path.into(),
+            capacity: self.capacity,
+            max_files: self.max_files,
+        }
+    }
+
+    /// Specifies the size in bytes of the mapped region, i.e. the maximum size
+    /// of each log file.
+    ///
+    /// A single record larger than this cannot be written, and
+    /// [`Error::WriteRecord`] is returned for it. The capacity cannot be `0`,
+    /// building fails with [`Error::InvalidArgument`] for it.
+    ///
+    /// This parameter is **optional**, and defaults to 16 MiB.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Specifies the maximum number of log files to keep, including the
+    /// current one.
+    ///
+    /// When rotating, the oldest file is removed if the limit is reached.
+    /// `0` behaves like `1`, i.e. only the current file is kept, and its
+    /// previous contents are discarded on rotation.
+    ///
+    /// This parameter is **optional**, and defaults to `2`.
+    #[must_use]
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    helper::common_impl!(@SinkBuilder: common_builder_impl);
+}
+
+impl MmapFileSinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `path`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl MmapFileSinkBuilder<PathBuf> {
+    /// Builds a [`MmapFileSink`].
+    ///
+    /// # Errors
+    ///
+    /// If the capacity is `0`, [`Error::InvalidArgument`] will be returned.
+    ///
+    /// If an error occurs rotating the existing file, or creating and mapping
+    /// the new file, [`Error::RenameFile`], [`Error::RemoveFile`],
+    /// [`Error::CreateDirectory`], [`Error::OpenFile`] or [`Error::ResizeFile`]
+    /// will be returned.
+    pub fn build(self) -> Result<MmapFileSink> {
+        if self.capacity == 0 {
+            return Err(Error::InvalidArgument(InvalidArgumentError::MmapCapacity(
+                "cannot be 0".to_string(),
+            )));
+        }
+
+        let is_empty = fs::metadata(&self.path).map_or(true, |metadata| metadata.len() == 0);
+        if !is_empty {
+            rotate_files(&self.path, self.max_files)?;
+        }
+
+        let region = MmapRegion::create(&self.path, self.capacity)?;
+
+        let sink = MmapFileSink {
+            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl)
+                .with_target_path(&self.path),
+            base_path: self.path,
+            capacity: self.capacity,
+            max_files: self.max_files,
+            region: RwLock::new(Some(region)),
+            rotation_pending: AtomicBool::new(false),
+            cursor: AtomicUsize::new(0),
+        };
+        Ok(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    fn build_logger(path: &Path, capacity: usize) -> (Arc<MmapFileSink>, Logger) {
+        let sink = Arc::new(
+            MmapFileSink::builder()
+                .path(path)
+                .capacity(capacity)
+                .formatter(Box::new(NoModFormatter::new()))
+                .build()
+                .unwrap(),
+        );
+        let logger = test_logger_builder()
+            .sink(sink.clone())
+            .level_filter(LevelFilter::All)
+            .build()
+            .unwrap();
+        (sink, logger)
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let path = TEST_LOGS_PATH.join("mmap_file_sink_write.log");
+        if path.exists() {
+            fs::remove_file(&path).unwrap();
+        }
+
+        let (sink, logger) = build_logger(&path, 64);
+        info!(logger: logger, "hello\n");
+        info!(logger: logger, "MmapFileSink\n");
+        logger.flush();
+
+        let content = fs::read(&path).unwrap();
+        assert_eq!(content.len(), 64);
+        assert_eq!(&content[..19], b"hello\nMmapFileSink\n");
+
+        drop(logger);
+        drop(sink);
+        assert_eq!(fs::read(&path).unwrap(), b"hello\nMmapFileSink\n");
+    }
+
+    #[test]
+    fn rotate_when_full() {
+        let path = TEST_LOGS_PATH.join("mmap_file_sink_rotate.log");
+        let rotated_path = RotatorFileSize::calc_file_path(&path, 1);
+        for path in [&path, &rotated_path] {
+            if path.exists() {
+                fs::remove_file(path).unwrap();
+            }
+        }
+
+        let (sink, logger) = build_logger(&path, 16);
+        info!(logger: logger, "0123456789\n");
+        info!(logger: logger, "abcdefghij\n");
+        assert!(sink
+            .log(&Record::new(Level::Info, "0123456789abcdefg"))
+            .is_err());
+        drop(logger);
+        drop(sink);
+
+        assert_eq!(fs::read(&rotated_path).unwrap(), b"0123456789\n");
+        assert_eq!(fs::read(&path).unwrap(), b"abcdefghij\n");
+    }
+
+    #[test]
+    fn retry_failed_rotation() {
+        let path = TEST_LOGS_PATH.join("mmap_file_sink_retry_rotation.log");
+        let rotated_path = RotatorFileSize::calc_file_path(&path, 1);
+        if path.exists() {
+            fs::remove_file(&path).unwrap();
+        }
+        if rotated_path.is_dir() {
+            fs::remove_dir_all(&rotated_path).unwrap();
+        } else if rotated_path.exists() {
+            fs::remove_file(&rotated_path).unwrap();
+        }
+
+        let (sink, logger) = build_logger(&path, 16);
+        sink.log(&Record::new(Level::Info, "0123456789\n")).unwrap();
+
+        // A non-empty directory in the way fails the rotation
+        fs::create_dir_all(rotated_path.join("blocker")).unwrap();
+        assert!(sink.log(&Record::new(Level::Info, "abcdefghij\n")).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"0123456789\n");
+
+        // The rotation is retried instead of truncating the finished file
+        fs::remove_dir_all(&rotated_path).unwrap();
+        sink.log(&Record::new(Level::Info, "abcdefghij\n")).unwrap();
+        drop(logger);
+        drop(sink);
+
+        assert_eq!(fs::read(&rotated_path).unwrap(), b"0123456789\n");
+        assert_eq!(fs::read(&path).unwrap(), b"abcdefghij\n");
+    }
+
+    #[test]
+    fn capacity_0() {
+        assert!(matches!(
+            MmapFileSink::builder()
+                .path(TEST_LOGS_PATH.join("mmap_file_sink_capacity_0.log"))
+                .capacity(0)
+                .build(),
+            Err(Error::InvalidArgument(InvalidArgumentError::MmapCapacity(
+                _
+            )))
+        ));
+    }
+}
@@ -47,43 +47,82 @@
 
 #[cfg(feature = "multi-thread")]
 pub(crate) mod async_sink;
+mod backoff;
+mod callback_sink;
 mod date_and_hour_rotating_file_sink;
+#[cfg(any(feature = "debug-http", all(doc, not(doctest))))]
+mod debug_http_sink;
+mod dup_filter_sink;
 mod file_sink;
+mod first_seen_sink;
+mod flush_policy;
+mod fmt_write_sink;
 mod helper;
 #[cfg(any(
     all(target_os = "linux", feature = "native", feature = "libsystemd"),
     all(doc, not(doctest))
 ))]
 mod journald_sink;
+mod level_router_sink;
+#[cfg(all(unix, any(feature = "mmap", all(doc, not(doctest)))))]
+mod mmap_file_sink;
+mod multi_format_sink;
+mod null_sink;
+mod retry_sink;
 mod rotating_file_sink;
+mod sharding_sink;
 mod std_stream_sink;
+mod tcp_sink;
 #[cfg(any(all(windows, feature = "native"), all(doc, not(doctest))))]
 mod win_debug_sink;
 mod write_sink;
 
 #[cfg(feature = "multi-thread")]
 pub use async_sink::*;
+pub use backoff::*;
+pub use callback_sink::*;
 pub use date_and_hour_rotating_file_sink::*;
+#[cfg(any(feature = "debug-http", all(doc, not(doctest))))]
+pub use debug_http_sink::*;
+pub use dup_filter_sink::*;
 pub use file_sink::*;
+pub use first_seen_sink::*;
+pub use flush_policy::*;
+pub use fmt_write_sink::*;
 #[cfg(any(
     all(target_os = "linux", feature = "native", feature = "libsystemd"),
     all(doc, not(doctest))
 ))]
 pub use journald_sink::*;
+pub use level_router_sink::*;
+#[cfg(all(unix, any(feature = "mmap", all(doc, not(doctest)))))]
+pub use mmap_file_sink::*;
+pub use multi_format_sink::*;
+pub use null_sink::*;
+pub use retry_sink::*;
 pub use rotating_file_sink::*;
+pub use sharding_sink::*;
 pub use std_stream_sink::*;
+pub use tcp_sink::*;
 #[cfg(any(all(windows, feature = "native"), all(doc, not(doctest))))]
 pub use win_debug_sink::*;
 pub use write_sink::*;
 
-use crate::{formatter::Formatter, sync::*, ErrorHandler, Level, LevelFilter, Record, Result};
+use std::{thread, time::Duration};
+
+use crate::{
+    formatter::Formatter, sync::*, ErrorHandler, Level, LevelFilter, Record, RecordOwned, Result,
+};
 
 /// A trait for sinks.
 pub trait Sink: Sync + Send {
     /// Determines if a log message with the specified level would be logged.
+    ///
+    /// The default implementation returns `false` while the sink is paused,
+    /// see [`Sink::pause`].
     #[must_use]
     fn should_log(&self, level: Level) -> bool {
-        self.level_filter().compare(level)
+        !self.is_paused() && self.level_filter().compare(level)
     }
 
     /// Logs a record.
@@ -92,6 +131,28 @@ pub trait Sink: Sync + Send {
     /// filter records.
     fn log(&self, record: &Record) -> Result<()>;
 
+    /// Logs a message that has already been formatted, e.g. by a relay that
+    /// received formatted text from elsewhere.
+    ///
+    /// The message is filtered by the level filter of the sink, and sinks that
+    /// write bytes to their targets (e.g. [`FileSink`]) write it verbatim,
+    /// without running their formatter. The message should contain the EOL if
+    /// one is desired.
+    ///
+    /// The default implementation logs a record with the message (converted
+    /// lossily to UTF-8) as payload, marked by [`Record::is_preformatted`]. The
+    /// built-in sinks write such records as-is instead of passing them to their
+    /// formatter, custom sinks that format records should check it and do the
+    /// same, or override this method.
+    fn log_preformatted(&self, bytes: &[u8], level: Level) -> Result<()> {
+        if !self.should_log(level) {
+            return Ok(());
+        }
+        let mut record = Record::new(level, String::from_utf8_lossy(bytes));
+        record.set_preformatted();
+        self.log(&record)
+    }
+
     /// Flushes any buffered records.
     fn flush(&self) -> Result<()>;
 
@@ -116,7 +177,190 @@ pub trait Sink: Sync + Send {
     ///
     /// [`Logger`]: crate::logger::Logger
     fn set_error_handler(&self, handler: Option<ErrorHandler>);
+
+    /// Removes and returns the records that have been accepted by the sink but
+    /// could not be written.
+    ///
+    /// This is useful during a controlled shutdown, to persist the records that
+    /// could not be delivered (e.g. while the network is down) somewhere else.
+    ///
+    /// Only sinks that buffer records implement this, the default
+    /// implementation returns an empty `Vec`.
+    #[must_use]
+    fn drain_pending(&self) -> Vec<RecordOwned> {
+        vec![]
+    }
+
+    /// Pauses the sink, records logged to it are dropped until
+    /// [`Sink::resume`] is called.
+    ///
+    /// This is useful to temporarily silence a target without rebuilding the
+    /// logger, e.g. while the file it writes to is being moved away. Flushing
+    /// is not affected, so records written before pausing can still be
+    /// flushed.
+    ///
+    /// All built-in sinks that have their own target support pausing. The
+    /// default implementation does nothing, in which case
+    /// [`Sink::is_paused`] keeps returning `false`.
+    fn pause(&self) {}
+
+    /// Resumes the sink paused by [`Sink::pause`].
+    ///
+    /// Records dropped while the sink was paused are not recovered.
+    fn resume(&self) {}
+
+    /// Determines if the sink is paused, see [`Sink::pause`].
+    #[must_use]
+    fn is_paused(&self) -> bool {
+        false
+    }
 }
 
 /// A container for [`Sink`]s.
 pub type Sinks = Vec<Arc<dyn Sink>>;
+
+/// A weak handle to a [`Sink`], which does not keep the sink alive.
+///
+/// Background threads and decorators that only need to access a sink while it's
+/// in use should hold this instead of an [`Arc`], so that dropping the last
+/// strong reference tears the sink down. It's obtained by [`Arc::downgrade`],
+/// and the sink is accessed by [`Weak::upgrade`].
+pub type WeakSink = Weak<dyn Sink>;
+
+/// Flushes a sink periodically on a background thread.
+///
+/// The thread only holds a [`WeakSink`], it exits at the next period once all
+/// strong references to the sink have been dropped. The returned handle can be
+/// used to wait for that.
+///
+/// Errors that occur while flushing are printed to `stderr` and then ignored.
+///
+/// # Panics
+///
+/// Panics if `interval` is zero.
+pub fn flush_periodically(sink: &Arc<dyn Sink>, interval: Duration) -> thread::JoinHandle<()> {
+    if interval.is_zero() {
+        panic!("flush_periodically: the interval cannot be zero")
+    }
+
+    let sink: WeakSink = Arc::downgrade(sink);
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+
+        match sink.upgrade() {
+            Some(sink) => {
+                if let Err(err) = sink.flush() {
+                    crate::default_error_handler("flush_periodically", err);
+                }
+            }
+            None => return,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    #[test]
+    fn flush_periodically_exits_on_drop() {
+        let sink = Arc::new(CounterSink::new());
+        let handle =
+            flush_periodically(&(sink.clone() as Arc<dyn Sink>), Duration::from_millis(10));
+
+        thread::sleep(Duration::from_millis(100));
+        assert!(sink.flush_count() > 0);
+
+        let exited = Arc::new(AtomicBool::new(false));
+        let watcher = {
+            let exited = exited.clone();
+            thread::spawn(move || {
+                handle.join().unwrap();
+                exited.store(true, Ordering::SeqCst);
+            })
+        };
+
+        // The background thread doesn't keep the sink alive
+        drop(sink);
+
+        for _ in 0..100 {
+            if exited.load(Ordering::SeqCst) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(exited.load(Ordering::SeqCst));
+        watcher.join().unwrap();
+    }
+
+    #[test]
+    fn pause_resume() {
+        let sink = Arc::new(
+            WriteSink::builder()
+                .target(Vec::new())
+                .formatter(Box::new(NoModFormatter::new()))
+                .build()
+                .unwrap(),
+        );
+        let logger = test_logger_builder().sink(sink.clone()).build().unwrap();
+
+        sink.pause();
+        assert!(sink.is_paused());
+        info!(logger: logger, "dropped");
+        assert!(sink.clone_target().is_empty());
+
+        sink.resume();
+        assert!(!sink.is_paused());
+        info!(logger: logger, "captured");
+        assert_eq!(String::from_utf8(sink.clone_target()).unwrap(), "captured");
+    }
+
+    #[test]
+    fn log_preformatted_custom_sink() {
+        // A sink not built on the common implementation, which formats records
+        // itself.
+        struct BracketSink {
+            written: Mutex<String>,
+        }
+
+        impl Sink for BracketSink {
+            fn log(&self, record: &Record) -> Result<()> {
+                let mut written = self.written.lock_expect();
+                if record.is_preformatted() {
+                    written.push_str(record.payload());
+                } else {
+                    written.push_str(&format!("[{}] {}\n", record.level(), record.payload()));
+                }
+                Ok(())
+            }
+
+            fn flush(&self) -> Result<()> {
+                Ok(())
+            }
+
+            fn level_filter(&self) -> LevelFilter {
+                LevelFilter::MoreSevereEqual(Level::Info)
+            }
+
+            fn set_level_filter(&self, _level_filter: LevelFilter) {}
+
+            fn set_formatter(&self, _formatter: Box<dyn Formatter>) {}
+
+            fn set_error_handler(&self, _handler: Option<ErrorHandler>) {}
+        }
+
+        let sink = BracketSink {
+            written: Mutex::new(String::new()),
+        };
+        sink.log(&Record::new(Level::Info, "formatted")).unwrap();
+        sink.log_preformatted(b"[relayed] [info] verbatim\n", Level::Info)
+            .unwrap();
+        sink.log_preformatted(b"filtered\n", Level::Debug).unwrap();
+
+        assert_eq!(
+            *sink.written.lock_expect(),
+            "[info] formatted\n[relayed] [info] verbatim\n"
+        );
+    }
+}
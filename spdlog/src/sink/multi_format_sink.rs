@@ -0,0 +1,216 @@
+use crate::{
+    formatter::Formatter,
+    sink::{helper, Sink, Sinks},
+    sync::*,
+    ErrorHandler, LevelFilter, Record, Result,
+};
+
+/// A [combined sink] that forwards each record to all of its internal sinks,
+/// each formatting it with its own formatter.
+///
+/// This is useful for relays writing the same record stream to different
+/// backends in different encodings, e.g. JSON to a collector and plain text to
+/// a file. The record is formatted once per internal sink, by the formatter
+/// paired with it in [`MultiFormatSinkBuilder::sink`].
+///
+/// Since the formatters are fixed per destination, [`Sink::set_formatter`] on
+/// this sink has no effect.
+///
+/// [combined sink]: index.html#combined-sink
+pub struct MultiFormatSink {
+    level_filter: Atomic<LevelFilter>,
+    sinks: Sinks,
+}
+
+impl MultiFormatSink {
+    /// Constructs a builder of `MultiFormatSink`.
+    #[must_use]
+    pub fn builder() -> MultiFormatSinkBuilder {
+        MultiFormatSinkBuilder {
+            level_filter: helper::SINK_DEFAULT_LEVEL_FILTER,
+            sinks: vec![],
+        }
+    }
+
+    /// Gets a reference to internal sinks in the combined sink.
+    #[must_use]
+    pub fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &self.sinks
+    }
+}
+
+impl Sink for MultiFormatSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            let res = sink.log(record);
+            if result.is_ok() {
+                result = res;
+            }
+        }
+        result
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            let res = sink.flush();
+            if result.is_ok() {
+                result = res;
+            }
+        }
+        result
+    }
+
+    /// For [`MultiFormatSink`], the function has no effect, the internal sinks
+    /// keep the formatters they were paired with.
+    fn set_formatter(&self, _formatter: Box<dyn Formatter>) {}
+
+    /// For [`MultiFormatSink`], the function performs the same call to all
+    /// internal sinks.
+    fn set_error_handler(&self, handler: Option<ErrorHandler>) {
+        for sink in &self.sinks {
+            sink.set_error_handler(handler)
+        }
+    }
+
+    helper::common_impl! {
+        @SinkCustom {
+            level_filter: level_filter,
+            formatter: None,
+            error_handler: None,
+        }
+    }
+}
+
+/// The builder of [`MultiFormatSink`].
+///
+/// # Examples
+///
+/// - Writing JSON to one file and plain text to another.
+///
+///   ```no_run
+///   use std::sync::Arc;
+///
+///   use spdlog::{
+///       formatter::{FullFormatter, JsonFormatter},
+///       prelude::*,
+///       sink::{FileSink, MultiFormatSink},
+///   };
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let json = Arc::new(FileSink::builder().path("app.json").build()?);
+///   let text = Arc::new(FileSink::builder().path("app.log").build()?);
+///   let sink: MultiFormatSink = MultiFormatSink::builder()
+///       .sink(json, Box::new(JsonFormatter::new()))
+///       .sink(text, Box::new(FullFormatter::new()))
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct MultiFormatSinkBuilder {
+    level_filter: LevelFilter,
+    sinks: Vec<(Arc<dyn Sink>, Box<dyn Formatter>)>,
+}
+
+impl MultiFormatSinkBuilder {
+    /// Add a [`Sink`] as a destination, paired with the formatter used to
+    /// format records for it.
+    ///
+    /// The formatter is set to the sink when the combined sink is built.
+    #[must_use]
+    pub fn sink(mut self, sink: Arc<dyn Sink>, formatter: Box<dyn Formatter>) -> Self {
+        self.sinks.push((sink, formatter));
+        self
+    }
+
+    /// Builds a [`MultiFormatSink`].
+    pub fn build(self) -> Result<MultiFormatSink> {
+        let sinks = self
+            .sinks
+            .into_iter()
+            .map(|(sink, formatter)| {
+                sink.set_formatter(formatter);
+                sink
+            })
+            .collect();
+
+        Ok(MultiFormatSink {
+            level_filter: Atomic::new(self.level_filter),
+            sinks,
+        })
+    }
+
+    helper::common_impl!(@SinkBuilderCustom {
+        level_filter: level_filter,
+        formatter: None,
+        error_handler: None,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        formatter::{FmtExtraInfo, FullFormatter},
+        prelude::*,
+        sink::WriteSink,
+        test_utils::*,
+        StringBuf,
+    };
+
+    #[derive(Clone)]
+    struct UpperFormatter;
+
+    impl Formatter for UpperFormatter {
+        fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+            dest.push_str(&record.payload().to_uppercase());
+            Ok(FmtExtraInfo::new())
+        }
+
+        fn clone_box(&self) -> Box<dyn Formatter> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn per_destination_format() {
+        let plain = Arc::new(
+            WriteSink::builder()
+                .target(Vec::<u8>::new())
+                .build()
+                .unwrap(),
+        );
+        let upper = Arc::new(
+            WriteSink::builder()
+                .target(Vec::<u8>::new())
+                .build()
+                .unwrap(),
+        );
+        let sink = Arc::new(
+            MultiFormatSink::builder()
+                .sink(plain.clone(), Box::new(NoModFormatter::new()))
+                .sink(upper.clone(), Box::new(UpperFormatter))
+                .build()
+                .unwrap(),
+        );
+        let logger = test_logger_builder().sink(sink.clone()).build().unwrap();
+
+        info!(logger: logger, "hello");
+        // The formatters of the destinations are kept.
+        sink.set_formatter(Box::new(FullFormatter::new()));
+        info!(logger: logger, "world");
+
+        assert_eq!(
+            String::from_utf8(plain.clone_target()).unwrap(),
+            "helloworld"
+        );
+        assert_eq!(
+            String::from_utf8(upper.clone_target()).unwrap(),
+            "HELLOWORLD"
+        );
+    }
+}
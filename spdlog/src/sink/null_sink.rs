@@ -0,0 +1,166 @@
+use crate::{
+    sink::{helper, Sink},
+    sync::*,
+    Record, Result, StringBuf,
+};
+
+/// A sink that formats log messages and then discards them.
+///
+/// `NullSink` performs no I/O, so it is mainly useful for measuring the cost
+/// of formatting and the logging pipeline itself, e.g. as a baseline in
+/// benchmarks.
+///
+/// Unless disabled via [`NullSinkBuilder::counting`], the numbers of records
+/// and formatted bytes passed through the sink are counted and can be queried
+/// with [`NullSink::record_count`] and [`NullSink::byte_count`].
+pub struct NullSink {
+    common_impl: helper::CommonImpl,
+    counting: bool,
+    record_counter: AtomicUsize,
+    byte_counter: AtomicUsize,
+}
+
+impl NullSink {
+    /// Constructs a builder of `NullSink`.
+    #[must_use]
+    pub fn builder() -> NullSinkBuilder {
+        NullSinkBuilder {
+            common_builder_impl: helper::CommonBuilderImpl::new(),
+            counting: true,
+        }
+    }
+
+    /// Gets the number of records that have been formatted and discarded.
+    ///
+    /// Always returns `0` if counting is disabled.
+    #[must_use]
+    pub fn record_count(&self) -> usize {
+        self.record_counter.load(Ordering::Relaxed)
+    }
+
+    /// Gets the total number of formatted bytes that have been discarded.
+    ///
+    /// Always returns `0` if counting is disabled.
+    #[must_use]
+    pub fn byte_count(&self) -> usize {
+        self.byte_counter.load(Ordering::Relaxed)
+    }
+
+    /// Resets the record and byte counters to `0`.
+    pub fn reset_counters(&self) {
+        self.record_counter.store(0, Ordering::Relaxed);
+        self.byte_counter.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Sink for NullSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let mut string_buf = StringBuf::new();
+        self.common_impl.format(record, &mut string_buf)?;
+
+        if self.counting {
+            self.record_counter.fetch_add(1, Ordering::Relaxed);
+            self.byte_counter
+                .fetch_add(string_buf.len(), Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    helper::common_impl!(@Sink: common_impl);
+}
+
+/// The builder of [`NullSink`].
+///
+/// # Examples
+///
+/// - Building a [`NullSink`].
+///
+///   ```
+///   use spdlog::{prelude::*, sink::NullSink};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: NullSink = NullSink::builder()
+///       // .counting(false) // optional
+///       // .level_filter(LevelFilter::MoreSevere(Level::Info)) // optional
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct NullSinkBuilder {
+    common_builder_impl: helper::CommonBuilderImpl,
+    counting: bool,
+}
+
+impl NullSinkBuilder {
+    /// Specifies whether to count records and formatted bytes.
+    ///
+    /// This parameter is **optional**, and defaults to `true`.
+    #[must_use]
+    pub fn counting(mut self, counting: bool) -> Self {
+        self.counting = counting;
+        self
+    }
+
+    helper::common_impl!(@SinkBuilder: common_builder_impl);
+
+    /// Builds a [`NullSink`].
+    pub fn build(self) -> Result<NullSink> {
+        let sink = NullSink {
+            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
+            counting: self.counting,
+            record_counter: AtomicUsize::new(0),
+            byte_counter: AtomicUsize::new(0),
+        };
+        Ok(sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    #[test]
+    fn counters() {
+        let sink = Arc::new(NullSink::builder().build().unwrap());
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+        let logger = test_logger_builder()
+            .sink(sink.clone())
+            .level_filter(LevelFilter::All)
+            .build()
+            .unwrap();
+
+        assert_eq!(sink.record_count(), 0);
+        assert_eq!(sink.byte_count(), 0);
+
+        info!(logger: logger, "hello");
+        info!(logger: logger, "NullSink");
+        logger.flush();
+
+        assert_eq!(sink.record_count(), 2);
+        assert_eq!(sink.byte_count(), "hello".len() + "NullSink".len());
+
+        sink.reset_counters();
+        assert_eq!(sink.record_count(), 0);
+        assert_eq!(sink.byte_count(), 0);
+    }
+
+    #[test]
+    fn counting_disabled() {
+        let sink = Arc::new(NullSink::builder().counting(false).build().unwrap());
+        let logger = test_logger_builder().sink(sink.clone()).build().unwrap();
+
+        info!(logger: logger, "hello NullSink");
+
+        assert_eq!(sink.record_count(), 0);
+        assert_eq!(sink.byte_count(), 0);
+    }
+}
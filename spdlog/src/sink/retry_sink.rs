@@ -0,0 +1,405 @@
+use std::{convert::Infallible, thread};
+
+use crate::{
+    formatter::Formatter,
+    sink::{helper, Backoff, Sink},
+    sync::*,
+    ErrorHandler, LevelFilter, Record, Result,
+};
+
+/// A [combined sink] that retries logging a record to its internal sink when
+/// it fails with a retryable error.
+///
+/// When the internal sink returns an error classified as retryable by
+/// [`Error::is_retryable`], the record is logged again, up to `max_retries`
+/// times, sleeping between attempts according to the [`Backoff`] policy. If
+/// all attempts fail, the record is routed to the fallback sink if one is
+/// configured, otherwise the last error is returned. Non-retryable errors are
+/// returned immediately.
+///
+/// Sinks that keep a failed record to write it later, e.g. [`TcpSink`], don't
+/// return an error for it, so wrapping them does not write the record twice.
+///
+/// Since retries sleep on the thread calling `log`, consider wrapping this sink
+/// in an [`AsyncPoolSink`] to keep the logging threads responsive.
+///
+/// [combined sink]: index.html#combined-sink
+/// [`Error::is_retryable`]: crate::Error::is_retryable
+/// [`AsyncPoolSink`]: crate::sink::AsyncPoolSink
+/// [`TcpSink`]: crate::sink::TcpSink
+pub struct RetrySink {
+    level_filter: Atomic<LevelFilter>,
+    sink: Arc<dyn Sink>,
+    fallback: Option<Arc<dyn Sink>>,
+    max_retries: usize,
+    backoff: Backoff,
+}
+
+impl RetrySink {
+    /// Constructs a builder of `RetrySink`.
+    #[must_use]
+    pub fn builder() -> RetrySinkBuilder<()> {
+        RetrySinkBuilder {
+            level_filter: helper::SINK_DEFAULT_LEVEL_FILTER,
+            sink: (),
+            fallback: None,
+            max_retries: 3,
+            backoff: Backoff::default(),
+        }
+    }
+
+    /// Gets a reference to the internal sink.
+    #[must_use]
+    pub fn sink(&self) -> &Arc<dyn Sink> {
+        &self.sink
+    }
+
+    /// Gets a reference to the fallback sink, if any.
+    #[must_use]
+    pub fn fallback(&self) -> Option<&Arc<dyn Sink>> {
+        self.fallback.as_ref()
+    }
+
+    fn retry<F>(&self, mut op: F) -> Result<()>
+    where
+        F: FnMut() -> Result<()>,
+    {
+        let mut delays = self.backoff.delays();
+        let mut retries = 0;
+        loop {
+            match op() {
+                Err(err) if err.is_retryable() && retries < self.max_retries => {
+                    retries += 1;
+                    thread::sleep(delays.next().unwrap_or_default());
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+impl Sink for RetrySink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        match (self.retry(|| self.sink.log(record)), &self.fallback) {
+            (Err(err), Some(fallback)) if err.is_retryable() => fallback.log(record),
+            (res, _) => res,
+        }
+    }
+
+    fn flush(&self) -> Result<()> {
+        let res = self.retry(|| self.sink.flush());
+        match &self.fallback {
+            Some(fallback) => {
+                let fallback_res = fallback.flush();
+                res.and(fallback_res)
+            }
+            None => res,
+        }
+    }
+
+    /// For [`RetrySink`], the function performs the same call to the internal
+    /// sink and the fallback sink.
+    fn set_formatter(&self, formatter: Box<dyn Formatter>) {
+        if let Some(fallback) = &self.fallback {
+            fallback.set_formatter(formatter.clone_box());
+        }
+        self.sink.set_formatter(formatter);
+    }
+
+    /// For [`RetrySink`], the function performs the same call to the internal
+    /// sink and the fallback sink.
+    fn set_error_handler(&self, handler: Option<ErrorHandler>) {
+        self.sink.set_error_handler(handler);
+        if let Some(fallback) = &self.fallback {
+            fallback.set_error_handler(handler);
+        }
+    }
+
+    helper::common_impl! {
+        @SinkCustom {
+            level_filter: level_filter,
+            formatter: None,
+            error_handler: None,
+        }
+    }
+}
+
+/// The builder of [`RetrySink`].
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+/// # Examples
+///
+/// - Retrying a file sink on a network share, and writing to a local file if
+///   the share stays unreachable.
+///
+///   ```no_run
+///   use std::{sync::Arc, time::Duration};
+///
+///   use spdlog::sink::{Backoff, FileSink, RetrySink};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let remote = Arc::new(FileSink::builder().path("/mnt/share/app.log").build()?);
+///   let local = Arc::new(FileSink::builder().path("fallback.log").build()?);
+///   let sink: RetrySink = RetrySink::builder()
+///       .sink(remote) // required
+///       .max_retries(5) // optional, defaults to `3`
+///       .backoff(Backoff::builder().max(Duration::from_secs(1)).build()?) // optional
+///       .fallback(local) // optional
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct RetrySinkBuilder<ArgSink> {
+    level_filter: LevelFilter,
+    sink: ArgSink,
+    fallback: Option<Arc<dyn Sink>>,
+    max_retries: usize,
+    backoff: Backoff,
+}
+
+impl<ArgSink> RetrySinkBuilder<ArgSink> {
+    /// Specifies the internal sink that records are logged to.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn sink(self, sink: Arc<dyn Sink>) -> RetrySinkBuilder<Arc<dyn Sink>> {
+        RetrySinkBuilder {
+            level_filter: self.level_filter,
+            sink,
+            fallback: self.fallback,
+            max_retries: self.max_retries,
+            backoff: self.backoff,
+        }
+    }
+
+    /// Specifies a sink that records are routed to when all attempts failed
+    /// with retryable errors.
+    ///
+    /// This parameter is **optional**, and defaults to no fallback, the last
+    /// error is returned instead.
+    #[must_use]
+    pub fn fallback(mut self, fallback: Arc<dyn Sink>) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Specifies the maximum number of retries after the first attempt.
+    ///
+    /// This parameter is **optional**, and defaults to `3`.
+    #[must_use]
+    pub fn max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Specifies the policy of sleeping between attempts.
+    ///
+    /// This parameter is **optional**, and defaults to [`Backoff::default`].
+    #[must_use]
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    helper::common_impl!(@SinkBuilderCustom {
+        level_filter: level_filter,
+        formatter: None,
+        error_handler: None,
+    });
+}
+
+impl RetrySinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `sink`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl RetrySinkBuilder<Arc<dyn Sink>> {
+    /// Builds a [`RetrySink`].
+    pub fn build(self) -> Result<RetrySink> {
+        Ok(RetrySink {
+            level_filter: Atomic::new(self.level_filter),
+            sink: self.sink,
+            fallback: self.fallback,
+            max_retries: self.max_retries,
+            backoff: self.backoff,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        net::{Ipv4Addr, TcpListener},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{prelude::*, sink::TcpSink, test_utils::*, Error};
+
+    // Fails with the given error for the first `failures` records.
+    struct FlakySink {
+        inner: CounterSink,
+        failures: AtomicUsize,
+        error_kind: io::ErrorKind,
+    }
+
+    impl FlakySink {
+        #[must_use]
+        fn new(failures: usize, error_kind: io::ErrorKind) -> Self {
+            Self {
+                inner: CounterSink::new(),
+                failures: AtomicUsize::new(failures),
+                error_kind,
+            }
+        }
+    }
+
+    impl Sink for FlakySink {
+        fn log(&self, record: &Record) -> Result<()> {
+            let failed = self
+                .failures
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |failures| {
+                    failures.checked_sub(1)
+                })
+                .is_ok();
+            if failed {
+                return Err(Error::WriteRecord(io::Error::from(self.error_kind)));
+            }
+            self.inner.log(record)
+        }
+
+        fn flush(&self) -> Result<()> {
+            self.inner.flush()
+        }
+
+        fn level_filter(&self) -> LevelFilter {
+            self.inner.level_filter()
+        }
+
+        fn set_level_filter(&self, level_filter: LevelFilter) {
+            self.inner.set_level_filter(level_filter)
+        }
+
+        fn set_formatter(&self, formatter: Box<dyn Formatter>) {
+            self.inner.set_formatter(formatter)
+        }
+
+        fn set_error_handler(&self, handler: Option<ErrorHandler>) {
+            self.inner.set_error_handler(handler)
+        }
+    }
+
+    #[must_use]
+    fn no_delay() -> Backoff {
+        Backoff::builder()
+            .initial(Duration::ZERO)
+            .max(Duration::ZERO)
+            .multiplier(1.0)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn retry_until_success() {
+        let inner = Arc::new(FlakySink::new(2, io::ErrorKind::ConnectionReset));
+        let sink = RetrySink::builder()
+            .sink(inner.clone())
+            .max_retries(2)
+            .backoff(no_delay())
+            .build()
+            .unwrap();
+
+        sink.log(&Record::new(Level::Info, "hello")).unwrap();
+        assert_eq!(inner.inner.payloads(), vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn exhausted_and_fallback() {
+        let inner = Arc::new(FlakySink::new(3, io::ErrorKind::TimedOut));
+        let sink = RetrySink::builder()
+            .sink(inner.clone())
+            .max_retries(2)
+            .backoff(no_delay())
+            .build()
+            .unwrap();
+        assert!(matches!(
+            sink.log(&Record::new(Level::Info, "hello")),
+            Err(Error::WriteRecord(_))
+        ));
+        assert_eq!(inner.inner.log_count(), 0);
+
+        let inner = Arc::new(FlakySink::new(3, io::ErrorKind::TimedOut));
+        let fallback = Arc::new(CounterSink::new());
+        let sink = RetrySink::builder()
+            .sink(inner.clone())
+            .max_retries(2)
+            .backoff(no_delay())
+            .fallback(fallback.clone())
+            .build()
+            .unwrap();
+        sink.log(&Record::new(Level::Info, "hello")).unwrap();
+        assert_eq!(inner.inner.log_count(), 0);
+        assert_eq!(fallback.payloads(), vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn tcp_sink_buffers_once() {
+        // Find a free port, and then stop listening on it.
+        let address = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let inner = Arc::new(
+            TcpSink::builder()
+                .address(address)
+                .backoff(no_delay())
+                .formatter(Box::new(NoModFormatter::new()))
+                .error_handler(|_| {})
+                .build()
+                .unwrap(),
+        );
+        let fallback = Arc::new(CounterSink::new());
+        let sink = RetrySink::builder()
+            .sink(inner.clone())
+            .max_retries(1)
+            .backoff(no_delay())
+            .fallback(fallback.clone())
+            .build()
+            .unwrap();
+
+        sink.log(&Record::new(Level::Info, "hello")).unwrap();
+        assert_eq!(inner.buffered_bytes(), 5);
+        sink.log(&Record::new(Level::Info, "world")).unwrap();
+        assert_eq!(inner.buffered_bytes(), 10);
+        assert_eq!(fallback.log_count(), 0);
+    }
+
+    #[test]
+    fn non_retryable() {
+        let inner = Arc::new(FlakySink::new(1, io::ErrorKind::PermissionDenied));
+        let fallback = Arc::new(CounterSink::new());
+        let sink = RetrySink::builder()
+            .sink(inner.clone())
+            .backoff(no_delay())
+            .fallback(fallback.clone())
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            sink.log(&Record::new(Level::Info, "hello")),
+            Err(Error::WriteRecord(_))
+        ));
+        assert_eq!(inner.inner.log_count(), 0);
+        assert_eq!(fallback.log_count(), 0);
+
+        sink.log(&Record::new(Level::Info, "world")).unwrap();
+        assert_eq!(inner.inner.payloads(), vec!["world".to_string()]);
+    }
+}
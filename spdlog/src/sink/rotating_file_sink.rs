@@ -1,22 +1,27 @@
 //! Provides a rotating file sink.
 
 use std::{
-    collections::LinkedList,
+    borrow::Cow,
+    collections::{LinkedList, VecDeque},
     convert::Infallible,
     ffi::OsString,
+    fmt::Write as _,
     fs::{self, File},
     hash::Hash,
     io::{BufWriter, Write},
+    mem,
     path::{Path, PathBuf},
     result::Result as StdResult,
+    sync::mpsc::{self, Receiver, SyncSender},
     time::{Duration, SystemTime},
 };
 
 use chrono::prelude::*;
 
 use crate::{
+    clock::{self, Clock},
     error::InvalidArgumentError,
-    sink::{helper, Sink},
+    sink::{file_sink::UTF8_BOM, helper, FlushPolicy, FlushPolicyState, Sink},
     sync::*,
     utils, Error, Record, Result, StringBuf,
 };
@@ -53,7 +58,7 @@ pub enum RotationPolicy {
         /// Max file size (in bytes). Range: (0, u64::MAX].
         u64,
     ),
-    /// Rotates daily at the given time point.
+    /// Rotates daily at the given time point of the local time zone.
     Daily {
         /// Hour of the time point. Range: [0, 23].
         hour: u32,
@@ -64,6 +69,27 @@ pub enum RotationPolicy {
     Hourly,
 }
 
+/// Policies for [`RotatingFileSink`] when a rotation fails to open the new
+/// file.
+///
+/// For the [`RotationPolicy::FileSize`] rotation policy, moving the existing
+/// files to their new indexes is also considered part of opening the new file.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum OpenErrorPolicy {
+    /// Returns the error, the record being logged is lost.
+    ///
+    /// The rotation is retried on the next record.
+    FailFast,
+    /// Keeps writing records to the current file, and retries the rotation on
+    /// the next record.
+    ///
+    /// If the current file cannot be written either, the error is returned.
+    KeepLast,
+    /// Discards records silently until a rotation succeeds, which is retried
+    /// on each record.
+    Drop,
+}
+
 trait Rotator {
     #[allow(clippy::ptr_arg)]
     fn log(&self, record: &Record, string_buf: &StringBuf) -> Result<()>;
@@ -78,23 +104,47 @@ enum RotatorKind {
     TimePoint(RotatorTimePoint),
 }
 
-struct RotatorFileSize {
+pub(crate) struct RotatorFileSize {
     base_path: PathBuf,
     max_size: u64,
     max_files: usize,
-    inner: SpinMutex<RotatorFileSizeInner>,
+    rename_on_close: bool,
+    open_error_policy: OpenErrorPolicy,
+    manifest: Option<Manifest>,
+    write_bom: bool,
+    marker: Option<Marker>,
+    rotation_events: Option<SyncSender<RotationEvent>>,
+    retention_manager: Option<Arc<RetentionManager>>,
+    // The critical section performs file I/O (and rotations), which may take a
+    // while, so a blocking mutex is used rather than a spin one, threads waiting
+    // for it under contention are parked instead of burning CPU.
+    inner: Mutex<RotatorFileSizeInner>,
 }
 
 struct RotatorFileSizeInner {
     file: Option<BufWriter<File>>,
     current_size: u64,
+    record_times: RecordTimeRange,
+    // The time ranges of the rotated files written by the sink, the element at
+    // index `i` is of the file at index `i + 1`.
+    rotated_record_times: VecDeque<RecordTimeRange>,
 }
 
 struct RotatorTimePoint {
     base_path: PathBuf,
     time_point: TimePoint,
+    twelve_hour_clock: bool,
     max_files: usize,
-    inner: SpinMutex<RotatorTimePointInner>,
+    rename_on_close: bool,
+    open_error_policy: OpenErrorPolicy,
+    manifest: Option<Manifest>,
+    write_bom: bool,
+    marker: Option<Marker>,
+    rotation_events: Option<SyncSender<RotationEvent>>,
+    retention_manager: Option<Arc<RetentionManager>>,
+    clock: Arc<dyn Clock>,
+    // See `RotatorFileSize::inner`.
+    inner: Mutex<RotatorTimePointInner>,
 }
 
 #[derive(Copy, Clone)]
@@ -105,10 +155,199 @@ enum TimePoint {
 
 struct RotatorTimePointInner {
     file: BufWriter<File>,
+    // The final path of the current file, even if it's written under the partial
+    // path.
+    file_path: PathBuf,
+    file_size: u64,
+    record_times: RecordTimeRange,
     rotation_time_point: SystemTime,
     file_paths: Option<LinkedList<PathBuf>>,
 }
 
+// The time range of records written by the sink into the current file.
+#[derive(Default)]
+struct RecordTimeRange {
+    first: Option<SystemTime>,
+    last: Option<SystemTime>,
+}
+
+// A sidecar file next to the base path, listing each rotated log file as one
+// JSON object per line.
+//
+// The file is opened on each write rather than kept open, since rotations are
+// rare and the file may be replaced by `Manifest::rewrite`.
+struct Manifest {
+    path: PathBuf,
+    sync: bool,
+}
+
+/// An event of a rotation performed by [`RotatingFileSink`].
+///
+/// See [`RotatingFileSinkBuilder::rotation_events`].
+#[derive(Clone, Debug)]
+pub struct RotationEvent {
+    closed: PathBuf,
+    opened: PathBuf,
+    at: SystemTime,
+}
+
+impl RotationEvent {
+    /// Gets the final path of the file closed by the rotation.
+    ///
+    /// For the [`RotationPolicy::FileSize`] rotation policy, it's the path the
+    /// file was moved to (i.e. with index `1`), or the base path if the file
+    /// was discarded since [`max_files`] is less than `2`.
+    ///
+    /// [`max_files`]: RotatingFileSinkBuilder::max_files
+    #[must_use]
+    pub fn closed(&self) -> &Path {
+        &self.closed
+    }
+
+    /// Gets the final path of the file opened by the rotation.
+    ///
+    /// For the [`RotationPolicy::FileSize`] rotation policy, it's always the
+    /// base path.
+    #[must_use]
+    pub fn opened(&self) -> &Path {
+        &self.opened
+    }
+
+    /// Gets the time of the record that triggered the rotation.
+    #[must_use]
+    pub fn at(&self) -> SystemTime {
+        self.at
+    }
+}
+
+// Sends the event without blocking, it's dropped if the channel is full or the
+// receiver is gone.
+fn send_rotation_event(
+    sender: &Option<SyncSender<RotationEvent>>,
+    event: impl FnOnce() -> RotationEvent,
+) {
+    if let Some(sender) = sender {
+        let _ = sender.try_send(event());
+    }
+}
+
+/// A cleanup budget shared by multiple [`RotatingFileSink`]s.
+///
+/// Each sink built with [`RotatingFileSinkBuilder::retention_manager`]
+/// registers the files it rotates with the manager. When the total number of
+/// rotated files across all the sinks exceeds the budget, the oldest ones are
+/// deleted, regardless of which sink they belong to. This is useful when
+/// several sinks write to the same directory, and the disk usage should be
+/// bounded as a whole rather than per sink.
+///
+/// The budget applies in addition to the [`max_files`] of each sink. Only
+/// files rotated while the manager is alive are counted, i.e. files left over
+/// from a previous run and the files currently being written are not.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use spdlog::sink::{RetentionManager, RotatingFileSink, RotationPolicy};
+///
+/// # fn main() -> Result<(), spdlog::Error> {
+/// let manager = Arc::new(RetentionManager::new(10));
+/// let app: RotatingFileSink = RotatingFileSink::builder()
+///     .base_path("/path/to/logs/app.log")
+///     .rotation_policy(RotationPolicy::Hourly)
+///     .retention_manager(manager.clone())
+///     .build()?;
+/// let access: RotatingFileSink = RotatingFileSink::builder()
+///     .base_path("/path/to/logs/access.log")
+///     .rotation_policy(RotationPolicy::FileSize(1024 * 1024))
+///     .max_files(5)
+///     .retention_manager(manager)
+///     .build()?;
+/// # Ok(()) }
+/// ```
+///
+/// [`max_files`]: RotatingFileSinkBuilder::max_files
+pub struct RetentionManager {
+    max_files: usize,
+    // Rotated files of all the sinks, the oldest first.
+    files: Mutex<VecDeque<PathBuf>>,
+}
+
+impl RetentionManager {
+    /// Constructs a `RetentionManager` keeping at most `max_files` rotated
+    /// files in total.
+    ///
+    /// Pass `0` for no limit.
+    #[must_use]
+    pub fn new(max_files: usize) -> Self {
+        Self {
+            max_files,
+            files: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Gets the maximum number of rotated files in total.
+    #[must_use]
+    pub fn max_files(&self) -> usize {
+        self.max_files
+    }
+
+    /// Gets the number of rotated files currently counted against the budget.
+    #[must_use]
+    pub fn retained_files(&self) -> usize {
+        self.files.lock_expect().len()
+    }
+
+    // Rotators keep the manager locked while moving their files, so that
+    // another sink cannot delete a file by an outdated path meanwhile.
+    fn lock(&self) -> RetainedFiles<'_> {
+        RetainedFiles {
+            max_files: self.max_files,
+            files: self.files.lock_expect(),
+        }
+    }
+}
+
+struct RetainedFiles<'a> {
+    max_files: usize,
+    files: MutexGuard<'a, VecDeque<PathBuf>>,
+}
+
+impl RetainedFiles<'_> {
+    // Counts a newly rotated file, and deletes the oldest files if the budget is
+    // exceeded.
+    fn retain(&mut self, path: PathBuf) -> Result<()> {
+        self.removed(&path);
+        self.files.push_back(path);
+
+        while self.max_files > 0 && self.files.len() > self.max_files {
+            let oldest = self.files.pop_front().unwrap();
+            if oldest.exists() {
+                fs::remove_file(oldest).map_err(Error::RemoveFile)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn renamed(&mut self, from: &Path, to: &Path) {
+        if let Some(file) = self.files.iter_mut().find(|file| *file == from) {
+            *file = to.to_owned();
+        }
+    }
+
+    fn removed(&mut self, path: &Path) {
+        self.files.retain(|file| file != path);
+    }
+}
+
+// A line written into the log file when the sink starts and after each
+// rotation, see `RotatingFileSinkBuilder::marker`.
+struct Marker {
+    template: String,
+    eol: String,
+}
+
 /// A sink with a collection of files as the target, rotating according to the
 /// rotation policy.
 ///
@@ -161,6 +400,7 @@ struct RotatorTimePointInner {
 pub struct RotatingFileSink {
     common_impl: helper::CommonImpl,
     rotator: RotatorKind,
+    flush_policy: FlushPolicyState,
 }
 
 /// The builder of [`RotatingFileSink`].
@@ -178,6 +418,7 @@ pub struct RotatingFileSink {
 ///       .rotation_policy(RotationPolicy::Hourly) // required
 ///       // .max_files(100) // optional, defaults to `0` for no limit
 ///       // .rotate_on_open(true) // optional, defaults to `false`
+///       // .flush_policy(FlushPolicy::EveryN(100)) // optional, defaults to `FlushPolicy::Never`
 ///       .build()?;
 ///   # Ok(()) }
 ///   ```
@@ -216,6 +457,18 @@ pub struct RotatingFileSinkBuilder<ArgBP, ArgRP> {
     rotation_policy: ArgRP,
     max_files: usize,
     rotate_on_open: bool,
+    rename_on_close: bool,
+    open_error_policy: OpenErrorPolicy,
+    manifest: bool,
+    manifest_sync: bool,
+    write_bom: bool,
+    marker: Option<String>,
+    rotation_events: Option<SyncSender<RotationEvent>>,
+    retention_manager: Option<Arc<RetentionManager>>,
+    twelve_hour_clock: bool,
+    max_create_dir_depth: Option<usize>,
+    flush_policy: FlushPolicy,
+    clock: Arc<dyn Clock>,
 }
 
 impl RotatingFileSink {
@@ -228,6 +481,18 @@ impl RotatingFileSink {
             rotation_policy: (),
             max_files: 0,
             rotate_on_open: false,
+            rename_on_close: false,
+            open_error_policy: OpenErrorPolicy::FailFast,
+            manifest: false,
+            manifest_sync: false,
+            marker: None,
+            rotation_events: None,
+            retention_manager: None,
+            twelve_hour_clock: false,
+            write_bom: false,
+            max_create_dir_depth: None,
+            flush_policy: FlushPolicy::Never,
+            clock: clock::default_clock(),
         }
     }
 
@@ -277,7 +542,7 @@ impl RotatingFileSink {
     #[must_use]
     fn _current_size(&self) -> u64 {
         if let RotatorKind::FileSize(rotator) = &self.rotator {
-            rotator.inner.lock().current_size
+            rotator.inner.lock_expect().current_size
         } else {
             panic!();
         }
@@ -291,12 +556,15 @@ impl Sink for RotatingFileSink {
         }
 
         let mut string_buf = StringBuf::new();
-        self.common_impl
-            .formatter
-            .read()
-            .format(record, &mut string_buf)?;
+        self.common_impl.format(record, &mut string_buf)?;
+
+        self.rotator.log(record, &string_buf)?;
+
+        if self.flush_policy.should_flush(record.level()) {
+            self.rotator.flush()?;
+        }
 
-        self.rotator.log(record, &string_buf)
+        Ok(())
     }
 
     fn flush(&self) -> Result<()> {
@@ -364,39 +632,147 @@ impl Rotator for RotatorKind {
 }
 
 impl RotatorFileSize {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         base_path: PathBuf,
         max_size: u64,
         max_files: usize,
         rotate_on_open: bool,
+        rename_on_close: bool,
+        open_error_policy: OpenErrorPolicy,
+        manifest: Option<Manifest>,
+        write_bom: bool,
+        marker: Option<Marker>,
+        rotation_events: Option<SyncSender<RotationEvent>>,
+        retention_manager: Option<Arc<RetentionManager>>,
     ) -> Result<Self> {
-        let file = utils::open_file(&base_path, false)?;
+        let file = utils::open_file(
+            active_file_path(&base_path, rename_on_close).as_ref(),
+            false,
+        )?;
         let current_size = file.metadata().map_err(Error::QueryFileMetadata)?.len();
 
         let res = Self {
             base_path,
             max_size,
             max_files,
-            inner: SpinMutex::new(RotatorFileSizeInner::new(file, current_size)),
+            rename_on_close,
+            open_error_policy,
+            manifest,
+            write_bom,
+            marker,
+            rotation_events,
+            retention_manager,
+            inner: Mutex::new(RotatorFileSizeInner::new(file, current_size)),
         };
 
-        if rotate_on_open && current_size > 0 {
-            res.rotate(&mut res.inner.lock())?;
-            res.inner.lock().current_size = 0;
+        {
+            let mut inner = res.inner.lock_expect();
+            if rotate_on_open && current_size > 0 {
+                res.rotate(&mut inner)?;
+            }
+            // Lists the rotated files left by previous runs, the time ranges of their
+            // records are unknown.
+            res.rewrite_manifest(&mut inner, None)?;
+            if inner.current_size == 0 {
+                res.write_bom(&mut inner)?;
+            }
+            res.write_marker(&mut inner, Marker::STARTED)?;
         }
 
         Ok(res)
     }
 
+    fn write_bom(&self, inner: &mut RotatorFileSizeInner) -> Result<()> {
+        if self.write_bom {
+            inner
+                .file
+                .as_mut()
+                .unwrap()
+                .write_all(UTF8_BOM)
+                .map_err(Error::WriteRecord)?;
+            inner.current_size += UTF8_BOM.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn write_marker(&self, inner: &mut RotatorFileSizeInner, event: &str) -> Result<()> {
+        if let Some(marker) = &self.marker {
+            let line = marker.format(event, &self.base_path);
+            inner
+                .file
+                .as_mut()
+                .unwrap()
+                .write_all(line.as_bytes())
+                .map_err(Error::WriteRecord)?;
+            inner.current_size += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    // Each rotation shifts the indexes of all rotated files, so the manifest is
+    // rewritten as a whole to list them under their current paths.
+    //
+    // `rotated` is the time range of the file that has just been rotated to index
+    // 1, if any.
+    fn rewrite_manifest(
+        &self,
+        inner: &mut RotatorFileSizeInner,
+        rotated: Option<RecordTimeRange>,
+    ) -> Result<()> {
+        let manifest = match &self.manifest {
+            Some(manifest) => manifest,
+            None => return Ok(()),
+        };
+
+        if let Some(rotated) = rotated {
+            inner.rotated_record_times.push_front(rotated);
+        }
+        inner
+            .rotated_record_times
+            .truncate(self.max_files.saturating_sub(1));
+
+        let unknown = RecordTimeRange::default();
+        let mut lines = String::new();
+        for index in 1..self.max_files {
+            let file_path = Self::calc_file_path(&self.base_path, index);
+            // The file may have been discarded by the retention manager.
+            let size = match fs::metadata(&file_path) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => continue,
+            };
+            let record_times = inner.rotated_record_times.get(index - 1);
+            Manifest::write_line(
+                &mut lines,
+                &file_path,
+                record_times.unwrap_or(&unknown),
+                size,
+            );
+        }
+        manifest.rewrite(&lines)
+    }
+
     fn reopen(&self) -> Result<File> {
         // always truncate
-        utils::open_file(&self.base_path, true)
+        utils::open_file(
+            active_file_path(&self.base_path, self.rename_on_close).as_ref(),
+            true,
+        )
     }
 
-    fn rotate(&self, opened_file: &mut SpinMutexGuard<RotatorFileSizeInner>) -> Result<()> {
-        let inner = || {
+    fn rotate(&self, opened_file: &mut MutexGuard<RotatorFileSizeInner>) -> Result<()> {
+        let mut retained = self
+            .retention_manager
+            .as_deref()
+            .map(RetentionManager::lock);
+
+        let mut inner = || {
             for i in (1..self.max_files).rev() {
-                let src = Self::calc_file_path(&self.base_path, i - 1);
+                let src = if i == 1 {
+                    active_file_path(&self.base_path, self.rename_on_close).into_owned()
+                } else {
+                    Self::calc_file_path(&self.base_path, i - 1)
+                };
                 if !src.exists() {
                     continue;
                 }
@@ -404,9 +780,17 @@ impl RotatorFileSize {
                 let dst = Self::calc_file_path(&self.base_path, i);
                 if dst.exists() {
                     fs::remove_file(&dst).map_err(Error::RemoveFile)?;
+                    if let Some(retained) = retained.as_mut() {
+                        retained.removed(&dst);
+                    }
                 }
 
-                fs::rename(src, dst).map_err(Error::RenameFile)?;
+                fs::rename(&src, &dst).map_err(Error::RenameFile)?;
+                match retained.as_mut() {
+                    Some(retained) if i == 1 => retained.retain(dst)?,
+                    Some(retained) => retained.renamed(&src, &dst),
+                    None => {}
+                }
             }
             Ok(())
         };
@@ -414,17 +798,24 @@ impl RotatorFileSize {
         opened_file.file = None;
 
         let res = inner();
-        if res.is_err() {
-            opened_file.current_size = 0;
+        if res.is_err() && self.open_error_policy != OpenErrorPolicy::FailFast {
+            // The current file has not been moved, keep its contents.
+            opened_file.file = Some(BufWriter::new(utils::open_file(
+                active_file_path(&self.base_path, self.rename_on_close).as_ref(),
+                false,
+            )?));
+            return res;
         }
 
         opened_file.file = Some(BufWriter::new(self.reopen()?));
+        opened_file.current_size = 0;
+        self.write_bom(opened_file)?;
 
         res
     }
 
     #[must_use]
-    fn calc_file_path(base_path: impl AsRef<Path>, index: usize) -> PathBuf {
+    pub(crate) fn calc_file_path(base_path: impl AsRef<Path>, index: usize) -> PathBuf {
         let base_path = base_path.as_ref();
 
         if index == 0 {
@@ -451,24 +842,59 @@ impl RotatorFileSize {
     }
 
     // if `self.inner.file` is `None`, try to reopen the file.
-    fn lock_inner(&self) -> Result<SpinMutexGuard<RotatorFileSizeInner>> {
-        let mut inner = self.inner.lock();
+    fn lock_inner(&self) -> Result<MutexGuard<'_, RotatorFileSizeInner>> {
+        let mut inner = self.inner.lock_expect();
         if inner.file.is_none() {
             inner.file = Some(BufWriter::new(self.reopen()?));
+            inner.current_size = 0;
+            self.write_bom(&mut inner)?;
         }
         Ok(inner)
     }
 }
 
 impl Rotator for RotatorFileSize {
-    fn log(&self, _record: &Record, string_buf: &StringBuf) -> Result<()> {
-        let mut inner = self.lock_inner()?;
+    fn log(&self, record: &Record, string_buf: &StringBuf) -> Result<()> {
+        let mut inner = match self.lock_inner() {
+            Ok(inner) => inner,
+            Err(_) if self.open_error_policy == OpenErrorPolicy::Drop => return Ok(()),
+            Err(err) => return Err(err),
+        };
 
-        inner.current_size += string_buf.len() as u64;
-        if inner.current_size > self.max_size {
-            self.rotate(&mut inner)?;
-            inner.current_size = string_buf.len() as u64;
+        if inner.current_size + string_buf.len() as u64 > self.max_size {
+            let rotated_record_times = mem::take(&mut inner.record_times);
+
+            match self.rotate(&mut inner) {
+                Ok(()) => {
+                    self.rewrite_manifest(&mut inner, Some(rotated_record_times))?;
+                    self.write_marker(&mut inner, Marker::ROTATED)?;
+                    send_rotation_event(&self.rotation_events, || RotationEvent {
+                        closed: if self.max_files > 1 {
+                            Self::calc_file_path(&self.base_path, 1)
+                        } else {
+                            self.base_path.clone()
+                        },
+                        opened: self.base_path.clone(),
+                        at: record.time(),
+                    });
+                }
+                // The current file is still open, the rotation will be retried on the next
+                // record since the size limit is still exceeded.
+                Err(_)
+                    if self.open_error_policy == OpenErrorPolicy::KeepLast
+                        && inner.file.is_some() =>
+                {
+                    inner.record_times = rotated_record_times;
+                }
+                Err(_) if self.open_error_policy == OpenErrorPolicy::Drop => {
+                    inner.record_times = rotated_record_times;
+                    return Ok(());
+                }
+                Err(err) => return Err(err),
+            }
         }
+        inner.current_size += string_buf.len() as u64;
+        inner.record_times.update(record.time());
 
         inner
             .file
@@ -488,7 +914,7 @@ impl Rotator for RotatorFileSize {
     }
 
     fn drop_flush(&mut self) -> Result<()> {
-        let mut inner = self.inner.lock();
+        let mut inner = self.inner.lock_expect();
         if let Some(file) = inner.file.as_mut() {
             file.flush().map_err(Error::FlushBuffer)
         } else {
@@ -503,23 +929,43 @@ impl RotatorFileSizeInner {
         Self {
             file: Some(BufWriter::new(file)),
             current_size,
+            record_times: RecordTimeRange::default(),
+            rotated_record_times: VecDeque::new(),
         }
     }
 }
 
 impl RotatorTimePoint {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         base_path: PathBuf,
         time_point: TimePoint,
+        twelve_hour_clock: bool,
         max_files: usize,
         truncate: bool,
+        rename_on_close: bool,
+        open_error_policy: OpenErrorPolicy,
+        manifest: Option<Manifest>,
+        write_bom: bool,
+        marker: Option<Marker>,
+        rotation_events: Option<SyncSender<RotationEvent>>,
+        retention_manager: Option<Arc<RetentionManager>>,
+        clock: Arc<dyn Clock>,
     ) -> Result<Self> {
-        let now = SystemTime::now();
-        let file_path = Self::calc_file_path(base_path.as_path(), time_point, now);
-        let file = utils::open_file(file_path, truncate)?;
+        let now = clock.now();
+        let file_path =
+            Self::calc_file_path(base_path.as_path(), time_point, twelve_hour_clock, now);
+        let file = utils::open_file(
+            active_file_path(&file_path, rename_on_close).as_ref(),
+            truncate,
+        )?;
+        let file_size = file.metadata().map_err(Error::QueryFileMetadata)?.len();
 
         let inner = RotatorTimePointInner {
             file: BufWriter::new(file),
+            file_path,
+            file_size,
+            record_times: RecordTimeRange::default(),
             rotation_time_point: Self::next_rotation_time_point(time_point, now),
             file_paths: None,
         };
@@ -527,11 +973,27 @@ impl RotatorTimePoint {
         let mut res = Self {
             base_path,
             time_point,
+            twelve_hour_clock,
             max_files,
-            inner: SpinMutex::new(inner),
+            rename_on_close,
+            open_error_policy,
+            manifest,
+            write_bom,
+            marker,
+            rotation_events,
+            retention_manager,
+            clock,
+            inner: Mutex::new(inner),
         };
 
         res.init_previous_file_paths(max_files, now);
+        {
+            let mut inner = res.inner.lock_expect();
+            if inner.file_size == 0 {
+                res.write_bom(&mut inner)?;
+            }
+            res.write_marker(&mut inner, Marker::STARTED)?;
+        }
 
         Ok(res)
     }
@@ -541,9 +1003,16 @@ impl RotatorTimePoint {
             let mut file_paths = LinkedList::new();
 
             for _ in 0..max_files {
-                let file_path = Self::calc_file_path(&self.base_path, self.time_point, now);
-
-                if !file_path.exists() {
+                let file_path = Self::calc_file_path(
+                    &self.base_path,
+                    self.time_point,
+                    self.twelve_hour_clock,
+                    now,
+                );
+
+                let occupied = file_path.exists()
+                    || (self.rename_on_close && partial_file_path(&file_path).exists());
+                if !occupied {
                     break;
                 }
 
@@ -551,7 +1020,7 @@ impl RotatorTimePoint {
                 now = now.checked_sub(self.time_point.delta_std()).unwrap()
             }
 
-            self.inner.get_mut().file_paths = Some(file_paths);
+            self.inner.lock_expect().file_paths = Some(file_paths);
         }
     }
 
@@ -559,51 +1028,83 @@ impl RotatorTimePoint {
     // constructor.
     #[must_use]
     fn next_rotation_time_point(time_point: TimePoint, now: SystemTime) -> SystemTime {
-        let now: DateTime<Utc> = now.into();
-        let mut rotation_time: DateTime<Utc> = now;
+        Self::next_rotation_time_point_in(&Local, time_point, now)
+    }
+
+    // The time point is computed in the given time zone, the same one used by
+    // `calc_file_path_in`, so that the date and hour in the file name always
+    // match the period the file was opened in. The elapsed time of the current
+    // hour is subtracted from the instant instead of setting the fields of the
+    // local time, which may be ambiguous around DST transitions.
+    #[must_use]
+    fn next_rotation_time_point_in<Tz: TimeZone>(
+        tz: &Tz,
+        time_point: TimePoint,
+        now: SystemTime,
+    ) -> SystemTime {
+        let local_time = DateTime::<Utc>::from(now).with_timezone(tz);
 
         match time_point {
             TimePoint::Daily { hour, minute } => {
-                rotation_time = rotation_time
-                    .with_hour(hour)
-                    .unwrap()
-                    .with_minute(minute)
-                    .unwrap()
-                    .with_second(0)
-                    .unwrap()
-                    .with_nanosecond(0)
-                    .unwrap()
+                // A day is not 24 hours long around DST transitions, so the time
+                // point is looked up in the time zone on each date if it exists.
+                let time_point_on = |date: NaiveDate| {
+                    date.and_hms_opt(hour, minute, 0)
+                        .and_then(|time| tz.from_local_datetime(&time).earliest())
+                        .map(SystemTime::from)
+                };
+                let today = local_time.date_naive();
+                match time_point_on(today) {
+                    Some(rotation_time) if rotation_time >= now => rotation_time,
+                    _ => today
+                        .succ_opt()
+                        .and_then(time_point_on)
+                        .unwrap_or_else(|| now + time_point.delta_std()),
+                }
             }
             TimePoint::Hourly => {
-                rotation_time = rotation_time
-                    .with_minute(0)
-                    .unwrap()
-                    .with_second(0)
-                    .unwrap()
-                    .with_nanosecond(0)
-                    .unwrap()
+                let elapsed =
+                    Duration::from_secs(u64::from(local_time.minute() * 60 + local_time.second()))
+                        + Duration::from_nanos(u64::from(local_time.nanosecond()));
+                now - elapsed + time_point.delta_std()
             }
-        };
+        }
+    }
 
-        if rotation_time < now {
-            rotation_time = rotation_time
-                .checked_add_signed(time_point.delta_chrono())
-                .unwrap();
+    fn write_bom(&self, inner: &mut RotatorTimePointInner) -> Result<()> {
+        if self.write_bom {
+            inner.file.write_all(UTF8_BOM).map_err(Error::WriteRecord)?;
+            inner.file_size += UTF8_BOM.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn write_marker(&self, inner: &mut RotatorTimePointInner, event: &str) -> Result<()> {
+        if let Some(marker) = &self.marker {
+            let line = marker.format(event, &inner.file_path);
+            inner
+                .file
+                .write_all(line.as_bytes())
+                .map_err(Error::WriteRecord)?;
+            inner.file_size += line.len() as u64;
         }
-        rotation_time.into()
+        Ok(())
     }
 
     fn push_new_remove_old(
         &self,
         new: PathBuf,
-        inner: &mut SpinMutexGuard<RotatorTimePointInner>,
+        inner: &mut MutexGuard<RotatorTimePointInner>,
     ) -> Result<()> {
         let file_paths = inner.file_paths.as_mut().unwrap();
 
         while file_paths.len() >= self.max_files {
             let old = file_paths.pop_front().unwrap();
             if old.exists() {
-                fs::remove_file(old).map_err(Error::RemoveFile)?;
+                fs::remove_file(&old).map_err(Error::RemoveFile)?;
+            }
+            if let Some(retention_manager) = &self.retention_manager {
+                retention_manager.lock().removed(&old);
             }
         }
         file_paths.push_back(new);
@@ -615,10 +1116,28 @@ impl RotatorTimePoint {
     fn calc_file_path(
         base_path: impl AsRef<Path>,
         time_point: TimePoint,
+        twelve_hour_clock: bool,
+        system_time: SystemTime,
+    ) -> PathBuf {
+        Self::calc_file_path_in(
+            &Local,
+            base_path,
+            time_point,
+            twelve_hour_clock,
+            system_time,
+        )
+    }
+
+    #[must_use]
+    fn calc_file_path_in<Tz: TimeZone>(
+        tz: &Tz,
+        base_path: impl AsRef<Path>,
+        time_point: TimePoint,
+        twelve_hour_clock: bool,
         system_time: SystemTime,
     ) -> PathBuf {
         let base_path = base_path.as_ref();
-        let local_time: DateTime<Local> = system_time.into();
+        let local_time = DateTime::<Utc>::from(system_time).with_timezone(tz);
 
         let mut file_name = base_path
             .file_stem()
@@ -640,12 +1159,17 @@ impl RotatorTimePoint {
             TimePoint::Hourly => {
                 // append y-m-d_h
                 file_name.push(format!(
-                    "_{}-{:02}-{:02}_{:02}",
+                    "_{}-{:02}-{:02}_",
                     local_time.year(),
                     local_time.month(),
                     local_time.day(),
-                    local_time.hour()
                 ));
+                if twelve_hour_clock {
+                    let (is_pm, hour) = local_time.hour12();
+                    file_name.push(format!("{:02}{}", hour, if is_pm { "PM" } else { "AM" }));
+                } else {
+                    file_name.push(format!("{:02}", local_time.hour()));
+                }
             }
         }
 
@@ -661,37 +1185,222 @@ impl RotatorTimePoint {
 
 impl Rotator for RotatorTimePoint {
     fn log(&self, record: &Record, string_buf: &StringBuf) -> Result<()> {
-        let mut inner = self.inner.lock();
+        let mut inner = self.inner.lock_expect();
 
         let mut file_path = None;
-        let record_time = record.time();
-        let should_rotate = record_time >= inner.rotation_time_point;
+        let now = self.clock.now();
+        let should_rotate = now >= inner.rotation_time_point;
 
         if should_rotate {
-            file_path = Some(Self::calc_file_path(
+            let new_file_path = Self::calc_file_path(
                 &self.base_path,
                 self.time_point,
-                record_time,
-            ));
-            inner.file = BufWriter::new(utils::open_file(file_path.as_ref().unwrap(), true)?);
-            inner.rotation_time_point =
-                Self::next_rotation_time_point(self.time_point, record_time);
+                self.twelve_hour_clock,
+                now,
+            );
+
+            // A spurious rotation (e.g. caused by a clock adjustment or a replayed
+            // record) may map to the file that is already open, reopening it with
+            // truncation would clobber its contents, so it's coalesced instead.
+            let opened = if new_file_path == inner.file_path {
+                true
+            } else {
+                match utils::open_file(
+                    active_file_path(&new_file_path, self.rename_on_close).as_ref(),
+                    true,
+                ) {
+                    Ok(file) => {
+                        inner.file = BufWriter::new(file);
+                        true
+                    }
+                    Err(err) => match self.open_error_policy {
+                        OpenErrorPolicy::FailFast => return Err(err),
+                        OpenErrorPolicy::KeepLast => false,
+                        OpenErrorPolicy::Drop => return Ok(()),
+                    },
+                }
+            };
+
+            if opened && new_file_path != inner.file_path {
+                let rotated_path = mem::replace(&mut inner.file_path, new_file_path.clone());
+                if self.rename_on_close {
+                    fs::rename(partial_file_path(&rotated_path), &rotated_path)
+                        .map_err(Error::RenameFile)?;
+                }
+                let rotated_size = mem::take(&mut inner.file_size);
+                let rotated_record_times = mem::take(&mut inner.record_times);
+                if let Some(manifest) = &self.manifest {
+                    manifest.append(&rotated_path, &rotated_record_times, rotated_size)?;
+                }
+                if let Some(retention_manager) = &self.retention_manager {
+                    retention_manager.lock().retain(rotated_path.clone())?;
+                }
+                self.write_bom(&mut inner)?;
+                self.write_marker(&mut inner, Marker::ROTATED)?;
+                send_rotation_event(&self.rotation_events, || RotationEvent {
+                    closed: rotated_path,
+                    opened: new_file_path.clone(),
+                    at: now,
+                });
+                file_path = Some(new_file_path);
+            }
+            // Otherwise the rotation will be retried on the next record.
+            if opened {
+                inner.rotation_time_point = Self::next_rotation_time_point(self.time_point, now);
+            }
         }
 
         inner
             .file
             .write_all(string_buf.as_bytes())
             .map_err(Error::WriteRecord)?;
+        inner.file_size += string_buf.len() as u64;
+        inner.record_times.update(record.time());
 
-        if should_rotate && inner.file_paths.is_some() {
-            self.push_new_remove_old(file_path.unwrap(), &mut inner)?;
+        if let Some(file_path) = file_path.filter(|_| inner.file_paths.is_some()) {
+            self.push_new_remove_old(file_path, &mut inner)?;
         }
 
         Ok(())
     }
 
     fn flush(&self) -> Result<()> {
-        self.inner.lock().file.flush().map_err(Error::FlushBuffer)
+        self.inner
+            .lock_expect()
+            .file
+            .flush()
+            .map_err(Error::FlushBuffer)
+    }
+}
+
+// The path of the file being written, which is `path` itself, or a hidden
+// temporary name next to it if the file is renamed when it's closed.
+#[must_use]
+fn active_file_path(path: &Path, rename_on_close: bool) -> Cow<'_, Path> {
+    if rename_on_close {
+        Cow::Owned(partial_file_path(path))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+// e.g. `/path/to/app_05.log` -> `/path/to/.app_05.log.partial`
+#[must_use]
+fn partial_file_path(path: &Path) -> PathBuf {
+    let mut file_name = OsString::from(".");
+    if let Some(name) = path.file_name() {
+        file_name.push(name);
+    }
+    file_name.push(".partial");
+    path.with_file_name(file_name)
+}
+
+impl RecordTimeRange {
+    fn update(&mut self, time: SystemTime) {
+        self.first.get_or_insert(time);
+        self.last = Some(time);
+    }
+}
+
+impl Manifest {
+    const FILE_NAME_SUFFIX: &'static str = ".manifest.jsonl";
+
+    // Creates the manifest file if it doesn't exist.
+    fn open(base_path: &Path, sync: bool) -> Result<Self> {
+        let path = Self::calc_file_path(base_path);
+        utils::open_file(&path, false)?;
+        Ok(Self { path, sync })
+    }
+
+    // The manifest is named after the base file, e.g. `app.manifest.jsonl` for
+    // `app.log`, so that sinks sharing a directory don't share a manifest.
+    #[must_use]
+    fn calc_file_path(base_path: &Path) -> PathBuf {
+        let mut file_name = base_path
+            .file_stem()
+            .map(|stem| stem.to_os_string())
+            .unwrap_or_default();
+        file_name.push(Self::FILE_NAME_SUFFIX);
+        base_path.with_file_name(file_name)
+    }
+
+    fn append(&self, file_path: &Path, record_times: &RecordTimeRange, size: u64) -> Result<()> {
+        let mut line = String::new();
+        Self::write_line(&mut line, file_path, record_times, size);
+
+        // The file is unbuffered and each line is written with a single call, so a
+        // crash cannot leave a partially buffered line behind.
+        let mut file = utils::open_file(&self.path, false)?;
+        file.write_all(line.as_bytes())
+            .map_err(Error::WriteRecord)?;
+        if self.sync {
+            file.sync_data().map_err(Error::FlushBuffer)?;
+        }
+        Ok(())
+    }
+
+    // Replaces the content of the manifest with `lines`.
+    //
+    // The lines are written to a temporary file which is then renamed over the
+    // manifest, so readers never see a partially written manifest.
+    fn rewrite(&self, lines: &str) -> Result<()> {
+        let mut temp_file_name = self.path.file_name().unwrap().to_os_string();
+        temp_file_name.push(".tmp");
+        let temp_path = self.path.with_file_name(temp_file_name);
+
+        let mut file = utils::open_file(&temp_path, true)?;
+        file.write_all(lines.as_bytes())
+            .map_err(Error::WriteRecord)?;
+        if self.sync {
+            file.sync_data().map_err(Error::FlushBuffer)?;
+        }
+        drop(file);
+        fs::rename(&temp_path, &self.path).map_err(Error::RenameFile)
+    }
+
+    fn write_line(dest: &mut String, file_path: &Path, record_times: &RecordTimeRange, size: u64) {
+        dest.push_str("{\"path\":");
+        utils::write_json_string(dest, &file_path.to_string_lossy()).unwrap();
+        dest.push_str(",\"start\":");
+        Self::write_time(dest, record_times.first);
+        dest.push_str(",\"end\":");
+        Self::write_time(dest, record_times.last);
+        writeln!(dest, ",\"size\":{}}}", size).unwrap();
+    }
+
+    fn write_time(dest: &mut String, time: Option<SystemTime>) {
+        match time {
+            Some(time) => {
+                let time: DateTime<Utc> = time.into();
+                write!(
+                    dest,
+                    "\"{}\"",
+                    time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+                )
+                .unwrap();
+            }
+            None => dest.push_str("null"),
+        }
+    }
+}
+
+impl Marker {
+    const STARTED: &'static str = "started";
+    const ROTATED: &'static str = "rotated";
+
+    #[must_use]
+    fn format(&self, event: &str, file_path: &Path) -> String {
+        let file_name = file_path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        let mut line = self
+            .template
+            .replace("{event}", event)
+            .replace("{file}", &file_name);
+        line.push_str(&self.eol);
+        line
     }
 }
 
@@ -706,14 +1415,6 @@ impl TimePoint {
             Self::Hourly { .. } => HOUR_1,
         }
     }
-
-    #[must_use]
-    fn delta_chrono(&self) -> chrono::Duration {
-        match self {
-            Self::Daily { .. } => chrono::Duration::days(1),
-            Self::Hourly { .. } => chrono::Duration::hours(1),
-        }
-    }
 }
 
 impl<ArgBP, ArgRP> RotatingFileSinkBuilder<ArgBP, ArgRP> {
@@ -748,6 +1449,18 @@ impl<ArgBP, ArgRP> RotatingFileSinkBuilder<ArgBP, ArgRP> {
             rotation_policy: self.rotation_policy,
             max_files: self.max_files,
             rotate_on_open: self.rotate_on_open,
+            rename_on_close: self.rename_on_close,
+            open_error_policy: self.open_error_policy,
+            manifest: self.manifest,
+            manifest_sync: self.manifest_sync,
+            marker: self.marker,
+            rotation_events: self.rotation_events,
+            retention_manager: self.retention_manager,
+            twelve_hour_clock: self.twelve_hour_clock,
+            write_bom: self.write_bom,
+            max_create_dir_depth: self.max_create_dir_depth,
+            flush_policy: self.flush_policy,
+            clock: self.clock,
         }
     }
 
@@ -765,6 +1478,18 @@ impl<ArgBP, ArgRP> RotatingFileSinkBuilder<ArgBP, ArgRP> {
             rotation_policy,
             max_files: self.max_files,
             rotate_on_open: self.rotate_on_open,
+            rename_on_close: self.rename_on_close,
+            open_error_policy: self.open_error_policy,
+            manifest: self.manifest,
+            manifest_sync: self.manifest_sync,
+            marker: self.marker,
+            rotation_events: self.rotation_events,
+            retention_manager: self.retention_manager,
+            twelve_hour_clock: self.twelve_hour_clock,
+            write_bom: self.write_bom,
+            max_create_dir_depth: self.max_create_dir_depth,
+            flush_policy: self.flush_policy,
+            clock: self.clock,
         }
     }
 
@@ -782,6 +1507,15 @@ impl<ArgBP, ArgRP> RotatingFileSinkBuilder<ArgBP, ArgRP> {
         self
     }
 
+    /// Specifies when the sink flushes itself after logging a record.
+    ///
+    /// This parameter is **optional**, and defaults to [`FlushPolicy::Never`].
+    #[must_use]
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
     /// Specifies whether to rotate files once when constructing
     /// `RotatingFileSink`.
     ///
@@ -797,66 +1531,338 @@ impl<ArgBP, ArgRP> RotatingFileSinkBuilder<ArgBP, ArgRP> {
         self
     }
 
-    helper::common_impl!(@SinkBuilder: common_builder_impl);
-}
+    /// Specifies whether to render the hour in file names in the 12-hour clock
+    /// with an `AM`/`PM` suffix, e.g. `base_file_2022-03-24_05PM.log` rather
+    /// than `base_file_2022-03-24_17.log`.
+    ///
+    /// It only affects the [`RotationPolicy::Hourly`] rotation policy. Note that
+    /// the file names of a day no longer sort chronologically, e.g.
+    /// `_01PM` sorts before `_02AM` and `_12AM` (midnight) sorts last.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[must_use]
+    pub fn twelve_hour_clock(mut self, twelve_hour_clock: bool) -> Self {
+        self.twelve_hour_clock = twelve_hour_clock;
+        self
+    }
 
-impl<ArgRP> RotatingFileSinkBuilder<(), ArgRP> {
-    #[doc(hidden)]
-    #[deprecated(note = "\n\n\
-        builder compile-time error:\n\
-        - missing required field `base_path`\n\n\
-    ")]
-    pub fn build(self, _: Infallible) {}
-}
+    /// Specifies whether to write the current file under a temporary name and
+    /// rename it to its final name when a rotation closes it.
+    ///
+    /// The temporary name is the final name prefixed with `.` and suffixed
+    /// with `.partial`, e.g. `.app_2022-03-23.log.partial` for
+    /// `app_2022-03-23.log`. Since the rename is atomic, external watchers can
+    /// treat files with final names as complete.
+    ///
+    /// For the [`RotationPolicy::FileSize`] rotation policy, the current file
+    /// is written to `.app.log.partial` for base path `app.log`, and renamed to
+    /// `app_1.log` on rotation.
+    ///
+    /// The current file is not renamed when the sink is dropped, so that a sink
+    /// built again with the same base path continues writing to it.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    #[must_use]
+    pub fn rename_on_close(mut self, rename_on_close: bool) -> Self {
+        self.rename_on_close = rename_on_close;
+        self
+    }
 
-impl RotatingFileSinkBuilder<PathBuf, ()> {
-    #[doc(hidden)]
-    #[deprecated(note = "\n\n\
-        builder compile-time error:\n\
-        - missing required field `rotation_policy`\n\n\
-    ")]
-    pub fn build(self, _: Infallible) {}
-}
+    /// Specifies what to do when a rotation fails to open the new file.
+    ///
+    /// See the documentation of [`OpenErrorPolicy`] for the details of each
+    /// policy.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`OpenErrorPolicy::FailFast`].
+    #[must_use]
+    pub fn open_error_policy(mut self, policy: OpenErrorPolicy) -> Self {
+        self.open_error_policy = policy;
+        self
+    }
 
-impl RotatingFileSinkBuilder<PathBuf, RotationPolicy> {
-    /// Builds a [`RotatingFileSink`].
+    /// Specifies whether to maintain a manifest file next to the base path.
     ///
-    /// # Errors
+    /// The manifest is named after the base file, e.g. `app.manifest.jsonl`
+    /// for `app.log`. It lists each rotated file as one line, with its path,
+    /// the time range of the records written into it by the sink and its size
+    /// (in bytes), e.g.
     ///
-    /// If the argument `rotation_policy` is invalid, or an error occurs opening
-    /// the file, [`Error::CreateDirectory`] or [`Error::OpenFile`] will be
-    /// returned.
-    pub fn build(self) -> Result<RotatingFileSink> {
+    /// ```text
+    /// {"path":"/path/to/base_file_2022-03-23_03.log","start":"2022-03-23T03:00:01.002Z","end":"2022-03-23T03:59:58.123Z","size":4096}
+    /// ```
+    ///
+    /// `start` and `end` are `null` if they are unknown, e.g. for files that
+    /// were rotated before the sink was built. This allows tooling to discover
+    /// rotated files without scanning the directory.
+    ///
+    /// For time point rotation policies, a line is appended on every rotation.
+    /// For the [`RotationPolicy::FileSize`] rotation policy, every rotation
+    /// shifts the indexes of all rotated files, so instead the manifest is
+    /// rewritten on every rotation (and when the sink is built) to list the
+    /// rotated files that currently exist under their current names, i.e. the
+    /// ones kept according to [`max_files`].
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    ///
+    /// [`max_files`]: RotatingFileSinkBuilder::max_files
+    #[must_use]
+    pub fn manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Specifies whether to call `fsync` on the manifest file after each
+    /// write.
+    ///
+    /// Lines are always written to the manifest unbuffered, this option
+    /// additionally ensures they reach the disk at the cost of performance.
+    /// It has no effect unless [`manifest`] is enabled.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    ///
+    /// [`manifest`]: RotatingFileSinkBuilder::manifest
+    #[must_use]
+    pub fn manifest_sync(mut self, manifest_sync: bool) -> Self {
+        self.manifest_sync = manifest_sync;
+        self
+    }
+
+    /// Specifies whether to write a UTF-8 BOM at the start of each log file.
+    ///
+    /// The BOM is only written into files created or truncated by the sink,
+    /// or existing empty files, never in the middle of a file being appended
+    /// to. It's written before the [`marker`] line, and counts towards the
+    /// file size. Some Windows tools rely on it to detect the encoding of the
+    /// file.
+    ///
+    /// This parameter is **optional**, and defaults to `false`.
+    ///
+    /// [`marker`]: RotatingFileSinkBuilder::marker
+    #[must_use]
+    pub fn write_bom(mut self, write_bom: bool) -> Self {
+        self.write_bom = write_bom;
+        self
+    }
+
+    /// Specifies a template of a marker line written into the log file when
+    /// the sink starts and after each rotation.
+    ///
+    /// Markers make the boundaries visible when log files are concatenated or
+    /// aggregated. The following placeholders in the template are replaced:
+    ///
+    /// - `{event}`: `started` for the marker written when the sink is built,
+    ///   `rotated` for the marker written after a rotation.
+    /// - `{file}`: the file name of the log file being written, e.g.
+    ///   `base_file_2022-03-24.log`. For the [`RotationPolicy::FileSize`]
+    ///   rotation policy, it's always the file name of the base path.
+    ///
+    /// For example, the template `--- log {event}: {file} ---` writes a line
+    /// `--- log rotated: base_file_2022-03-24.log ---`.
+    ///
+    /// Markers are written as-is followed by the EOL, they are not formatted
+    /// by the formatter of the sink, and they count towards the file size.
+    ///
+    /// This parameter is **optional**, and defaults to no marker.
+    #[must_use]
+    pub fn marker<S>(mut self, template: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.marker = Some(template.into());
+        self
+    }
+
+    /// Creates a channel receiving a [`RotationEvent`] on each rotation
+    /// triggered by logging records, and returns the builder along with the
+    /// receiver.
+    ///
+    /// The events are sent without blocking, so a slow consumer cannot stall
+    /// logging. If the channel already holds `capacity` events, or the
+    /// receiver has been dropped, new events are dropped.
+    ///
+    /// The rotation performed by [`rotate_on_open`] is not reported.
+    ///
+    /// This parameter is **optional**, and defaults to no channel.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use spdlog::sink::{RotatingFileSink, RotationPolicy};
+    ///
+    /// # fn main() -> Result<(), spdlog::Error> {
+    /// let (builder, events) = RotatingFileSink::builder()
+    ///     .base_path("/path/to/base_log_file")
+    ///     .rotation_policy(RotationPolicy::Hourly)
+    ///     .rotation_events(16);
+    /// let sink: RotatingFileSink = builder.build()?;
+    ///
+    /// std::thread::spawn(move || {
+    ///     for event in events {
+    ///         println!("{} -> {}", event.closed().display(), event.opened().display());
+    ///     }
+    /// });
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// [`rotate_on_open`]: RotatingFileSinkBuilder::rotate_on_open
+    #[must_use]
+    pub fn rotation_events(mut self, capacity: usize) -> (Self, Receiver<RotationEvent>) {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        self.rotation_events = Some(sender);
+        (self, receiver)
+    }
+
+    /// Specifies a [`RetentionManager`] enforcing a budget of rotated files
+    /// shared with other sinks.
+    ///
+    /// This parameter is **optional**, and defaults to no shared budget.
+    #[must_use]
+    pub fn retention_manager(mut self, retention_manager: Arc<RetentionManager>) -> Self {
+        self.retention_manager = Some(retention_manager);
+        self
+    }
+
+    /// Specifies the maximum number of levels of missing parent directories
+    /// that may be created for the base path.
+    ///
+    /// If opening the log file requires creating more levels of directories,
+    /// building fails with [`Error::CreateDirectory`] instead, to avoid
+    /// accidentally creating a deep directory tree from a malformed path. Pass
+    /// `0` to require the parent directory to already exist.
+    ///
+    /// This parameter is **optional**, and defaults to no limit.
+    #[must_use]
+    pub fn max_create_dir_depth(mut self, max_depth: usize) -> Self {
+        self.max_create_dir_depth = Some(max_depth);
+        self
+    }
+
+    /// Specifies the clock providing the current time.
+    ///
+    /// The clock decides when time-based rotations happen and the files they
+    /// open, by the time a record is written rather than its [`Record::time`],
+    /// and when [`FlushPolicy::EveryDuration`] flushes. A [`MockClock`] makes
+    /// them deterministic in tests.
+    ///
+    /// This parameter is **optional**, and defaults to [`SystemClock`].
+    ///
+    /// [`MockClock`]: crate::MockClock
+    /// [`SystemClock`]: crate::SystemClock
+    #[must_use]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    helper::common_impl!(@SinkBuilder: common_builder_impl);
+}
+
+impl<ArgRP> RotatingFileSinkBuilder<(), ArgRP> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `base_path`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl RotatingFileSinkBuilder<PathBuf, ()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `rotation_policy`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl RotatingFileSinkBuilder<PathBuf, RotationPolicy> {
+    /// Builds a [`RotatingFileSink`].
+    ///
+    /// # Errors
+    ///
+    /// If the argument `rotation_policy` is invalid, or an error occurs opening
+    /// the file, [`Error::CreateDirectory`] or [`Error::OpenFile`] will be
+    /// returned.
+    pub fn build(self) -> Result<RotatingFileSink> {
         self.rotation_policy
             .validate()
             .map_err(|err| Error::InvalidArgument(InvalidArgumentError::RotationPolicy(err)))?;
 
+        if let Some(max_depth) = self.max_create_dir_depth {
+            utils::check_create_dir_depth(&self.base_path, max_depth)?;
+        }
+
+        let manifest = if self.manifest {
+            Some(Manifest::open(&self.base_path, self.manifest_sync)?)
+        } else {
+            None
+        };
+
+        let marker = self.marker.map(|template| Marker {
+            template,
+            eol: self
+                .common_builder_impl
+                .eol_override
+                .clone()
+                .unwrap_or_else(|| crate::EOL.to_string()),
+        });
+
+        let common_impl = helper::CommonImpl::from_builder(self.common_builder_impl)
+            .with_target_path(&self.base_path);
+
         let rotator = match self.rotation_policy {
             RotationPolicy::FileSize(max_size) => RotatorKind::FileSize(RotatorFileSize::new(
                 self.base_path,
                 max_size,
                 self.max_files,
                 self.rotate_on_open,
+                self.rename_on_close,
+                self.open_error_policy,
+                manifest,
+                self.write_bom,
+                marker,
+                self.rotation_events,
+                self.retention_manager,
             )?),
             RotationPolicy::Daily { hour, minute } => {
                 RotatorKind::TimePoint(RotatorTimePoint::new(
                     self.base_path,
                     TimePoint::Daily { hour, minute },
+                    self.twelve_hour_clock,
                     self.max_files,
                     self.rotate_on_open,
+                    self.rename_on_close,
+                    self.open_error_policy,
+                    manifest,
+                    self.write_bom,
+                    marker,
+                    self.rotation_events,
+                    self.retention_manager,
+                    self.clock.clone(),
                 )?)
             }
             RotationPolicy::Hourly => RotatorKind::TimePoint(RotatorTimePoint::new(
                 self.base_path,
                 TimePoint::Hourly,
+                self.twelve_hour_clock,
                 self.max_files,
                 self.rotate_on_open,
+                self.rename_on_close,
+                self.open_error_policy,
+                manifest,
+                self.write_bom,
+                marker,
+                self.rotation_events,
+                self.retention_manager,
+                self.clock.clone(),
             )?),
         };
 
         let res = RotatingFileSink {
-            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
+            common_impl,
             rotator,
+            flush_policy: FlushPolicyState::new(self.flush_policy, self.clock),
         };
 
         Ok(res)
@@ -1107,6 +2113,7 @@ mod tests {
                 RotatorTimePoint::calc_file_path(
                     base_path,
                     TimePoint::Daily { hour: 8, minute: 9 },
+                    false,
                     system_time,
                 )
                 .to_str()
@@ -1115,7 +2122,7 @@ mod tests {
             };
 
             let calc_hourly = |base_path| {
-                RotatorTimePoint::calc_file_path(base_path, TimePoint::Hourly, system_time)
+                RotatorTimePoint::calc_file_path(base_path, TimePoint::Hourly, false, system_time)
                     .to_str()
                     .unwrap()
                     .to_string()
@@ -1143,9 +2150,92 @@ mod tests {
             run();
         }
 
+        #[test]
+        fn calc_file_path_twelve_hour_clock() {
+            let calc_hourly = |hour| {
+                let system_time = Local
+                    .with_ymd_and_hms(2012, 3, 4, hour, 6, 7)
+                    .unwrap()
+                    .into();
+                RotatorTimePoint::calc_file_path("test.log", TimePoint::Hourly, true, system_time)
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            };
+
+            assert_eq!(calc_hourly(5), "test_2012-03-04_05AM.log");
+            assert_eq!(calc_hourly(17), "test_2012-03-04_05PM.log");
+            assert_eq!(calc_hourly(0), "test_2012-03-04_12AM.log");
+            assert_eq!(calc_hourly(12), "test_2012-03-04_12PM.log");
+
+            // The option has no effect on the daily policy.
+            let system_time = Local.with_ymd_and_hms(2012, 3, 4, 17, 6, 7).unwrap().into();
+            let daily = RotatorTimePoint::calc_file_path(
+                "test.log",
+                TimePoint::Daily { hour: 8, minute: 9 },
+                true,
+                system_time,
+            );
+            assert_eq!(daily.to_str().unwrap(), "test_2012-03-04.log");
+        }
+
+        #[test]
+        fn rotation_time_point_matches_file_path() {
+            // A non-whole-hour offset, so that the local hours and the UTC hours
+            // start at different instants
+            let tz = FixedOffset::east_opt(5 * 60 * 60 + 30 * 60).unwrap();
+            let now: SystemTime = tz.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+
+            let file_name = |time_point, time| {
+                RotatorTimePoint::calc_file_path_in(&tz, "test.log", time_point, false, time)
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            };
+
+            let rotation_time =
+                RotatorTimePoint::next_rotation_time_point_in(&tz, TimePoint::Hourly, now);
+            assert_eq!(
+                rotation_time,
+                tz.with_ymd_and_hms(2012, 3, 4, 6, 0, 0).unwrap().into()
+            );
+            assert_eq!(file_name(TimePoint::Hourly, now), "test_2012-03-04_05.log");
+            assert_eq!(
+                file_name(TimePoint::Hourly, rotation_time - Duration::from_nanos(1)),
+                "test_2012-03-04_05.log"
+            );
+            assert_eq!(
+                file_name(TimePoint::Hourly, rotation_time),
+                "test_2012-03-04_06.log"
+            );
+
+            let daily = |hour, minute| {
+                let time_point = TimePoint::Daily { hour, minute };
+                let rotation_time =
+                    RotatorTimePoint::next_rotation_time_point_in(&tz, time_point, now);
+                (rotation_time, file_name(time_point, rotation_time))
+            };
+            // Later today
+            assert_eq!(
+                daily(8, 9),
+                (
+                    tz.with_ymd_and_hms(2012, 3, 4, 8, 9, 0).unwrap().into(),
+                    "test_2012-03-04.log".to_string()
+                )
+            );
+            // Already passed today, the next one is tomorrow
+            assert_eq!(
+                daily(2, 0),
+                (
+                    tz.with_ymd_and_hms(2012, 3, 5, 2, 0, 0).unwrap().into(),
+                    "test_2012-03-05.log".to_string()
+                )
+            );
+        }
+
         #[test]
         fn rotate() {
-            let build = |rotate_on_open| {
+            let build = |rotate_on_open, clock: Arc<crate::MockClock>| {
                 fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
                 fs::create_dir(LOGS_PATH.as_path()).unwrap();
 
@@ -1153,10 +2243,11 @@ mod tests {
                     .base_path(LOGS_PATH.join("hourly.log"))
                     .rotation_policy(RotationPolicy::Hourly)
                     .rotate_on_open(rotate_on_open)
+                    .clock(clock.clone())
                     .build()
                     .unwrap();
 
-                let local_time_now = Local::now();
+                let local_time_now: DateTime<Local> = clock.now().into();
                 let daily_sink = RotatingFileSink::builder()
                     .base_path(LOGS_PATH.join("daily.log"))
                     .rotation_policy(RotationPolicy::Daily {
@@ -1164,6 +2255,7 @@ mod tests {
                         minute: local_time_now.minute(),
                     })
                     .rotate_on_open(rotate_on_open)
+                    .clock(clock)
                     .build()
                     .unwrap();
 
@@ -1198,9 +2290,10 @@ mod tests {
             const DAY_1: Duration = Duration::from_secs(60 * 60 * 24);
 
             {
-                let logger = build(true);
-                let mut record = Record::new(Level::Info, "test log message");
-                let initial_time = record.time();
+                let initial_time = SystemTime::now();
+                let clock = Arc::new(crate::MockClock::new(initial_time));
+                let logger = build(true, clock.clone());
+                let record = Record::new(Level::Info, "test log message");
 
                 assert_eq!(exist_hourly_files(), 1);
                 assert_eq!(exist_daily_files(), 1);
@@ -1209,22 +2302,22 @@ mod tests {
                 assert_eq!(exist_hourly_files(), 1);
                 assert_eq!(exist_daily_files(), 1);
 
-                record.set_time(record.time() + HOUR_1 + SECOND_1);
+                clock.advance(HOUR_1 + SECOND_1);
                 logger.log(&record);
                 assert_eq!(exist_hourly_files(), 2);
                 assert_eq!(exist_daily_files(), 1);
 
-                record.set_time(record.time() + HOUR_1 + SECOND_1);
+                clock.advance(HOUR_1 + SECOND_1);
                 logger.log(&record);
                 assert_eq!(exist_hourly_files(), 3);
                 assert_eq!(exist_daily_files(), 1);
 
-                record.set_time(record.time() + SECOND_1);
+                clock.advance(SECOND_1);
                 logger.log(&record);
                 assert_eq!(exist_hourly_files(), 3);
                 assert_eq!(exist_daily_files(), 1);
 
-                record.set_time(initial_time + DAY_1 + SECOND_1);
+                clock.set(initial_time + DAY_1 + SECOND_1);
                 logger.log(&record);
                 assert_eq!(exist_hourly_files(), 4);
                 assert_eq!(exist_daily_files(), 2);
@@ -1232,6 +2325,761 @@ mod tests {
         }
     }
 
+    #[test]
+    fn mock_clock() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("mock_clock");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let clock = Arc::new(crate::MockClock::new(start_time));
+
+        let sink = RotatingFileSink::builder()
+            .base_path(LOGS_PATH.join("hourly.log"))
+            .rotation_policy(RotationPolicy::Hourly)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        let hourly_file_exists = |hour| {
+            LOGS_PATH
+                .join(format!("hourly_2012-03-04_{:02}.log", hour))
+                .exists()
+        };
+        assert!(hourly_file_exists(5));
+        assert!(!hourly_file_exists(6));
+
+        // Rotations are decided by the clock rather than the time of the record
+        let mut record = Record::new(Level::Info, "test log message");
+        record.set_time(start_time + Duration::from_secs(60 * 60));
+        clock.set(start_time + Duration::from_secs(10 * 60));
+        sink.log(&record).unwrap();
+        assert!(!hourly_file_exists(6));
+
+        record.set_time(start_time);
+        clock.set(start_time + Duration::from_secs(60 * 60));
+        sink.log(&record).unwrap();
+        assert!(hourly_file_exists(6));
+    }
+
+    #[test]
+    fn coalesce_same_file_rotation() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("coalesce_same_file_rotation");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let sink = RotatingFileSink::builder()
+            .base_path(LOGS_PATH.join("hourly.log"))
+            .rotation_policy(RotationPolicy::Hourly)
+            .clock(Arc::new(crate::MockClock::new(start_time)))
+            .build()
+            .unwrap();
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+
+        sink.log(&Record::new(Level::Info, "a")).unwrap();
+
+        // Trigger a spurious rotation, the computed path is still the current file
+        match &sink.rotator {
+            RotatorKind::TimePoint(rotator) => {
+                rotator.inner.lock_expect().rotation_time_point = SystemTime::UNIX_EPOCH
+            }
+            RotatorKind::FileSize(_) => unreachable!(),
+        }
+        sink.log(&Record::new(Level::Info, "b")).unwrap();
+        sink.flush().unwrap();
+
+        let file_path = LOGS_PATH.join("hourly_2012-03-04_05.log");
+        assert_eq!(fs::read_to_string(file_path).unwrap(), "ab");
+        assert_eq!(fs::read_dir(LOGS_PATH.as_path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn concurrent_logging() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("concurrent_logging");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        const THREADS: usize = 16;
+        const RECORDS_PER_THREAD: usize = 100;
+        const PAYLOAD: &str = "012345678\n";
+
+        let sink = Arc::new(
+            RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("test.log"))
+                .rotation_policy(RotationPolicy::FileSize(256))
+                .max_files(1000)
+                .build()
+                .unwrap(),
+        );
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+
+        let handles = (0..THREADS)
+            .map(|_| {
+                let sink = sink.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..RECORDS_PER_THREAD {
+                        sink.log(&Record::new(Level::Info, PAYLOAD)).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        sink.flush().unwrap();
+
+        let mut lines = 0;
+        for entry in fs::read_dir(LOGS_PATH.as_path()).unwrap() {
+            let content = fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert!(content.len() <= 256);
+            for line in content.lines() {
+                assert_eq!(line, PAYLOAD.trim_end());
+                lines += 1;
+            }
+        }
+        assert_eq!(lines, THREADS * RECORDS_PER_THREAD);
+    }
+
+    #[test]
+    fn open_error_policy() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("open_error_policy");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let hourly_path = |hour| LOGS_PATH.join(format!("hourly_2012-03-04_{:02}.log", hour));
+
+        for policy in [
+            OpenErrorPolicy::FailFast,
+            OpenErrorPolicy::KeepLast,
+            OpenErrorPolicy::Drop,
+        ] {
+            fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+            fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+            // time point
+            {
+                let clock = Arc::new(crate::MockClock::new(start_time));
+                let sink = RotatingFileSink::builder()
+                    .base_path(LOGS_PATH.join("hourly.log"))
+                    .rotation_policy(RotationPolicy::Hourly)
+                    .open_error_policy(policy)
+                    .clock(clock.clone())
+                    .build()
+                    .unwrap();
+                sink.set_formatter(Box::new(NoModFormatter::new()));
+                let log = |payload, time| {
+                    clock.set(time);
+                    let record = Record::new(Level::Info, payload);
+                    let res = sink.log(&record);
+                    sink.flush().unwrap();
+                    res
+                };
+
+                // A directory at the path of the next file makes opening it fail.
+                fs::create_dir(hourly_path(6)).unwrap();
+
+                log("a", start_time).unwrap();
+                let res = log("b", start_time + Duration::from_secs(60 * 60));
+                assert_eq!(res.is_err(), policy == OpenErrorPolicy::FailFast);
+                let expected = match policy {
+                    OpenErrorPolicy::KeepLast => "ab",
+                    _ => "a",
+                };
+                assert_eq!(fs::read_to_string(hourly_path(5)).unwrap(), expected);
+
+                // The rotation is retried once the file can be opened.
+                fs::remove_dir(hourly_path(6)).unwrap();
+                log("c", start_time + Duration::from_secs(60 * 60 + 1)).unwrap();
+                assert_eq!(fs::read_to_string(hourly_path(5)).unwrap(), expected);
+                assert_eq!(fs::read_to_string(hourly_path(6)).unwrap(), "c");
+            }
+
+            // file size
+            if policy != OpenErrorPolicy::FailFast {
+                let base_path = LOGS_PATH.join("size.log");
+                let sink = RotatingFileSink::builder()
+                    .base_path(&base_path)
+                    .rotation_policy(RotationPolicy::FileSize(1))
+                    .max_files(2)
+                    .open_error_policy(policy)
+                    .build()
+                    .unwrap();
+                sink.set_formatter(Box::new(NoModFormatter::new()));
+
+                // A directory at the path of the rotated file makes moving the current file
+                // fail.
+                let rotated_path = RotatorFileSize::calc_file_path(&base_path, 1);
+                fs::create_dir(&rotated_path).unwrap();
+
+                sink.log(&Record::new(Level::Info, "a")).unwrap();
+                sink.log(&Record::new(Level::Info, "b")).unwrap();
+                sink.flush().unwrap();
+                let expected = match policy {
+                    OpenErrorPolicy::KeepLast => "ab",
+                    _ => "a",
+                };
+                assert_eq!(fs::read_to_string(&base_path).unwrap(), expected);
+
+                fs::remove_dir(&rotated_path).unwrap();
+                sink.log(&Record::new(Level::Info, "c")).unwrap();
+                sink.flush().unwrap();
+                assert_eq!(fs::read_to_string(&rotated_path).unwrap(), expected);
+                assert_eq!(fs::read_to_string(&base_path).unwrap(), "c");
+            }
+        }
+    }
+
+    #[test]
+    fn rename_on_close() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("rename_on_close");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        // time point
+        {
+            let start_time: SystemTime =
+                Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+            let clock = Arc::new(crate::MockClock::new(start_time));
+            let sink = RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("hourly.log"))
+                .rotation_policy(RotationPolicy::Hourly)
+                .rename_on_close(true)
+                .clock(clock.clone())
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+
+            let partial_05 = LOGS_PATH.join(".hourly_2012-03-04_05.log.partial");
+            let final_05 = LOGS_PATH.join("hourly_2012-03-04_05.log");
+            let partial_06 = LOGS_PATH.join(".hourly_2012-03-04_06.log.partial");
+
+            clock.set(start_time);
+            let record = Record::new(Level::Info, "a");
+            sink.log(&record).unwrap();
+            assert!(partial_05.exists());
+            assert!(!final_05.exists());
+
+            clock.set(start_time + Duration::from_secs(60 * 60));
+            let record = Record::new(Level::Info, "b");
+            sink.log(&record).unwrap();
+            assert!(!partial_05.exists());
+            assert_eq!(fs::read_to_string(final_05).unwrap(), "a");
+            assert!(partial_06.exists());
+        }
+
+        // file size
+        {
+            let sink = RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("size.log"))
+                .rotation_policy(RotationPolicy::FileSize(4))
+                .max_files(2)
+                .rename_on_close(true)
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+
+            let partial = LOGS_PATH.join(".size.log.partial");
+            let rotated = LOGS_PATH.join("size_1.log");
+
+            sink.log(&Record::new(Level::Info, "abcd")).unwrap();
+            assert!(partial.exists());
+            assert!(!LOGS_PATH.join("size.log").exists());
+            assert!(!rotated.exists());
+
+            sink.log(&Record::new(Level::Info, "efgh")).unwrap();
+            sink.flush().unwrap();
+            assert_eq!(fs::read_to_string(rotated).unwrap(), "abcd");
+            assert_eq!(fs::read_to_string(partial).unwrap(), "efgh");
+        }
+    }
+
+    #[test]
+    fn retention_manager() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("retention_manager");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let manager = Arc::new(RetentionManager::new(3));
+        let build = |file_name: &str| {
+            let sink = RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join(file_name))
+                .rotation_policy(RotationPolicy::FileSize(4))
+                .max_files(10)
+                .retention_manager(manager.clone())
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+            sink
+        };
+        let first = build("first.log");
+        let second = build("second.log");
+
+        let file_count = || fs::read_dir(LOGS_PATH.as_path()).unwrap().count();
+
+        for _ in 0..5 {
+            first.log(&Record::new(Level::Info, "abcd")).unwrap();
+            second.log(&Record::new(Level::Info, "efgh")).unwrap();
+            // 2 files currently being written, plus the shared budget
+            assert!(file_count() <= 2 + 3);
+            assert!(manager.retained_files() <= 3);
+        }
+        assert_eq!(file_count(), 2 + 3);
+
+        // The oldest rotated files are deleted first, regardless of the sink
+        assert!(LOGS_PATH.join("first_1.log").exists());
+        assert!(!LOGS_PATH.join("first_2.log").exists());
+        assert!(LOGS_PATH.join("second_1.log").exists());
+        assert!(LOGS_PATH.join("second_2.log").exists());
+    }
+
+    #[test]
+    fn flush_policy() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("flush_policy");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let path = LOGS_PATH.join("size.log");
+        let sink = RotatingFileSink::builder()
+            .base_path(&path)
+            .rotation_policy(RotationPolicy::FileSize(1024))
+            .flush_policy(FlushPolicy::OnLevel(Level::Warn))
+            .build()
+            .unwrap();
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+
+        sink.log(&Record::new(Level::Info, "a")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        sink.log(&Record::new(Level::Warn, "b")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "ab");
+    }
+
+    #[test]
+    fn manifest() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("manifest");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let base_path = LOGS_PATH.join("hourly.log");
+        let manifest_path = LOGS_PATH.join("hourly.manifest.jsonl");
+
+        let initial_time = SystemTime::now();
+        let clock = Arc::new(crate::MockClock::new(initial_time));
+        let sink = RotatingFileSink::builder()
+            .base_path(&base_path)
+            .rotation_policy(RotationPolicy::Hourly)
+            .manifest(true)
+            .manifest_sync(true)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        sink.set_formatter(Box::new(NoModFormatter::new()));
+        let logger = test_logger_builder().sink(Arc::new(sink)).build().unwrap();
+        logger.set_level_filter(LevelFilter::All);
+
+        let read_manifest = || {
+            fs::read_to_string(&manifest_path)
+                .unwrap()
+                .lines()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+        };
+        let expected_path = |time| {
+            let mut path = String::new();
+            utils::write_json_string(
+                &mut path,
+                &RotatorTimePoint::calc_file_path(&base_path, TimePoint::Hourly, false, time)
+                    .to_string_lossy(),
+            )
+            .unwrap();
+            format!("{{\"path\":{},", path)
+        };
+
+        const SECOND_1: Duration = Duration::from_secs(1);
+        const HOUR_1: Duration = Duration::from_secs(60 * 60);
+
+        let mut record = Record::new(Level::Info, "abcd");
+        record.set_time(initial_time);
+
+        logger.log(&record);
+        assert!(read_manifest().is_empty());
+
+        record.set_time(initial_time + HOUR_1 + SECOND_1);
+        clock.set(record.time());
+        logger.log(&record);
+        let lines = read_manifest();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with(&expected_path(initial_time)));
+        assert!(lines[0].ends_with(",\"size\":4}"));
+
+        record.set_time(initial_time + (HOUR_1 + SECOND_1) * 2);
+        clock.set(record.time());
+        logger.log(&record);
+        let lines = read_manifest();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with(&expected_path(initial_time + HOUR_1 + SECOND_1)));
+        assert!(lines[1].ends_with(",\"size\":4}"));
+    }
+
+    #[test]
+    fn manifest_file_size() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("manifest_file_size");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let build_logger = |base_path: &Path| {
+            let sink = RotatingFileSink::builder()
+                .base_path(base_path)
+                .rotation_policy(RotationPolicy::FileSize(4))
+                .max_files(3)
+                .manifest(true)
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+            let logger = test_logger_builder().sink(Arc::new(sink)).build().unwrap();
+            logger.set_level_filter(LevelFilter::All);
+            logger
+        };
+        let read_manifest = |name| {
+            fs::read_to_string(LOGS_PATH.join(name))
+                .unwrap()
+                .lines()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+        };
+        let json_path = |path: PathBuf| {
+            let mut json = String::new();
+            utils::write_json_string(&mut json, &path.to_string_lossy()).unwrap();
+            format!("{{\"path\":{},", json)
+        };
+
+        // Sinks in the same directory have their own manifests
+        let app_path = LOGS_PATH.join("app.log");
+        let app = build_logger(&app_path);
+        let audit = build_logger(&LOGS_PATH.join("audit.log"));
+        assert!(read_manifest("app.manifest.jsonl").is_empty());
+        assert!(read_manifest("audit.manifest.jsonl").is_empty());
+
+        audit.log(&Record::new(Level::Info, "abcd"));
+        audit.log(&Record::new(Level::Info, "abcd"));
+        assert_eq!(read_manifest("audit.manifest.jsonl").len(), 1);
+
+        app.log(&Record::new(Level::Info, "abcd"));
+        app.log(&Record::new(Level::Info, "efgh"));
+        let lines = read_manifest("app.manifest.jsonl");
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with(&json_path(RotatorFileSize::calc_file_path(&app_path, 1))));
+
+        // The paths follow the files as the indexes shift, and files discarded due
+        // to `max_files` are no longer listed
+        app.log(&Record::new(Level::Info, "ijkl"));
+        app.log(&Record::new(Level::Info, "mnop"));
+        let lines = read_manifest("app.manifest.jsonl");
+        assert_eq!(lines.len(), 2);
+        for (index, line) in lines.iter().enumerate() {
+            let file_path = RotatorFileSize::calc_file_path(&app_path, index + 1);
+            assert!(line.starts_with(&json_path(file_path)));
+            assert!(!line.contains("null"));
+            assert!(line.ends_with(",\"size\":4}"));
+        }
+        assert_eq!(
+            fs::read_to_string(RotatorFileSize::calc_file_path(&app_path, 2)).unwrap(),
+            "efgh"
+        );
+        assert_eq!(read_manifest("audit.manifest.jsonl").len(), 1);
+
+        // Files rotated before the sink was built are listed with unknown time ranges
+        drop(app);
+        let _app = build_logger(&app_path);
+        let lines = read_manifest("app.manifest.jsonl");
+        assert_eq!(lines.len(), 2);
+        assert!(lines
+            .iter()
+            .all(|line| line.contains("\"start\":null,\"end\":null")));
+    }
+
+    #[test]
+    fn marker() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("marker");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        // policy file size
+        {
+            let sink = RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("size.log"))
+                .rotation_policy(RotationPolicy::FileSize(64))
+                .max_files(10)
+                .marker("[{event} {file}]")
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+
+            for _ in 0..10 {
+                sink.log(&Record::new(Level::Info, "0123456789")).unwrap();
+            }
+            sink.flush().unwrap();
+
+            let read = |name| fs::read_to_string(LOGS_PATH.join(name)).unwrap();
+            let records = |count| "0123456789".repeat(count);
+            assert_eq!(
+                read("size_2.log"),
+                format!("[started size.log]{}{}", crate::EOL, records(4))
+            );
+            assert_eq!(
+                read("size_1.log"),
+                format!("[rotated size.log]{}{}", crate::EOL, records(4))
+            );
+            assert_eq!(
+                read("size.log"),
+                format!("[rotated size.log]{}{}", crate::EOL, records(2))
+            );
+        }
+
+        // policy hourly
+        {
+            let start_time: SystemTime =
+                Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+            let clock = Arc::new(crate::MockClock::new(start_time));
+            let sink = RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("hourly.log"))
+                .rotation_policy(RotationPolicy::Hourly)
+                .marker("[{event} {file}]")
+                .clock(clock.clone())
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+
+            for (payload, offset) in [("a", 0), ("b", 10 * 60), ("c", 60 * 60)] {
+                clock.set(start_time + Duration::from_secs(offset));
+                let record = Record::new(Level::Info, payload);
+                sink.log(&record).unwrap();
+            }
+            sink.flush().unwrap();
+
+            let read = |hour| {
+                let file_name = format!("hourly_2012-03-04_{:02}.log", hour);
+                (
+                    fs::read_to_string(LOGS_PATH.join(&file_name)).unwrap(),
+                    file_name,
+                )
+            };
+            let (content, file_name) = read(5);
+            assert_eq!(content, format!("[started {}]{}ab", file_name, crate::EOL));
+            let (content, file_name) = read(6);
+            assert_eq!(content, format!("[rotated {}]{}c", file_name, crate::EOL));
+        }
+    }
+
+    #[test]
+    fn log_preformatted() {
+        let base_path = BASE_LOGS_PATH.join("log_preformatted.log");
+        if base_path.exists() {
+            fs::remove_file(&base_path).unwrap();
+        }
+
+        // Uses the default implementation, which must not run the formatter
+        let sink = RotatingFileSink::builder()
+            .base_path(&base_path)
+            .rotation_policy(RotationPolicy::FileSize(1024))
+            .level_filter(LevelFilter::MoreSevereEqual(Level::Info))
+            .build()
+            .unwrap();
+
+        sink.log_preformatted(b"[relayed] [info] first\n", Level::Info)
+            .unwrap();
+        sink.log_preformatted(b"filtered\n", Level::Debug).unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&base_path).unwrap(),
+            "[relayed] [info] first\n"
+        );
+    }
+
+    #[test]
+    fn write_bom() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("write_bom");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let read = |name| fs::read(LOGS_PATH.join(name)).unwrap();
+        let with_bom = |content: &str| [b"\xEF\xBB\xBF", content.as_bytes()].concat();
+
+        // policy file size
+        {
+            let sink = RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("size.log"))
+                .rotation_policy(RotationPolicy::FileSize(20))
+                .max_files(2)
+                .write_bom(true)
+                .marker("[{event}]")
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+
+            // The BOM counts towards the file size
+            sink.log(&Record::new(Level::Info, "0123")).unwrap();
+            sink.log(&Record::new(Level::Info, "4567")).unwrap();
+            sink.flush().unwrap();
+
+            let marker = |event| format!("[{}]{}", event, crate::EOL);
+            assert_eq!(
+                read("size_1.log"),
+                with_bom(&format!("{}0123", marker("started")))
+            );
+            assert_eq!(
+                read("size.log"),
+                with_bom(&format!("{}4567", marker("rotated")))
+            );
+        }
+
+        // policy hourly
+        {
+            let start_time: SystemTime =
+                Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+            let clock = Arc::new(crate::MockClock::new(start_time));
+            let sink = RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("hourly.log"))
+                .rotation_policy(RotationPolicy::Hourly)
+                .write_bom(true)
+                .clock(clock.clone())
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+
+            for (payload, offset) in [("a", 0), ("b", 60 * 60)] {
+                clock.set(start_time + Duration::from_secs(offset));
+                let record = Record::new(Level::Info, payload);
+                sink.log(&record).unwrap();
+            }
+            sink.flush().unwrap();
+
+            assert_eq!(read("hourly_2012-03-04_05.log"), with_bom("a"));
+            assert_eq!(read("hourly_2012-03-04_06.log"), with_bom("b"));
+        }
+
+        // appending to an existing file
+        {
+            let sink = RotatingFileSink::builder()
+                .base_path(LOGS_PATH.join("size.log"))
+                .rotation_policy(RotationPolicy::FileSize(64))
+                .write_bom(true)
+                .build()
+                .unwrap();
+            sink.set_formatter(Box::new(NoModFormatter::new()));
+            sink.log(&Record::new(Level::Info, "89")).unwrap();
+            sink.flush().unwrap();
+
+            assert_eq!(
+                read("size.log"),
+                with_bom(&format!("[rotated]{}456789", crate::EOL))
+            );
+        }
+    }
+
+    #[test]
+    fn rotation_events() {
+        static LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
+            let path = BASE_LOGS_PATH.join("rotation_events");
+            fs::create_dir_all(&path).unwrap();
+            path
+        });
+
+        fs::remove_dir_all(LOGS_PATH.as_path()).unwrap();
+        fs::create_dir(LOGS_PATH.as_path()).unwrap();
+
+        let start_time: SystemTime = Local.with_ymd_and_hms(2012, 3, 4, 5, 6, 7).unwrap().into();
+        let clock = Arc::new(crate::MockClock::new(start_time));
+        let (builder, events) = RotatingFileSink::builder()
+            .base_path(LOGS_PATH.join("hourly.log"))
+            .rotation_policy(RotationPolicy::Hourly)
+            .clock(clock.clone())
+            .rotation_events(1);
+        let sink = builder.build().unwrap();
+        let hourly_path = |hour| LOGS_PATH.join(format!("hourly_2012-03-04_{:02}.log", hour));
+
+        let log_at = |secs| {
+            clock.set(start_time + Duration::from_secs(secs));
+            let record = Record::new(Level::Info, "test log message");
+            sink.log(&record).unwrap();
+        };
+
+        log_at(10 * 60);
+        assert!(events.try_recv().is_err());
+
+        log_at(60 * 60);
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.closed(), hourly_path(5));
+        assert_eq!(event.opened(), hourly_path(6));
+        assert_eq!(event.at(), start_time + Duration::from_secs(60 * 60));
+
+        log_at(2 * 60 * 60);
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.closed(), hourly_path(6));
+        assert_eq!(event.opened(), hourly_path(7));
+
+        // The channel is full, the event is dropped without blocking.
+        log_at(3 * 60 * 60);
+        log_at(4 * 60 * 60);
+        assert_eq!(events.try_recv().unwrap().opened(), hourly_path(8));
+        assert!(events.try_recv().is_err());
+    }
+
     #[test]
     fn test_builder_optional_params() {
         // workaround for the missing `no_run` attribute
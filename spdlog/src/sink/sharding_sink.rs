@@ -0,0 +1,260 @@
+//! Provides a sink routing records to one of its internal sinks by a key.
+
+use crate::{
+    formatter::Formatter,
+    sink::{helper, Sink, Sinks},
+    sync::*,
+    ErrorHandler, LevelFilter, Record, Result,
+};
+
+/// The function type used by [`ShardingSink`] to compute the key of a record.
+pub type ShardingKeyFn = Box<dyn Fn(&Record) -> u64 + Send + Sync>;
+
+/// A [combined sink] that routes each record to one of its internal sinks by a
+/// key.
+///
+/// The key of a record is computed by a user-provided function, and defaults
+/// to the 64-bit FNV-1a hash of the logger name, which is stable across
+/// processes and Rust versions. Records without a logger name are hashed as if
+/// the name was empty. A record is forwarded only to the sink at
+/// index `key % N`, where `N` is the number of internal sinks, so records with
+/// the same key always land in the same sink. This is useful to split records
+/// deterministically into multiple files for parallel downstream processing.
+///
+/// The assignment only stays the same as long as the number and the order of
+/// the internal sinks do not change. If there are no internal sinks, records
+/// are dropped silently.
+///
+/// [combined sink]: index.html#combined-sink
+pub struct ShardingSink {
+    level_filter: Atomic<LevelFilter>,
+    sinks: Sinks,
+    key_fn: ShardingKeyFn,
+}
+
+impl ShardingSink {
+    /// Constructs a builder of `ShardingSink`.
+    #[must_use]
+    pub fn builder() -> ShardingSinkBuilder {
+        ShardingSinkBuilder {
+            level_filter: helper::SINK_DEFAULT_LEVEL_FILTER,
+            sinks: Sinks::new(),
+            key_fn: None,
+        }
+    }
+
+    /// Gets a reference to internal sinks in the combined sink.
+    #[must_use]
+    pub fn sinks(&self) -> &[Arc<dyn Sink>] {
+        &self.sinks
+    }
+
+    /// Gets the index of the internal sink that the record would be routed to.
+    ///
+    /// Returns `None` if there are no internal sinks.
+    #[must_use]
+    pub fn shard_index(&self, record: &Record) -> Option<usize> {
+        if self.sinks.is_empty() {
+            return None;
+        }
+        Some(((self.key_fn)(record) % self.sinks.len() as u64) as usize)
+    }
+
+    #[must_use]
+    fn default_key(record: &Record) -> u64 {
+        // `DefaultHasher` is not guaranteed to be the same across Rust releases,
+        // which would move loggers to other shards after upgrading.
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+        record
+            .logger_name()
+            .unwrap_or_default()
+            .bytes()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+            })
+    }
+}
+
+impl Sink for ShardingSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        match self.shard_index(record) {
+            Some(index) => self.sinks[index].log(record),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            let res = sink.flush();
+            if result.is_ok() {
+                result = res;
+            }
+        }
+        result
+    }
+
+    /// For [`ShardingSink`], the function performs the same call to all
+    /// internal sinks.
+    fn set_formatter(&self, formatter: Box<dyn Formatter>) {
+        for sink in &self.sinks {
+            sink.set_formatter(formatter.clone_box())
+        }
+    }
+
+    /// For [`ShardingSink`], the function performs the same call to all
+    /// internal sinks.
+    fn set_error_handler(&self, handler: Option<ErrorHandler>) {
+        for sink in &self.sinks {
+            sink.set_error_handler(handler)
+        }
+    }
+
+    helper::common_impl! {
+        @SinkCustom {
+            level_filter: level_filter,
+            formatter: None,
+            error_handler: None,
+        }
+    }
+}
+
+/// The builder of [`ShardingSink`].
+///
+/// # Examples
+///
+/// - Sharding records into 2 files by logger name.
+///
+///   ```no_run
+///   use std::sync::Arc;
+///
+///   use spdlog::{prelude::*, sink::{FileSink, ShardingSink}};
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let shard_0 = Arc::new(FileSink::builder().path("shard_0.log").build()?);
+///   let shard_1 = Arc::new(FileSink::builder().path("shard_1.log").build()?);
+///   let sink: ShardingSink = ShardingSink::builder()
+///       .sink(shard_0)
+///       .sink(shard_1)
+///       // .key(|record| record.payload().len() as u64) // optional
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct ShardingSinkBuilder {
+    level_filter: LevelFilter,
+    sinks: Sinks,
+    key_fn: Option<ShardingKeyFn>,
+}
+
+impl ShardingSinkBuilder {
+    /// Add a [`Sink`] as the next shard.
+    #[must_use]
+    pub fn sink(mut self, sink: Arc<dyn Sink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Add multiple [`Sink`]s as the next shards.
+    #[must_use]
+    pub fn sinks<I>(mut self, sinks: I) -> Self
+    where
+        I: IntoIterator<Item = Arc<dyn Sink>>,
+    {
+        self.sinks.append(&mut sinks.into_iter().collect());
+        self
+    }
+
+    /// Specifies the function computing the key of a record.
+    ///
+    /// The record is forwarded to the internal sink at index `key % N`. The
+    /// function should be deterministic, and spread keys evenly if the shards
+    /// are expected to be balanced.
+    ///
+    /// This parameter is **optional**, and defaults to a hash of the logger
+    /// name.
+    #[must_use]
+    pub fn key<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&Record) -> u64 + Send + Sync + 'static,
+    {
+        self.key_fn = Some(Box::new(key_fn));
+        self
+    }
+
+    /// Builds a [`ShardingSink`].
+    pub fn build(self) -> Result<ShardingSink> {
+        Ok(ShardingSink {
+            level_filter: Atomic::new(self.level_filter),
+            sinks: self.sinks,
+            key_fn: self
+                .key_fn
+                .unwrap_or_else(|| Box::new(ShardingSink::default_key)),
+        })
+    }
+
+    helper::common_impl!(@SinkBuilderCustom {
+        level_filter: level_filter,
+        formatter: None,
+        error_handler: None,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::*};
+
+    #[test]
+    fn consistent_shard() {
+        let shards = (0..4)
+            .map(|_| Arc::new(CounterSink::new()))
+            .collect::<Vec<_>>();
+        let sink = Arc::new(
+            ShardingSink::builder()
+                .sinks(shards.iter().map(|shard| shard.clone() as Arc<dyn Sink>))
+                .build()
+                .unwrap(),
+        );
+        let logger_a = test_logger_builder()
+            .name("a")
+            .sink(sink.clone())
+            .build()
+            .unwrap();
+        let logger_b = test_logger_builder().name("b").sink(sink).build().unwrap();
+
+        for _ in 0..3 {
+            info!(logger: logger_a, "a");
+            info!(logger: logger_b, "b");
+        }
+
+        // All the records with the same key land in the same shard.
+        for payload in ["a", "b"] {
+            let counts = shards
+                .iter()
+                .map(|shard| shard.payloads().iter().filter(|p| *p == payload).count())
+                .filter(|count| *count != 0)
+                .collect::<Vec<_>>();
+            assert_eq!(counts, [3]);
+        }
+        assert_eq!(
+            shards.iter().map(|shard| shard.log_count()).sum::<usize>(),
+            6
+        );
+    }
+
+    #[test]
+    fn default_key_is_fnv1a() {
+        let mut record = Record::new(Level::Info, "payload");
+        assert_eq!(ShardingSink::default_key(&record), 0xcbf29ce484222325);
+        record.set_logger_name("a");
+        assert_eq!(ShardingSink::default_key(&record), 0xaf63dc4c8601ec8c);
+        record.set_logger_name("foobar");
+        assert_eq!(ShardingSink::default_key(&record), 0x85944171f73967e8);
+    }
+}
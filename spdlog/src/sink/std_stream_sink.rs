@@ -3,13 +3,15 @@
 use std::{
     convert::Infallible,
     io::{self, Write},
+    ops::Range,
 };
 
 use if_chain::if_chain;
 
 use crate::{
-    sink::{helper, Sink},
-    terminal_style::{LevelStyleCodes, Style, StyleMode},
+    clock,
+    sink::{helper, FlushPolicy, FlushPolicyState, Sink},
+    terminal_style::{LevelStyleCodes, Style, StyleCode, StyleMode},
     Error, Level, Record, Result, StringBuf,
 };
 
@@ -82,10 +84,12 @@ impl_write_for_dest!(StdStreamDest<io::StdoutLock<'_>, io::StderrLock<'_>>);
 ///
 /// It writes styled text or plain text according to the given [`StyleMode`].
 ///
-/// Note that this sink always flushes the buffer once with each logging.
+/// Note that by default this sink flushes the buffer once with each logging,
+/// see [`StdStreamSinkBuilder::flush_policy`].
 pub struct StdStreamSink {
     common_impl: helper::CommonImpl,
     dest: StdStreamDest<io::Stdout, io::Stderr>,
+    flush_policy: FlushPolicyState,
     should_render_style: bool,
     level_style_codes: LevelStyleCodes,
 }
@@ -98,6 +102,7 @@ impl StdStreamSink {
             common_builder_impl: helper::CommonBuilderImpl::new(),
             std_stream: (),
             style_mode: StyleMode::Auto,
+            flush_policy: FlushPolicy::EveryN(1),
         }
     }
 
@@ -148,11 +153,7 @@ impl Sink for StdStreamSink {
         }
 
         let mut string_buf = StringBuf::new();
-        let extra_info = self
-            .common_impl
-            .formatter
-            .read()
-            .format(record, &mut string_buf)?;
+        let extra_info = self.common_impl.format(record, &mut string_buf)?;
 
         let mut dest = self.dest.lock();
 
@@ -161,13 +162,18 @@ impl Sink for StdStreamSink {
                 if self.should_render_style;
                 if let Some(style_range) = extra_info.style_range();
                 then {
-                    let style_code = self.level_style_codes.code(record.level());
-
-                    dest.write_all(string_buf[..style_range.start].as_bytes())?;
-                    dest.write_all(style_code.start.as_bytes())?;
-                    dest.write_all(string_buf[style_range.start..style_range.end].as_bytes())?;
-                    dest.write_all(style_code.end.as_bytes())?;
-                    dest.write_all(string_buf[style_range.end..].as_bytes())?;
+                    debug_assert!(
+                        string_buf.is_char_boundary(style_range.start)
+                            && string_buf.is_char_boundary(style_range.end),
+                        "style range {:?} is not on UTF-8 char boundaries",
+                        style_range
+                    );
+                    write_styled(
+                        &mut dest,
+                        &string_buf,
+                        style_range,
+                        self.level_style_codes.code(record.level()),
+                    )?;
                 } else {
                     dest.write_all(string_buf.as_bytes())?;
                 }
@@ -179,7 +185,9 @@ impl Sink for StdStreamSink {
         // stderr is not buffered, so we don't need to flush it.
         // https://doc.rust-lang.org/std/io/fn.stderr.html
         if let StdStreamDest::Stdout(_) = dest {
-            dest.flush().map_err(Error::FlushBuffer)?;
+            if self.flush_policy.should_flush(record.level()) {
+                dest.flush().map_err(Error::FlushBuffer)?;
+            }
         }
 
         Ok(())
@@ -192,6 +200,41 @@ impl Sink for StdStreamSink {
     helper::common_impl!(@Sink: common_impl);
 }
 
+// Formatters are responsible for placing style range boundaries on UTF-8 char
+// boundaries, but a buggy one should not make us slice in the middle of a char
+// and panic, so widen the range to the nearest boundaries instead.
+#[must_use]
+fn snap_to_char_boundary(s: &str, range: Range<usize>) -> Range<usize> {
+    let mut start = range.start.min(s.len());
+    while !s.is_char_boundary(start) {
+        start -= 1;
+    }
+
+    let mut end = range.end.clamp(start, s.len());
+    while !s.is_char_boundary(end) {
+        end += 1;
+    }
+
+    start..end
+}
+
+// Writes the formatted text, wrapping the style range with the style code.
+fn write_styled(
+    dest: &mut impl Write,
+    text: &str,
+    style_range: Range<usize>,
+    style_code: &StyleCode,
+) -> io::Result<()> {
+    let style_range = snap_to_char_boundary(text, style_range);
+    let bytes = text.as_bytes();
+
+    dest.write_all(&bytes[..style_range.start])?;
+    dest.write_all(style_code.start.as_bytes())?;
+    dest.write_all(&bytes[style_range.start..style_range.end])?;
+    dest.write_all(style_code.end.as_bytes())?;
+    dest.write_all(&bytes[style_range.end..])
+}
+
 // --------------------------------------------------
 
 /// The builder of [`StdStreamSink`].
@@ -236,6 +279,7 @@ pub struct StdStreamSinkBuilder<ArgSS> {
     common_builder_impl: helper::CommonBuilderImpl,
     std_stream: ArgSS,
     style_mode: StyleMode,
+    flush_policy: FlushPolicy,
 }
 
 impl<ArgSS> StdStreamSinkBuilder<ArgSS> {
@@ -248,6 +292,7 @@ impl<ArgSS> StdStreamSinkBuilder<ArgSS> {
             common_builder_impl: self.common_builder_impl,
             std_stream,
             style_mode: self.style_mode,
+            flush_policy: self.flush_policy,
         }
     }
 
@@ -260,6 +305,18 @@ impl<ArgSS> StdStreamSinkBuilder<ArgSS> {
         self
     }
 
+    /// Specifies when the sink flushes stdout after logging a record.
+    ///
+    /// Since stderr is not buffered, this parameter has no effect on it.
+    ///
+    /// This parameter is **optional**, and defaults to
+    /// [`FlushPolicy::EveryN`]`(1)`, i.e. flushes after every record.
+    #[must_use]
+    pub fn flush_policy(mut self, flush_policy: FlushPolicy) -> Self {
+        self.flush_policy = flush_policy;
+        self
+    }
+
     helper::common_impl!(@SinkBuilder: common_builder_impl);
 }
 
@@ -278,6 +335,7 @@ impl StdStreamSinkBuilder<StdStream> {
         Ok(StdStreamSink {
             common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
             dest: StdStreamDest::new(self.std_stream),
+            flush_policy: FlushPolicyState::new(self.flush_policy, clock::default_clock()),
             should_render_style: StdStreamSink::should_render_style(
                 self.style_mode,
                 self.std_stream,
@@ -327,3 +385,89 @@ fn enable_ansi_escape_sequences() -> bool {
 fn enable_ansi_escape_sequences() -> bool {
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_style_range_to_char_boundary() {
+        // "名" and "字" are 3 bytes each in UTF-8
+        let s = "[名字] [info] hello";
+        let level_start = s.find("info").unwrap();
+
+        assert_eq!(
+            snap_to_char_boundary(s, level_start..level_start + 4),
+            level_start..level_start + 4
+        );
+
+        let snapped = snap_to_char_boundary(s, 2..6);
+        assert_eq!(snapped, 1..7);
+        assert_eq!(&s[snapped], "名字");
+
+        assert_eq!(snap_to_char_boundary(s, 3..100), 1..s.len());
+        assert_eq!(snap_to_char_boundary(s, 100..200), s.len()..s.len());
+    }
+
+    #[test]
+    fn write_styled_multibyte() {
+        let style_code = LevelStyleCodes::default().code(Level::Info).clone();
+        let styled = |text: &str, style_range: Range<usize>| {
+            let mut dest = Vec::new();
+            write_styled(&mut dest, text, style_range, &style_code).unwrap();
+            String::from_utf8(dest).unwrap()
+        };
+
+        // "名字" is a multi-byte logger name before the level
+        let text = "[名字] [info] hello\n";
+        let level_start = text.find("info").unwrap();
+        assert_eq!(
+            styled(text, level_start..level_start + 4),
+            format!(
+                "[名字] [{}info{}] hello\n",
+                style_code.start, style_code.end
+            )
+        );
+
+        // Starts and ends in the middle of "名" and "字", snapped to the whole chars
+        assert_eq!(
+            styled(text, 2..6),
+            format!(
+                "[{}名字{}] [info] hello\n",
+                style_code.start, style_code.end
+            )
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "is not on UTF-8 char boundaries")]
+    fn log_with_style_range_inside_multibyte_char() {
+        use crate::formatter::{FmtExtraInfo, Formatter};
+
+        #[derive(Clone)]
+        struct SplitCharFormatter;
+
+        impl Formatter for SplitCharFormatter {
+            fn format(&self, record: &Record, dest: &mut StringBuf) -> Result<FmtExtraInfo> {
+                dest.push_str(record.payload());
+                // Starts and ends in the middle of "名" and "字"
+                Ok(FmtExtraInfo::builder().style_range(1..5).build())
+            }
+
+            fn clone_box(&self) -> Box<dyn Formatter> {
+                Box::new(self.clone())
+            }
+        }
+
+        let sink = StdStreamSink::builder()
+            .std_stream(StdStream::Stderr)
+            .style_mode(StyleMode::Always)
+            .formatter(Box::new(SplitCharFormatter))
+            .build()
+            .unwrap();
+
+        // Panics before anything is written
+        let _ = sink.log(&Record::new(Level::Info, "名字\n"));
+    }
+}
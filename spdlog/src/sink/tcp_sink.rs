@@ -0,0 +1,483 @@
+//! Provides a TCP sink.
+
+use std::{
+    convert::Infallible,
+    io::Write,
+    net::{SocketAddr, TcpStream},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    clock::{self, Clock},
+    sink::{helper, Backoff, BackoffDelays, Sink},
+    sync::*,
+    Error, Record, Result, StringBuf,
+};
+
+/// A sink that ships formatted records to a remote collector over TCP.
+///
+/// Each record is formatted by the formatter of the sink, and the bytes are
+/// written to a TCP stream connected to the configured address. The stream is
+/// connected lazily when the first record is logged, so building the sink
+/// succeeds even if the collector is not reachable yet.
+///
+/// When the connection is not established or drops, records are kept in an
+/// in-memory buffer and the sink reconnects on subsequent `log` or `flush`
+/// calls, waiting between attempts according to its [`Backoff`] policy. The
+/// wait never blocks the caller, records logged while waiting are just
+/// buffered. Once reconnected, the buffered bytes are written before the new
+/// record. When the buffer is full, records are dropped and
+/// [`Error::WriteRecord`] is returned.
+///
+/// Once a record is buffered, `log` returns `Ok`, since the record will be
+/// written after reconnecting and logging it again would duplicate it. The
+/// error of the failed connection attempt ([`Error::ConnectSocket`]) or write
+/// ([`Error::WriteRecord`]) is passed to the error handler of the sink
+/// instead. Since it's unknown how many bytes of a failed write reached the
+/// collector, the record may be received partially and then again in full
+/// after reconnecting.
+///
+/// Connecting and writing block the logging thread for at most the configured
+/// [`connect_timeout`] and [`write_timeout`] respectively. Combine the sink
+/// with an [`AsyncPoolSink`] to move the I/O off the logging threads.
+///
+/// [`connect_timeout`]: TcpSinkBuilder::connect_timeout
+/// [`write_timeout`]: TcpSinkBuilder::write_timeout
+/// [`AsyncPoolSink`]: crate::sink::AsyncPoolSink
+pub struct TcpSink {
+    common_impl: helper::CommonImpl,
+    address: SocketAddr,
+    connect_timeout: Duration,
+    write_timeout: Duration,
+    max_buffer_size: usize,
+    clock: Arc<dyn Clock>,
+    // A blocking mutex, since connecting and writing happen while it's held.
+    state: Mutex<TcpSinkState>,
+}
+
+struct TcpSinkState {
+    stream: Option<TcpStream>,
+    buffer: Vec<u8>,
+    delays: BackoffDelays,
+    next_attempt: Option<SystemTime>,
+}
+
+impl TcpSink {
+    /// Constructs a builder of `TcpSink`.
+    #[must_use]
+    pub fn builder() -> TcpSinkBuilder<()> {
+        TcpSinkBuilder {
+            common_builder_impl: helper::CommonBuilderImpl::new(),
+            address: (),
+            connect_timeout: Duration::from_secs(10),
+            write_timeout: Duration::from_secs(10),
+            backoff: Backoff::default(),
+            max_buffer_size: 1024 * 1024,
+            clock: clock::default_clock(),
+        }
+    }
+
+    /// Gets the address of the remote collector.
+    #[must_use]
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns `true` if the sink is currently connected to the collector.
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.state.lock_expect().stream.is_some()
+    }
+
+    /// Gets the number of bytes buffered while the sink is not connected.
+    #[must_use]
+    pub fn buffered_bytes(&self) -> usize {
+        self.state.lock_expect().buffer.len()
+    }
+
+    fn connect(&self) -> Result<TcpStream> {
+        let stream = TcpStream::connect_timeout(&self.address, self.connect_timeout)
+            .map_err(Error::ConnectSocket)?;
+        stream
+            .set_write_timeout(Some(self.write_timeout))
+            .map_err(Error::ConnectSocket)?;
+        Ok(stream)
+    }
+
+    // Ensures the stream is connected and the buffered bytes are written.
+    //
+    // Returns `Ok(false)` if it's not the time to reconnect yet.
+    fn reconnect_and_drain(&self, state: &mut TcpSinkState) -> Result<bool> {
+        if state.stream.is_none() {
+            if matches!(state.next_attempt, Some(next_attempt) if self.clock.now() < next_attempt) {
+                return Ok(false);
+            }
+            match self.connect() {
+                Ok(stream) => {
+                    state.stream = Some(stream);
+                    state.delays.reset();
+                    state.next_attempt = None;
+                }
+                Err(err) => {
+                    let delay = state.delays.next().unwrap_or_default();
+                    state.next_attempt = Some(self.clock.now() + delay);
+                    return Err(err);
+                }
+            }
+        }
+
+        if !state.buffer.is_empty() {
+            let stream = state.stream.as_mut().unwrap();
+            if let Err(err) = stream.write_all(&state.buffer) {
+                // Reconnect on the next call without waiting, the connection
+                // was established successfully before.
+                state.stream = None;
+                return Err(Error::WriteRecord(err));
+            }
+            state.buffer.clear();
+        }
+        Ok(true)
+    }
+
+    fn push_to_buffer(&self, state: &mut TcpSinkState, bytes: &[u8]) -> Result<()> {
+        if state.buffer.len() + bytes.len() > self.max_buffer_size {
+            return Err(Error::WriteRecord(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "the buffer of TcpSink is full, the record is dropped",
+            )));
+        }
+        state.buffer.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl Sink for TcpSink {
+    fn log(&self, record: &Record) -> Result<()> {
+        if !self.should_log(record.level()) {
+            return Ok(());
+        }
+
+        let mut string_buf = StringBuf::new();
+        self.common_impl.format(record, &mut string_buf)?;
+        let bytes = string_buf.as_bytes();
+
+        let mut state = self.state.lock_expect();
+        let err = match self.reconnect_and_drain(&mut state) {
+            Ok(true) => {
+                let stream = state.stream.as_mut().unwrap();
+                match stream.write_all(bytes) {
+                    Ok(()) => return Ok(()),
+                    Err(err) => {
+                        state.stream = None;
+                        Error::WriteRecord(err)
+                    }
+                }
+            }
+            Ok(false) => return self.push_to_buffer(&mut state, bytes),
+            Err(err) => err,
+        };
+
+        // The record is written after reconnecting, so the error is not returned,
+        // otherwise callers retrying the record would buffer it again.
+        self.push_to_buffer(&mut state, bytes)?;
+        self.common_impl.non_returnable_error("TcpSink", err);
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut state = self.state.lock_expect();
+        if state.stream.is_none() && state.buffer.is_empty() {
+            return Ok(());
+        }
+        if !self.reconnect_and_drain(&mut state)? {
+            return Ok(());
+        }
+
+        let stream = state.stream.as_mut().unwrap();
+        if let Err(err) = stream.flush() {
+            state.stream = None;
+            return Err(Error::FlushBuffer(err));
+        }
+        Ok(())
+    }
+
+    helper::common_impl!(@Sink: common_impl);
+}
+
+impl Drop for TcpSink {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            self.common_impl.non_returnable_error("TcpSink", err)
+        }
+    }
+}
+
+// --------------------------------------------------
+
+/// The builder of [`TcpSink`].
+#[doc = include_str!("../include/doc/generic-builder-note.md")]
+/// # Examples
+///
+/// - Building a [`TcpSink`].
+///
+///   ```no_run
+///   use std::time::Duration;
+///
+///   use spdlog::sink::TcpSink;
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: TcpSink = TcpSink::builder()
+///       .address(([10, 0, 0, 1], 5170)) // required
+///       // .connect_timeout(Duration::from_secs(3)) // optional, defaults to 10 seconds
+///       .build()?;
+///   # Ok(()) }
+///   ```
+///
+/// - If any required parameters are missing, a compile-time error will be
+///   raised.
+///
+///   ```compile_fail,E0061
+///   use std::time::Duration;
+///
+///   use spdlog::sink::TcpSink;
+///
+///   # fn main() -> Result<(), spdlog::Error> {
+///   let sink: TcpSink = TcpSink::builder()
+///       // .address(([10, 0, 0, 1], 5170)) // required
+///       .connect_timeout(Duration::from_secs(3)) // optional, defaults to 10 seconds
+///       .build()?;
+///   # Ok(()) }
+///   ```
+pub struct TcpSinkBuilder<ArgAddress> {
+    common_builder_impl: helper::CommonBuilderImpl,
+    address: ArgAddress,
+    connect_timeout: Duration,
+    write_timeout: Duration,
+    backoff: Backoff,
+    max_buffer_size: usize,
+    clock: Arc<dyn Clock>,
+}
+
+impl<ArgAddress> TcpSinkBuilder<ArgAddress> {
+    /// The address of the remote collector.
+    ///
+    /// This parameter is **required**.
+    #[must_use]
+    pub fn address<A>(self, address: A) -> TcpSinkBuilder<SocketAddr>
+    where
+        A: Into<SocketAddr>,
+    {
+        TcpSinkBuilder {
+            common_builder_impl: self.common_builder_impl,
+            address: address.into(),
+            connect_timeout: self.connect_timeout,
+            write_timeout: self.write_timeout,
+            backoff: self.backoff,
+            max_buffer_size: self.max_buffer_size,
+            clock: self.clock,
+        }
+    }
+
+    /// Specifies the timeout of each connection attempt. It must not be zero.
+    ///
+    /// This parameter is **optional**, and defaults to 10 seconds.
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Specifies the timeout of each write to the stream. It must not be zero.
+    ///
+    /// A write that times out is handled like a dropped connection, the
+    /// record is buffered and the sink reconnects.
+    ///
+    /// This parameter is **optional**, and defaults to 10 seconds.
+    #[must_use]
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = timeout;
+        self
+    }
+
+    /// Specifies the policy of waiting between reconnection attempts.
+    ///
+    /// This parameter is **optional**, and defaults to [`Backoff::default`].
+    #[must_use]
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Specifies the maximum number of bytes buffered while the sink is not
+    /// connected.
+    ///
+    /// This parameter is **optional**, and defaults to 1 MiB.
+    #[must_use]
+    pub fn max_buffer_size(mut self, max_buffer_size: usize) -> Self {
+        self.max_buffer_size = max_buffer_size;
+        self
+    }
+
+    /// Specifies the clock providing the current time.
+    ///
+    /// The clock decides when the wait between reconnection attempts is over,
+    /// a [`MockClock`] makes it deterministic in tests.
+    ///
+    /// This parameter is **optional**, and defaults to [`SystemClock`].
+    ///
+    /// [`MockClock`]: crate::MockClock
+    /// [`SystemClock`]: crate::SystemClock
+    #[must_use]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    helper::common_impl!(@SinkBuilder: common_builder_impl);
+}
+
+impl TcpSinkBuilder<()> {
+    #[doc(hidden)]
+    #[deprecated(note = "\n\n\
+        builder compile-time error:\n\
+        - missing required field `address`\n\n\
+    ")]
+    pub fn build(self, _: Infallible) {}
+}
+
+impl TcpSinkBuilder<SocketAddr> {
+    /// Builds a [`TcpSink`].
+    ///
+    /// The stream is not connected until the first record is logged.
+    pub fn build(self) -> Result<TcpSink> {
+        Ok(TcpSink {
+            common_impl: helper::CommonImpl::from_builder(self.common_builder_impl),
+            address: self.address,
+            connect_timeout: self.connect_timeout,
+            write_timeout: self.write_timeout,
+            max_buffer_size: self.max_buffer_size,
+            clock: self.clock,
+            state: Mutex::new(TcpSinkState {
+                stream: None,
+                buffer: vec![],
+                delays: self.backoff.delays(),
+                next_attempt: None,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Read,
+        net::{Ipv4Addr, TcpListener},
+    };
+
+    use super::*;
+    use crate::{test_utils::*, Level};
+
+    fn read_all(listener: &TcpListener) -> String {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        received
+    }
+
+    #[test]
+    fn ship_records() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let sink = TcpSink::builder()
+            .address(listener.local_addr().unwrap())
+            .formatter(Box::new(NoModFormatter::new()))
+            .build()
+            .unwrap();
+
+        sink.log(&Record::new(Level::Info, "hello ")).unwrap();
+        sink.log(&Record::new(Level::Info, "world")).unwrap();
+        assert!(sink.is_connected());
+        drop(sink);
+
+        assert_eq!(read_all(&listener), "hello world");
+    }
+
+    #[test]
+    fn buffer_and_reconnect() {
+        // Find a free port, and then stop listening on it.
+        let address = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let backoff = Backoff::builder()
+            .initial(Duration::ZERO)
+            .max(Duration::ZERO)
+            .multiplier(1.0)
+            .build()
+            .unwrap();
+        static CONNECT_ERRORS: AtomicUsize = AtomicUsize::new(0);
+        let sink = TcpSink::builder()
+            .address(address)
+            .backoff(backoff)
+            .formatter(Box::new(NoModFormatter::new()))
+            .error_handler(|err| {
+                assert!(matches!(err, Error::ConnectSocket(_)));
+                CONNECT_ERRORS.fetch_add(1, Ordering::Relaxed);
+            })
+            .build()
+            .unwrap();
+
+        sink.log(&Record::new(Level::Info, "hello ")).unwrap();
+        assert_eq!(CONNECT_ERRORS.load(Ordering::Relaxed), 1);
+        assert!(!sink.is_connected());
+        assert_eq!(sink.buffered_bytes(), 6);
+
+        let listener = TcpListener::bind(address).unwrap();
+        sink.log(&Record::new(Level::Info, "world")).unwrap();
+        assert!(sink.is_connected());
+        assert_eq!(sink.buffered_bytes(), 0);
+        drop(sink);
+
+        assert_eq!(read_all(&listener), "hello world");
+    }
+
+    #[test]
+    fn backoff_with_mock_clock() {
+        // Find a free port, and then stop listening on it.
+        let address = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let backoff = Backoff::builder()
+            .initial(Duration::from_secs(60))
+            .max(Duration::from_secs(60))
+            .multiplier(1.0)
+            .build()
+            .unwrap();
+        let clock = Arc::new(clock::MockClock::new(SystemTime::UNIX_EPOCH));
+        let sink = TcpSink::builder()
+            .address(address)
+            .backoff(backoff)
+            .clock(clock.clone())
+            .formatter(Box::new(NoModFormatter::new()))
+            .error_handler(|_| {})
+            .build()
+            .unwrap();
+
+        sink.log(&Record::new(Level::Info, "a")).unwrap();
+        assert!(!sink.is_connected());
+
+        // Not reconnecting until the delay has elapsed on the clock
+        let listener = TcpListener::bind(address).unwrap();
+        clock.advance(Duration::from_secs(59));
+        sink.log(&Record::new(Level::Info, "b")).unwrap();
+        assert!(!sink.is_connected());
+        assert_eq!(sink.buffered_bytes(), 2);
+
+        clock.advance(Duration::from_secs(1));
+        sink.log(&Record::new(Level::Info, "c")).unwrap();
+        assert!(sink.is_connected());
+        drop(sink);
+
+        assert_eq!(read_all(&listener), "abc");
+    }
+}
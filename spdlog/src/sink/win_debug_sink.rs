@@ -41,10 +41,7 @@ impl Sink for WinDebugSink {
         }
 
         let mut string_buf = StringBuf::new();
-        self.common_impl
-            .formatter
-            .read()
-            .format(record, &mut string_buf)?;
+        self.common_impl.format(record, &mut string_buf)?;
 
         let wide: Vec<u16> = OsStr::new(&string_buf)
             .encode_wide()
@@ -3,7 +3,7 @@ use std::{convert::Infallible, io::Write, marker::PhantomData};
 use crate::{
     sink::{helper, Sink},
     sync::*,
-    Error, Record, Result, StringBuf,
+    Error, Level, Record, Result, StringBuf,
 };
 
 /// A sink that writes log messages into an arbitrary `impl Write` object.
@@ -64,7 +64,7 @@ where
         callback(&mut *self.lock_target())
     }
 
-    fn lock_target(&self) -> MutexGuard<W> {
+    fn lock_target(&self) -> MutexGuard<'_, W> {
         self.target.lock_expect()
     }
 }
@@ -90,10 +90,7 @@ where
         }
 
         let mut string_buf = StringBuf::new();
-        self.common_impl
-            .formatter
-            .read()
-            .format(record, &mut string_buf)?;
+        self.common_impl.format(record, &mut string_buf)?;
 
         self.lock_target()
             .write_all(string_buf.as_bytes())
@@ -102,6 +99,16 @@ where
         Ok(())
     }
 
+    fn log_preformatted(&self, bytes: &[u8], level: Level) -> Result<()> {
+        if !self.should_log(level) {
+            return Ok(());
+        }
+
+        self.lock_target()
+            .write_all(bytes)
+            .map_err(Error::WriteRecord)
+    }
+
     fn flush(&self) -> Result<()> {
         self.lock_target().flush().map_err(Error::FlushBuffer)
     }
@@ -224,4 +231,62 @@ mod tests {
         let data = sink.clone_target();
         assert_eq!(data.as_slice(), b"hello WriteSink");
     }
+
+    #[test]
+    fn eol_override() {
+        let sink = Arc::new(
+            WriteSink::builder()
+                .target(Vec::new())
+                .formatter(Box::new(NoModFormatter::new()))
+                .eol_override("\r\n")
+                .build()
+                .unwrap(),
+        );
+        let logger = test_logger_builder()
+            .sink(sink.clone())
+            .level_filter(LevelFilter::All)
+            .build()
+            .unwrap();
+
+        info!(logger: logger, "hello\n");
+        info!(logger: logger, "WriteSink\r\n");
+        info!(logger: logger, "no eol");
+
+        let data = sink.clone_target();
+        assert_eq!(data.as_slice(), b"hello\r\nWriteSink\r\nno eol");
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn disk_full() {
+        struct DiskFullWriter;
+
+        impl Write for DiskFullWriter {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                #[cfg(unix)]
+                let code = libc::ENOSPC;
+                #[cfg(windows)]
+                let code = windows_sys::Win32::Foundation::ERROR_DISK_FULL as i32;
+
+                Err(std::io::Error::from_raw_os_error(code))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let sink = WriteSink::builder().target(DiskFullWriter).build().unwrap();
+        let err = sink
+            .log(&Record::new(Level::Info, "hello WriteSink"))
+            .unwrap_err();
+        assert!(matches!(err, Error::WriteRecord(_)));
+        assert!(err.is_disk_full());
+
+        let err = Error::WriteRecord(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "not a disk full error",
+        ));
+        assert!(!err.is_disk_full());
+    }
 }
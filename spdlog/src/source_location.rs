@@ -2,6 +2,8 @@
 
 use std::path;
 
+use crate::sync::*;
+
 /// Represents a location in source code.
 ///
 /// Usually users don't need to construct it manually, but if you do, use macro
@@ -30,6 +32,20 @@ impl SourceLocation {
         }
     }
 
+    /// Constructs a placeholder `SourceLocation`, whose module path and file
+    /// are both `name`, and whose line and column are both `0`.
+    ///
+    /// It is intended to be passed to [`set_source_location_placeholder`].
+    #[must_use]
+    pub fn placeholder(name: &'static str) -> Self {
+        Self {
+            module_path: name,
+            file: name,
+            line: 0,
+            column: 0,
+        }
+    }
+
     /// Gets the module path.
     #[must_use]
     pub fn module_path(&self) -> &'static str {
@@ -89,6 +105,50 @@ impl SourceLocation {
     }
 }
 
+static PLACEHOLDER: SpinRwLock<Option<SourceLocation>> = SpinRwLock::new(None);
+
+/// Sets a placeholder used in place of the source location of records that
+/// don't have one.
+///
+/// When crate feature `source-location` is enabled, records may still be built
+/// without a source location, e.g. records forwarded from crate `log` lacking
+/// file information. By default, the source location patterns (`{source}`,
+/// `{file}`, `{line}`, etc.) write nothing and [`FullFormatter`] omits the
+/// source location part for such records, so their output doesn't line up with
+/// other records. Setting a placeholder makes them write the placeholder
+/// instead.
+///
+/// Passing `None` removes the placeholder. This function has no effect if crate
+/// feature `source-location` is disabled.
+///
+/// # Examples
+///
+/// ```
+/// use spdlog::SourceLocation;
+///
+/// // Writes `<unknown>:0` for the `{source}` pattern.
+/// spdlog::set_source_location_placeholder(Some(SourceLocation::placeholder("<unknown>")));
+/// ```
+///
+/// [`FullFormatter`]: crate::formatter::FullFormatter
+pub fn set_source_location_placeholder(placeholder: Option<SourceLocation>) {
+    *PLACEHOLDER.write() = placeholder;
+}
+
+// Returns the given source location, or the placeholder if it's `None`.
+#[must_use]
+pub(crate) fn source_location_or_placeholder(
+    srcloc: Option<&SourceLocation>,
+) -> Option<SourceLocation> {
+    match srcloc {
+        Some(srcloc) => Some(srcloc.clone()),
+        #[cfg(feature = "source-location")]
+        None => PLACEHOLDER.read().clone(),
+        #[cfg(not(feature = "source-location"))]
+        None => None,
+    }
+}
+
 /// Constructs a [`SourceLocation`] with current source location.
 ///
 /// Returns `None` if the feature `source-location` is not enabled.
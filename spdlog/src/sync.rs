@@ -4,10 +4,7 @@ pub use std::sync::{
 
 pub use arc_swap::{ArcSwap, ArcSwapOption};
 pub use once_cell::sync::{Lazy, OnceCell};
-pub use spin::{
-    Mutex as SpinMutex, MutexGuard as SpinMutexGuard, RwLock as SpinRwLock,
-    RwLockReadGuard as SpinRwLockReadGuard, RwLockWriteGuard as SpinRwLockWriteGuard,
-};
+pub use spin::{Mutex as SpinMutex, RwLock as SpinRwLock};
 
 pub mod atomic {
     pub use std::sync::atomic::*;
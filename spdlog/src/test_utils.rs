@@ -1,10 +1,10 @@
-use std::{env, fs, path::PathBuf, thread::sleep, time::Duration};
+use std::{env, fs, io, path::PathBuf, thread::sleep, time::Duration};
 
 use crate::{
     formatter::{FmtExtraInfo, Formatter},
     sink::Sink,
     sync::*,
-    ErrorHandler, LevelFilter, Logger, LoggerBuilder, Record, Result, StringBuf,
+    Error, ErrorHandler, LevelFilter, Logger, LoggerBuilder, Record, Result, StringBuf,
 };
 
 pub static TEST_LOGS_PATH: Lazy<PathBuf> = Lazy::new(|| {
@@ -115,6 +115,41 @@ impl Default for CounterSink {
     }
 }
 
+// A sink whose `log` and `flush` always fail.
+pub struct FailingSink;
+
+impl Sink for FailingSink {
+    fn log(&self, _record: &Record) -> Result<()> {
+        Err(Error::WriteRecord(io::Error::new(
+            io::ErrorKind::Other,
+            "FailingSink::log",
+        )))
+    }
+
+    fn flush(&self) -> Result<()> {
+        Err(Error::FlushBuffer(io::Error::new(
+            io::ErrorKind::Other,
+            "FailingSink::flush",
+        )))
+    }
+
+    fn level_filter(&self) -> LevelFilter {
+        LevelFilter::All
+    }
+
+    fn set_level_filter(&self, _level_filter: LevelFilter) {
+        // no-op
+    }
+
+    fn set_formatter(&self, _formatter: Box<dyn Formatter>) {
+        // no-op
+    }
+
+    fn set_error_handler(&self, _handler: Option<ErrorHandler>) {
+        // no-op
+    }
+}
+
 impl NoModFormatter {
     #[must_use]
     pub fn new() -> Self {
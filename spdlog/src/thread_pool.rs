@@ -1,11 +1,18 @@
-use std::thread::{self, JoinHandle};
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
-use crossbeam::channel::{self as mpmc, Receiver, Sender};
+use crossbeam::channel::{
+    self as mpmc, Receiver, SendError, SendTimeoutError, Sender, TryRecvError, TrySendError,
+};
 use once_cell::sync::Lazy;
 
 use crate::{
     error::{Error, InvalidArgumentError},
-    sink::{OverflowPolicy, Task},
+    sink::{Backend, OverflowPolicy, Task},
     sync::*,
     Result,
 };
@@ -34,6 +41,7 @@ use crate::{
 pub struct ThreadPool {
     threads: Vec<Option<JoinHandle<()>>>,
     sender: Option<Sender<Task>>,
+    evicting_queue: Arc<EvictingQueue>,
 }
 
 /// The builder of [`ThreadPool`].
@@ -44,6 +52,39 @@ pub struct ThreadPoolBuilder {
 
 struct Worker {
     receiver: Receiver<Task>,
+    evicting_queue: Arc<EvictingQueue>,
+}
+
+// A bounded queue of the tasks of sinks using `OverflowPolicy::DropOldest`.
+//
+// A channel cannot be used for them, because a task of a specific sink needs to
+// be evicted from the middle of the queue. Tasks of other sinks go through the
+// lock-free channel instead, so that they don't contend on the lock.
+struct EvictingQueue {
+    state: Mutex<EvictingQueueState>,
+    // The number of queued tasks, so that workers can check the queue without
+    // locking it.
+    len: AtomicUsize,
+    capacity: usize,
+    // Notified when a task is popped while pushers are waiting for room.
+    popped: Condvar,
+    // A token is sent after a task is pushed, to wake up a worker waiting on the
+    // channel. The channel is bounded to 1, since a pending token is enough to make
+    // a worker check the queue again.
+    pushed_sender: Sender<()>,
+    pushed_receiver: Receiver<()>,
+}
+
+struct EvictingQueueState {
+    tasks: VecDeque<Task>,
+    // The number of pushers waiting on `popped`.
+    waiting: usize,
+}
+
+thread_local! {
+    // The address of the evicting queue of the thread pool that the current thread
+    // is a worker of, or 0 if it's not a worker.
+    static WORKER_OF: Cell<usize> = const { Cell::new(0) };
 }
 
 impl ThreadPool {
@@ -61,16 +102,96 @@ impl ThreadPool {
         Self::builder().build()
     }
 
-    pub(super) fn assign_task(&self, task: Task, overflow_policy: OverflowPolicy) -> Result<()> {
-        let sender = self.sender.as_ref().unwrap();
+    /// Gets the number of operations currently queued in the channel, waiting
+    /// to be processed.
+    ///
+    /// The channel is shared by all sinks using this thread pool, the
+    /// operations of sinks using [`OverflowPolicy::DropOldest`] are queued
+    /// separately and included.
+    ///
+    /// This only reads the lengths of the queues without locking them, so it
+    /// doesn't contend with logging threads or workers. Since the queues are
+    /// read one after another, the sum is approximate while they're changing.
+    #[must_use]
+    pub fn queue_len(&self) -> usize {
+        self.sender().len() + self.evicting_queue.len.load(Ordering::Relaxed)
+    }
+
+    /// Gets the capacity of the operation channel.
+    ///
+    /// See [`ThreadPoolBuilder::capacity`].
+    #[must_use]
+    pub fn queue_capacity(&self) -> usize {
+        self.evicting_queue.capacity
+    }
+
+    // Returns `true` if the current thread is a worker of this thread pool, in
+    // which case waiting for a task to be processed would wait on itself.
+    #[must_use]
+    pub(super) fn is_current_thread_worker(&self) -> bool {
+        WORKER_OF.with(|worker_of| worker_of.get() == self.evicting_queue.address())
+    }
+
+    // Assigns the task, blocking for at most `timeout` if the channel is full,
+    // fails with `Error::ShutdownTimeout` if it's still full then.
+    pub(super) fn assign_task_timeout(&self, task: Task, timeout: Duration) -> Result<()> {
+        if task.uses_evicting_queue() {
+            return self.evicting_queue.push_timeout(task, timeout);
+        }
+
+        self.sender()
+            .send_timeout(task, timeout)
+            .map_err(|err| match err {
+                SendTimeoutError::Timeout(_) => Error::ShutdownTimeout,
+                SendTimeoutError::Disconnected(task) => Error::from_crossbeam_send(SendError(task)),
+            })
+    }
+
+    // `on_overflow` is called once if the channel is full when the task is
+    // assigned, before it's handled according to the overflow policy.
+    pub(super) fn assign_task(
+        &self,
+        task: Task,
+        overflow_policy: OverflowPolicy,
+        on_overflow: impl FnOnce(),
+    ) -> Result<()> {
+        if task.uses_evicting_queue() {
+            return self
+                .evicting_queue
+                .assign(task, overflow_policy, on_overflow);
+        }
+
+        let sender = self.sender();
+        let task = match sender.try_send(task) {
+            Err(TrySendError::Full(task)) => {
+                on_overflow();
+                task
+            }
+            res => return res.map_err(Error::from_crossbeam_try_send),
+        };
 
         match overflow_policy {
             OverflowPolicy::Block => sender.send(task).map_err(Error::from_crossbeam_send),
-            OverflowPolicy::DropIncoming => sender
-                .try_send(task)
-                .map_err(Error::from_crossbeam_try_send),
+            // Tasks of sinks using `DropOldest` are queued in the evicting queue, so it
+            // never gets here.
+            OverflowPolicy::DropIncoming | OverflowPolicy::DropOldest => {
+                Err(Error::from_crossbeam_try_send(TrySendError::Full(task)))
+            }
         }
     }
+
+    // Removes the oldest queued log task of the sink, for `OverflowPolicy::DropOldest`.
+    // Returns `None` if no log task of the sink is queued.
+    #[must_use]
+    pub(super) fn evict_oldest(&self, backend: &Arc<Backend>) -> Option<Task> {
+        let mut state = self.evicting_queue.state.lock_expect();
+        self.evicting_queue.evict_locked(&mut state, backend)
+    }
+
+    #[must_use]
+    fn sender(&self) -> &Sender<Task> {
+        self.sender.as_ref().unwrap()
+    }
 }
 
 impl Drop for ThreadPool {
@@ -98,6 +219,10 @@ impl ThreadPoolBuilder {
     /// When a new operation is incoming, but the channel is full, it will be
     /// handled by sink according to the [`OverflowPolicy`] that has been set.
     ///
+    /// The operations of sinks using [`OverflowPolicy::DropOldest`] are queued
+    /// separately with the same capacity, since they need to be evicted from
+    /// the middle of the queue.
+    ///
     /// # Panics
     ///
     /// Panics if the value is zero.
@@ -132,28 +257,235 @@ impl ThreadPoolBuilder {
         }
 
         let (sender, receiver) = mpmc::bounded(self.capacity);
+        let evicting_queue = Arc::new(EvictingQueue::new(self.capacity));
 
         let mut threads = Vec::new();
         threads.resize_with(self.threads, || {
-            let receiver = receiver.clone();
-            Some(thread::spawn(move || Worker { receiver }.run()))
+            let worker = Worker {
+                receiver: receiver.clone(),
+                evicting_queue: Arc::clone(&evicting_queue),
+            };
+            Some(thread::spawn(move || worker.run()))
         });
 
         Ok(ThreadPool {
             threads,
             sender: Some(sender),
+            evicting_queue,
         })
     }
 }
 
 impl Worker {
     fn run(&self) {
-        while let Ok(task) = self.receiver.recv() {
+        WORKER_OF.with(|worker_of| worker_of.set(self.evicting_queue.address()));
+
+        loop {
+            // Take turns between the queues, so that neither of them starves the other.
+            let evicting_task = self.evicting_queue.try_pop();
+            let busy = evicting_task.is_some();
+            if let Some(task) = evicting_task {
+                task.exec();
+            }
+
+            match self.receiver.try_recv() {
+                Ok(task) => task.exec(),
+                Err(TryRecvError::Disconnected) => break,
+                Err(TryRecvError::Empty) if busy => {}
+                Err(TryRecvError::Empty) => {
+                    // Both queues are empty, wait for either of them.
+                    mpmc::select! {
+                        recv(self.receiver) -> task => match task {
+                            Ok(task) => task.exec(),
+                            Err(_) => break,
+                        },
+                        recv(self.evicting_queue.pushed_receiver) -> _ => {}
+                    }
+                }
+            }
+        }
+
+        // The thread pool is being dropped, process the remaining tasks.
+        while let Some(task) = self.evicting_queue.try_pop() {
             task.exec();
         }
     }
 }
 
+impl EvictingQueue {
+    #[must_use]
+    fn new(capacity: usize) -> Self {
+        let (pushed_sender, pushed_receiver) = mpmc::bounded(1);
+        Self {
+            state: Mutex::new(EvictingQueueState {
+                tasks: VecDeque::with_capacity(capacity),
+                waiting: 0,
+            }),
+            len: AtomicUsize::new(0),
+            capacity,
+            popped: Condvar::new(),
+            pushed_sender,
+            pushed_receiver,
+        }
+    }
+
+    fn assign(
+        &self,
+        task: Task,
+        overflow_policy: OverflowPolicy,
+        on_overflow: impl FnOnce(),
+    ) -> Result<()> {
+        let task = match self.try_push(task) {
+            None => return Ok(()),
+            Some(task) => {
+                on_overflow();
+                task
+            }
+        };
+
+        match overflow_policy {
+            OverflowPolicy::Block => {
+                let state = self.wait_for_room(None).unwrap();
+                self.push_locked(state, task);
+                Ok(())
+            }
+            OverflowPolicy::DropIncoming => {
+                Err(Error::from_crossbeam_try_send(TrySendError::Full(task)))
+            }
+            OverflowPolicy::DropOldest => {
+                if let Some(evicted) = self.push_evicting(task)? {
+                    evicted.discard();
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Hands the task back if the queue is full.
+    fn try_push(&self, task: Task) -> Option<Task> {
+        let state = self.state.lock_expect();
+        if state.tasks.len() >= self.capacity {
+            return Some(task);
+        }
+        self.push_locked(state, task);
+        None
+    }
+
+    // Fails with `Error::ShutdownTimeout` if the queue is still full after the
+    // timeout.
+    fn push_timeout(&self, task: Task, timeout: Duration) -> Result<()> {
+        match self.wait_for_room(Instant::now().checked_add(timeout)) {
+            Some(state) => {
+                self.push_locked(state, task);
+                Ok(())
+            }
+            None => Err(Error::ShutdownTimeout),
+        }
+    }
+
+    // Waits until the queue is not full, without a deadline if it's `None`.
+    // Returns `None` if the deadline is reached first.
+    #[must_use]
+    fn wait_for_room(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Option<MutexGuard<'_, EvictingQueueState>> {
+        let mut state = self.state.lock_expect();
+        while state.tasks.len() >= self.capacity {
+            let timeout = match deadline {
+                None => None,
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(timeout) if !timeout.is_zero() => Some(timeout),
+                    // Giving up doesn't lose a notification meant for another pusher, since
+                    // the queue is full again, the room has been taken by someone else.
+                    _ => return None,
+                },
+            };
+
+            state.waiting += 1;
+            state = match timeout {
+                None => self.popped.wait(state).expect("lock is poisoned"),
+                Some(timeout) => {
+                    self.popped
+                        .wait_timeout(state, timeout)
+                        .expect("lock is poisoned")
+                        .0
+                }
+            };
+            state.waiting -= 1;
+        }
+        Some(state)
+    }
+
+    // Pushes the task, evicting the oldest log task of the same sink if the queue
+    // is full. The evicted task is returned to be discarded by the caller.
+    //
+    // Tasks of other sinks and flush tasks are never evicted, if there is nothing
+    // to evict, the incoming task is rejected as if the policy was
+    // `OverflowPolicy::DropIncoming`.
+    fn push_evicting(&self, task: Task) -> Result<Option<Task>> {
+        let mut state = self.state.lock_expect();
+        let evicted = if state.tasks.len() >= self.capacity {
+            match self.evict_locked(&mut state, task.backend()) {
+                Some(evicted) => Some(evicted),
+                None => return Err(Error::from_crossbeam_try_send(TrySendError::Full(task))),
+            }
+        } else {
+            None
+        };
+        self.push_locked(state, task);
+        Ok(evicted)
+    }
+
+    // Removes the oldest log task of the sink, see `Task::is_evictable_for`.
+    #[must_use]
+    fn evict_locked(&self, state: &mut EvictingQueueState, backend: &Arc<Backend>) -> Option<Task> {
+        let index = state
+            .tasks
+            .iter()
+            .position(|queued| queued.is_evictable_for(backend))?;
+        let evicted = state.tasks.remove(index);
+        self.len.fetch_sub(1, Ordering::Release);
+        // Room is made the same as popping, see `try_pop`.
+        if state.waiting > 0 {
+            self.popped.notify_one();
+        }
+        evicted
+    }
+
+    fn push_locked(&self, mut state: MutexGuard<EvictingQueueState>, task: Task) {
+        state.tasks.push_back(task);
+        self.len.fetch_add(1, Ordering::Release);
+        drop(state);
+        // If the channel is full, a token is already pending.
+        let _ = self.pushed_sender.try_send(());
+    }
+
+    #[must_use]
+    fn try_pop(&self) -> Option<Task> {
+        // Checked without locking, since workers call this for every task of the
+        // channel too.
+        if self.len.load(Ordering::Acquire) == 0 {
+            return None;
+        }
+
+        let mut state = self.state.lock_expect();
+        let task = state.tasks.pop_front()?;
+        self.len.fetch_sub(1, Ordering::Release);
+        // Each popped task makes room for exactly one pusher. Pushers only wait while
+        // holding the lock, so no notification is missed.
+        if state.waiting > 0 {
+            self.popped.notify_one();
+        }
+        Some(task)
+    }
+
+    #[must_use]
+    fn address(&self) -> usize {
+        self as *const Self as usize
+    }
+}
+
 #[must_use]
 pub(crate) fn default_thread_pool() -> Arc<ThreadPool> {
     static POOL_WEAK: Lazy<Mutex<Weak<ThreadPool>>> = Lazy::new(|| Mutex::new(Weak::new()));
@@ -1,5 +1,7 @@
 use std::{
+    fmt,
     fs::{self, File, OpenOptions},
+    io,
     path::Path,
 };
 
@@ -10,6 +12,44 @@ use windows_sys::Win32::Storage::FileSystem::{FILE_SHARE_READ, FILE_SHARE_WRITE}
 
 /// Open file and forbid "delete" on windows when log file is in use.
 pub fn open_file(path: impl AsRef<Path>, truncate: bool) -> crate::Result<File> {
+    open_file_with(path, truncate, |_| {})
+}
+
+/// Opens a file like [`open_file`], but with `O_DIRECT`, so that writes bypass
+/// the page cache.
+///
+/// If the file system does not support `O_DIRECT` (e.g. tmpfs),
+/// [`Error::OpenFile`] with [`io::ErrorKind::InvalidInput`] is returned.
+///
+/// [`Error::OpenFile`]: crate::Error::OpenFile
+#[cfg(target_os = "linux")]
+pub fn open_file_direct(path: impl AsRef<Path>, truncate: bool) -> crate::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let path = path.as_ref();
+    open_file_with(path, truncate, |open_options| {
+        open_options.custom_flags(libc::O_DIRECT);
+    })
+    .map_err(|err| match err {
+        crate::Error::OpenFile(err) if err.raw_os_error() == Some(libc::EINVAL) => {
+            crate::Error::OpenFile(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "the file system of '{}' does not support O_DIRECT: {}",
+                    path.display(),
+                    err
+                ),
+            ))
+        }
+        err => err,
+    })
+}
+
+fn open_file_with(
+    path: impl AsRef<Path>,
+    truncate: bool,
+    configure: impl FnOnce(&mut OpenOptions),
+) -> crate::Result<File> {
     if let Some(parent) = path.as_ref().parent() {
         if !parent.exists() {
             fs::create_dir_all(parent).map_err(crate::Error::CreateDirectory)?;
@@ -33,6 +73,8 @@ pub fn open_file(path: impl AsRef<Path>, truncate: bool) -> crate::Result<File>
         open_options.share_mode(FILE_SHARE_READ | FILE_SHARE_WRITE);
     }
 
+    configure(&mut open_options);
+
     let open_result = open_options.create(true).open(path);
 
     let f = match open_result {
@@ -44,3 +86,106 @@ pub fn open_file(path: impl AsRef<Path>, truncate: bool) -> crate::Result<File>
 
     Ok(f)
 }
+
+/// Writes `value` as a quoted JSON string.
+///
+/// Control characters are written as escapes, so the output never spans
+/// multiple lines.
+pub fn write_json_string(dest: &mut impl fmt::Write, value: &str) -> fmt::Result {
+    dest.write_char('"')?;
+    for ch in value.chars() {
+        match ch {
+            '"' => dest.write_str("\\\"")?,
+            '\\' => dest.write_str("\\\\")?,
+            '\n' => dest.write_str("\\n")?,
+            '\r' => dest.write_str("\\r")?,
+            '\t' => dest.write_str("\\t")?,
+            ch if ch.is_control() => write!(dest, "\\u{:04x}", ch as u32)?,
+            ch => dest.write_char(ch)?,
+        }
+    }
+    dest.write_char('"')
+}
+
+/// Checks that opening a file at `path` would not create more than `max_depth`
+/// levels of missing parent directories.
+///
+/// This guards against accidentally creating a deep directory tree from a
+/// malformed path, since [`open_file`] creates all the missing parents.
+pub fn check_create_dir_depth(path: impl AsRef<Path>, max_depth: usize) -> crate::Result<()> {
+    let path = path.as_ref();
+
+    let missing_depth = path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .count();
+
+    if missing_depth > max_depth {
+        return Err(crate::Error::CreateDirectory(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "opening '{}' requires {} levels of new directories, the limit is {}",
+                path.display(),
+                missing_depth,
+                max_depth
+            ),
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the directory a file at `path` is written into.
+///
+/// A path without parent, e.g. a bare file name, is in the current directory.
+#[must_use]
+pub fn target_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Returns the number of bytes available to the current user on the file
+/// system containing `path`.
+#[cfg(unix)]
+pub fn disk_free_space(path: &Path) -> io::Result<u64> {
+    let stat = rustix::fs::statvfs(path)?;
+    Ok(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+/// Returns the number of bytes available to the current user on the file
+/// system containing `path`.
+#[cfg(windows)]
+pub fn disk_free_space(path: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut free_bytes = 0;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide_path.as_ptr(),
+            &mut free_bytes,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(free_bytes)
+}
+
+/// Returns the number of bytes available to the current user on the file
+/// system containing `path`.
+///
+/// Always returns an error, since it is not supported on this platform.
+#[cfg(not(any(unix, windows)))]
+pub fn disk_free_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "querying disk free space is not supported on this platform",
+    ))
+}
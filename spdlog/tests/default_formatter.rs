@@ -0,0 +1,28 @@
+// This test sets the global default formatter, so it lives in its own test
+// binary to not affect the sinks built by other tests.
+
+use std::sync::Arc;
+
+use spdlog::{
+    formatter::{pattern, PatternFormatter},
+    prelude::*,
+    sink::WriteSink,
+};
+
+#[test]
+fn test_default_formatter() {
+    spdlog::set_default_formatter(Box::new(PatternFormatter::new(pattern!(
+        "[{level}] {payload}{eol}"
+    ))));
+
+    let sink = Arc::new(WriteSink::builder().target(Vec::new()).build().unwrap());
+    let logger = Logger::builder().sink(sink.clone()).build().unwrap();
+
+    info!(logger: logger, "hello");
+    warn!(logger: logger, "world");
+
+    assert_eq!(
+        String::from_utf8(sink.clone_target()).unwrap(),
+        format!("[info] hello{0}[warn] world{0}", spdlog::EOL)
+    );
+}
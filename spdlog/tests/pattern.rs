@@ -2,6 +2,7 @@ use std::{
     fmt::Write,
     ops::{Range, RangeInclusive},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use cfg_if::cfg_if;
@@ -49,6 +50,100 @@ fn test_style_range() {
     );
 }
 
+#[test]
+fn test_conditional_style_range() {
+    let sink = Arc::new(MockSink::new());
+    sink.set_formatter(Box::new(PatternFormatter::new(pattern!(
+        "[{level}] {^error+:{payload}}"
+    ))));
+    let logger = Logger::builder().sink(sink.clone()).build().unwrap();
+
+    error!(logger: logger, "failed");
+    assert_eq!(
+        sink.get_last_msg().unwrap(),
+        (String::from("[error] failed"), Some(8..14))
+    );
+
+    info!(logger: logger, "ok");
+    assert_eq!(
+        sink.get_last_msg().unwrap(),
+        (String::from("[info] ok"), None)
+    );
+}
+
+#[test]
+fn test_optional_group() {
+    let sink = Arc::new(MockSink::new());
+    sink.set_formatter(Box::new(PatternFormatter::new(pattern!(
+        "{^{?[{logger}] }}[{level}] {payload}"
+    ))));
+    let named = Logger::builder()
+        .name("logger_name")
+        .sink(sink.clone())
+        .build()
+        .unwrap();
+    let unnamed = Logger::builder().sink(sink.clone()).build().unwrap();
+
+    info!(logger: named, "hello");
+    assert_eq!(
+        sink.get_last_msg().unwrap(),
+        (String::from("[logger_name] [info] hello"), Some(0..14))
+    );
+
+    info!(logger: unnamed, "hello");
+    assert_eq!(
+        sink.get_last_msg().unwrap(),
+        (String::from("[info] hello"), Some(0..0))
+    );
+}
+
+#[test]
+fn test_padding() {
+    test_pattern(
+        pattern!("[{level:<8}] [{logger:>13}] [{$mock1:^4!}] {payload}",
+            {$mock1} => MockPattern1::default,
+        ),
+        "[error   ] [  logger_name] [mock] record_payload",
+        None,
+    );
+    test_pattern(
+        pattern!("[{logger:6!}] [{level:^9}]{?[{duration:<4}]}"),
+        "[logger] [  error  ]",
+        None,
+    );
+}
+
+#[test]
+fn test_duration() {
+    let sink = Arc::new(MockSink::new());
+    sink.set_formatter(Box::new(PatternFormatter::new(pattern!(
+        "{payload} in {duration}"
+    ))));
+    let logger = Logger::builder().sink(sink.clone()).build().unwrap();
+
+    info!(logger: logger, duration: Duration::from_millis(1500), "handled");
+    assert_eq!(sink.get_last_msg().unwrap().0, "handled in 1.5s");
+
+    info!(logger: logger, "handled");
+    assert_eq!(sink.get_last_msg().unwrap().0, "handled in ");
+}
+
+#[test]
+fn test_tid_hex() {
+    let sink = Arc::new(MockSink::new());
+    sink.set_formatter(Box::new(PatternFormatter::new(pattern!("{tid} {tid:hex}"))));
+    let logger = Logger::builder().sink(sink.clone()).build().unwrap();
+
+    info!(logger: logger, "hello");
+    let msg = sink.get_last_msg().unwrap().0;
+    let (tid, tid_hex) = msg.split_once(' ').unwrap();
+    let tid_hex = tid_hex.strip_prefix("0x").unwrap();
+    assert_eq!(
+        u64::from_str_radix(tid_hex, 16).unwrap(),
+        tid.parse::<u64>().unwrap()
+    );
+}
+
 #[track_caller]
 fn test_pattern<P, F>(pat: P, expect_formatted: F, expect_style_range: Option<Range<usize>>)
 where
@@ -371,6 +466,21 @@ fn test_builtin_patterns() {
         None as Option<&str>,
         vec![0..=i32::MAX as u64],
     );
+    check(
+        pattern!("{strftime:%Y/%m/%d}"),
+        Some("0000/00/00"),
+        vec![YEAR_RANGE, MONTH_RANGE, DAY_RANGE],
+    );
+    check(
+        pattern!("{strftime_utc:%H:%M}"),
+        Some("00:00"),
+        vec![HOUR_RANGE, MINUTE_RANGE],
+    );
+    check(
+        pattern!("{datetime:%Y-%m-%d}"),
+        Some("0000-00-00"),
+        vec![YEAR_RANGE, MONTH_RANGE, DAY_RANGE],
+    );
 
     cfg_if! {
         if #[cfg(feature = "source-location")] {
@@ -407,6 +517,7 @@ fn test_builtin_patterns() {
 
     check(pattern!("{level}"), Some("info"), vec![]);
     check(pattern!("{level_short}"), Some("I"), vec![]);
+    check(pattern!("{level:spdlog}"), Some("info"), vec![]);
     cfg_if! {
         if #[cfg(feature = "source-location")] {
             check(pattern!("{source}"), Some(format!("{}:000", file!())), vec![SOURCE_RANGE]);
@@ -425,10 +536,41 @@ fn test_builtin_patterns() {
         }
     }
     check(pattern!("{logger}"), Some("logger-name"), vec![]);
+    check(pattern!("{logger:short}"), Some("logger-name"), vec![]);
     check(pattern!("{payload}"), Some("test payload"), vec![]);
     check(pattern!("{pid}"), None as Option<&str>, vec![OS_ID_RANGE]);
     check(pattern!("{tid}"), None as Option<&str>, vec![OS_ID_RANGE]);
     check(pattern!("{eol}"), Some("{eol}"), vec![]);
+    check(
+        pattern!("{const:region=us-east; a:b/c \"d\" <e> & f%}"),
+        Some("region=us-east; a:b/c \"d\" <e> & f%"),
+        vec![],
+    );
+}
+
+#[test]
+fn test_sink_count() {
+    let build_sink = || {
+        Arc::new(
+            WriteSink::builder()
+                .formatter(Box::new(PatternFormatter::new(pattern!(
+                    "{sink_count}{eol}"
+                ))))
+                .target(Vec::new())
+                .build()
+                .unwrap(),
+        )
+    };
+    let buffer_sink = build_sink();
+    let sinks: [Arc<dyn Sink>; 3] = [buffer_sink.clone(), build_sink(), build_sink()];
+    let logger = Logger::builder().sinks(sinks).build().unwrap();
+    // Sinks of other loggers are not counted
+    let _other_logger = Logger::builder().sink(build_sink()).build().unwrap();
+
+    info!(logger: logger, "");
+
+    let buffer = String::from_utf8(buffer_sink.clone_target()).unwrap();
+    assert_eq!(buffer.lines().collect::<Vec<_>>(), ["3"]);
 }
 
 #[cfg(feature = "multi-thread")]
@@ -467,3 +609,79 @@ fn test_different_context_thread() {
     assert_eq!(buffer.len(), 2);
     buffer.windows(2).for_each(|w| assert_eq!(w[0], w[1]))
 }
+
+#[cfg(feature = "multi-thread")]
+#[test]
+fn test_format_on_enqueue() {
+    use std::thread;
+
+    use spdlog::{formatter::PatternContext, sink::AsyncPoolSink, ThreadPool};
+
+    #[derive(Default, Clone)]
+    struct ThreadName;
+
+    impl Pattern for ThreadName {
+        fn format(
+            &self,
+            _record: &spdlog::Record,
+            dest: &mut StringBuf,
+            _ctx: &mut PatternContext,
+        ) -> spdlog::Result<()> {
+            let name = thread::current().name().unwrap_or_default().to_string();
+            write!(dest, "{name}").map_err(spdlog::Error::FormatRecord)
+        }
+    }
+
+    let formatter = || {
+        Box::new(PatternFormatter::new(pattern!(
+            "[{$thread_name}] {payload}{eol}",
+            {$thread_name} => ThreadName::default,
+        )))
+    };
+    let thread_pool = Arc::new(ThreadPool::builder().build().unwrap());
+    let buffer_sink = Arc::new(
+        WriteSink::builder()
+            .formatter(formatter())
+            .target(Vec::new())
+            .build()
+            .unwrap(),
+    );
+    let build_async_sink = |format_on_enqueue| {
+        Arc::new(
+            AsyncPoolSink::builder()
+                .sink(buffer_sink.clone())
+                .thread_pool(thread_pool.clone())
+                .formatter(formatter())
+                .format_on_enqueue(format_on_enqueue)
+                .build()
+                .unwrap(),
+        )
+    };
+    let (on_enqueue, on_worker) = (build_async_sink(true), build_async_sink(false));
+    let build_logger = |sink: Arc<AsyncPoolSink>| Logger::builder().sink(sink).build().unwrap();
+    let (on_enqueue_logger, on_worker_logger) = (
+        build_logger(on_enqueue.clone()),
+        build_logger(on_worker.clone()),
+    );
+
+    thread::Builder::new()
+        .name("producer".into())
+        .spawn(move || {
+            info!(logger: on_enqueue_logger, "enqueue");
+            info!(logger: on_worker_logger, "worker");
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    on_enqueue.flush_blocking().unwrap();
+    on_worker.flush_blocking().unwrap();
+
+    let buffer = String::from_utf8(buffer_sink.clone_target()).unwrap();
+    let buffer = buffer.lines().collect::<Vec<_>>();
+    assert_eq!(buffer.len(), 2);
+    // Formatted on the producer thread
+    assert!(buffer.contains(&"[producer] enqueue"));
+    // Formatted by the internal sink on a worker thread
+    assert!(!buffer.contains(&"[producer] worker"));
+    assert!(buffer.iter().any(|line| line.ends_with("] worker")));
+}
@@ -0,0 +1,49 @@
+// This test sets a global placeholder, so it lives in its own test binary to
+// not affect the output of other tests.
+#![cfg(all(feature = "log", feature = "source-location"))]
+
+use std::sync::Arc;
+
+use spdlog::{
+    formatter::{pattern, PatternFormatter},
+    prelude::*,
+    sink::WriteSink,
+    SourceLocation,
+};
+
+#[test]
+fn test_source_location_placeholder() {
+    let formatter = Box::new(PatternFormatter::new(pattern!(
+        "[{source}] [{module_path}] {payload}{eol}"
+    )));
+    let sink = Arc::new(
+        WriteSink::builder()
+            .formatter(formatter)
+            .target(Vec::new())
+            .build()
+            .unwrap(),
+    );
+    let logger = Arc::new(Logger::builder().sink(sink.clone()).build().unwrap());
+    spdlog::log_crate_proxy().set_logger(Some(logger));
+
+    let log_without_location = |payload: &str| {
+        log::Log::log(
+            spdlog::log_crate_proxy(),
+            &log::Record::builder()
+                .level(log::Level::Info)
+                .args(format_args!("{}", payload))
+                .build(),
+        );
+    };
+
+    log_without_location("no placeholder");
+    spdlog::set_source_location_placeholder(Some(SourceLocation::placeholder("<unknown>")));
+    log_without_location("placeholder");
+    spdlog::set_source_location_placeholder(None);
+
+    let expected = format!(
+        "[] [] no placeholder{eol}[<unknown>:0] [<unknown>] placeholder{eol}",
+        eol = spdlog::EOL
+    );
+    assert_eq!(String::from_utf8(sink.clone_target()).unwrap(), expected);
+}